@@ -0,0 +1,223 @@
+//! Files holding large task outputs, such as generated code, so task results in storage
+//! keep only a reference to them
+//!
+//! Artifacts live at `<artifact_dir>/<blake3 of task id>/<name>` and are served by
+//! `GET /tasks/{id}/artifacts`. Task ids come from clients, so the directory is named by
+//! digest rather than by the id itself.
+use crate::error::{CwHoError, Result};
+use crate::CwHoConfig;
+use camino::Utf8PathBuf;
+use commonware_cryptography::{blake3, Hasher};
+use ho_std::constants::ARTIFACT_INLINE_MAX_BYTES;
+use ho_std::prelude::ArtifactInfo;
+use pbjson_types::{value::Kind, Struct, Value};
+
+pub struct ArtifactStore {
+    dir: Utf8PathBuf,
+}
+
+impl ArtifactStore {
+    pub fn new(dir: impl Into<Utf8PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The node's store, `None` when `artifact_dir` is unset and outputs stay inline
+    pub fn from_config(config: &CwHoConfig) -> Option<Self> {
+        config.artifact_dir.as_deref().map(Self::new)
+    }
+
+    /// Write `contents` as `task_id`'s artifact `name`, returning the reference a task
+    /// result records in its place: `{"artifact": name, "bytes": len}`
+    pub fn write(&self, task_id: &str, name: &str, contents: &str) -> Result<Value> {
+        let task_dir = self.task_dir(task_id);
+        std::fs::create_dir_all(&task_dir)?;
+        std::fs::write(task_dir.join(checked_segment(name)?), contents)?;
+
+        let reference = Struct {
+            fields: [
+                (
+                    "artifact".to_string(),
+                    Value {
+                        kind: Some(Kind::StringValue(name.to_string())),
+                    },
+                ),
+                (
+                    "bytes".to_string(),
+                    Value {
+                        kind: Some(Kind::NumberValue(contents.len() as f64)),
+                    },
+                ),
+            ]
+            .into(),
+        };
+        Ok(Value {
+            kind: Some(Kind::StructValue(reference)),
+        })
+    }
+
+    /// Move every string field of `result` longer than [`ARTIFACT_INLINE_MAX_BYTES`] to an
+    /// artifact named `<field>.txt`, leaving its reference behind. Returns how many moved.
+    pub fn offload(&self, task_id: &str, result: &mut Struct) -> Result<usize> {
+        let mut moved = 0;
+        for (field, value) in result.fields.iter_mut() {
+            let Some(Kind::StringValue(text)) = &value.kind else {
+                continue;
+            };
+            if text.len() <= ARTIFACT_INLINE_MAX_BYTES {
+                continue;
+            }
+            let reference = self.write(task_id, &format!("{}.txt", field), text)?;
+            *value = reference;
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    /// `task_id`'s artifacts by name; none when it never wrote any
+    pub fn list(&self, task_id: &str) -> Result<Vec<ArtifactInfo>> {
+        let task_dir = self.task_dir(task_id);
+        let entries = match std::fs::read_dir(&task_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut artifacts = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            if let Ok(name) = entry.file_name().into_string() {
+                artifacts.push(ArtifactInfo {
+                    name,
+                    bytes: metadata.len(),
+                });
+            }
+        }
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(artifacts)
+    }
+
+    /// Contents of `task_id`'s artifact `name`, `None` when there is no such artifact
+    pub fn read(&self, task_id: &str, name: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.task_dir(task_id).join(checked_segment(name)?);
+        match std::fs::read(path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Directory of `task_id`'s artifacts, named by the hex blake3 digest of the id so any
+    /// id maps to a single safe path component
+    fn task_dir(&self, task_id: &str) -> Utf8PathBuf {
+        self.dir
+            .join(blake3::Blake3::hash(task_id.as_bytes()).to_string())
+    }
+}
+
+/// `segment` as a single path component, refusing anything that could leave the artifact dir
+fn checked_segment(segment: &str) -> Result<&str> {
+    let valid = !segment.is_empty()
+        && !segment.starts_with('.')
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(segment)
+    } else {
+        Err(CwHoError::InvalidRequest(format!(
+            "invalid artifact path segment: {:?}",
+            segment
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_code_moves_to_artifact_and_result_references_it() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-artifacts-{}", uuid::Uuid::new_v4()));
+        let store = ArtifactStore::new(Utf8PathBuf::from_path_buf(dir).unwrap());
+
+        let code = "fn main() {}\n".repeat(ARTIFACT_INLINE_MAX_BYTES);
+        let text = |s: &str| Value {
+            kind: Some(Kind::StringValue(s.to_string())),
+        };
+        // A code-generation result: the code is large, the language is not
+        let mut result = Struct {
+            fields: [
+                ("language".to_string(), text("rust")),
+                ("code".to_string(), text(&code)),
+            ]
+            .into(),
+        };
+        assert_eq!(store.offload("codegen-1", &mut result).unwrap(), 1);
+        assert_eq!(result.fields["language"], text("rust"));
+
+        let Some(Kind::StructValue(reference)) = &result.fields["code"].kind else {
+            panic!(
+                "code was not replaced by a reference: {:?}",
+                result.fields["code"]
+            );
+        };
+        assert_eq!(reference.fields["artifact"], text("code.txt"));
+        assert_eq!(
+            reference.fields["bytes"].kind,
+            Some(Kind::NumberValue(code.len() as f64))
+        );
+
+        assert_eq!(
+            store.list("codegen-1").unwrap(),
+            [ArtifactInfo {
+                name: "code.txt".to_string(),
+                bytes: code.len() as u64,
+            }]
+        );
+        assert_eq!(
+            store.read("codegen-1", "code.txt").unwrap(),
+            Some(code.into_bytes())
+        );
+        assert_eq!(store.read("codegen-1", "missing.txt").unwrap(), None);
+        assert!(store.list("never-ran").unwrap().is_empty());
+        // Names can't reach outside the task's directory
+        assert!(store.read("codegen-1", "../codegen-1").is_err());
+    }
+
+    #[test]
+    fn test_task_ids_outside_path_charset_still_offload() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-artifacts-{}", uuid::Uuid::new_v4()));
+        let store = ArtifactStore::new(Utf8PathBuf::from_path_buf(dir).unwrap());
+        let long = "x".repeat(ARTIFACT_INLINE_MAX_BYTES + 1);
+
+        for task_id in ["job:1", "tâche/ünïcode", "../escape", ".hidden"] {
+            let mut result = Struct {
+                fields: [(
+                    "response".to_string(),
+                    Value {
+                        kind: Some(Kind::StringValue(long.clone())),
+                    },
+                )]
+                .into(),
+            };
+            assert_eq!(
+                store.offload(task_id, &mut result).unwrap(),
+                1,
+                "{}",
+                task_id
+            );
+            assert_eq!(
+                store.read(task_id, "response.txt").unwrap(),
+                Some(long.clone().into_bytes()),
+                "{}",
+                task_id
+            );
+        }
+        // Each id got its own directory directly under the artifact dir
+        assert_eq!(std::fs::read_dir(&store.dir).unwrap().count(), 4);
+        assert!(store.list("job:2").unwrap().is_empty());
+    }
+}
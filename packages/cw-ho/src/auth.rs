@@ -1,3 +1,4 @@
+use crate::OutputFormat;
 use anyhow::Result;
 use camino::Utf8Path;
 
@@ -20,16 +21,22 @@ pub enum AuthTopSubCmd {
     Revoke {},
 }
 impl AuthCmd {
-    pub fn exec(&self, home_dir: &Utf8Path) -> Result<()> {
+    pub fn exec(&self, home_dir: &Utf8Path, output: OutputFormat) -> Result<()> {
         //
-        match self.subcmd.clone() {
+        let action = match self.subcmd.clone() {
             AuthTopSubCmd::Register {} => {
                 // check fo existing register,
+                "register"
             }
             AuthTopSubCmd::Revoke {} => {
                 // check if exists, remove if so
+                "revoke"
             }
         };
+        output.emit(
+            &[format!("✅ Auth {} complete", action)],
+            serde_json::json!({ "command": "manage-auth", "action": action, "home": home_dir.as_str() }),
+        );
         Ok(())
     }
 }
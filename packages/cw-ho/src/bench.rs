@@ -0,0 +1,329 @@
+//! `cw-ho bench`: latency, throughput and cost of the configured LLM providers
+use crate::profile::Profile;
+use crate::{load_config, LlmRouter, OutputFormat};
+use anyhow::{bail, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use futures::StreamExt;
+use ho_std::llm::CostCalculator;
+use ho_std::prelude::*;
+use ho_std::traits::{HoConfigTrait, LlmModelTrait};
+use std::time::{Duration, Instant};
+
+/// Prompt sent when no `--prompt-file` is given
+const DEFAULT_BENCH_PROMPT: &str = "Reply with one short sentence describing a tetrahedron.";
+
+#[derive(Debug, clap::Parser)]
+pub struct BenchCmd {
+    /// Provider to benchmark, e.g. openai or akash_chat [default: every enabled provider]
+    #[arg(long)]
+    pub provider: Option<String>,
+    /// Prompts sent to each provider
+    #[arg(long, default_value_t = 20)]
+    pub requests: usize,
+    /// Requests kept in flight at once
+    #[arg(long, default_value_t = 4)]
+    pub concurrency: usize,
+    /// File with one prompt per line, cycled through in order
+    #[arg(long)]
+    pub prompt_file: Option<Utf8PathBuf>,
+}
+
+impl BenchCmd {
+    pub fn exec(
+        &self,
+        home: &Utf8Path,
+        profile: Option<Profile>,
+        output: OutputFormat,
+    ) -> Result<()> {
+        if self.requests == 0 || self.concurrency == 0 {
+            bail!("--requests and --concurrency must be at least 1");
+        }
+        let mut config = load_config(home)?;
+        if let Some(profile) = profile {
+            profile.apply(&mut config)?;
+        }
+        let prompts = match &self.prompt_file {
+            Some(path) => read_prompts(path)?,
+            None => vec![DEFAULT_BENCH_PROMPT.to_string()],
+        };
+
+        let entities: Vec<LlmEntity> = config
+            .llm()
+            .enabled_entities()
+            .into_iter()
+            .filter(|e| {
+                self.provider
+                    .as_deref()
+                    .is_none_or(|p| provider_matches(e, p))
+            })
+            .cloned()
+            .collect();
+        if entities.is_empty() {
+            bail!(
+                "no enabled provider matches {}",
+                self.provider.as_deref().unwrap_or("the config")
+            );
+        }
+
+        let results = tokio::runtime::Runtime::new()?.block_on(async {
+            let router = LlmRouter::new(config.llm()).await?;
+            let mut results = Vec::new();
+            for entity in &entities {
+                results.push(
+                    bench_provider(&router, entity, &prompts, self.requests, self.concurrency)
+                        .await,
+                );
+            }
+            anyhow::Ok(results)
+        })?;
+
+        output.emit(
+            &table(&results),
+            serde_json::json!({ "command": "bench", "results": results }),
+        );
+        Ok(())
+    }
+}
+
+/// Whether `name` refers to the entity, by proto name or api-keys.json key
+fn provider_matches(entity: &LlmEntity, name: &str) -> bool {
+    entity.name.eq_ignore_ascii_case(name)
+        || LlmModel::from_str_name(&entity.name)
+            .is_some_and(|model| model.provider_key().eq_ignore_ascii_case(name))
+}
+
+fn read_prompts(path: &Utf8Path) -> Result<Vec<String>> {
+    let prompts: Vec<String> = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read prompt file {}", path))?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if prompts.is_empty() {
+        bail!("prompt file {} has no prompts", path);
+    }
+    Ok(prompts)
+}
+
+/// Send `requests` prompts to one entity's default model, `concurrency` at a time. A
+/// prompt answered by any other provider counts as an error.
+pub async fn bench_provider(
+    router: &LlmRouter,
+    entity: &LlmEntity,
+    prompts: &[String],
+    requests: usize,
+    concurrency: usize,
+) -> ProviderBench {
+    let known = LlmModel::from_str_name(&entity.name);
+    let provider = known
+        .map(|model| model.provider_key().to_string())
+        .unwrap_or_else(|| entity.name.clone());
+    let model = entity.default_model.clone();
+    let llm_config = known.map(|model| router.prompt_config(model, None));
+
+    let started = Instant::now();
+    let outcomes: Vec<Option<(Duration, TokenUsage)>> = futures::stream::iter(0..requests)
+        .map(|i| {
+            let request = PromptRequest {
+                messages: vec![PromptMessage {
+                    role: "user".to_string(),
+                    content: prompts[i % prompts.len()].clone(),
+                }],
+                model: model.clone(),
+                provider: Some(entity.name.clone()),
+                llm_config: llm_config.clone(),
+                ..Default::default()
+            };
+            let (model, provider) = (&model, &provider);
+            async move {
+                let sent = Instant::now();
+                match router.process_request(&request, model).await {
+                    // The mock router answers as itself for every provider
+                    Ok(response) if response.provider != *provider && !router.mock => {
+                        tracing::warn!(
+                            "⏱️ Bench request to {} was answered by {}",
+                            provider,
+                            response.provider
+                        );
+                        None
+                    }
+                    Ok(response) => {
+                        Some((sent.elapsed(), response.tokens_used.unwrap_or_default()))
+                    }
+                    Err(e) => {
+                        tracing::warn!("⏱️ Bench request to {} failed: {}", model, e);
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut latencies: Vec<f64> = Vec::new();
+    let (mut completion_tokens, mut cost) = (0u64, 0.0);
+    for (latency, usage) in outcomes.iter().flatten() {
+        latencies.push(latency.as_secs_f64() * 1000.0);
+        completion_tokens += usage.completion as u64;
+        cost += CostCalculator::calculate_cost(&provider, &model, usage.prompt, usage.completion);
+    }
+    latencies.sort_by(f64::total_cmp);
+    let errors = requests - latencies.len();
+
+    ProviderBench {
+        provider,
+        model,
+        requests: requests as u64,
+        errors: errors as u64,
+        error_rate: errors as f64 / requests as f64,
+        p50_ms: percentile(&latencies, 50.0),
+        p95_ms: percentile(&latencies, 95.0),
+        p99_ms: percentile(&latencies, 99.0),
+        tokens_per_sec: match elapsed > 0.0 {
+            true => completion_tokens as f64 / elapsed,
+            false => 0.0,
+        },
+        estimated_cost: cost,
+    }
+}
+
+/// Nearest-rank percentile of ascending `sorted` values, 0 when empty
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn table(results: &[ProviderBench]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{:<14} {:<28} {:>6} {:>9} {:>9} {:>9} {:>10} {:>7} {:>10}",
+        "provider", "model", "reqs", "p50 ms", "p95 ms", "p99 ms", "tok/s", "errors", "cost $"
+    )];
+    lines.extend(results.iter().map(|r| {
+        format!(
+            "{:<14} {:<28} {:>6} {:>9.1} {:>9.1} {:>9.1} {:>10.1} {:>6.1}% {:>10.4}",
+            r.provider,
+            r.model,
+            r.requests,
+            r.p50_ms,
+            r.p95_ms,
+            r.p99_ms,
+            r.tokens_per_sec,
+            r.error_rate * 100.0,
+            r.estimated_cost
+        )
+    }));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_router_config, FakeBackend};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let sorted: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&sorted, 50.0), 50.0);
+        assert_eq!(percentile(&sorted, 95.0), 95.0);
+        assert_eq!(percentile(&sorted, 99.0), 99.0);
+        assert_eq!(percentile(&[7.0], 99.0), 7.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_bench_against_mock_router() {
+        let entity = LlmModel::OpenAi.default_entity();
+        let config = LlmRouterConfig {
+            mock: Some(true),
+            ..test_router_config(vec![entity.clone()])
+        };
+        let router = LlmRouter::new(&config).await.unwrap();
+        let prompts = vec!["one".to_string(), "two".to_string()];
+
+        let report = bench_provider(&router, &entity, &prompts, 10, 3).await;
+        assert_eq!(report.provider, "openai");
+        assert_eq!((report.requests, report.errors), (10, 0));
+        assert!(report.p50_ms <= report.p95_ms && report.p95_ms <= report.p99_ms);
+        assert!(report.tokens_per_sec > 0.0);
+        assert!(provider_matches(&entity, "openai"));
+        assert!(provider_matches(&entity, entity.name.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_answers_from_another_provider_count_as_errors() {
+        let completion = HttpResponse::new(
+            reqwest::StatusCode::OK,
+            serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "four faces"}}],
+            })
+            .to_string(),
+        );
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .extend([completion.clone(), completion]);
+        let akash = LlmModel::AkashChat.default_entity();
+        let openai = LlmEntity {
+            enabled: false,
+            ..LlmModel::OpenAi.default_entity()
+        };
+        let config = test_router_config(vec![akash.clone(), openai.clone()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        router.api_keys.openai = Some("test-key".to_string());
+        let prompts = vec![DEFAULT_BENCH_PROMPT.to_string()];
+
+        let report = bench_provider(&router, &akash, &prompts, 1, 1).await;
+        assert_eq!((report.provider.as_str(), report.errors), ("akash_chat", 0));
+
+        // The disabled OpenAI entity falls back to AkashChat, which says nothing of OpenAI
+        let report = bench_provider(&router, &openai, &prompts, 1, 1).await;
+        assert_eq!((report.provider.as_str(), report.errors), ("openai", 1));
+        assert_eq!(backend.urls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bench_against_anthropic_sends_sampling_settings() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(HttpResponse::new(
+                reqwest::StatusCode::OK,
+                serde_json::json!({
+                    "content": [{"type": "text", "text": "four faces"}],
+                    "usage": {"input_tokens": 10, "output_tokens": 5},
+                })
+                .to_string(),
+            ));
+        let anthropic = LlmModel::Anthropic.default_entity();
+        let config = test_router_config(vec![anthropic.clone()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.anthropic = Some("test-key".to_string());
+        let prompts = vec![DEFAULT_BENCH_PROMPT.to_string()];
+
+        let report = bench_provider(&router, &anthropic, &prompts, 1, 1).await;
+        assert_eq!((report.provider.as_str(), report.errors), ("anthropic", 0));
+        let body = backend.bodies.lock().unwrap()[0].clone();
+        assert_eq!(
+            body["max_tokens"],
+            router.prompt_config(LlmModel::Anthropic, None).max_tokens
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use crate::error::Result;
 use crate::traits::Wrap;
-use crate::{CwHoConfig, CwHoLlmRouterConfig};
+use crate::{load_config, CwHoConfig, CwHoLlmRouterConfig, OutputFormat};
 
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
+use ho_std::constants::{ARTIFACTS_FOLDER_NAME, MAX_LLM_TIMEOUT_SECS};
 use ho_std::llm::{HoError, HoResult};
 use ho_std::orchestrate::HoConfig;
 use ho_std::prelude::*;
@@ -24,6 +25,15 @@ impl HoConfigTrait for CwHoConfig {
             identity: Some(NodeIdentity::new()),
             storage: Some(StorageConfig::new(home_dir)),
             llm: Some(LlmRouterConfig::new(home_dir)),
+            logging: Some(LoggingConfig::new()),
+            egress: None,
+            task_timeout_secs: None,
+            worker_threads: None,
+            max_blocking_threads: None,
+            max_workspace_archive_bytes: None,
+            artifact_dir: Some(home_dir.join(ARTIFACTS_FOLDER_NAME).to_string()),
+            ssh_registration_concurrency: None,
+            ssh_transient_retries: None,
         })
     }
 
@@ -50,6 +60,7 @@ impl HoConfigTrait for CwHoConfig {
     fn validate(&self) -> Self::HoConfigResult {
         self.network().validate()?;
         self.llm().validate()?;
+        self.identity().checked_api_port()?;
         // Note: identity validation should be handled through the trait
         // self.identity().validate()?;
         //     // validate llm config
@@ -179,6 +190,67 @@ impl LLMRouterConfigTrait for CwHoLlmRouterConfig {
         todo!()
     }
     fn validate(&self) -> HoResult<()> {
-        Ok(())
+        if self.timeout_seconds > MAX_LLM_TIMEOUT_SECS {
+            return Err(HoError::Config(format!(
+                "LLM timeout_seconds {} must be at most {}",
+                self.timeout_seconds, MAX_LLM_TIMEOUT_SECS
+            )));
+        }
+        self.validate_entities()
+    }
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ConfigCmd {
+    #[clap(subcommand)]
+    pub subcmd: ConfigSubCmd,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum ConfigSubCmd {
+    /// Print the loaded config, followed by the api keys file it points to
+    Dump {
+        /// Drop the node's private key and hide api keys, keeping `${ENV}` references,
+        /// so the output can be pasted into a bug report
+        #[arg(long)]
+        redacted: bool,
+    },
+}
+
+impl ConfigCmd {
+    pub fn exec(&self, home: &Utf8Path, output: OutputFormat) -> anyhow::Result<()> {
+        match self.subcmd {
+            ConfigSubCmd::Dump { redacted } => dump(home, redacted, output),
+        }
+    }
+}
+
+fn dump(home: &Utf8Path, redacted: bool, output: OutputFormat) -> anyhow::Result<()> {
+    let config = load_config(home)?.unwrap();
+    let api_keys_file = config
+        .llm
+        .as_ref()
+        .map(|llm| Utf8PathBuf::from(&llm.api_keys_file))
+        .filter(|path| path.is_file());
+    let api_keys = api_keys_file.as_ref().map(ApiKeysJson::load).transpose()?;
+    let (config, api_keys) = match redacted {
+        true => (config.redacted(), api_keys.map(|keys| keys.redacted())),
+        false => (config, api_keys),
+    };
+
+    let mut lines = vec![toml::to_string_pretty(&config)?];
+    if let (Some(path), Some(api_keys)) = (&api_keys_file, &api_keys) {
+        lines.push(format!("# {}", path));
+        lines.push(serde_json::to_string_pretty(api_keys)?);
     }
+    output.emit(
+        &lines,
+        serde_json::json!({
+            "command": "config dump",
+            "redacted": redacted,
+            "config": config,
+            "api_keys": api_keys,
+        }),
+    );
+    Ok(())
 }
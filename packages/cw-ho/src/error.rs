@@ -1,7 +1,11 @@
-use axum::Json;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 use ho_std::commonware::error::CommonwareNetworkError;
+use ho_std::config::api_keys::get_env_var_name;
+use ho_std::constants::LLM_API_KEYS_FILE;
 use ho_std::llm::HoError;
-use reqwest::StatusCode;
+use ho_std::prelude::LlmModel;
+use ho_std::routes::ApiError;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, CwHoError>;
@@ -14,6 +18,9 @@ pub enum CwHoError {
     #[error("Storage error: {0}")]
     Storage(#[from] anyhow::Error),
 
+    #[error("Storage at {path} is locked by running process {pid}; stop that process or start with --ephemeral")]
+    StorageLocked { path: String, pid: u32 },
+
     #[error("HoError error: {0}")]
     HoError(#[from] HoError),
 
@@ -32,31 +39,157 @@ pub enum CwHoError {
     #[error("LLM provider error: {0}")]
     LlmEntity(String),
 
+    #[error("Rate limited by {provider}{}", .retry_after.map(|d| format!(", retry after {}s", d.as_secs())).unwrap_or_default())]
+    RateLimited {
+        provider: String,
+        retry_after: Option<std::time::Duration>,
+    },
+
+    #[error("{provider} returned an error: {message}")]
+    ProviderHttp { provider: String, message: String },
+
+    #[error("{provider} rejected the API key: {message}")]
+    Auth { provider: String, message: String },
+
+    #[error("{provider} returned an empty completion")]
+    EmptyCompletion { provider: String },
+
+    #[error("No enabled LLM provider available")]
+    NoHealthyProvider,
+
+    #[error("Fallback deadline exceeded after trying: [{}]", .failures.join("; "))]
+    DeadlineExceeded { failures: Vec<String> },
+
+    #[error("Every provider in the fallback chain failed: [{}]", .failures.join("; "))]
+    FallbackExhausted { failures: Vec<String> },
+
+    #[error("Node {0} is already registered")]
+    DuplicateNode(String),
+
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
+
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("no handler registered for custom task '{0}'")]
+    NoCustomHandler(String),
+}
+
+impl CwHoError {
+    /// What the operator can do about this error, for the variants where it is known
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            CwHoError::Auth { provider, .. } => {
+                let source = match LlmModel::from_str_name(provider) {
+                    Some(model) => format!("the {} env var", get_env_var_name(model)),
+                    None => "its env var".to_string(),
+                };
+                Some(format!(
+                    "check your {} API key in {} or {}",
+                    provider, LLM_API_KEYS_FILE, source
+                ))
+            }
+            CwHoError::RateLimited {
+                provider,
+                retry_after,
+            } => Some(match retry_after {
+                Some(after) => format!(
+                    "slow down, {} is rate limiting requests; retry after {}s",
+                    provider,
+                    after.as_secs()
+                ),
+                None => format!(
+                    "slow down, {} is rate limiting requests; retry later",
+                    provider
+                ),
+            }),
+            CwHoError::NoHealthyProvider => Some(
+                "all providers are disabled; enable one under llm.entities in the config"
+                    .to_string(),
+            ),
+            CwHoError::DeadlineExceeded { .. } => Some(
+                "the fallback chain ran out of time; raise the task's timeout_secs or task_timeout_secs, or lower llm.timeout_seconds"
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// HTTP status a route answers with when it fails with this error: 400 for a bad
+    /// request, 429 when a provider rate limits us, 502 when providers fail, 500 otherwise
+    pub fn status(&self) -> StatusCode {
+        match self {
+            CwHoError::InvalidRequest(_)
+            | CwHoError::DuplicateNode(_)
+            | CwHoError::NoCustomHandler(_) => StatusCode::BAD_REQUEST,
+            CwHoError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            CwHoError::Http(_)
+            | CwHoError::LlmEntity(_)
+            | CwHoError::ProviderHttp { .. }
+            | CwHoError::Auth { .. }
+            | CwHoError::EmptyCompletion { .. }
+            | CwHoError::NoHealthyProvider
+            | CwHoError::DeadlineExceeded { .. }
+            | CwHoError::FallbackExhausted { .. } => StatusCode::BAD_GATEWAY,
+            CwHoError::HoError(HoError::Network(_) | HoError::Timeout(_) | HoError::Llm(_)) => {
+                StatusCode::BAD_GATEWAY
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Machine-readable code for the error envelope
+    pub fn code(&self) -> &'static str {
+        match self {
+            CwHoError::Config(_) | CwHoError::HoError(HoError::Config(_)) => "CONFIG_ERROR",
+            CwHoError::Storage(_) | CwHoError::HoError(HoError::Storage(_)) => "STORAGE_ERROR",
+            CwHoError::StorageLocked { .. } => "STORAGE_LOCKED",
+            CwHoError::CommonwareNetworkError(_) => "NETWORK_ERROR",
+            CwHoError::Http(e) if e.is_timeout() => "PROVIDER_TIMEOUT",
+            CwHoError::HoError(HoError::Timeout(_)) => "PROVIDER_TIMEOUT",
+            CwHoError::Http(_) | CwHoError::HoError(HoError::Network(_)) => "PROVIDER_UNREACHABLE",
+            CwHoError::LlmEntity(_) | CwHoError::HoError(HoError::Llm(_)) => "LLM_ERROR",
+            CwHoError::RateLimited { .. } => "RATE_LIMITED",
+            CwHoError::ProviderHttp { .. } => "PROVIDER_ERROR",
+            CwHoError::Auth { .. } => "PROVIDER_AUTH",
+            CwHoError::EmptyCompletion { .. } => "EMPTY_COMPLETION",
+            CwHoError::NoHealthyProvider => "NO_HEALTHY_PROVIDER",
+            CwHoError::DeadlineExceeded { .. } => "DEADLINE_EXCEEDED",
+            CwHoError::FallbackExhausted { .. } => "FALLBACK_EXHAUSTED",
+            CwHoError::DuplicateNode(_) => "DUPLICATE_NODE",
+            CwHoError::InvalidRequest(_) => "INVALID_REQUEST",
+            CwHoError::Cancelled(_) => "CANCELLED",
+            CwHoError::NoCustomHandler(_) => "NO_CUSTOM_HANDLER",
+            _ => "INTERNAL_ERROR",
+        }
+    }
+}
+
+impl From<CwHoError> for ApiError {
+    fn from(error: CwHoError) -> Self {
+        ApiError::new(error.status(), error.code(), error.to_string()).with_hint(error.hint())
+    }
 }
 
-/// Helper function to create error JSON responses
-pub fn error_json(message: &str, code: &str) -> serde_json::Value {
-    serde_json::json!({
-        "error": message,
-        "code": code,
-        "timestamp": chrono::Utc::now()
-    })
+impl IntoResponse for CwHoError {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
+    }
 }
 
-/// Helper function to create API error responses
-pub fn api_error(status: StatusCode, message: &str, code: &str) -> Json<serde_json::Value> {
-    Json(error_json(
-        message,
-        &format!("code:{},status: {}", code, status),
-    ))
+/// Hint of the first [`CwHoError`] in `error`'s chain
+pub fn error_hint(error: &anyhow::Error) -> Option<String> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<CwHoError>())
+        .and_then(CwHoError::hint)
 }
 
-/// Helper function to create error responses
-pub fn error_response(status: StatusCode, message: &str, code: &str) -> Json<serde_json::Value> {
-    Json(error_json(
-        message,
-        &format!("code:{},status: {}", code, status),
-    ))
+/// `error` and its chain, followed by its [`error_hint`]
+pub fn render_error(error: &anyhow::Error) -> String {
+    match error_hint(error) {
+        Some(hint) => format!("{:#}\nhint: {}", error, hint),
+        None => format!("{:#}", error),
+    }
 }
@@ -0,0 +1,87 @@
+//! `cw-ho export`: prompts stored by the running node, as ndjson `/prompts/import` accepts
+use crate::{load_config, OutputFormat};
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use ho_std::commonware::identity::NodePrivKey;
+use ho_std::prelude::*;
+use ho_std::routes::auth::signed_headers;
+use ho_std::storage::parse_time_bound;
+use ho_std::traits::{HoConfigTrait, NodeIdentityTrait};
+
+/// Most prompts a single query returns; the node caps queries at this many
+const EXPORT_LIMIT: u32 = 1000;
+
+#[derive(Debug, clap::Parser)]
+pub struct ExportCmd {
+    /// Only prompts newer than this: an age such as 24h or 7d, or an RFC 3339 time
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Only prompts older than this, in the same forms as --since
+    #[arg(long)]
+    pub until: Option<String>,
+    /// Base url of the node's API [default: http:// and the configured api address]
+    #[arg(long)]
+    pub url: Option<String>,
+}
+
+impl ExportCmd {
+    pub fn exec(&self, home: &Utf8Path, output: OutputFormat) -> Result<()> {
+        // Reject malformed bounds before reaching out to the node
+        let now = chrono::Utc::now();
+        for bound in [&self.since, &self.until].into_iter().flatten() {
+            parse_time_bound(bound, now)?;
+        }
+
+        let config = load_config(home)?;
+        let identity = config.identity();
+        let key = identity
+            .private_key
+            .as_deref()
+            .and_then(NodePrivKey::from_bytes)
+            .context("node identity has no private key to sign the request with")?;
+        let url = match &self.url {
+            Some(url) => format!("{}/api/prompts", url.trim_end_matches('/')),
+            None => format!("http://{}/api/prompts", identity.api_address()),
+        };
+        let limit = EXPORT_LIMIT.to_string();
+        let mut params = vec![("limit", limit.as_str())];
+        params.extend(self.since.as_deref().map(|since| ("since", since)));
+        params.extend(self.until.as_deref().map(|until| ("until", until)));
+
+        let timestamp = now.timestamp() as u64;
+        let body: serde_json::Value = tokio::runtime::Runtime::new()?.block_on(async {
+            let mut request = reqwest::Client::new().get(&url).query(&params);
+            for (name, value) in signed_headers(&key, &[], timestamp) {
+                request = request.header(name, value);
+            }
+            let response = request
+                .send()
+                .await
+                .with_context(|| format!("Failed to reach {}", url))?
+                .error_for_status()?;
+            anyhow::Ok(response.json().await?)
+        })?;
+        if let Some(error) = body.get("error").and_then(|e| e.as_str()) {
+            anyhow::bail!("{}", error);
+        }
+        let prompts: Vec<PromptResponse> = serde_json::from_value(body)?;
+        // A full page means the node may hold more prompts than it returned
+        let truncated = prompts.len() >= EXPORT_LIMIT as usize;
+        if truncated {
+            tracing::warn!(
+                "✂️ Export stopped at the node's limit of {} prompts, narrow --since/--until for the rest",
+                EXPORT_LIMIT
+            );
+        }
+
+        let lines = prompts
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        output.emit(
+            &lines,
+            serde_json::json!({ "command": "export", "prompts": prompts, "truncated": truncated }),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! `cw-ho gc`: prune old prompts and compact storage now, instead of waiting for the node
+use crate::{load_config, CwHoStorage, OutputFormat};
+use anyhow::{bail, Context, Result};
+use camino::Utf8Path;
+use ho_std::prelude::GcReport;
+use ho_std::storage::parse_time_bound;
+use ho_std::traits::HoConfigTrait;
+use std::io::{IsTerminal as _, Write as _};
+
+#[derive(Debug, clap::Parser)]
+pub struct GcCmd {
+    /// Delete prompts older than this age, such as 12h or 30d
+    #[arg(long, value_parser = parse_max_age)]
+    pub max_age: Option<chrono::Duration>,
+    /// Then delete the oldest prompts until they fit in this many megabytes
+    #[arg(long)]
+    pub target_size_mb: Option<u32>,
+    /// Delete without asking for confirmation
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+impl GcCmd {
+    pub fn exec(&self, home: &Utf8Path, output: OutputFormat) -> Result<()> {
+        let config = load_config(home)?;
+        let data_dir = &config.storage().data_dir;
+        if !self.yes && !confirm(&format!("Prune and compact the storage at {}?", data_dir))? {
+            bail!("gc cancelled");
+        }
+
+        let cutoff = self.max_age.map(|age| chrono::Utc::now() - age);
+        let target_bytes = self.target_size_mb.map(|mb| mb as u64 * 1024 * 1024);
+        let report = tokio::runtime::Runtime::new()?.block_on(async {
+            let storage = CwHoStorage::from_config(config.storage())
+                .await
+                .context("Failed to open storage; stop the node before running gc")?;
+            let report = run_gc(&storage, cutoff, target_bytes).await;
+            storage.close().await;
+            report
+        })?;
+
+        output.emit(
+            &[format!(
+                "pruned {} prompts, compacted {} records, reclaimed {} bytes",
+                report.prompts_pruned, report.records_compacted, report.bytes_reclaimed
+            )],
+            serde_json::json!({ "command": "gc", "report": report }),
+        );
+        Ok(())
+    }
+}
+
+/// Prune the prompts stored before `cutoff` and down to `target_bytes`, then compact
+pub async fn run_gc(
+    storage: &CwHoStorage,
+    cutoff: Option<chrono::DateTime<chrono::Utc>>,
+    target_bytes: Option<u64>,
+) -> Result<GcReport> {
+    let before = storage.stored_bytes().await?;
+    let prompts_pruned = storage.prune_storage(cutoff, target_bytes).await?;
+    let records_compacted = storage.compact().await?;
+    let after = storage.stored_bytes().await?;
+    Ok(GcReport {
+        prompts_pruned,
+        records_compacted,
+        bytes_reclaimed: before.saturating_sub(after),
+    })
+}
+
+/// `--max-age` as the age it names, in the forms `--since` takes
+fn parse_max_age(value: &str) -> std::result::Result<chrono::Duration, String> {
+    let now = chrono::Utc::now();
+    let bound = parse_time_bound(value, now).map_err(|e| e.to_string())?;
+    Ok(now - bound)
+}
+
+/// Ask `question` on the terminal. Without one there is nobody to answer, so `--yes` is
+/// required instead.
+fn confirm(question: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        bail!("not a terminal, pass --yes to run gc without confirmation");
+    }
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ho_std::prelude::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_gc_prunes_prompts_past_max_age() {
+        let storage = CwHoStorage::memory().with_prompt_dedup(true);
+        let now = chrono::Utc::now();
+        let prompt = |text: &str, age: chrono::Duration| PromptResponse {
+            id: Uuid::new_v4().as_bytes().to_vec(),
+            prompt: text.to_string(),
+            response: vec![format!("reply to {}", text)],
+            timestamp: Some((now - age).into()),
+            context: Some(PromptContext {
+                session_id: Some("s-1".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        for text in ["first", "second"] {
+            storage
+                .store_prompt(&prompt(text, chrono::Duration::days(40)))
+                .await
+                .unwrap();
+        }
+        let recent = prompt("recent", chrono::Duration::minutes(5));
+        storage.store_prompt(&recent).await.unwrap();
+
+        let report = run_gc(&storage, Some(now - chrono::Duration::days(30)), None)
+            .await
+            .unwrap();
+        assert_eq!(report.prompts_pruned, 2);
+        // the two old prompt bodies no reference uses any more
+        assert_eq!(report.records_compacted, 2);
+        assert!(report.bytes_reclaimed > 0);
+
+        let left = storage
+            .query_prompts(&QueryRequest {
+                session_id: Some("s-1".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, recent.id);
+        assert_eq!(left[0].prompt, "recent");
+        assert_eq!(storage.metrics().await.unwrap().prompt_bodies, Some(1));
+
+        // Nothing else is old enough
+        let again = run_gc(&storage, Some(now - chrono::Duration::days(30)), None)
+            .await
+            .unwrap();
+        assert_eq!(again, GcReport::default());
+    }
+}
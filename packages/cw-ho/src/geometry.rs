@@ -0,0 +1,186 @@
+//! Sacred-geometry diagnostics of a running node, served under `/geometry`
+use ho_std::constants::{
+    FRACTAL_MAX_DEPTH, GOLDEN_RATIO, GOLDEN_RATIO_TOLERANCE, KEPLER_PACKING_DENSITY,
+};
+use ho_std::orchestrate::golden_ratio_allocation_valid;
+use ho_std::prelude::NodeType;
+
+use crate::network::topology::NetworkTopology;
+
+/// Node types that occupy the four tetrahedral vertices
+const VERTEX_TYPES: [NodeType; 4] = [
+    NodeType::Coordinator,
+    NodeType::Executor,
+    NodeType::Referee,
+    NodeType::Development,
+];
+
+/// Check the geometric invariants against the current topology. `storage_error` is the
+/// failure of the storage health check, if it failed.
+pub fn validate_sacred_geometry(
+    topology: &NetworkTopology,
+    storage_error: Option<&str>,
+) -> serde_json::Value {
+    // Resources are split 1/φ to the fast partition, the remainder to the slow one
+    let fast = 1.0 / GOLDEN_RATIO;
+    let golden_ratio_valid =
+        golden_ratio_allocation_valid(fast, 1.0 - fast, GOLDEN_RATIO_TOLERANCE);
+
+    let online_vertices: Vec<&str> = VERTEX_TYPES
+        .iter()
+        .filter(|vertex| topology.nearest_node_of_type(**vertex).is_some())
+        .map(|vertex| vertex.as_str_name())
+        .collect();
+    let tetrahedral_valid = topology.is_complete_tetrahedron();
+
+    let overall_validity = golden_ratio_valid && tetrahedral_valid && storage_error.is_none();
+    serde_json::json!({
+        "sacred_geometry_validation": "complete",
+        "overall_validity": overall_validity,
+        "validations": {
+            "golden_ratio_allocation": {
+                "valid": golden_ratio_valid,
+                "expected_ratio": GOLDEN_RATIO,
+                "description": "61.8% fast partition, 38.2% slow partition"
+            },
+            "tetrahedral_connectivity": {
+                "valid": tetrahedral_valid,
+                "vertices": online_vertices,
+                "coverage": online_vertices.len() as f64 / VERTEX_TYPES.len() as f64,
+                "description": "Four-vertex sacred geometric topology"
+            },
+            "storage_geometry": {
+                "connectivity": {
+                    "accessible": storage_error.is_none(),
+                    "error": storage_error,
+                },
+                "kepler_packing": {
+                    "theoretical_density": KEPLER_PACKING_DENSITY,
+                    "description": "Optimal sphere packing density for compression"
+                },
+                "fractal_depth": {
+                    "max_depth": FRACTAL_MAX_DEPTH,
+                    "scaling_factor": GOLDEN_RATIO,
+                    "description": "Fractal expansion with φⁿ scaling"
+                }
+            }
+        },
+        "timestamp": chrono::Utc::now(),
+        "cosmic_harmony": overall_validity
+    })
+}
+
+/// [`validate_sacred_geometry`] alongside how the topology's nodes fill the vertices
+pub fn sacred_geometry_metrics(
+    node_id: &str,
+    topology: &NetworkTopology,
+    storage_error: Option<&str>,
+) -> serde_json::Value {
+    let stats = topology.stats();
+    let distribution: serde_json::Map<String, serde_json::Value> = VERTEX_TYPES
+        .iter()
+        .map(|vertex| {
+            let nodes = topology.nodes_by_type(*vertex);
+            (
+                vertex.as_str_name().to_string(),
+                serde_json::json!({
+                    "nodes": nodes.len(),
+                    "online": nodes.iter().filter(|node| node.online).count(),
+                }),
+            )
+        })
+        .collect();
+
+    serde_json::json!({
+        "sacred_geometry_health": validate_sacred_geometry(topology, storage_error),
+        "topology_metrics": {
+            "total_nodes": stats.total_nodes,
+            "online_nodes": stats.online_nodes,
+            "total_connections": stats.total_connections,
+            "tetrahedral_distribution": distribution,
+        },
+        "golden_ratio_constant": GOLDEN_RATIO,
+        "node_id": node_id,
+        "timestamp": chrono::Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ho_std::prelude::NodeInfo;
+
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_geometry_reports_expected_keys() {
+        let mut topology = NetworkTopology::new();
+        topology.add_node(NodeInfo {
+            node_id: "exec".to_string(),
+            node_type: NodeType::Executor.as_str_name().to_string(),
+            online: true,
+            last_seen: 0,
+            capabilities: Vec::new(),
+        });
+
+        let validation = validate_sacred_geometry(&topology, None);
+        assert_eq!(
+            keys(&validation),
+            [
+                "cosmic_harmony",
+                "overall_validity",
+                "sacred_geometry_validation",
+                "timestamp",
+                "validations"
+            ]
+        );
+        assert_eq!(
+            keys(&validation["validations"]),
+            [
+                "golden_ratio_allocation",
+                "storage_geometry",
+                "tetrahedral_connectivity"
+            ]
+        );
+        // One vertex online is no tetrahedron
+        assert_eq!(validation["overall_validity"], false);
+        assert_eq!(
+            validation["validations"]["tetrahedral_connectivity"]["coverage"],
+            0.25
+        );
+        assert_eq!(
+            validation["validations"]["golden_ratio_allocation"]["valid"],
+            true
+        );
+
+        let metrics = sacred_geometry_metrics("me", &topology, Some("disk gone"));
+        assert_eq!(
+            keys(&metrics),
+            [
+                "golden_ratio_constant",
+                "node_id",
+                "sacred_geometry_health",
+                "timestamp",
+                "topology_metrics"
+            ]
+        );
+        assert_eq!(
+            metrics["topology_metrics"]["tetrahedral_distribution"]["NODE_TYPE_EXECUTOR"]["online"],
+            1
+        );
+        assert_eq!(
+            metrics["sacred_geometry_health"]["validations"]["storage_geometry"]["connectivity"]
+                ["accessible"],
+            false
+        );
+    }
+}
@@ -1,10 +1,13 @@
-use crate::CwHoConfig;
+use crate::{CwHoConfig, OutputFormat};
 use anyhow::{Context, Result};
 use camino::Utf8Path;
 use ho_std::config::api_keys::configure_api_keys_interactive;
-use ho_std::constants::{ENV_VARIABLES_FILE, LLM_API_KEYS_FILE};
+use ho_std::constants::{
+    DEFAULT_LLM_TIMEOUT_SECS, ENV_VARIABLES_FILE, LLM_API_KEYS_FILE, SSH_JSON_PATH,
+};
 use ho_std::traits::DomainType;
 use ho_std::traits::HoConfigTrait;
+use ho_std::transports::ssh::{ssh_config_path, ssh_config_template, validate_ssh_config};
 use ho_std_keys::keys::{SeedPhrase, SpendKey};
 use rand_core::OsRng;
 use std::{env, fs};
@@ -25,10 +28,25 @@ pub enum InitTopSubCmd {
     // configure llm api keys
     // #[clap(flatten)]
     #[clap(display_order = 100)]
-    New {},
+    New {
+        /// Overwrite an existing config
+        #[clap(long, action)]
+        force: bool,
+    },
     // prompt cli helper for guiding through configuring api keys
     #[clap(display_order = 200)]
-    LlmApiKeys {},
+    LlmApiKeys {
+        /// Print the resulting api-keys.json to stdout instead of saving it
+        #[clap(long, action)]
+        dry_run: bool,
+    },
+    // scaffold a template ssh node map for multi-node orchestration
+    #[clap(display_order = 300)]
+    SshNodes {
+        /// Overwrite an existing ssh config
+        #[clap(long, action)]
+        force: bool,
+    },
     // configure
     #[clap(display_order = 900)]
     UnsafeWipe {},
@@ -87,54 +105,137 @@ fn prompt_for_password(msg: &str) -> Result<String> {
 }
 
 impl InitCmd {
-    pub fn init(&self, home_dir: impl AsRef<camino::Utf8Path>) -> Result<()> {
+    pub fn init(&self, home_dir: impl AsRef<camino::Utf8Path>, output: OutputFormat) -> Result<()> {
         let config_path = home_dir.as_ref().join(ho_std::constants::CONFIG_FILE_NAME);
-        let config = match self.subcmd.clone() {
-            InitTopSubCmd::New {} => {
-                let config = CwHoConfig::new(home_dir.as_ref());
+        // human readable lines, and the files written for the json report
+        let mut lines = Vec::new();
+        let mut files = Vec::new();
+        let (subcommand, config) = match self.subcmd.clone() {
+            InitTopSubCmd::New { force } => {
+                refuse_overwrite(&config_path, "Config", force)?;
+                let config = default_config(home_dir.as_ref())?;
+                fs::create_dir_all(home_dir.as_ref()).with_context(|| {
+                    format!("Failed to create directory: {}", home_dir.as_ref())
+                })?;
                 let current = env::current_dir().unwrap();
                 let template_path = camino::Utf8Path::new(current.to_str().unwrap());
                 let output_path = home_dir.as_ref().join(".env");
 
-                println!("{:#?}", output_path);
+                lines.push(format!("{:#?}", output_path));
                 let env_content = fs::read_to_string(template_path.join("templates/example.env"))
                     .expect("Failed to read templates/example.env. Make sure it exists.");
-                std::fs::write(output_path, env_content).expect("Failed to write.");
-                config
+                std::fs::write(&output_path, env_content).expect("Failed to write.");
+                files.push(output_path.to_string());
+                ("new", config)
             }
-            InitTopSubCmd::LlmApiKeys {} => {
+            InitTopSubCmd::LlmApiKeys { dry_run } => {
                 // Run interactive API keys configuration
                 let api_keys_path = home_dir.as_ref().join(LLM_API_KEYS_FILE);
-                configure_api_keys_interactive(&api_keys_path)?;
-                println!("\n✅ API keys configured successfully!");
-                println!("   File: {}", api_keys_path);
-                println!("   Remember to add this file to .gitignore!");
-                CwHoConfig::load(&config_path)?
+                if let Some(json) = configure_api_keys_interactive(&api_keys_path, dry_run)? {
+                    // nothing is written on a dry run, config.toml included
+                    println!("{}", json);
+                    return Ok(());
+                }
+                lines.push("\n✅ API keys configured successfully!".to_string());
+                lines.push(format!("   File: {}", api_keys_path));
+                lines.push("   Remember to add this file to .gitignore!".to_string());
+                files.push(api_keys_path.to_string());
+                ("llm-api-keys", CwHoConfig::load(&config_path)?)
+            }
+            InitTopSubCmd::SshNodes { force } => {
+                // A config that exists but fails to parse is reported, never replaced
+                let config = if config_path.exists() {
+                    CwHoConfig::load(&config_path)?
+                } else {
+                    default_config(home_dir.as_ref())?
+                };
+                let ssh_config_path = ssh_config_path(home_dir.as_ref());
+                write_ssh_config_template(&ssh_config_path, force)?;
+                lines.push("\n✅ SSH node config scaffolded!".to_string());
+                lines.push(format!("   File: {}", ssh_config_path));
+                lines.push("   Replace the placeholder node before running bootstrap.".to_string());
+                files.push(ssh_config_path.to_string());
+                ("ssh-nodes", config)
             }
             InitTopSubCmd::UnsafeWipe {} => {
-                let new_config = self.fresh(home_dir.as_ref());
-                println!("Deleting all data in {}...", home_dir.as_ref());
+                let new_config = default_config(home_dir.as_ref())?;
+                lines.push(format!("Deleting all data in {}...", home_dir.as_ref()));
                 std::fs::remove_dir_all(home_dir.as_ref())?;
-                new_config
+                ("unsafe-wipe", new_config)
             }
             InitTopSubCmd::Migrate {} => {
                 // TODO: implement interface for modular migrations
-                CwHoConfig::load(&config_path)?
+                ("migrate", CwHoConfig::load(&config_path)?)
             }
         };
 
-        println!("Writing generated config to {}", &config_path);
-        config.save(config_path)?;
+        lines.push(format!("Writing generated config to {}", &config_path));
+        config.save(&config_path)?;
+        files.push(config_path.to_string());
 
+        output.emit(
+            &lines,
+            serde_json::json!({
+                "command": "init",
+                "subcommand": subcommand,
+                "files": files,
+            }),
+        );
         Ok(())
     }
+}
 
-    fn fresh(&self, home_dir: impl AsRef<camino::Utf8Path>) -> CwHoConfig {
-        let config = CwHoConfig::new(home_dir.as_ref());
-        // generate default env file in home dir as well
+/// Config written by `init new`: the defaults with an llm timeout set, api keys read from
+/// `home`, and checked by the same validation the node runs at startup
+pub fn default_config(home: &Utf8Path) -> Result<CwHoConfig> {
+    let mut config = CwHoConfig::new(home);
+    config
+        .0
+        .llm
+        .as_mut()
+        .expect("new configs have an llm router")
+        .timeout_seconds = DEFAULT_LLM_TIMEOUT_SECS;
+    config
+        .validate()
+        .context("Generated config failed validation")?;
+    Ok(config)
+}
 
-        config
+/// Fail when `path` exists, unless `force` allows overwriting it
+fn refuse_overwrite(path: &Utf8Path, what: &str, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        anyhow::bail!(
+            "{} already exists at {}; pass --force to overwrite",
+            what,
+            path
+        );
     }
+    Ok(())
+}
+
+/// Write the template ssh node map, validating it and restricting permissions to the owner
+fn write_ssh_config_template(path: &Utf8Path, force: bool) -> Result<()> {
+    refuse_overwrite(path, "SSH config", force)?;
+
+    let template = ssh_config_template();
+    validate_ssh_config(&template).context("SSH config template failed validation")?;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent))?;
+    }
+    let json = serde_json::to_string_pretty(&template).context("Failed to serialize SSH config")?;
+    fs::write(path, json).with_context(|| format!("Failed to write SSH config: {}", path))?;
+
+    // Set restrictive permissions (owner read/write only)
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to set permissions on: {}", path))?;
+    }
+
+    Ok(())
 }
 
 impl SoftKmsInitCmd {
@@ -168,3 +269,75 @@ impl SoftKmsInitCmd {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use ho_std::constants::CONFIG_FILE_NAME;
+
+    #[test]
+    fn test_new_config_is_valid_and_never_silently_overwritten() {
+        let home = camino::Utf8PathBuf::from_path_buf(
+            env::temp_dir().join(format!("cw-ho-init-{}", uuid::Uuid::new_v4())),
+        )
+        .unwrap();
+
+        let config = default_config(&home).unwrap();
+        assert!(config.validate().is_ok());
+        let llm = config.llm();
+        assert_eq!(llm.api_keys_file, home.join(LLM_API_KEYS_FILE).as_str());
+        assert_eq!(llm.timeout_seconds, DEFAULT_LLM_TIMEOUT_SECS);
+        assert_eq!(llm.enabled_entities().len(), 1);
+        assert!(llm
+            .entities
+            .iter()
+            .all(|e| e.timeout_seconds == DEFAULT_LLM_TIMEOUT_SECS));
+
+        // An existing config survives `init new` without --force
+        fs::create_dir_all(&home).unwrap();
+        let config_path = home.join(CONFIG_FILE_NAME);
+        fs::write(&config_path, "# hand tuned\n").unwrap();
+        let init = InitCmd::try_parse_from(["init", "new"]).unwrap();
+        let err = init.init(&home, OutputFormat::Text).unwrap_err();
+        assert!(
+            err.to_string().contains("pass --force to overwrite"),
+            "{}",
+            err
+        );
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), "# hand tuned\n");
+
+        let forced = InitCmd::try_parse_from(["init", "new", "--force"]).unwrap();
+        assert!(matches!(forced.subcmd, InitTopSubCmd::New { force: true }));
+        assert!(refuse_overwrite(&config_path, "Config", true).is_ok());
+
+        fs::remove_dir_all(&home).ok();
+    }
+
+    #[test]
+    fn test_ssh_nodes_keeps_unparseable_config_and_writes_under_home() {
+        let home = camino::Utf8PathBuf::from_path_buf(
+            env::temp_dir().join(format!("cw-ho-init-ssh-{}", uuid::Uuid::new_v4())),
+        )
+        .unwrap();
+        fs::create_dir_all(&home).unwrap();
+        let config_path = home.join(CONFIG_FILE_NAME);
+        fs::write(&config_path, "not = [valid toml").unwrap();
+
+        let init = InitCmd::try_parse_from(["init", "ssh-nodes"]).unwrap();
+        assert!(init.init(&home, OutputFormat::Text).is_err());
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "not = [valid toml"
+        );
+        assert!(!home.join(SSH_JSON_PATH).exists());
+
+        // Without a config, both files land in the config dir
+        fs::remove_file(&config_path).unwrap();
+        init.init(&home, OutputFormat::Text).unwrap();
+        assert!(home.join(SSH_JSON_PATH).exists());
+        assert!(config_path.exists());
+
+        fs::remove_dir_all(&home).ok();
+    }
+}
@@ -1,23 +1,50 @@
+pub mod artifacts;
 pub mod auth;
+pub mod bench;
 pub mod config;
 pub mod error;
+pub mod export;
+pub mod gc;
+pub mod geometry;
 pub mod init;
 pub mod llm;
+pub mod logging;
 pub mod network;
+pub mod peers;
+pub mod profile;
+pub mod replay;
 pub mod server;
+pub mod ssh_nodes;
 pub mod storage;
+pub mod tasks;
+#[cfg(test)]
+mod test_server;
 pub mod traits;
+pub mod wal;
 
 // Re-export the macro for external use
 
 use crate::auth::AuthCmd;
+use crate::bench::BenchCmd;
+use crate::config::ConfigCmd;
+use crate::export::ExportCmd;
+use crate::gc::GcCmd;
 use crate::init::InitCmd;
 use crate::llm::ApiKeys;
-use crate::network::{manager::PeerInfo, topology::NetworkTopology};
+use crate::network::{
+    dispatch::MessageDispatcher,
+    events::NetworkEvents,
+    manager::PeerInfo,
+    queue::{OutboundMessage, PrioritySendQueue},
+    rpc::PendingRequests,
+    topology::NetworkTopology,
+};
+use crate::peers::PeersCmd;
+use crate::profile::Profile;
+use crate::replay::ReplayCmd;
 use crate::server::Server;
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Parser, Subcommand};
-use cnidarium::Storage as CnidariumStorage;
 use commonware_cryptography::ed25519;
 use commonware_p2p::authenticated;
 use commonware_runtime::tokio::Context;
@@ -26,12 +53,13 @@ use commonware_runtime::Runner as _;
 use ho_std::config::env::default_home;
 use ho_std::constants::CONFIG_FILE_NAME;
 use ho_std::prelude::*;
-use ho_std::traits::HoConfigTrait;
+use ho_std::traits::{HoConfigTrait, NodeIdentityTrait};
 use reqwest::Client;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{mpsc, RwLock};
 
@@ -44,14 +72,49 @@ define_wrapper!(CwHoLlmRouterConfig, LlmRouterConfig);
 /// Defines the storage used for this CwHo.
 /// implemenations in ./storage.rs
 pub struct CwHoStorage {
-    cnidarium: CnidariumStorage,
+    backend: crate::storage::RecordStore,
+    /// Directory holding the database and its pid lock, `None` for in-memory storage
+    data_dir: Option<std::path::PathBuf>,
+    /// Compression ratio achieved by the latest snapshot
+    snapshot_ratio: std::sync::Mutex<Option<f64>>,
+    /// Store prompt texts once under their digest instead of inline in every record
+    dedupe_prompts: bool,
+    /// Logs each commit before it reaches the store, `None` for in-memory storage
+    wal: Option<Arc<crate::wal::WriteAheadLog>>,
+    /// What replaying the write-ahead log did when the store was opened
+    recovery: WalRecovery,
+    /// Held by a conversation append from reading where it starts until it commits
+    conversation_writes: tokio::sync::Mutex<()>,
 }
 
 /// Defines the Llm router used for this CwHo
 pub struct LlmRouter {
     client: Client,
+    /// Transport for provider requests, reqwest unless replaced with `with_backend`
+    backend: Arc<dyn ho_std::traits::HttpBackend>,
     api_keys: ApiKeys,
-    config: LlmRouterConfig,
+    /// Entities and selection settings, changed live by `add_entity` and friends
+    config: std::sync::RwLock<LlmRouterConfig>,
+    /// Per-entity concurrency limits, keyed by entity name
+    limits: std::sync::RwLock<HashMap<String, Arc<tokio::sync::Semaphore>>>,
+    /// Queue wait statistics per entity
+    queue_metrics: std::sync::Mutex<HashMap<String, ProviderQueueMetrics>>,
+    /// Token size and latency histograms of answered requests; `queues` is left empty
+    request_metrics: std::sync::Mutex<LlmRouterMetrics>,
+    /// Pre-flight results per entity, filled by `warm_up`
+    readiness: std::sync::RwLock<HashMap<String, ProviderReadiness>>,
+    /// Moderation pass over outgoing prompts and incoming responses
+    moderation: Arc<dyn ho_std::traits::ModerationHook>,
+    /// Answer prompts locally instead of calling providers
+    mock: bool,
+    /// Hosts provider requests may be sent to
+    egress: std::sync::RwLock<EgressPolicy>,
+    /// Selections made so far, drives round-robin and golden-ratio strategies
+    selections: std::sync::atomic::AtomicUsize,
+    /// Randomness behind weighted selection, from entropy unless fixed with `with_seed`
+    rng: std::sync::Mutex<rand::rngs::StdRng>,
+    /// Where the golden-ratio walk starts, drawn from `rng` on first use
+    golden_start: std::sync::OnceLock<f64>,
 }
 
 /// Minimal network manager for cw-ho/
@@ -64,12 +127,33 @@ pub struct CwHoNetworkManifold {
     channel_senders: HashMap<u8, authenticated::lookup::Sender<ed25519::PublicKey>>,
     /// Channel receivers for different message types
     channel_receivers: HashMap<u8, authenticated::lookup::Receiver<ed25519::PublicKey>>,
+    /// Outbound messages awaiting the send worker, highest priority first
+    send_queue: Arc<std::sync::Mutex<PrioritySendQueue<OutboundMessage>>>,
+    /// Wakes the send worker when messages are queued
+    send_ready: Arc<tokio::sync::Notify>,
     /// Connected peers
     peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>>,
     /// Network topology
     topology: Arc<RwLock<NetworkTopology>>,
-    /// Event sender for network events
-    event_tx: mpsc::UnboundedSender<NetworkEvent>,
+    /// Handlers for verified inbound messages
+    dispatcher: Arc<RwLock<MessageDispatcher>>,
+    /// Requests sent to peers awaiting their response
+    pending_requests: Arc<PendingRequests>,
+    /// Silence after which a node is marked offline
+    node_stale_after: Duration,
+    /// Peers admitted on their first message, set from the network config on start
+    peer_limit: Arc<std::sync::RwLock<network::dispatch::PeerLimit>>,
+    /// Which gossiped messages were relayed already
+    gossip: Arc<network::gossip::Gossip>,
+    /// Relays to pass on, drained by the gossip forwarder
+    gossip_tx: mpsc::UnboundedSender<network::gossip::Forward>,
+    gossip_rx: Option<mpsc::UnboundedReceiver<network::gossip::Forward>>,
+    /// Peers the handlers or the peer limit cut off, their connections closed by the
+    /// p2p oracle once the network runs
+    disconnect_tx: mpsc::UnboundedSender<ed25519::PublicKey>,
+    disconnect_rx: Option<mpsc::UnboundedReceiver<ed25519::PublicKey>>,
+    /// Event sender for network events, also feeding live topology streams
+    event_tx: NetworkEvents,
     /// Event receiver
     event_rx: Option<mpsc::UnboundedReceiver<NetworkEvent>>,
     /// Shutdown signal
@@ -85,6 +169,16 @@ pub struct AppState {
     pub network_manifold: Arc<tokio::sync::Mutex<CwHoNetworkManifold>>,
     pub start_time: Instant,
     pub config: CwHoConfig,
+    /// python3 and tool scripts found at startup, gating Python-dependent routes
+    pub python: ho_std::python::capabilities::PythonCapabilities,
+    /// Config file runtime changes such as the default LLM entity are saved to,
+    /// `None` to keep them in memory only
+    pub config_path: Option<Utf8PathBuf>,
+    /// Home directory the node's files beside its config, such as the SSH node map,
+    /// are read from
+    pub home: Utf8PathBuf,
+    /// Runs submitted tasks, cancelled when the server shuts down
+    pub tasks: Arc<tasks::TaskRunner>,
 }
 
 #[derive(Parser)]
@@ -98,9 +192,63 @@ pub struct Cli {
     #[clap(long, default_value_t = default_home(), env = "NODE_DATA_PATH")]
     pub home: Utf8PathBuf,
 
-    /// Log level
-    #[arg(long, default_value = "info")]
-    pub log_level: String,
+    /// Log level [default: info, or debug with `--profile dev`]
+    #[arg(long)]
+    pub log_level: Option<String>,
+
+    /// Bundle of defaults applied to unset config fields
+    #[arg(long, value_enum, global = true)]
+    pub profile: Option<Profile>,
+
+    /// Output format for command results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub output: OutputFormat,
+}
+
+/// How command results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// A single JSON object per command, for scripting with jq
+    Json,
+}
+
+impl OutputFormat {
+    /// Print a command result: the text lines, or the structured report as json
+    pub fn emit(self, lines: &[String], report: serde_json::Value) {
+        match self {
+            OutputFormat::Text => lines.iter().for_each(|l| println!("{}", l)),
+            OutputFormat::Json => println!("{}", report),
+        }
+    }
+
+    /// Print a failed command with guidance for known provider failures,
+    /// json mode gets an `{"error": ..., "hint": ...}` object
+    pub fn emit_error(self, error: &anyhow::Error) {
+        match self {
+            OutputFormat::Text => eprintln!("Error: {}", error::render_error(error)),
+            OutputFormat::Json => {
+                let mut report = serde_json::json!({ "error": format!("{:#}", error) });
+                if let Some(hint) = error::error_hint(error) {
+                    report["hint"] = hint.into();
+                }
+                println!("{}", report)
+            }
+        }
+    }
+}
+
+impl Cli {
+    /// Requested log level, falling back to the profile's default
+    pub fn log_level(&self) -> &str {
+        match (&self.log_level, self.profile) {
+            (Some(level), _) => level,
+            (None, Some(profile)) => profile.log_level(),
+            (None, None) => "info",
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -110,36 +258,135 @@ pub enum Commands {
         /// HTTP server port (overrides config)
         #[arg(short, long)]
         port: Option<u16>,
+        /// IP address the HTTP server binds to, e.g. `0.0.0.0` in a container.
+        /// Overrides `network.listen_address` [precedence: flag > BIND_ADDRESS env > config]
+        #[arg(long, env = "BIND_ADDRESS")]
+        bind: Option<IpAddr>,
+        /// Keep storage in memory instead of the configured data dir (testing only)
+        #[arg(long)]
+        ephemeral: bool,
+        #[command(flatten)]
+        runtime: RuntimeArgs,
     },
     /// Generate a sample configuration file
     Init(InitCmd),
     /// register/revoke
     ManageAuth(AuthCmd),
+    /// Measure latency, throughput and cost of the configured LLM providers
+    Bench(BenchCmd),
+    /// List the running node's peers with their protocol version and round-trip time
+    Peers(PeersCmd),
+    /// Print the running node's stored prompts as ndjson, optionally within a time range
+    Export(ExportCmd),
+    /// Inspect the node's configuration
+    Config(ConfigCmd),
+    /// Re-run a stored task's prompt and diff the result against the original
+    Replay(ReplayCmd),
+    /// Prune old prompts and compact storage now; the node must be stopped
+    Gc(GcCmd),
+}
+
+/// Load the config under `home`, rejecting values that would otherwise panic at startup
+pub fn load_config(home: &Utf8Path) -> Result<CwHoConfig> {
+    let config = CwHoConfig::load(home.join(CONFIG_FILE_NAME))?;
+    config.identity().checked_api_port()?;
+    config.llm().validate_entities()?;
+    Ok(config)
+}
+
+/// Thread pools of the node runtime, overriding `worker_threads`/`max_blocking_threads`
+#[derive(Debug, Clone, Copy, Default, clap::Args)]
+pub struct RuntimeArgs {
+    /// Tokio worker threads [default: config, else the runtime default]. One per core suits
+    /// CPU-bound fractal and embedding work; IO-bound nodes need fewer
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Most threads the blocking pool may grow to [default: config, else the runtime default]
+    #[arg(long)]
+    pub blocking_threads: Option<usize>,
+}
+
+impl RuntimeArgs {
+    /// Runtime config with these thread counts, or the config's where unset. Zero is
+    /// rejected; more workers than available cores only warns, as IO-bound nodes may want it.
+    pub fn runtime_config(&self, config: &HoConfig) -> Result<RuntimeConfig> {
+        let mut runtime = RuntimeConfig::new();
+        let threads = self.threads.or(config.worker_threads.map(|n| n as usize));
+        if let Some(threads) = threads {
+            if threads == 0 {
+                anyhow::bail!("worker threads must be at least 1");
+            }
+            let cores = std::thread::available_parallelism().map_or(1, usize::from);
+            if threads > cores {
+                warn!(
+                    "⚠️ {} worker threads requested but only {} cores are available",
+                    threads, cores
+                );
+            }
+            runtime = runtime.with_worker_threads(threads);
+        }
+        let blocking = self
+            .blocking_threads
+            .or(config.max_blocking_threads.map(|n| n as usize));
+        if let Some(blocking) = blocking {
+            if blocking == 0 {
+                anyhow::bail!("blocking threads must be at least 1");
+            }
+            runtime = runtime.with_max_blocking_threads(blocking);
+        }
+        Ok(runtime)
+    }
 }
 
-pub fn start(cli: Cli, port: Option<u16>) -> Result<()> {
+/// Point the HTTP server at `bind` instead of the configured `network.listen_address`
+pub fn apply_bind(config: &mut CwHoConfig, bind: Option<IpAddr>) {
+    if let (Some(bind), Some(network)) = (bind, config.0.network.as_mut()) {
+        network.listen_address = bind.to_string();
+    }
+}
+
+pub fn start(
+    cli: Cli,
+    port: Option<u16>,
+    bind: Option<IpAddr>,
+    ephemeral: bool,
+    runtime: RuntimeArgs,
+) -> Result<()> {
     info!("🚀 Starting CW-AGENT Minimal Prompt Capture Service");
-    let path = cli.home.as_path().join(CONFIG_FILE_NAME);
     // Load configuration
-    let config = CwHoConfig::load(&path)?;
+    let mut config = load_config(&cli.home)?;
+    if let Some(profile) = cli.profile {
+        profile.apply(&mut config)?;
+        info!("🧰 Applied {:?} profile defaults", profile);
+    }
+    apply_bind(&mut config, bind);
 
     // Override port if provided
-    let server_port = port.unwrap_or(config.identity().api_port.try_into().unwrap());
+    let server_port = match port {
+        Some(port) => port,
+        None => config.identity().checked_api_port()?,
+    };
     info!(
-        "🔌 Server will listen on port {}\n
+        "🔌 Server will listen on {} port {}\n
         💾 Data directory: {}\n",
+        config.network().listen_address,
         server_port,
         config.storage().data_dir
     );
 
     // Create commonware runtime configuration
-    let runtime_config = RuntimeConfig::default();
+    let runtime_config = runtime.runtime_config(&config)?;
+    info!(
+        "🧵 Runtime with {} worker threads",
+        runtime_config.worker_threads()
+    );
     let runner = Runner::new(runtime_config);
 
+    let config_path = cli.home.join(CONFIG_FILE_NAME);
     info!("🌐 Starting within commonware runtime context");
     runner.start(|context| async move {
-        let server = match Server::new(config.clone(), context).await {
-            Ok(s) => s,
+        let server = match Server::new(config.clone(), &cli.home, context, ephemeral).await {
+            Ok(s) => s.with_config_path(config_path),
             Err(e) => {
                 error!("❌ Failed to initialize server: {}", e);
                 return;
@@ -151,3 +398,83 @@ pub fn start(cli: Cli, port: Option<u16>) -> Result<()> {
     });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_of_range_api_port_fails_cleanly() {
+        let home = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("cw-ho-port-{}", uuid::Uuid::new_v4())),
+        )
+        .unwrap();
+        std::fs::create_dir_all(&home).unwrap();
+        let mut config = CwHoConfig::new(&home);
+        config.0.identity.as_mut().unwrap().api_port = 70000;
+        config.save(home.join(CONFIG_FILE_NAME)).unwrap();
+
+        let err = load_config(&home)
+            .err()
+            .expect("port 70000 must be rejected");
+        assert!(err
+            .to_string()
+            .contains("api_port 70000 is out of range for u16"));
+
+        config.0.identity.as_mut().unwrap().api_port = 8080;
+        config.save(home.join(CONFIG_FILE_NAME)).unwrap();
+        assert!(load_config(&home).is_ok());
+        std::fs::remove_dir_all(home).ok();
+    }
+
+    #[test]
+    fn test_bind_flag_overrides_configured_listen_address() {
+        let bind = |args: &[&str]| {
+            let cli = Cli::try_parse_from([&["cw-ho", "start"][..], args].concat())?;
+            match cli.command {
+                Commands::Start { bind, .. } => Ok(bind),
+                _ => unreachable!(),
+            }
+        };
+        let mut config = CwHoConfig::new(Utf8Path::new("/tmp/cw-ho-bind"));
+        config.0.network.as_mut().unwrap().listen_address = "127.0.0.1".to_string();
+
+        apply_bind(&mut config, bind(&["--bind", "0.0.0.0"]).unwrap());
+        assert_eq!(config.network().listen_address, "0.0.0.0");
+        assert!(bind(&["--bind", "not-an-ip"]).is_err());
+    }
+
+    #[test]
+    fn test_threads_flag_sets_runtime_workers() {
+        let runtime = |args: &[&str]| {
+            let cli = Cli::try_parse_from([&["cw-ho", "start"][..], args].concat()).unwrap();
+            match cli.command {
+                Commands::Start { runtime, .. } => runtime,
+                _ => unreachable!(),
+            }
+        };
+        let mut config = HoConfig {
+            worker_threads: Some(6),
+            ..Default::default()
+        };
+
+        let flagged = runtime(&["--threads", "2", "--blocking-threads", "8"]);
+        let runtime_config = flagged.runtime_config(&config).unwrap();
+        assert_eq!(runtime_config.worker_threads(), 2);
+        assert_eq!(runtime_config.max_blocking_threads(), 8);
+
+        // Without the flag the config decides, and zero is refused either way
+        assert_eq!(
+            runtime(&[])
+                .runtime_config(&config)
+                .unwrap()
+                .worker_threads(),
+            6
+        );
+        config.worker_threads = Some(0);
+        assert!(runtime(&[]).runtime_config(&config).is_err());
+        assert!(runtime(&["--threads", "0"])
+            .runtime_config(&HoConfig::default())
+            .is_err());
+    }
+}
@@ -1,15 +1,186 @@
 use crate::error::{CwHoError, Result};
-use crate::LlmRouter;
+use crate::{CwHoStorage, LlmRouter};
 use chrono::DateTime;
 use commonware_cryptography::{blake3, Hasher};
 use ho_std::constants::*;
-use ho_std::llm::CostCalculator;
+use ho_std::llm::{
+    build_client, normalize_usage, resolve_api_key, tokenizer_for, trim_history, CostCalculator,
+    KeywordFilter, NoopModeration, ReqwestBackend,
+};
 use ho_std::orchestrate::*;
-use ho_std::traits::MessageExt;
-use pbjson_types::Timestamp;
-use reqwest::Client;
-use std::time::Duration;
-use tracing::{error, warn};
+use ho_std::traits::{HttpBackend, LlmModelTrait, MessageExt, ModerationHook, Tokenizer};
+use ho_std::utils::backoff::{retry_async, ExponentialBackoff};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, error, info, warn};
+
+/// Upper bound for a single warm-up request
+const WARM_UP_TIMEOUT_SECS: u64 = 30;
+/// Longest Retry-After delay honored before the next attempt
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+/// Backoff after a 429 that carries no Retry-After header, doubled per attempt
+const RATE_LIMIT_BACKOFF_MS: u64 = 1_000;
+/// Largest fraction randomly taken off that backoff, so limited clients spread out
+const RATE_LIMIT_JITTER: f64 = 0.25;
+/// Least budget left for the fallback chain to start another provider
+const MIN_FALLBACK_ATTEMPT_MS: u64 = 500;
+
+/// How a pre-flight outcome reads on `/health`: anything other than a rejected key or
+/// missing settings means the provider could not be used as configured
+fn provider_health(result: &Result<()>) -> ProviderHealth {
+    match result {
+        Ok(()) => ProviderHealth::Ready,
+        Err(CwHoError::Auth { .. }) => ProviderHealth::AuthError,
+        Err(CwHoError::Config(_)) => ProviderHealth::Misconfigured,
+        Err(_) => ProviderHealth::Unreachable,
+    }
+}
+
+/// Models `entity` serves: its default model first, then the rest of its list
+fn entity_models(entity: &LlmEntity) -> impl Iterator<Item = &String> {
+    std::iter::once(&entity.default_model)
+        .filter(|model| !model.is_empty())
+        .chain(
+            entity
+                .models
+                .iter()
+                .filter(move |model| **model != entity.default_model),
+        )
+}
+
+/// Entity requests naming no provider go to: the default entity while the config sets one
+/// and it is enabled, else the highest-priority enabled one. `None` when nothing is enabled.
+fn effective_default(config: &LlmRouterConfig) -> Option<&LlmEntity> {
+    let mut enabled = config.enabled_entities();
+    let default = config.configured_default();
+    if let Some(entity) =
+        default.and_then(|model| enabled.iter().find(|e| e.name == model.as_str_name()))
+    {
+        return Some(*entity);
+    }
+    // stable sort keeps config order between equal priorities
+    enabled.sort_by(|a, b| b.priority.cmp(&a.priority));
+    enabled.first().copied()
+}
+
+/// The default entity when it is configured but disabled
+fn disabled_default(config: &LlmRouterConfig) -> Option<&LlmEntity> {
+    let default = config.configured_default()?;
+    config
+        .entities
+        .iter()
+        .find(|e| e.name == default.as_str_name() && !e.enabled)
+}
+
+/// Index of the slot `point`, in `[0, 1)`, falls in when `weights` are laid end to end
+fn weighted_slot(weights: &[u64], point: f64) -> usize {
+    let mut target = (point * weights.iter().sum::<u64>() as f64) as u64;
+    for (i, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return i;
+        }
+        target -= weight;
+    }
+    weights.len() - 1
+}
+
+/// Delay requested by a `Retry-After` header, in either delta-seconds or HTTP-date form
+fn parse_retry_after(response: &HttpResponse) -> Option<Duration> {
+    let value = response
+        .header(reqwest::header::RETRY_AFTER.as_str())?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means we may retry immediately
+    Some(
+        (at.to_utc() - chrono::Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+fn missing_api_key(provider: LlmModel) -> CwHoError {
+    CwHoError::Auth {
+        provider: provider.as_str_name().to_string(),
+        message: "no API key configured".to_string(),
+    }
+}
+
+/// Message contents of an OpenAI-compatible completion body. A body without choices
+/// surfaces the provider's `error` object, or an empty-completion error if it has none.
+fn completion_contents(provider: &str, body: serde_json::Value) -> Result<Vec<String>> {
+    let has_choices = body
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .is_some_and(|c| !c.is_empty());
+    if !has_choices {
+        let message = body.get("error").map(|e| match e.get("message") {
+            Some(serde_json::Value::String(msg)) => msg.clone(),
+            _ => e
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| e.to_string()),
+        });
+        return Err(match message {
+            Some(message) => CwHoError::ProviderHttp {
+                provider: provider.to_string(),
+                message,
+            },
+            None => CwHoError::EmptyCompletion {
+                provider: provider.to_string(),
+            },
+        });
+    }
+
+    let response: OpenAiResponse = serde_json::from_value(body)?;
+    let content: Vec<String> = response
+        .choices
+        .into_iter()
+        .filter_map(|c| c.message.map(|m| m.content))
+        .collect();
+    match content.is_empty() {
+        true => Err(CwHoError::EmptyCompletion {
+            provider: provider.to_string(),
+        }),
+        false => Ok(content),
+    }
+}
+
+/// `provider`'s answer to `req`, billed by its reported `usage` and stamped as received now
+fn provider_response(
+    provider: LlmModel,
+    req: &PromptRequest,
+    content: Vec<String>,
+    usage: TokenUsage,
+    latency: Duration,
+) -> PromptResponse {
+    let key = provider.provider_key();
+    let encoded = req.to_bytes().expect("encoding into a Vec never fails");
+    PromptResponse {
+        id: uuid::Uuid::new_v4().as_bytes().to_vec(),
+        provider: key.to_string(),
+        model: req.model.to_string(),
+        prompt: blake3::Blake3::hash(&encoded).to_string(),
+        response: content,
+        timestamp: Some(chrono::Utc::now().into()),
+        tokens_used: Some(usage),
+        cost: Some(CostCalculator::calculate_cost(
+            key,
+            &req.model,
+            usage.prompt,
+            usage.completion,
+        )),
+        latency_ms: Some(latency.as_millis() as u64),
+        request_id: None,
+        context: None,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ApiKeys {
@@ -23,58 +194,682 @@ pub struct ApiKeys {
 }
 
 impl LlmRouter {
+    /// Router over `config`'s entities. A config without any entities, e.g. a stripped one,
+    /// gets the local Ollama entity as its only and default entity rather than a router that
+    /// can never answer.
     pub async fn new(config: &LlmRouterConfig) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_seconds))
-            .build()
-            .map_err(|e| CwHoError::Config(format!("Failed to create HTTP client: {}", e)))?;
+        let mut config = config.clone();
+        if config.entities.is_empty() {
+            let fallback = LlmModel::OllamaLocal;
+            warn!(
+                "⚠️ No LLM entities configured, falling back to {}",
+                fallback.as_str_name()
+            );
+            config.entities.push(fallback.default_entity());
+            config.update_default_entity(fallback);
+        } else if config.enabled_entities().is_empty() {
+            warn!("⚠️ Every LLM entity is disabled, requests will fail until one is enabled");
+        } else if let (Some(disabled), Some(fallback)) =
+            (disabled_default(&config), effective_default(&config))
+        {
+            warn!(
+                "⚠️ Default LLM entity {} is disabled, requests naming no provider go to {}",
+                disabled.name, fallback.name
+            );
+        }
+        let config = &config;
+        config.validate_entities()?;
+        let timeout = Duration::from_secs(config.timeout_seconds);
+        let client = build_client(timeout, None)?;
+        // entities behind an internal CA or requiring mTLS get their own client
+        let mut backend = ReqwestBackend::new(client.clone());
+        for entity in config.entities.iter().filter(|e| e.tls.is_some()) {
+            if entity.base_url.is_empty() {
+                warn!(
+                    "⚠️ {} has TLS settings but no base_url, ignoring them",
+                    entity.name
+                );
+                continue;
+            }
+            backend = backend.with_scope(
+                &entity.base_url,
+                build_client(timeout, entity.tls.as_ref())?,
+            )?;
+        }
 
         let api_keys = Self::load_api_keys(&config.api_keys_file).await?;
 
+        let limits = config
+            .entities
+            .iter()
+            .filter_map(|e| Some((e.name.clone(), Self::concurrency_limit(e)?)))
+            .collect();
+
+        let moderation: Arc<dyn ModerationHook> = match &config.moderation {
+            Some(moderation) => Arc::new(KeywordFilter::from(moderation)),
+            None => Arc::new(NoopModeration),
+        };
+
         Ok(Self {
+            backend: Arc::new(backend),
             client,
             api_keys,
-            config: config.clone(),
+            config: std::sync::RwLock::new(config.clone()),
+            limits: std::sync::RwLock::new(limits),
+            queue_metrics: std::sync::Mutex::new(HashMap::new()),
+            request_metrics: std::sync::Mutex::new(LlmRouterMetrics {
+                prompt_tokens: Some(Histogram::with_bounds(TOKEN_HISTOGRAM_BOUNDS)),
+                completion_tokens: Some(Histogram::with_bounds(TOKEN_HISTOGRAM_BOUNDS)),
+                ..Default::default()
+            }),
+            readiness: std::sync::RwLock::new(HashMap::new()),
+            moderation,
+            mock: config.mock.unwrap_or(false),
+            egress: std::sync::RwLock::new(EgressPolicy::for_providers(config)),
+            selections: AtomicUsize::new(0),
+            rng: std::sync::Mutex::new(StdRng::from_entropy()),
+            golden_start: std::sync::OnceLock::new(),
         })
     }
 
-    async fn load_api_keys(path: &str) -> Result<ApiKeys> {
-        if std::path::Path::new(path).exists() {
-            let content = std::fs::read_to_string(path)?;
+    /// Pick an enabled entity with the given strategy. Disabled entities are never
+    /// considered; a higher `priority` value wins under the priority strategy, and the
+    /// unspecified strategy takes the default entity while it is enabled.
+    pub fn select_entity(&self, strategy: ModelSelectionStrategy) -> Result<LlmEntity> {
+        let config = self.config();
+        let mut enabled = config.enabled_entities();
+        if enabled.is_empty() {
+            return Err(CwHoError::NoHealthyProvider);
+        }
+        // stable sort keeps config order between equal priorities
+        enabled.sort_by(|a, b| b.priority.cmp(&a.priority));
+        let turn = self.selections.fetch_add(1, Ordering::Relaxed);
+
+        let selected = match strategy {
+            ModelSelectionStrategy::Unspecified => {
+                let selected = effective_default(&config).expect("enabled is not empty");
+                if let Some(disabled) = disabled_default(&config) {
+                    warn!(
+                        "⏭️ Default LLM entity {} is disabled, routing to {}",
+                        disabled.name, selected.name
+                    );
+                }
+                selected
+            }
+            ModelSelectionStrategy::Priority => enabled[0],
+            ModelSelectionStrategy::RoundRobin => enabled[turn % enabled.len()],
+            ModelSelectionStrategy::GoldenRatio => {
+                // low-discrepancy walk over priority-weighted slots, from a random start so
+                // routers restarted together don't all lead with the same entity
+                let weights: Vec<u64> = enabled.iter().map(|e| e.priority as u64 + 1).collect();
+                let start = *self
+                    .golden_start
+                    .get_or_init(|| self.rng.lock().unwrap().gen());
+                let point = (start + turn as f64 / GOLDEN_RATIO).fract();
+                enabled[weighted_slot(&weights, point)]
+            }
+            ModelSelectionStrategy::LoadBalanced => {
+                let limits = self.limits.read().unwrap();
+                enabled
+                    .iter()
+                    .copied()
+                    .max_by_key(|e| {
+                        // free request slots; entities without a limit are never saturated
+                        limits
+                            .get(&e.name)
+                            .map(|s| s.available_permits())
+                            .unwrap_or(usize::MAX)
+                    })
+                    .expect("enabled is not empty")
+            }
+        };
+        Ok(selected.clone())
+    }
 
-            // Parse the new JSON structure with ProviderWithAuth
-            let config: serde_json::Value = serde_json::from_str(&content)
-                .map_err(|e| CwHoError::Config(format!("Failed to parse API keys JSON: {}", e)))?;
+    /// Names of the entities worth calling, highest priority first: enabled, and not
+    /// marked down by the last warm-up. Entities never checked count as live.
+    pub fn live_chain(&self) -> Vec<String> {
+        let config = self.config();
+        let readiness = self.readiness.read().unwrap();
+        let mut live: Vec<&LlmEntity> = config
+            .enabled_entities()
+            .into_iter()
+            .filter(|e| readiness.get(&e.name).is_none_or(|r| r.ready))
+            .collect();
+        // stable sort keeps config order between equal priorities
+        live.sort_by(|a, b| b.priority.cmp(&a.priority));
+        live.into_iter().map(|e| e.name.clone()).collect()
+    }
+
+    /// Leading share of the live chain, `1/φ` of it rounded up. Empty when nothing is live.
+    pub fn get_primary_chain(&self) -> Vec<String> {
+        let mut chain = self.live_chain();
+        chain.truncate(Self::primary_len(chain.len()));
+        chain
+    }
+
+    /// Live entities after the primary chain, in priority order
+    pub fn get_fallback_chain(&self) -> Vec<String> {
+        let chain = self.live_chain();
+        chain[Self::primary_len(chain.len())..].to_vec()
+    }
+
+    fn primary_len(live: usize) -> usize {
+        (live as f64 / GOLDEN_RATIO).ceil() as usize
+    }
+
+    /// Draw weighted selections from a fixed seed instead of entropy, making the sequence
+    /// of picks reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = std::sync::Mutex::new(StdRng::seed_from_u64(seed));
+        self.golden_start = std::sync::OnceLock::new();
+        self
+    }
+
+    /// Send provider requests through `backend` instead of the default reqwest client
+    pub fn with_backend(mut self, backend: Arc<dyn HttpBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Replace the configured moderation pass, e.g. with an external provider
+    pub fn with_moderation(mut self, hook: Arc<dyn ModerationHook>) -> Self {
+        self.moderation = hook;
+        self
+    }
 
-            // Helper function to extract and resolve API key (handles ${ENV_VAR} syntax)
-            let get_key = |provider_name: &str| -> Option<String> {
-                let provider = config.get("providers")?.get(provider_name)?;
+    /// Restrict provider requests to `policy` instead of the configured provider endpoints
+    pub fn with_egress(mut self, policy: EgressPolicy) -> Self {
+        self.egress = std::sync::RwLock::new(policy);
+        self
+    }
 
-                // Get the api_key field
-                let key_value = provider.get("api_key")?;
+    /// Snapshot of the current entities and selection settings
+    pub fn config(&self) -> LlmRouterConfig {
+        self.config.read().unwrap().clone()
+    }
 
-                // Handle null values
-                if key_value.is_null() {
-                    return None;
+    /// Add `entity`, or replace the configured entity of the same name. It is selectable
+    /// from the next request on; connection pools, breakers and the request slots of
+    /// other entities are left as they are.
+    pub fn add_entity(&self, entity: LlmEntity) -> Result<()> {
+        if entity.enabled {
+            entity.validate_base_url()?;
+        }
+        if entity.tls.is_some() {
+            warn!(
+                "⚠️ {} has TLS settings, which take effect after a restart",
+                entity.name
+            );
+        }
+        self.update_entities(|config| {
+            let limit_changed = match config.entities.iter().position(|e| e.name == entity.name) {
+                Some(i) => {
+                    let previous = std::mem::replace(&mut config.entities[i], entity.clone());
+                    previous.max_concurrent_requests != entity.max_concurrent_requests
+                }
+                None => {
+                    config.entities.push(entity.clone());
+                    true
                 }
+            };
+            if limit_changed {
+                let mut limits = self.limits.write().unwrap();
+                match Self::concurrency_limit(&entity) {
+                    Some(limit) => limits.insert(entity.name.clone(), limit),
+                    None => limits.remove(&entity.name),
+                };
+            }
+        });
+        info!("➕ LLM entity {} added", entity.name);
+        Ok(())
+    }
+
+    /// Stop routing to the entity called `name`. Requests already holding one of its
+    /// slots finish normally. Returns false when no such entity is configured.
+    pub fn remove_entity(&self, name: &str) -> bool {
+        let mut removed = false;
+        self.update_entities(|config| {
+            let before = config.entities.len();
+            config.entities.retain(|e| e.name != name);
+            removed = config.entities.len() < before;
+        });
+        if removed {
+            self.limits.write().unwrap().remove(name);
+            self.readiness.write().unwrap().remove(name);
+            info!("➖ LLM entity {} removed", name);
+        }
+        removed
+    }
+
+    /// Make `model` the default entity, which must be configured and enabled. Requests
+    /// naming no provider go to it from the next one on.
+    pub fn set_default_entity(&self, model: LlmModel) -> Result<()> {
+        let mut config = self.config.write().unwrap();
+        let enabled = config.enabled_entities();
+        if !enabled.iter().any(|e| e.name == model.as_str_name()) {
+            let allowed: Vec<&str> = enabled.iter().map(|e| e.name.as_str()).collect();
+            return Err(CwHoError::InvalidRequest(format!(
+                "LLM entity {} is not enabled, expected one of: {}",
+                model.as_str_name(),
+                allowed.join(", ")
+            )));
+        }
+        config.update_default_entity(model);
+        info!("🎯 Default LLM entity set to {}", model.as_str_name());
+        Ok(())
+    }
 
-                let key_str = key_value.as_str()?;
+    /// Every configured entity in config order, flagging the default one
+    pub fn entity_statuses(&self) -> Vec<LlmEntityStatus> {
+        let config = self.config();
+        let configured = config.configured_default();
+        let effective = effective_default(&config).map(|e| e.name.clone());
+        config
+            .entities
+            .iter()
+            .map(|e| LlmEntityStatus {
+                name: e.name.clone(),
+                enabled: e.enabled,
+                priority: e.priority,
+                is_default: effective.as_ref() == Some(&e.name),
+                default_model: e.default_model.clone(),
+                configured_default: configured.is_some_and(|model| model.as_str_name() == e.name),
+            })
+            .collect()
+    }
 
-                // Check if it's an env var reference like ${OPENAI_API_KEY}
-                if key_str.starts_with("${") && key_str.ends_with('}') {
-                    let env_var = &key_str[2..key_str.len() - 1];
-                    std::env::var(env_var).ok()
-                } else {
-                    Some(key_str.to_string())
+    /// `response` as the entity that answered it allows it to be stored: `None` when the
+    /// entity keeps no responses, without the prompt text when it keeps no prompts. The
+    /// entity is the one behind the response's `provider`, which may not be the
+    /// `requested` one after a fallback; answers from no provider, such as mock ones, go
+    /// by `requested`. Providers without an entity keep everything.
+    pub fn retained(
+        &self,
+        requested: LlmModel,
+        response: &PromptResponse,
+    ) -> Option<PromptResponse> {
+        let provider = Self::provider_named(&response.provider).unwrap_or(requested);
+        let config = self.config();
+        let entity = config
+            .entities
+            .iter()
+            .find(|e| e.name == provider.as_str_name());
+        if entity.is_some_and(|e| !e.stores_responses()) {
+            return None;
+        }
+        let mut record = response.clone();
+        if entity.is_some_and(|e| !e.stores_prompts()) {
+            record.prompt.clear();
+        }
+        Some(record)
+    }
+
+    /// Every (provider, model) pair an enabled entity serves, in config order
+    pub fn available_models(&self) -> Vec<(String, String)> {
+        self.config()
+            .enabled_entities()
+            .into_iter()
+            .flat_map(|e| entity_models(e).map(move |model| (e.name.clone(), model.clone())))
+            .collect()
+    }
+
+    /// [`available_models`](Self::available_models) grouped by provider, flagging the
+    /// default entity. With `live_only`, entities the last warm-up marked down are left out.
+    pub fn model_listing(&self, live_only: bool) -> Vec<LlmProviderModels> {
+        let config = self.config();
+        let default = effective_default(&config).map(|e| e.name.clone());
+        let live = self.live_chain();
+        config
+            .enabled_entities()
+            .into_iter()
+            .filter(|e| !live_only || live.contains(&e.name))
+            .map(|e| LlmProviderModels {
+                provider: e.name.clone(),
+                models: entity_models(e).cloned().collect(),
+                default_model: e.default_model.clone(),
+                is_default: default.as_ref() == Some(&e.name),
+            })
+            .collect()
+    }
+
+    /// Apply `change` to the entities under the config lock. An egress policy derived from
+    /// the provider endpoints follows the change; one set with `with_egress` is kept.
+    fn update_entities(&self, change: impl FnOnce(&mut LlmRouterConfig)) {
+        let mut config = self.config.write().unwrap();
+        let mut egress = self.egress.write().unwrap();
+        let derived = *egress == EgressPolicy::for_providers(&config);
+        change(&mut config);
+        if derived {
+            *egress = EgressPolicy::for_providers(&config);
+        }
+    }
+
+    /// Request slots for an entity with a concurrency limit
+    fn concurrency_limit(entity: &LlmEntity) -> Option<Arc<Semaphore>> {
+        match entity.max_concurrent_requests {
+            Some(max) if max > 0 => Some(Arc::new(Semaphore::new(max as usize))),
+            _ => None,
+        }
+    }
+
+    /// Hand `request` to the backend once its host passes the egress allowlist
+    async fn send(&self, request: reqwest::Request) -> Result<HttpResponse> {
+        self.egress
+            .read()
+            .unwrap()
+            .check_url("llm", request.url())?;
+        Ok(self.backend.send(request).await?)
+    }
+
+    /// Issue a lightweight request to every enabled entity so TLS sessions (and Ollama models)
+    /// are ready before the first prompt. Failures are logged and recorded, never fatal.
+    pub async fn warm_up(&self) {
+        let config = self.config();
+        for entity in config.entities.iter().filter(|e| e.enabled) {
+            let result = self.preflight(entity).await;
+            let readiness = ProviderReadiness {
+                provider: entity.name.clone(),
+                ready: result.is_ok(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+                checked_at: chrono::Utc::now().timestamp() as u64,
+                health: provider_health(&result).into(),
+            };
+            match &result {
+                Ok(()) => info!("🔥 LLM entity {} warmed up", entity.name),
+                Err(e) => warn!("⚠️ LLM entity {} warm-up failed: {}", entity.name, e),
+            }
+            let ready = readiness.ready;
+            // entities never checked were routed to, so their circuit counts as closed
+            let was_ready = self
+                .readiness
+                .write()
+                .unwrap()
+                .insert(entity.name.clone(), readiness)
+                .is_none_or(|previous| previous.ready);
+            match (was_ready, ready) {
+                (true, false) => self.count_reliability(&entity.name, |m| m.circuit_opened += 1),
+                (false, true) => self.count_reliability(&entity.name, |m| m.circuit_closed += 1),
+                _ => {}
+            }
+        }
+    }
+
+    /// Readiness of each entity as of the last warm-up
+    pub fn readiness(&self) -> Vec<ProviderReadiness> {
+        let mut readiness: Vec<ProviderReadiness> =
+            self.readiness.read().unwrap().values().cloned().collect();
+        readiness.sort_by(|a, b| a.provider.cmp(&b.provider));
+        readiness
+    }
+
+    /// Send the cheapest request an entity supports: listing models for hosted providers,
+    /// or loading the default model for Ollama. Fails with `Config` when there is
+    /// nothing to probe and `Auth` when the key is missing or rejected.
+    async fn preflight(&self, entity: &LlmEntity) -> Result<()> {
+        let model = LlmModel::from_str_name(&entity.name);
+        let base_url = match entity.base_url.trim().is_empty() {
+            true => model.and_then(|m| m.default_base_url()).unwrap_or_default(),
+            false => entity.base_url.clone(),
+        };
+        if base_url.is_empty() || entity.default_model.trim().is_empty() {
+            return Err(CwHoError::Config(format!(
+                "{} needs a base_url and a default model",
+                entity.name
+            )));
+        }
+        let request = match model {
+            Some(LlmModel::OllamaLocal) => {
+                // A generate call without a prompt only loads the model into memory
+                self.client
+                    .post(format!("{}/api/generate", base_url.trim_end_matches('/')))
+                    .json(&serde_json::json!({
+                        "model": entity.default_model,
+                        "keep_alive": "10m",
+                    }))
+            }
+            _ => {
+                let request = self
+                    .client
+                    .get(format!("{}/models", base_url.trim_end_matches('/')));
+                match (model, model.and_then(|m| self.api_key_for(m))) {
+                    (Some(LlmModel::Anthropic), Some(key)) => request
+                        .header("x-api-key", key)
+                        .header("anthropic-version", "2023-06-01"),
+                    (_, Some(key)) => request.header("Authorization", format!("Bearer {}", key)),
+                    (_, None) => request,
                 }
+            }
+        };
+
+        let request = request
+            .timeout(Duration::from_secs(WARM_UP_TIMEOUT_SECS))
+            .build()?;
+        let response = self.send(request).await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                Err(CwHoError::Auth {
+                    provider: entity.name.clone(),
+                    message: format!("pre-flight returned {}", response.status()),
+                })
+            }
+            status => Err(CwHoError::LlmEntity(format!(
+                "{} pre-flight returned {}",
+                entity.name, status
+            ))),
+        }
+    }
+
+    /// Configured api key for a provider
+    fn api_key_for(&self, model: LlmModel) -> Option<&String> {
+        match model {
+            LlmModel::OpenAi => self.api_keys.openai.as_ref(),
+            LlmModel::Anthropic => self.api_keys.anthropic.as_ref(),
+            LlmModel::Grok => self.api_keys.grok.as_ref(),
+            LlmModel::AkashChat => self.api_keys.akash.as_ref(),
+            LlmModel::KimiResearch => self.api_keys.kimi.as_ref(),
+            LlmModel::OllamaLocal | LlmModel::Custom => None,
+        }
+    }
+
+    /// Send a provider request, retrying 429s up to the entity's `max_retries` with
+    /// jittered exponential backoff. A `Retry-After` header sets the exact delay instead,
+    /// capped at `MAX_RETRY_AFTER_SECS`.
+    async fn send_rate_limited<F>(&self, provider: LlmModel, build: F) -> Result<HttpResponse>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let max_retries = self
+            .config()
+            .entities
+            .iter()
+            .find(|e| e.name == provider.as_str_name())
+            .map(|e| e.max_retries)
+            .unwrap_or_default();
+
+        let max_delay = Duration::from_secs(MAX_RETRY_AFTER_SECS);
+        let policy =
+            ExponentialBackoff::new(Duration::from_millis(RATE_LIMIT_BACKOFF_MS), max_delay)
+                .with_jitter(RATE_LIMIT_JITTER)
+                .take(max_retries as usize);
+        let mut attempt = 0;
+        let should_retry = |err: &CwHoError, backoff: Duration| {
+            let CwHoError::RateLimited { retry_after, .. } = err else {
+                return None;
+            };
+            attempt += 1;
+            let delay = retry_after.unwrap_or(backoff).min(max_delay);
+            warn!(
+                "🐢 {} rate limited, retrying in {:?} (attempt {}/{})",
+                provider.as_str_name(),
+                delay,
+                attempt,
+                max_retries
+            );
+            Some(delay)
+        };
+        let build = &build;
+        retry_async(policy, should_retry, move || async move {
+            let response = self.send(build().build()?).await?;
+            if matches!(
+                response.status(),
+                reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+            ) {
+                return Err(CwHoError::Auth {
+                    provider: provider.as_str_name().to_string(),
+                    message: format!("{} {}", response.status(), response.text())
+                        .trim()
+                        .to_string(),
+                });
+            }
+            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(CwHoError::RateLimited {
+                    provider: provider.as_str_name().to_string(),
+                    retry_after: parse_retry_after(&response),
+                });
+            }
+            Ok::<_, CwHoError>(response)
+        })
+        .await
+    }
+
+    /// Wait for a request slot on the entity's concurrency limit, if it has one.
+    /// The returned permit must be held for the duration of the request.
+    async fn acquire_slot(&self, entity: &str) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self.limits.read().unwrap().get(entity)?.clone();
+        let queued_at = Instant::now();
+        let permit = semaphore.acquire_owned().await.ok()?;
+        let waited_ms = queued_at.elapsed().as_millis() as u64;
+
+        if waited_ms > 0 {
+            debug!("⏳ Waited {}ms for a request slot on {}", waited_ms, entity);
+        }
+        let mut metrics = self.queue_metrics.lock().unwrap();
+        let entry = metrics
+            .entry(entity.to_string())
+            .or_insert_with(|| ProviderQueueMetrics {
+                provider: entity.to_string(),
+                ..Default::default()
+            });
+        entry.acquired += 1;
+        entry.total_wait_ms += waited_ms;
+        entry.max_wait_ms = entry.max_wait_ms.max(waited_ms);
+
+        Some(permit)
+    }
+
+    /// Snapshot of router metrics
+    pub fn metrics(&self) -> LlmRouterMetrics {
+        let mut queues: Vec<ProviderQueueMetrics> = self
+            .queue_metrics
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        queues.sort_by(|a, b| a.provider.cmp(&b.provider));
+        LlmRouterMetrics {
+            queues,
+            ..self.request_metrics.lock().unwrap().clone()
+        }
+    }
+
+    /// Observe the token counts of an answered request and how long `provider` took
+    fn record_response(&self, provider: LlmModel, response: &PromptResponse, latency: Duration) {
+        let mut metrics = self.request_metrics.lock().unwrap();
+        if let Some(usage) = response.tokens_used {
+            let observe = |histogram: &mut Option<Histogram>, tokens: u32| {
+                histogram
+                    .get_or_insert_with(|| Histogram::with_bounds(TOKEN_HISTOGRAM_BOUNDS))
+                    .observe(tokens.into())
+            };
+            observe(&mut metrics.prompt_tokens, usage.prompt);
+            observe(&mut metrics.completion_tokens, usage.completion);
+        }
+        let name = provider.as_str_name();
+        let index = match metrics
+            .latency
+            .binary_search_by(|entry| entry.provider.as_str().cmp(name))
+        {
+            Ok(index) => index,
+            Err(index) => {
+                metrics.latency.insert(
+                    index,
+                    ProviderLatencyMetrics {
+                        provider: name.to_string(),
+                        latency_ms: Some(Histogram::with_bounds(LATENCY_HISTOGRAM_BOUNDS_MS)),
+                    },
+                );
+                index
+            }
+        };
+        metrics.latency[index]
+            .latency_ms
+            .get_or_insert_with(|| Histogram::with_bounds(LATENCY_HISTOGRAM_BOUNDS_MS))
+            .observe(latency.as_millis() as u64);
+    }
+
+    /// Bump `provider`'s reliability counters with `count`, keeping them sorted by provider
+    fn count_reliability(
+        &self,
+        provider: &str,
+        count: impl FnOnce(&mut ProviderReliabilityMetrics),
+    ) {
+        let mut metrics = self.request_metrics.lock().unwrap();
+        let index = match metrics
+            .reliability
+            .binary_search_by(|entry| entry.provider.as_str().cmp(provider))
+        {
+            Ok(index) => index,
+            Err(index) => {
+                metrics.reliability.insert(
+                    index,
+                    ProviderReliabilityMetrics {
+                        provider: provider.to_string(),
+                        ..Default::default()
+                    },
+                );
+                index
+            }
+        };
+        count(&mut metrics.reliability[index]);
+    }
+
+    /// Provider serving a given model name
+    fn provider_for_model(model: &str) -> LlmModel {
+        if model.contains("gpt") || model.contains("openai") {
+            LlmModel::OpenAi
+        } else if model.contains("claude") || model.contains("anthropic") {
+            LlmModel::Anthropic
+        } else if model.contains("grok") {
+            LlmModel::Grok
+        } else if model.contains("akash") {
+            LlmModel::AkashChat
+        } else {
+            // Default to OpenAI for unknown models
+            LlmModel::OpenAi
+        }
+    }
+
+    async fn load_api_keys(path: &str) -> Result<ApiKeys> {
+        if std::path::Path::new(path).exists() {
+            // The same loader as `config dump` and the setup, so provider keys are
+            // normalized and unknown ones rejected alike
+            let config = ApiKeysJson::load(&camino::Utf8PathBuf::from(path))
+                .map_err(|e| CwHoError::Config(format!("{:#}", e)))?;
+
+            // Resolve a provider's api key, expanding ${ENV_VAR} references
+            let get_key = |provider_key: &str| -> Option<String> {
+                resolve_api_key(config.providers.get(provider_key)?.api_key.as_deref()?)
             };
 
             Ok(ApiKeys {
-                openai: get_key("openai"),
-                anthropic: get_key("anthropic"),
-                grok: get_key("grok"),
-                akash: get_key("akash_chat"),
-                kimi: get_key("kimi"),
+                openai: get_key(LlmModel::OpenAi.provider_key()),
+                anthropic: get_key(LlmModel::Anthropic.provider_key()),
+                grok: get_key(LlmModel::Grok.provider_key()),
+                akash: get_key(LlmModel::AkashChat.provider_key()),
+                kimi: get_key(LlmModel::KimiResearch.provider_key()),
                 qwen: get_key("qwen"),
                 venice: get_key("venice"),
             })
@@ -98,164 +893,560 @@ impl LlmRouter {
         request: &PromptRequest,
         model: &str,
     ) -> Result<PromptResponse> {
-        // Determine provider based on model name for now
-        // TODO: Add provider field to request or use model-based routing
-        if model.contains("gpt") || model.contains("openai") {
-            self.call_openai(&request).await
-        } else if model.contains("claude") || model.contains("anthropic") {
-            self.call_anthropic(&request).await
-        } else if model.contains("grok") {
-            self.call_grok(&request).await
-        } else if model.contains("akash") {
-            self.call_akash(&request).await
-        } else {
-            // Default to OpenAI for unknown models
-            self.call_openai(&request).await
+        let provider = match request.provider.as_deref() {
+            Some(name) => Self::provider_named(name).ok_or_else(|| {
+                CwHoError::InvalidRequest(format!("unknown LLM provider {}", name))
+            })?,
+            None => Self::provider_for_model(model),
+        };
+        self.check_capabilities(provider, request, model)?;
+        for message in &request.messages {
+            self.moderate("prompt", &message.content).await?;
+        }
+        let format = request.response_format.clone().unwrap_or_default();
+        let mut request = request.clone();
+        if let Some(instruction) = format.instruction()? {
+            request.messages.insert(
+                0,
+                PromptMessage {
+                    role: "system".to_string(),
+                    content: instruction,
+                },
+            );
+        }
+
+        // Latency includes any wait for a concurrency slot, as the caller sees it
+        let started = Instant::now();
+        let mut response = match self.mock {
+            true => self.mock_response(&request, model),
+            false => {
+                // A disabled provider falls back to one that must support the request too
+                let (provider, entity) = self.enabled_entity(provider)?;
+                self.check_capabilities(provider, &request, model)?;
+                let _permit = self.acquire_slot(&entity).await;
+                match provider {
+                    LlmModel::Anthropic => self.call_anthropic(&request).await,
+                    LlmModel::Grok => self.call_grok(&request).await,
+                    LlmModel::AkashChat => self.call_akash(&request).await,
+                    _ => self.call_openai(&request).await,
+                }?
+            }
+        };
+        self.record_response(provider, &response, started.elapsed());
+        // Stored responses keep the session, user and thread they answered
+        response.context = request.context.clone();
+        for content in &response.response {
+            self.moderate("response", content).await?;
         }
+        response.response = response
+            .response
+            .iter()
+            .map(|content| format.enforce(content))
+            .collect::<ho_std::error::HoResult<_>>()?;
+        Ok(response)
     }
 
-    async fn call_akash(&self, req: &PromptRequest) -> Result<PromptResponse> {
-        let api_key = self
-            .api_keys
-            .openai
-            .as_ref()
-            .ok_or_else(|| CwHoError::LlmEntity("OpenAI API key not configured".to_string()))?;
+    /// [`process_request`](Self::process_request), abandoned as soon as `cancel` turns true.
+    /// The request future is dropped, which aborts the in-flight provider call and frees
+    /// its concurrency slot. A dropped sender never cancels.
+    pub async fn process_request_cancellable(
+        &self,
+        request: &PromptRequest,
+        model: &str,
+        mut cancel: tokio::sync::watch::Receiver<bool>,
+    ) -> Result<PromptResponse> {
+        tokio::select! {
+            result = self.process_request(request, model) => result,
+            Ok(_) = cancel.wait_for(|cancelled| *cancelled) => {
+                warn!("🛑 LLM request to {} cancelled", model);
+                Err(CwHoError::Cancelled(format!("LLM request to {}", model)))
+            }
+        }
+    }
 
-        let request = OpenAiRequest {
-            model: req.model.to_string(),
-            messages: req
-                .messages
-                .iter()
-                .map(|p| OpenAiMessage {
-                    role: p.role.to_string(),
-                    content: p.content.to_string(),
-                })
-                .collect(),
-            temperature: Some(69),    // Default temperature
-            max_tokens: Some(10_000), // Default max tokens
-        };
+    /// Try the live chain in priority order until a provider answers, all within `deadline`.
+    /// The provider the request names, when live, is tried first. No further provider is started once less than `MIN_FALLBACK_ATTEMPT_MS` remains, and
+    /// the attempt in flight is cut off when the budget runs out. Each provider is asked for
+    /// the request's model when it serves it, its default model otherwise.
+    pub async fn route_with_fallback(
+        &self,
+        request: &PromptRequest,
+        deadline: Duration,
+    ) -> Result<PromptResponse> {
+        let started = tokio::time::Instant::now();
+        let mut chain = self.live_chain();
+        if let Some(named) = request.provider.as_deref().and_then(Self::provider_named) {
+            if let Some(at) = chain.iter().position(|name| name == named.as_str_name()) {
+                let name = chain.remove(at);
+                chain.insert(0, name);
+            }
+        }
+        if chain.is_empty() {
+            return Err(CwHoError::NoHealthyProvider);
+        }
+        let config = self.config();
+        let mut failures = Vec::new();
+        // provider the request is being passed on from
+        let mut failed: Option<String> = None;
+        for name in chain {
+            let remaining = deadline.saturating_sub(started.elapsed());
+            if remaining < Duration::from_millis(MIN_FALLBACK_ATTEMPT_MS) {
+                warn!(
+                    "⌛ Fallback deadline of {}ms reached, not trying {} or later providers",
+                    deadline.as_millis(),
+                    name
+                );
+                return Err(CwHoError::DeadlineExceeded { failures });
+            }
+            let Some(entity) = config.entities.iter().find(|e| e.name == name) else {
+                continue;
+            };
+            if let Some(failed) = failed.take() {
+                self.count_reliability(&failed, |m| m.fallbacks += 1);
+            }
+            let mut attempt = request.clone();
+            attempt.provider = Some(name.clone());
+            if !entity.models.contains(&attempt.model) {
+                attempt.model = entity.default_model.clone();
+            }
+            let model = attempt.model.clone();
+            match tokio::time::timeout(remaining, self.process_request(&attempt, &model)).await {
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(e)) => {
+                    warn!("⏭️ {} failed, trying the next provider: {}", name, e);
+                    failures.push(format!("{}: {}", name, e));
+                    failed = Some(name);
+                }
+                Err(_) => {
+                    warn!("⌛ {} was cut off by the fallback deadline", name);
+                    failures.push(format!("{}: timed out", name));
+                    return Err(CwHoError::DeadlineExceeded { failures });
+                }
+            }
+        }
+        Err(CwHoError::FallbackExhausted { failures })
+    }
+
+    /// Route a request as the next turn of a stored conversation. Earlier messages for
+    /// `session_id` are trimmed to the history budget and sent ahead of the request's own,
+    /// then the new messages and the first completion are recorded as the latest turn.
+    /// Abandoned, recording nothing, as soon as `cancel` turns true.
+    pub async fn route_request_in_session(
+        &self,
+        storage: &CwHoStorage,
+        session_id: &str,
+        request: &PromptRequest,
+        model: &str,
+        cancel: tokio::sync::watch::Receiver<bool>,
+    ) -> Result<PromptResponse> {
+        let history = storage.session_history(session_id).await?;
+        let mut assembled = request.clone();
+        assembled.messages =
+            trim_history(&history, SESSION_HISTORY_TOKEN_BUDGET, tokenizer_for(model));
+        assembled.messages.extend(request.messages.iter().cloned());
+        assembled
+            .context
+            .get_or_insert_with(Default::default)
+            .session_id = Some(session_id.to_string());
 
         let response = self
-            .client
-            .post(AKASH_CHAT_BASE_URL)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
+            .process_request_cancellable(&assembled, model, cancel)
             .await?;
 
-        if response.status().is_success() {
-            let timestap: Timestamp = DateTime::parse_from_rfc2822(
-                response.headers().get("date").unwrap().to_str().unwrap(),
-            )
-            .unwrap()
-            .to_utc()
-            .into();
-
-            let openai_response: OpenAiResponse = response.json().await?;
-            let content: Vec<String> = openai_response
-                .choices
-                .iter()
-                .map(|c| c.message.clone().expect("should have msgs").content)
-                .collect();
-
-            let usage = openai_response.usage.unwrap_or(OpenAiUsage {
-                prompt_tokens: 0,
-                completion_tokens: 0,
-                total_tokens: 0,
+        let mut turn = request.messages.clone();
+        if let Some(reply) = response.response.first() {
+            turn.push(PromptMessage {
+                role: "assistant".to_string(),
+                content: reply.clone(),
             });
-
-            Ok(PromptResponse {
-                tokens_used: Some(TokenUsage {
-                    prompt: usage.prompt_tokens,
-                    completion: usage.completion_tokens,
-                    total: usage.total_tokens,
-                }),
-                model: req.model.to_string(),
-                prompt: blake3::Blake3::hash(&req.to_bytes().unwrap()).to_string(),
-                response: content.clone(),
-                timestamp: Some(timestap),
-                cost: Some(CostCalculator::calculate_cost(
-                    &"akash",
-                    &req.model,
-                    usage.prompt_tokens,
-                    usage.completion_tokens,
-                )),
-                latency_ms: None,
-                id: vec![],
-                provider: req.model.clone(),
-            })
-        } else {
-            let error_text = response.text().await?;
-            error!("OpenAI API error: {}", error_text);
-            Err(CwHoError::LlmEntity(format!(
-                "OpenAI error: {}",
-                error_text
-            )))
         }
+        storage.append_session_messages(session_id, &turn).await?;
+        Ok(response)
     }
-    async fn call_openai(&self, req: &PromptRequest) -> Result<PromptResponse> {
-        let api_key = self
-            .api_keys
-            .openai
-            .as_ref()
-            .ok_or_else(|| CwHoError::LlmEntity("OpenAI API key not configured".to_string()))?;
 
-        let request = OpenAiRequest {
-            model: req.model.to_string(),
-            messages: req
-                .messages
+    /// Provider for an entity name or api-keys.json key
+    pub(crate) fn provider_named(name: &str) -> Option<LlmModel> {
+        LlmModel::from_str_name(name).or_else(|| LlmModel::from_provider_key(&name.to_lowercase()))
+    }
+
+    /// Provider and model for a request that may name either, checked against the enabled
+    /// entities and their model lists. A model alone picks the entity listing it, a provider
+    /// alone its default model, and neither the entity of the default strategy.
+    pub fn resolve_selection(
+        &self,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<(LlmModel, String)> {
+        let config = self.config();
+        let enabled = config.enabled_entities();
+        let entity = match (provider, model) {
+            (None, None) => self.select_entity(config.default_strategy())?,
+            (Some(name), _) => Self::provider_named(name)
+                .and_then(|p| enabled.iter().find(|e| e.name == p.as_str_name()))
+                .map(|&e| e.clone())
+                .ok_or_else(|| {
+                    let allowed: Vec<&str> = enabled.iter().map(|e| e.name.as_str()).collect();
+                    CwHoError::InvalidRequest(format!(
+                        "LLM provider {} is not enabled, expected one of: {}",
+                        name,
+                        allowed.join(", ")
+                    ))
+                })?,
+            (None, Some(model)) => enabled
                 .iter()
-                .map(|p| OpenAiMessage {
-                    role: p.role.to_string(),
-                    content: p.content.to_string(),
-                })
-                .collect(),
-            temperature: Some(69),    // Default temperature
-            max_tokens: Some(10_000), // Default max tokens
+                .find(|e| e.models.iter().any(|m| m == model))
+                .map(|&e| e.clone())
+                .ok_or_else(|| {
+                    let allowed: Vec<&str> = enabled
+                        .iter()
+                        .flat_map(|e| e.models.iter().map(String::as_str))
+                        .collect();
+                    CwHoError::InvalidRequest(format!(
+                        "model {} is not served by an enabled provider, expected one of: {}",
+                        model,
+                        allowed.join(", ")
+                    ))
+                })?,
         };
+        let provider = Self::entity_provider(&entity)?;
+        match model {
+            None => Ok((provider, entity.default_model)),
+            Some(model) if entity.models.iter().any(|m| m == model) => {
+                Ok((provider, model.to_string()))
+            }
+            Some(model) => Err(CwHoError::InvalidRequest(format!(
+                "model {} is not served by {}, expected one of: {}",
+                model,
+                entity.name,
+                entity.models.join(", ")
+            ))),
+        }
+    }
 
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+    /// Provider and default model for `task`'s prompt, picked with the task's own
+    /// selection strategy when it sets one and the router's `default_strategy` otherwise
+    pub fn resolve_task_selection(&self, task: &CosmicTask) -> Result<(LlmModel, String)> {
+        let strategy = task
+            .strategy_override()
+            .unwrap_or_else(|| self.config().default_strategy());
+        let entity = self.select_entity(strategy)?;
+        Ok((Self::entity_provider(&entity)?, entity.default_model))
+    }
+
+    /// Answer `task`'s prompt as a user message, from the provider
+    /// [`resolve_task_selection`](Self::resolve_task_selection) picks. Given a `deadline`,
+    /// a provider failing passes the prompt down the live chain until one answers or the
+    /// deadline runs out.
+    pub async fn answer_task(
+        &self,
+        task: &CosmicTask,
+        deadline: Option<Duration>,
+    ) -> Result<PromptResponse> {
+        let (provider, model) = self.resolve_task_selection(task)?;
+        match deadline {
+            Some(deadline) => {
+                let request = self.task_request(task, provider, &model);
+                self.route_with_fallback(&request, deadline).await
+            }
+            None => self.answer_task_from(task, provider, &model).await,
+        }
+    }
+
+    /// Answer `task`'s prompt as a user message from `provider`'s `model`, whatever the
+    /// selection strategy would pick
+    pub async fn answer_task_from(
+        &self,
+        task: &CosmicTask,
+        provider: LlmModel,
+        model: &str,
+    ) -> Result<PromptResponse> {
+        let request = self.task_request(task, provider, model);
+        self.process_request(&request, model).await
+    }
+
+    fn task_request(&self, task: &CosmicTask, provider: LlmModel, model: &str) -> PromptRequest {
+        PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: task.prompt.clone(),
+            }],
+            model: model.to_string(),
+            provider: Some(provider.as_str_name().to_string()),
+            llm_config: Some(self.prompt_config(provider, None)),
+            ..Default::default()
+        }
+    }
+
+    fn entity_provider(entity: &LlmEntity) -> Result<LlmModel> {
+        LlmModel::from_str_name(&entity.name).ok_or_else(|| {
+            CwHoError::Config(format!(
+                "LLM entity {} is not a known provider",
+                entity.name
+            ))
+        })
+    }
+
+    /// The provider to call for a request and the entity answering it, falling back
+    /// through the default strategy when the operator disabled it. Request slots are
+    /// taken on the returned entity.
+    fn enabled_entity(&self, provider: LlmModel) -> Result<(LlmModel, String)> {
+        let config = self.config();
+        if config.enabled_entities().is_empty() {
+            return Err(CwHoError::NoHealthyProvider);
+        }
+        let disabled = config
+            .entities
+            .iter()
+            .any(|e| e.name == provider.as_str_name() && !e.enabled);
+        if !disabled {
+            return Ok((provider, provider.as_str_name().to_string()));
+        }
+        let fallback = self.select_entity(config.default_strategy())?;
+        warn!(
+            "⏭️ {} is disabled, falling back to {}",
+            provider.as_str_name(),
+            fallback.name
+        );
+        self.count_reliability(provider.as_str_name(), |m| m.fallbacks += 1);
+        let provider = Self::provider_named(&fallback.name).unwrap_or(provider);
+        Ok((provider, fallback.name))
+    }
+
+    /// Tokens `model` would see in `text`, exact for model families with a bundled
+    /// vocabulary and estimated from its length otherwise
+    pub fn estimate_tokens(&self, model: &str, text: &str) -> usize {
+        tokenizer_for(model).count_tokens(text)
+    }
+
+    /// Sampling settings for a request to `provider`: `requested`, or the defaults when
+    /// the request carries none, with `max_tokens` clamped to the provider's configured cap
+    pub(crate) fn prompt_config(
+        &self,
+        provider: LlmModel,
+        requested: Option<&LlmPromptConfig>,
+    ) -> LlmPromptConfig {
+        let mut config = requested.cloned().unwrap_or(LlmPromptConfig {
+            temperature: DEFAULT_TEMPERATURE,
+            max_tokens: DEFAULT_MAX_OUTPUT_TOKENS,
+            ..Default::default()
+        });
+        config.max_tokens = self
+            .config
+            .read()
+            .unwrap()
+            .clamp_max_tokens(provider, config.max_tokens);
+        config
+    }
+
+    /// Canned reply echoing the last prompt message, served when `mock` is configured
+    fn mock_response(&self, request: &PromptRequest, model: &str) -> PromptResponse {
+        let prompt = request
+            .messages
+            .last()
+            .map(|m| m.content.clone())
+            .unwrap_or_default();
+        let reply = format!("mock response to: {}", prompt);
+        let reply = match request
+            .response_format
+            .as_ref()
+            .is_some_and(|f| f.is_json())
+        {
+            true => serde_json::json!({ "response": reply }).to_string(),
+            false => reply,
+        };
+        let (prompt_tokens, reply_tokens) = (
+            self.estimate_tokens(model, &prompt) as u32,
+            self.estimate_tokens(model, &reply) as u32,
+        );
+        let usage = TokenUsage {
+            prompt: prompt_tokens,
+            completion: reply_tokens,
+            total: prompt_tokens + reply_tokens,
+        };
+        PromptResponse {
+            id: vec![],
+            provider: "mock".to_string(),
+            model: model.to_string(),
+            prompt,
+            response: vec![reply],
+            timestamp: Some(chrono::Utc::now().into()),
+            tokens_used: Some(usage),
+            cost: Some(0.0),
+            latency_ms: Some(0),
+            request_id: None,
+            context: None,
+        }
+    }
+
+    /// Run the moderation hook, failing with `HoError::Llm` on a blocked verdict
+    async fn moderate(&self, stage: &str, text: &str) -> Result<()> {
+        let verdict = self.moderation.check(text).await?;
+        if verdict.blocked {
+            warn!("🚫 {} blocked by moderation: {:?}", stage, verdict.category);
+        }
+        Ok(verdict.ensure_allowed(stage)?)
+    }
+
+    /// Capabilities of the configured entity for `provider`, or the provider's known ones
+    fn capabilities_of(&self, provider: LlmModel) -> LlmCapabilities {
+        self.config
+            .read()
+            .unwrap()
+            .entities
+            .iter()
+            .find(|e| e.name == provider.as_str_name())
+            .map(LlmEntity::resolved_capabilities)
+            .unwrap_or_else(|| provider.capabilities())
+    }
+
+    /// Reject `request` when it uses a feature `provider`'s entity doesn't support
+    fn check_capabilities(
+        &self,
+        provider: LlmModel,
+        request: &PromptRequest,
+        model: &str,
+    ) -> Result<()> {
+        match self.capabilities_of(provider).missing_for(request) {
+            Some(feature) => Err(CwHoError::InvalidRequest(format!(
+                "model {} doesn't support {}",
+                model, feature
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// OpenAI-compatible request body, with the tools and, where the entity supports
+    /// it, the JSON mode of `req`
+    fn openai_body(
+        &self,
+        provider: LlmModel,
+        request: &OpenAiRequest,
+        req: &PromptRequest,
+    ) -> Result<serde_json::Value> {
+        let mut body = serde_json::to_value(request)?;
+        if let Some(param) = req
+            .response_format
+            .as_ref()
+            .filter(|_| self.capabilities_of(provider).supports_json_mode)
+            .map(ResponseFormat::openai_param)
+            .transpose()?
+            .flatten()
+        {
+            body["response_format"] = param;
+        }
+        if !req.tools.is_empty() {
+            body["tools"] = req
+                .tools
+                .iter()
+                .map(ToolDefinition::openai_param)
+                .collect::<ho_std::error::HoResult<_>>()?;
+        }
+        Ok(body)
+    }
+
+    async fn call_akash(&self, req: &PromptRequest) -> Result<PromptResponse> {
+        let api_key = self
+            .api_keys
+            .akash
+            .as_ref()
+            .ok_or_else(|| missing_api_key(LlmModel::AkashChat))?;
+
+        let config = self.prompt_config(LlmModel::AkashChat, req.llm_config.as_ref());
+        let request = OpenAiRequest {
+            model: req.model.to_string(),
+            messages: req
+                .messages
+                .iter()
+                .map(|p| OpenAiMessage {
+                    role: p.role.to_string(),
+                    content: p.content.to_string(),
+                })
+                .collect(),
+            temperature: Some(config.temperature),
+            max_tokens: Some(config.max_tokens),
+        };
+
+        let body = self.openai_body(LlmModel::AkashChat, &request, req)?;
+
+        let started = Instant::now();
+        let response = self
+            .send_rate_limited(LlmModel::AkashChat, || {
+                self.client
+                    .post(AKASH_CHAT_BASE_URL)
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .header("Content-Type", "application/json")
+                    .json(&body)
+            })
+            .await?;
 
         if response.status().is_success() {
-            let openai_response: OpenAiResponse = response.json().await?;
-
-            let content = openai_response
-                .choices
-                .first()
-                .map(|c| c.message.clone().expect("should have msgs").content.clone())
-                .unwrap_or_else(|| "No response".to_string());
-
-            let usage = openai_response.usage.unwrap_or(OpenAiUsage {
-                prompt_tokens: 0,
-                completion_tokens: 0,
-                total_tokens: 0,
-            });
+            let body: serde_json::Value = response.json()?;
+            let usage = normalize_usage(&body, LlmModel::AkashChat, &req.model);
+            let content = completion_contents("akash", body)?;
+            Ok(provider_response(
+                LlmModel::AkashChat,
+                req,
+                content,
+                usage,
+                started.elapsed(),
+            ))
+        } else {
+            let error_text = response.text();
+            error!("OpenAI API error: {}", error_text);
+            Err(CwHoError::LlmEntity(format!(
+                "OpenAI error: {}",
+                error_text
+            )))
+        }
+    }
+    async fn call_openai(&self, req: &PromptRequest) -> Result<PromptResponse> {
+        let api_key = self
+            .api_keys
+            .openai
+            .as_ref()
+            .ok_or_else(|| missing_api_key(LlmModel::OpenAi))?;
 
-            Ok(PromptResponse {
-                tokens_used: Some(TokenUsage {
-                    prompt: usage.prompt_tokens,
-                    completion: usage.completion_tokens,
-                    total: usage.total_tokens,
-                }),
-                model: req.model.to_string(),
-                provider: "openai".to_string(),
-                prompt: todo!(),
-                response: todo!(),
-                timestamp: todo!(),
-                cost: todo!(),
-                latency_ms: todo!(),
-                id: todo!(),
+        let config = self.prompt_config(LlmModel::OpenAi, req.llm_config.as_ref());
+        let request = OpenAiRequest {
+            model: req.model.to_string(),
+            messages: req
+                .messages
+                .iter()
+                .map(|p| OpenAiMessage {
+                    role: p.role.to_string(),
+                    content: p.content.to_string(),
+                })
+                .collect(),
+            temperature: Some(config.temperature),
+            max_tokens: Some(config.max_tokens),
+        };
+
+        let body = self.openai_body(LlmModel::OpenAi, &request, req)?;
+
+        let started = Instant::now();
+        let response = self
+            .send_rate_limited(LlmModel::OpenAi, || {
+                self.client
+                    .post("https://api.openai.com/v1/chat/completions")
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .header("Content-Type", "application/json")
+                    .json(&body)
             })
+            .await?;
+
+        if response.status().is_success() {
+            let body: serde_json::Value = response.json()?;
+            let usage = normalize_usage(&body, LlmModel::OpenAi, &req.model);
+            let content = completion_contents("openai", body)?;
+            Ok(provider_response(
+                LlmModel::OpenAi,
+                req,
+                content,
+                usage,
+                started.elapsed(),
+            ))
         } else {
-            let error_text = response.text().await?;
+            let error_text = response.text();
             error!("OpenAI API error: {}", error_text);
             Err(CwHoError::LlmEntity(format!(
                 "OpenAI error: {}",
@@ -265,10 +1456,11 @@ impl LlmRouter {
     }
 
     async fn call_anthropic(&self, req: &PromptRequest) -> Result<PromptResponse> {
-        let api_key =
-            self.api_keys.anthropic.as_ref().ok_or_else(|| {
-                CwHoError::LlmEntity("Anthropic API key not configured".to_string())
-            })?;
+        let api_key = self
+            .api_keys
+            .anthropic
+            .as_ref()
+            .ok_or_else(|| missing_api_key(LlmModel::Anthropic))?;
 
         // Extract system prompt if present
         let mut system_opt: Option<String> = None;
@@ -289,29 +1481,39 @@ impl LlmRouter {
             }
         }
 
+        let config = self.prompt_config(LlmModel::Anthropic, req.llm_config.as_ref());
         let mut request = serde_json::json!({
             "model": req.model,
-            "max_tokens": req.llm_config.clone().expect("hah").max_tokens,
+            "max_tokens": config.max_tokens,
             "messages": messages,
-            "temperature":  req.llm_config.clone().expect("huh").temperature,
+            "temperature": config.temperature,
         });
 
         if let Some(system) = system_opt {
             request["system"] = serde_json::json!(system);
         }
+        if !req.tools.is_empty() {
+            request["tools"] = req
+                .tools
+                .iter()
+                .map(ToolDefinition::anthropic_param)
+                .collect::<ho_std::error::HoResult<_>>()?;
+        }
 
+        let started = Instant::now();
         let response = self
-            .client
-            .post(ANTHROPIC_MESSAGE_URL)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
+            .send_rate_limited(LlmModel::Anthropic, || {
+                self.client
+                    .post(ANTHROPIC_MESSAGE_URL)
+                    .header("x-api-key", api_key)
+                    .header("anthropic-version", "2023-06-01")
+                    .header("Content-Type", "application/json")
+                    .json(&request)
+            })
             .await?;
 
         if response.status().is_success() {
-            let anthropic_response: serde_json::Value = response.json().await?;
+            let anthropic_response: serde_json::Value = response.json()?;
 
             let content = anthropic_response
                 .get("content")
@@ -322,35 +1524,16 @@ impl LlmRouter {
                 .unwrap_or("No response")
                 .to_string();
 
-            let usage = anthropic_response
-                .get("usage")
-                .map(|u| TokenUsage {
-                    prompt: u.get("input_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
-                    completion: u.get("output_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
-                    total: 0, // Will be calculated below
-                })
-                .unwrap_or(TokenUsage {
-                    prompt: 0,
-                    completion: 0,
-                    total: 0,
-                });
-
-            let mut final_usage = usage;
-            final_usage.total = final_usage.prompt + final_usage.completion;
-
-            Ok(PromptResponse {
-                tokens_used: Some(final_usage),
-                model: req.model.to_string(),
-                provider: "anthropic".to_string(),
-                prompt: todo!(),
-                response: todo!(),
-                timestamp: todo!(),
-                cost: todo!(),
-                latency_ms: todo!(),
-                id: todo!(),
-            })
+            let usage = normalize_usage(&anthropic_response, LlmModel::Anthropic, &req.model);
+            Ok(provider_response(
+                LlmModel::Anthropic,
+                req,
+                vec![content],
+                usage,
+                started.elapsed(),
+            ))
         } else {
-            let error_text = response.text().await?;
+            let error_text = response.text();
             error!("Anthropic API error: {}", error_text);
             Err(CwHoError::LlmEntity(format!(
                 "Anthropic error: {}",
@@ -364,9 +1547,10 @@ impl LlmRouter {
             .api_keys
             .grok
             .as_ref()
-            .ok_or_else(|| CwHoError::LlmEntity("Grok API key not configured".to_string()))?;
+            .ok_or_else(|| missing_api_key(LlmModel::Grok))?;
 
         // Grok uses OpenAI-compatible API
+        let config = self.prompt_config(LlmModel::Grok, req.llm_config.as_ref());
         let request = OpenAiRequest {
             model: req.model.to_string(),
             messages: req
@@ -377,51 +1561,36 @@ impl LlmRouter {
                     content: p.content.to_string(),
                 })
                 .collect(),
-            temperature: Some(69),    // Default temperature
-            max_tokens: Some(10_000), // Default max tokens
+            temperature: Some(config.temperature),
+            max_tokens: Some(config.max_tokens),
         };
 
+        let body = self.openai_body(LlmModel::Grok, &request, req)?;
+
+        let started = Instant::now();
         let response = self
-            .client
-            .post("https://api.x.ai/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
+            .send_rate_limited(LlmModel::Grok, || {
+                self.client
+                    .post("https://api.x.ai/v1/chat/completions")
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .header("Content-Type", "application/json")
+                    .json(&body)
+            })
             .await?;
 
         if response.status().is_success() {
-            let grok_response: OpenAiResponse = response.json().await?;
-
-            let content = grok_response
-                .choices
-                .first()
-                .map(|c| c.message.clone().expect("should have msgs").content.clone())
-                .unwrap_or_else(|| "No response".to_string());
-
-            let usage = grok_response.usage.unwrap_or(OpenAiUsage {
-                prompt_tokens: 0,
-                completion_tokens: 0,
-                total_tokens: 0,
-            });
-
-            Ok(PromptResponse {
-                tokens_used: Some(TokenUsage {
-                    prompt: usage.prompt_tokens,
-                    completion: usage.completion_tokens,
-                    total: usage.total_tokens,
-                }),
-                model: req.model.to_string(),
-                provider: "grok".to_string(),
-                prompt: todo!(),
-                response: todo!(),
-                timestamp: todo!(),
-                cost: todo!(),
-                latency_ms: todo!(),
-                id: todo!(),
-            })
+            let body: serde_json::Value = response.json()?;
+            let usage = normalize_usage(&body, LlmModel::Grok, &req.model);
+            let content = completion_contents("grok", body)?;
+            Ok(provider_response(
+                LlmModel::Grok,
+                req,
+                content,
+                usage,
+                started.elapsed(),
+            ))
         } else {
-            let error_text = response.text().await?;
+            let error_text = response.text();
             error!("Grok API error: {}", error_text);
             Err(CwHoError::LlmEntity(format!("Grok error: {}", error_text)))
         }
@@ -457,3 +1626,1452 @@ impl LlmRouter {
         models.iter().map(|m| m.to_string()).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_router_config, FakeBackend};
+
+    #[tokio::test]
+    async fn test_concurrency_limit_serializes_requests() {
+        let mut entity = LlmModel::AkashChat.default_entity();
+        entity.max_concurrent_requests = Some(1);
+        let config = test_router_config(vec![entity.clone()]);
+        let router = Arc::new(LlmRouter::new(&config).await.unwrap());
+
+        let started = Instant::now();
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let router = router.clone();
+                let name = entity.name.clone();
+                tokio::spawn(async move {
+                    let _permit = router.acquire_slot(&name).await;
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        let metrics = router.metrics();
+        assert_eq!(metrics.queues[0].acquired, 2);
+        assert!(metrics.queues[0].max_wait_ms >= 40);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_marks_reachable_provider_ready() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mock = axum::Router::new().route("/models", axum::routing::get(|| async { "{}" }));
+        tokio::spawn(async move { axum::serve(listener, mock).await });
+
+        let mut entity = LlmModel::AkashChat.default_entity();
+        entity.base_url = format!("http://{}", addr);
+        let mut unreachable = LlmModel::Grok.default_entity();
+        unreachable.base_url = "http://127.0.0.1:1".to_string();
+        let config = test_router_config(vec![entity, unreachable]);
+        let router = LlmRouter::new(&config).await.unwrap();
+        router.warm_up().await;
+
+        let readiness = router.readiness();
+        let akash = readiness
+            .iter()
+            .find(|r| r.provider == "AkashChat")
+            .unwrap();
+        let grok = readiness.iter().find(|r| r.provider == "Grok").unwrap();
+        assert!(akash.ready);
+        assert!(!grok.ready && grok.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_health_tells_bad_keys_from_unreachable_providers() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mock = axum::Router::new().route(
+            "/models",
+            axum::routing::get(|| async { axum::http::StatusCode::UNAUTHORIZED }),
+        );
+        tokio::spawn(async move { axum::serve(listener, mock).await });
+
+        let mut bad_key = LlmModel::AkashChat.default_entity();
+        bad_key.base_url = format!("http://{}", addr);
+        let mut unresolvable = LlmModel::Grok.default_entity();
+        unresolvable.base_url = "http://llm.does-not-resolve.invalid".to_string();
+        let mut no_model = LlmModel::OpenAi.default_entity();
+        no_model.default_model = String::new();
+        let config = test_router_config(vec![bad_key, unresolvable, no_model]);
+        let router = LlmRouter::new(&config).await.unwrap();
+        router.warm_up().await;
+
+        let health: HashMap<String, ProviderHealth> = router
+            .readiness()
+            .into_iter()
+            .map(|r| (r.provider.clone(), r.health()))
+            .collect();
+        assert_eq!(health["AkashChat"], ProviderHealth::AuthError);
+        assert_eq!(health["Grok"], ProviderHealth::Unreachable);
+        assert_eq!(health["OpenAI"], ProviderHealth::Misconfigured);
+        assert!(router.readiness().iter().all(|r| !r.ready));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_honors_retry_after() {
+        let too_many = || {
+            HttpResponse::new(reqwest::StatusCode::TOO_MANY_REQUESTS, "")
+                .with_header("retry-after", "2")
+        };
+        let backend = Arc::new(FakeBackend::default());
+        backend.responses.lock().unwrap().extend([
+            too_many(),
+            HttpResponse::new(reqwest::StatusCode::OK, "{}"),
+            too_many(),
+        ]);
+        let mut entity = LlmModel::AkashChat.default_entity();
+        entity.base_url = "http://provider.invalid".to_string();
+        entity.max_retries = 1;
+        let config = test_router_config(vec![entity]);
+        let router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+
+        // Paused time skips the wait but still advances the clock by it
+        let started = tokio::time::Instant::now();
+        let response = router
+            .send_rate_limited(LlmModel::AkashChat, || {
+                router.client.get("http://provider.invalid/flaky")
+            })
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(backend.urls.lock().unwrap().len(), 2);
+        assert!(started.elapsed() >= Duration::from_secs(2));
+
+        // No configured retries for Grok, so the parsed delay is surfaced
+        match router
+            .send_rate_limited(LlmModel::Grok, || {
+                router.client.get("http://provider.invalid/limited")
+            })
+            .await
+        {
+            Err(CwHoError::RateLimited { retry_after, .. }) => {
+                assert_eq!(retry_after, Some(Duration::from_secs(2)))
+            }
+            other => panic!("expected RateLimited, got {:?}", other.map(|r| r.status())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_moderation_blocks_flagged_prompt() {
+        let config = LlmRouterConfig {
+            moderation: Some(ModerationConfig {
+                blocked_keywords: vec!["forbidden".to_string()],
+            }),
+            ..test_router_config(vec![])
+        };
+        let router = LlmRouter::new(&config).await.unwrap();
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "tell me something Forbidden".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        match router.process_request(&request, "gpt-4o").await {
+            Err(CwHoError::HoError(HoError::Llm(msg))) => assert!(msg.contains("prompt")),
+            other => panic!("expected moderation error, got {:?}", other.map(|r| r.id)),
+        }
+        assert!(router.moderate("response", "all clear").await.is_ok());
+        assert!(router.moderate("response", "FORBIDDEN").await.is_err());
+    }
+
+    #[test]
+    fn test_empty_choices_surface_provider_error() {
+        let error_only = serde_json::json!({
+            "error": {"message": "content filtered", "type": "invalid_request_error"}
+        });
+        match completion_contents("openai", error_only) {
+            Err(CwHoError::ProviderHttp { provider, message }) => {
+                assert_eq!(provider, "openai");
+                assert_eq!(message, "content filtered");
+            }
+            other => panic!("expected ProviderHttp, got {:?}", other),
+        }
+
+        let empty = serde_json::json!({"id": "x", "choices": []});
+        assert!(matches!(
+            completion_contents("grok", empty),
+            Err(CwHoError::EmptyCompletion { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retention_follows_the_answering_entity() {
+        let config = test_router_config(vec![
+            LlmModel::AkashChat.default_entity(),
+            LlmEntity {
+                store_responses: Some(false),
+                ..LlmModel::OpenAi.default_entity()
+            },
+        ]);
+        let router = LlmRouter::new(&config).await.unwrap();
+        let answered_by = |provider: &str| PromptResponse {
+            provider: provider.to_string(),
+            prompt: "hash".to_string(),
+            ..Default::default()
+        };
+
+        // Asked of Akash but answered by OpenAI, which keeps nothing
+        assert!(router
+            .retained(LlmModel::AkashChat, &answered_by("openai"))
+            .is_none());
+        assert!(router
+            .retained(LlmModel::OpenAi, &answered_by("akash_chat"))
+            .is_some());
+        // Mock answers go by the provider asked
+        assert!(router
+            .retained(LlmModel::OpenAi, &answered_by("mock"))
+            .is_none());
+        assert!(router
+            .retained(LlmModel::AkashChat, &answered_by("mock"))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_entity_is_never_selected() {
+        let mut disabled = LlmModel::Grok.default_entity();
+        disabled.priority = 10;
+        disabled.enabled = false;
+        let mut enabled = LlmModel::AkashChat.default_entity();
+        enabled.priority = 1;
+        let config = test_router_config(vec![disabled.clone(), enabled]);
+        let router = LlmRouter::new(&config).await.unwrap();
+
+        for strategy in [
+            ModelSelectionStrategy::Priority,
+            ModelSelectionStrategy::RoundRobin,
+            ModelSelectionStrategy::GoldenRatio,
+            ModelSelectionStrategy::LoadBalanced,
+        ] {
+            for _ in 0..3 {
+                assert_eq!(router.select_entity(strategy).unwrap().name, "AkashChat");
+            }
+        }
+        assert_eq!(
+            router.enabled_entity(LlmModel::Grok).unwrap(),
+            (LlmModel::AkashChat, "AkashChat".to_string())
+        );
+        // Falling back from the disabled provider counts against it
+        assert_eq!(
+            router.metrics().reliability,
+            [ProviderReliabilityMetrics {
+                provider: "Grok".to_string(),
+                fallbacks: 1,
+                ..Default::default()
+            }]
+        );
+
+        let none = LlmRouterConfig {
+            entities: vec![disabled],
+            ..config
+        };
+        let router = LlmRouter::new(&none).await.unwrap();
+        assert!(matches!(
+            router.select_entity(ModelSelectionStrategy::Priority),
+            Err(CwHoError::NoHealthyProvider)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_seeded_golden_ratio_selection_is_reproducible() {
+        let entities = [
+            (LlmModel::AkashChat, 3),
+            (LlmModel::Grok, 1),
+            (LlmModel::OpenAi, 0),
+        ]
+        .map(|(model, priority)| {
+            let mut entity = model.default_entity();
+            entity.priority = priority;
+            entity
+        });
+        let config = test_router_config(entities.to_vec());
+        let picks = |router: LlmRouter| -> Vec<String> {
+            (0..24)
+                .map(|_| {
+                    router
+                        .select_entity(ModelSelectionStrategy::GoldenRatio)
+                        .unwrap()
+                        .name
+                })
+                .collect()
+        };
+
+        let first = picks(LlmRouter::new(&config).await.unwrap().with_seed(7));
+        let again = picks(LlmRouter::new(&config).await.unwrap().with_seed(7));
+        assert_eq!(first, again);
+
+        // Every entity gets a share, the highest priority the largest
+        let count = |name: &str| first.iter().filter(|pick| *pick == name).count();
+        let (akash, grok, openai) = (count("AkashChat"), count("Grok"), count("OpenAI"));
+        assert!(openai > 0, "{:?}", first);
+        assert!(akash > grok && grok > openai, "{:?}", first);
+    }
+
+    #[tokio::test]
+    async fn test_task_strategy_overrides_router_default() {
+        let mut akash = LlmModel::AkashChat.default_entity();
+        akash.priority = 2;
+        let mut grok = LlmModel::Grok.default_entity();
+        grok.priority = 1;
+        let config = LlmRouterConfig {
+            default_strategy: ModelSelectionStrategy::Priority.into(),
+            ..test_router_config(vec![akash, grok])
+        };
+        let router = LlmRouter::new(&config).await.unwrap();
+        let picks = |task: &CosmicTask| -> Vec<LlmModel> {
+            (0..4)
+                .map(|_| router.resolve_task_selection(task).unwrap().0)
+                .collect()
+        };
+
+        // Without an override every pick follows the priority default
+        let mut task = CosmicTask::default();
+        assert_eq!(picks(&task), [LlmModel::AkashChat; 4]);
+
+        task.selection_strategy = Some(ModelSelectionStrategy::RoundRobin.into());
+        let rotated = picks(&task);
+        assert!(rotated.contains(&LlmModel::AkashChat), "{:?}", rotated);
+        assert!(rotated.contains(&LlmModel::Grok), "{:?}", rotated);
+        assert!(
+            rotated.windows(2).all(|pair| pair[0] != pair[1]),
+            "{:?}",
+            rotated
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_default_routes_to_next_enabled_entity() {
+        let entity = |model: LlmModel, priority: u32, enabled: bool| LlmEntity {
+            priority,
+            enabled,
+            ..model.default_entity()
+        };
+        let mut config = LlmRouterConfig {
+            default_entity: Some(LlmModel::OpenAi as u32),
+            mock: Some(true),
+            ..test_router_config(vec![
+                entity(LlmModel::OpenAi, 9, false),
+                entity(LlmModel::AkashChat, 1, true),
+                entity(LlmModel::Grok, 5, true),
+            ])
+        };
+        let router = LlmRouter::new(&config).await.unwrap();
+
+        let (provider, _) = router.resolve_selection(None, None).unwrap();
+        assert_eq!(provider, LlmModel::Grok);
+        let defaults: Vec<(String, bool, bool)> = router
+            .entity_statuses()
+            .into_iter()
+            .map(|s| (s.name, s.is_default, s.configured_default))
+            .collect();
+        assert_eq!(
+            defaults,
+            [
+                ("OpenAI".to_string(), false, true),
+                ("AkashChat".to_string(), false, false),
+                ("Grok".to_string(), true, false),
+            ]
+        );
+
+        // With nothing enabled there is no default to fall back to
+        for e in config.entities.iter_mut() {
+            e.enabled = false;
+        }
+        let router = LlmRouter::new(&config).await.unwrap();
+        assert!(matches!(
+            router.resolve_selection(None, None),
+            Err(CwHoError::NoHealthyProvider)
+        ));
+        assert!(router.entity_statuses().iter().all(|s| !s.is_default));
+    }
+
+    #[tokio::test]
+    async fn test_unset_default_is_not_akash_chat() {
+        let config = test_router_config(vec![
+            LlmEntity {
+                enabled: false,
+                ..LlmModel::AkashChat.default_entity()
+            },
+            LlmModel::OpenAi.default_entity(),
+        ]);
+        assert_eq!(config.configured_default(), None);
+        // AkashChat is enum value 0, but an unset default names no entity, disabled or not
+        assert!(disabled_default(&config).is_none());
+
+        let router = LlmRouter::new(&config).await.unwrap();
+        let (provider, _) = router.resolve_selection(None, None).unwrap();
+        assert_eq!(provider, LlmModel::OpenAi);
+        assert!(router
+            .entity_statuses()
+            .iter()
+            .all(|status| !status.configured_default));
+    }
+
+    #[tokio::test]
+    async fn test_provider_chains_skip_down_and_disabled_entities() {
+        let entity = |model: LlmModel, priority: u32, enabled: bool| LlmEntity {
+            priority,
+            enabled,
+            ..model.default_entity()
+        };
+        let config = test_router_config(vec![
+            entity(LlmModel::AkashChat, 1, true),
+            entity(LlmModel::Grok, 5, true),
+            entity(LlmModel::OpenAi, 9, false),
+            entity(LlmModel::Anthropic, 3, true),
+            entity(LlmModel::KimiResearch, 2, true),
+        ]);
+        let router = LlmRouter::new(&config).await.unwrap();
+        let mark = |name: &str, ready: bool| {
+            router.readiness.write().unwrap().insert(
+                name.to_string(),
+                ProviderReadiness {
+                    provider: name.to_string(),
+                    ready,
+                    ..Default::default()
+                },
+            );
+        };
+        mark("Grok", false);
+        mark("Anthropic", true);
+
+        assert_eq!(
+            router.live_chain(),
+            vec!["Anthropic", "KimiResearch", "AkashChat"]
+        );
+        assert_eq!(
+            router.get_primary_chain(),
+            vec!["Anthropic", "KimiResearch"]
+        );
+        assert_eq!(router.get_fallback_chain(), vec!["AkashChat"]);
+
+        for name in ["Anthropic", "KimiResearch", "AkashChat"] {
+            mark(name, false);
+        }
+        assert!(router.get_primary_chain().is_empty());
+        assert!(router.get_fallback_chain().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_provider_models_leave_the_listing() {
+        let akash = LlmModel::AkashChat.default_entity();
+        let grok = LlmModel::Grok.default_entity();
+        let config = test_router_config(vec![akash.clone(), grok.clone()]);
+        let router = LlmRouter::new(&config).await.unwrap();
+        router.set_default_entity(LlmModel::AkashChat).unwrap();
+        let providers = |pairs: Vec<(String, String)>| -> Vec<String> {
+            let mut names: Vec<String> = pairs.into_iter().map(|(provider, _)| provider).collect();
+            names.dedup();
+            names
+        };
+
+        let models = router.available_models();
+        assert_eq!(providers(models.clone()), ["AkashChat", "Grok"]);
+        assert!(models.contains(&("Grok".to_string(), grok.default_model.clone())));
+        let listing = router.model_listing(false);
+        assert_eq!(listing[0].models[0], akash.default_model);
+        assert!(listing[0].is_default);
+        assert!(!listing[1].is_default);
+
+        router
+            .add_entity(LlmEntity {
+                enabled: false,
+                ..grok
+            })
+            .unwrap();
+        assert_eq!(providers(router.available_models()), ["AkashChat"]);
+        let listing = router.model_listing(false);
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0].provider, "AkashChat");
+
+        // Asking for live providers only also drops those the warm-up marked down
+        router.readiness.write().unwrap().insert(
+            "AkashChat".to_string(),
+            ProviderReadiness {
+                provider: "AkashChat".to_string(),
+                ready: false,
+                ..Default::default()
+            },
+        );
+        assert!(router.model_listing(true).is_empty());
+        assert_eq!(router.model_listing(false).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_requested_provider_overrides_model_routing() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(akash_completion("routed"));
+        let config = test_router_config(vec![
+            LlmModel::OpenAi.default_entity(),
+            LlmModel::AkashChat.default_entity(),
+        ]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+
+        // Model names alone would send this to OpenAI
+        let (provider, model) = router
+            .resolve_selection(Some("akash_chat"), Some("DeepSeek-R1-0528"))
+            .unwrap();
+        assert_eq!(provider, LlmModel::AkashChat);
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "which provider?".to_string(),
+            }],
+            model: model.clone(),
+            provider: Some(provider.as_str_name().to_string()),
+            ..Default::default()
+        };
+        let response = router.process_request(&request, &model).await.unwrap();
+        assert_eq!(response.response, vec!["routed"]);
+        assert_eq!(*backend.urls.lock().unwrap(), vec![AKASH_CHAT_BASE_URL]);
+
+        let (provider, model) = router.resolve_selection(Some("OpenAI"), None).unwrap();
+        assert_eq!(provider, LlmModel::OpenAi);
+        assert_eq!(model, LlmModel::OpenAi.default_entity().default_model);
+        assert_eq!(
+            router
+                .resolve_selection(None, Some("DeepSeek-R1-0528"))
+                .unwrap()
+                .0,
+            LlmModel::AkashChat
+        );
+
+        let unknown = router.resolve_selection(Some("grok"), None).unwrap_err();
+        assert!(unknown.to_string().contains("OpenAI, AkashChat"));
+        let unlisted = router
+            .resolve_selection(Some("akash_chat"), Some("gpt-4o"))
+            .unwrap_err();
+        assert!(unlisted.to_string().contains("DeepSeek-R1-0528"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_entities_fall_back_to_local_ollama() {
+        let config = LlmRouterConfig {
+            default_entity: Some(LlmModel::AkashChat as u32),
+            ..test_router_config(vec![])
+        };
+        let router = LlmRouter::new(&config).await.unwrap();
+
+        let ollama = LlmModel::OllamaLocal.default_entity();
+        assert_eq!(router.config().entities, vec![ollama.clone()]);
+        assert_eq!(
+            router.config().configured_default(),
+            Some(LlmModel::OllamaLocal)
+        );
+        assert_eq!(router.live_chain(), vec![ollama.name.clone()]);
+        assert_eq!(
+            router
+                .select_entity(ModelSelectionStrategy::Priority)
+                .unwrap(),
+            ollama
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_reads_keys_under_any_spelling_of_the_provider() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-router-keys-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("api-keys.json");
+        let key = |api_key: &str| serde_json::json!({ "api_key": api_key });
+        let file = serde_json::json!({
+            "providers": {
+                "OpenAI": key("sk-openai"),
+                "AkashChat": key("ak-akash"),
+                "qwen": key("qw-qwen"),
+            }
+        });
+        std::fs::write(&path, file.to_string()).unwrap();
+
+        let config = LlmRouterConfig {
+            api_keys_file: path.display().to_string(),
+            ..test_router_config(vec![LlmModel::OpenAi.default_entity()])
+        };
+        let router = LlmRouter::new(&config).await.unwrap();
+        assert_eq!(router.api_keys.openai.as_deref(), Some("sk-openai"));
+        assert_eq!(router.api_keys.akash.as_deref(), Some("ak-akash"));
+        assert_eq!(router.api_keys.qwen.as_deref(), Some("qw-qwen"));
+
+        std::fs::write(&path, r#"{"providers": {"openrouter": {}}}"#).unwrap();
+        let err = LlmRouter::new(&config).await.err().unwrap();
+        assert!(err.to_string().contains("unknown provider"), "{}", err);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_answered_request_fills_size_histograms() {
+        let backend = Arc::new(FakeBackend::default());
+        let mut ok = akash_completion("sized");
+        ok.body = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": "sized"}}],
+            "usage": {"prompt_tokens": 100, "completion_tokens": 3000},
+        })
+        .to_string()
+        .into();
+        backend.responses.lock().unwrap().push_back(ok);
+        let router = akash_router(backend).await;
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "how big?".to_string(),
+            }],
+            model: "DeepSeek-R1-0528".to_string(),
+            provider: Some(LlmModel::AkashChat.as_str_name().to_string()),
+            ..Default::default()
+        };
+        router
+            .process_request(&request, &request.model)
+            .await
+            .unwrap();
+
+        let metrics = router.metrics();
+        let prompt = metrics.prompt_tokens.unwrap();
+        assert_eq!((prompt.count, prompt.sum), (1, 100));
+        // 100 tokens fall in the (64, 256] bucket
+        assert_eq!(prompt.counts, [0, 1, 0, 0, 0, 0, 0]);
+        let completion = metrics.completion_tokens.unwrap();
+        assert_eq!((completion.count, completion.sum), (1, 3000));
+        assert_eq!(completion.counts, [0, 0, 0, 1, 0, 0, 0]);
+
+        assert_eq!(metrics.latency.len(), 1);
+        assert_eq!(
+            metrics.latency[0].provider,
+            LlmModel::AkashChat.as_str_name()
+        );
+        let latency = metrics.latency[0].latency_ms.as_ref().unwrap();
+        assert_eq!(latency.count, 1);
+        assert_eq!(latency.counts.iter().sum::<u64>(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_entity_added_at_runtime_serves_next_request() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(akash_completion("live"));
+        let config = test_router_config(vec![LlmModel::OpenAi.default_entity()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        let router = Arc::new(router);
+
+        let mut added = LlmModel::AkashChat.default_entity();
+        added.priority = 5;
+        added.max_concurrent_requests = Some(1);
+        // Shared like the server's router: no exclusive access, no rebuild
+        tokio::spawn({
+            let router = router.clone();
+            async move { router.add_entity(added) }
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let (provider, model) = router.resolve_selection(None, None).unwrap();
+        assert_eq!(provider, LlmModel::AkashChat);
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "who answers?".to_string(),
+            }],
+            model: model.clone(),
+            provider: Some(provider.as_str_name().to_string()),
+            ..Default::default()
+        };
+        let response = router.process_request(&request, &model).await.unwrap();
+        assert_eq!(response.response, vec!["live"]);
+        // The derived egress allowlist now covers the new provider's endpoint
+        assert_eq!(*backend.urls.lock().unwrap(), vec![AKASH_CHAT_BASE_URL]);
+        assert!(router.limits.read().unwrap().contains_key("AkashChat"));
+
+        assert!(router.set_default_entity(LlmModel::Grok).is_err());
+        router.set_default_entity(LlmModel::AkashChat).unwrap();
+        assert_eq!(
+            router.config().configured_default(),
+            Some(LlmModel::AkashChat)
+        );
+
+        assert!(router.remove_entity("AkashChat"));
+        assert!(!router.remove_entity("AkashChat"));
+        assert_eq!(router.live_chain(), vec!["OpenAI"]);
+        assert!(router.limits.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_switched_default_serves_next_default_request() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(akash_completion("from akash"));
+        let entity = |model: LlmModel, priority: u32, enabled: bool| LlmEntity {
+            priority,
+            enabled,
+            ..model.default_entity()
+        };
+        let config = LlmRouterConfig {
+            default_entity: Some(LlmModel::OpenAi as u32),
+            ..test_router_config(vec![
+                entity(LlmModel::OpenAi, 5, true),
+                entity(LlmModel::AkashChat, 1, true),
+                entity(LlmModel::Grok, 9, false),
+            ])
+        };
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+
+        let (provider, _) = router.resolve_selection(None, None).unwrap();
+        assert_eq!(provider, LlmModel::OpenAi);
+
+        // Disabled entities cannot become the default
+        let err = router.set_default_entity(LlmModel::Grok).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("expected one of: OpenAI, AkashChat"));
+        router.set_default_entity(LlmModel::AkashChat).unwrap();
+        let defaults: Vec<(String, bool)> = router
+            .entity_statuses()
+            .into_iter()
+            .map(|status| (status.name, status.is_default))
+            .collect();
+        assert_eq!(
+            defaults,
+            [
+                ("OpenAI".to_string(), false),
+                ("AkashChat".to_string(), true),
+                ("Grok".to_string(), false)
+            ]
+        );
+
+        // Requests naming no provider now go to the new default, ahead of higher priorities
+        let (provider, model) = router.resolve_selection(None, None).unwrap();
+        assert_eq!(provider, LlmModel::AkashChat);
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "who answers?".to_string(),
+            }],
+            model: model.clone(),
+            provider: Some(provider.as_str_name().to_string()),
+            ..Default::default()
+        };
+        let response = router.process_request(&request, &model).await.unwrap();
+        assert_eq!(response.response, vec!["from akash"]);
+        assert_eq!(*backend.urls.lock().unwrap(), vec![AKASH_CHAT_BASE_URL]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fallback_chain_stops_at_deadline() {
+        /// Fails every request, but only after `delay`
+        struct SlowBackend {
+            delay: Duration,
+            calls: AtomicUsize,
+        }
+
+        #[async_trait::async_trait]
+        impl HttpBackend for SlowBackend {
+            async fn send(
+                &self,
+                _request: reqwest::Request,
+            ) -> ho_std::error::HoResult<HttpResponse> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(self.delay).await;
+                Ok(HttpResponse::new(
+                    reqwest::StatusCode::SERVICE_UNAVAILABLE,
+                    "overloaded",
+                ))
+            }
+        }
+
+        let backend = Arc::new(SlowBackend {
+            delay: Duration::from_millis(600),
+            calls: AtomicUsize::new(0),
+        });
+        let entity = |model: LlmModel, priority: u32| LlmEntity {
+            priority,
+            ..model.default_entity()
+        };
+        let config = test_router_config(vec![
+            entity(LlmModel::AkashChat, 3),
+            entity(LlmModel::OpenAi, 2),
+            entity(LlmModel::Anthropic, 1),
+        ]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        router.api_keys.openai = Some("test-key".to_string());
+        router.api_keys.anthropic = Some("test-key".to_string());
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "anyone there?".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        // The first provider uses up all but 400ms, too little to start the second
+        let started = tokio::time::Instant::now();
+        let err = router
+            .route_with_fallback(&request, Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(backend.calls.load(Ordering::SeqCst), 1);
+        let CwHoError::DeadlineExceeded { failures } = &err else {
+            panic!("expected a deadline error, got {}", err);
+        };
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("AkashChat: "));
+        assert!(err.to_string().contains("deadline exceeded"));
+    }
+
+    #[tokio::test]
+    async fn test_primary_failure_counts_fallback_and_circuit_open() {
+        let backend = Arc::new(FakeBackend::default());
+        backend.responses.lock().unwrap().extend([
+            // the primary refuses the prompt, the secondary answers it
+            HttpResponse::new(reqwest::StatusCode::UNAUTHORIZED, "bad key"),
+            akash_completion("from openai"),
+            // first warm-up: the primary is down, the secondary up
+            HttpResponse::new(reqwest::StatusCode::SERVICE_UNAVAILABLE, "down"),
+            HttpResponse::new(reqwest::StatusCode::OK, "{}"),
+            // second warm-up: both up
+            HttpResponse::new(reqwest::StatusCode::OK, "{}"),
+            HttpResponse::new(reqwest::StatusCode::OK, "{}"),
+        ]);
+        let entity = |model: LlmModel, priority: u32| LlmEntity {
+            priority,
+            max_retries: 0,
+            ..model.default_entity()
+        };
+        let config = test_router_config(vec![
+            entity(LlmModel::AkashChat, 3),
+            entity(LlmModel::OpenAi, 2),
+        ]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        router.api_keys.openai = Some("test-key".to_string());
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "anyone there?".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let response = router
+            .route_with_fallback(&request, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(response.response, ["from openai"]);
+        let counted = |router: &LlmRouter| router.metrics().reliability;
+        assert_eq!(
+            counted(&router),
+            [ProviderReliabilityMetrics {
+                provider: "AkashChat".to_string(),
+                fallbacks: 1,
+                ..Default::default()
+            }]
+        );
+
+        router.warm_up().await;
+        let akash = |router: &LlmRouter| counted(router)[0].clone();
+        assert_eq!(akash(&router).circuit_opened, 1);
+        assert_eq!(akash(&router).circuit_closed, 0);
+        // a provider staying up opens nothing
+        assert_eq!(counted(&router).len(), 1);
+
+        router.warm_up().await;
+        assert_eq!(akash(&router).circuit_opened, 1);
+        assert_eq!(akash(&router).circuit_closed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fake_backend_serves_canned_responses() {
+        let limited = HttpResponse::new(reqwest::StatusCode::TOO_MANY_REQUESTS, "")
+            .with_header("retry-after", "0");
+        let backend = Arc::new(FakeBackend::default());
+        backend.responses.lock().unwrap().extend([
+            HttpResponse::new(reqwest::StatusCode::OK, "{}"),
+            limited,
+            HttpResponse::new(reqwest::StatusCode::OK, r#"{"choices": []}"#),
+        ]);
+
+        let mut entity = LlmModel::AkashChat.default_entity();
+        entity.base_url = "http://provider.invalid".to_string();
+        entity.max_retries = 1;
+        let config = test_router_config(vec![entity]);
+        let router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+
+        router.warm_up().await;
+        assert!(router.readiness()[0].ready);
+
+        let response = router
+            .send_rate_limited(LlmModel::AkashChat, || {
+                router.client.post("http://provider.invalid/chat")
+            })
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().unwrap();
+        assert!(matches!(
+            completion_contents("akash", body),
+            Err(CwHoError::EmptyCompletion { .. })
+        ));
+        assert_eq!(
+            *backend.urls.lock().unwrap(),
+            vec![
+                "http://provider.invalid/models",
+                "http://provider.invalid/chat",
+                "http://provider.invalid/chat",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_egress_allowlist_blocks_unlisted_hosts() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(akash_completion("hello"));
+        let router = akash_router(backend.clone()).await;
+
+        let blocked = router
+            .send_rate_limited(LlmModel::AkashChat, || {
+                router.client.post("https://exfil.example.com/collect")
+            })
+            .await
+            .unwrap_err();
+        assert!(blocked
+            .to_string()
+            .contains("llm egress to exfil.example.com blocked"));
+        assert!(backend.urls.lock().unwrap().is_empty());
+
+        // The configured provider's endpoint is allowed by default
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }],
+            ..Default::default()
+        };
+        let response = router
+            .process_request(&request, "akash-chat")
+            .await
+            .unwrap();
+        assert_eq!(response.response[0], "hello");
+        assert_eq!(backend.urls.lock().unwrap().len(), 1);
+
+        // An explicit allowlist without the provider blocks it too
+        let router = router.with_egress(EgressPolicy::new(["*.openai.com"]));
+        assert!(router
+            .process_request(&request, "akash-chat")
+            .await
+            .is_err());
+        assert_eq!(backend.urls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_key_renders_auth_hint() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(HttpResponse::new(
+                reqwest::StatusCode::UNAUTHORIZED,
+                r#"{"error": "invalid api key"}"#,
+            ));
+        let router = akash_router(backend).await;
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let err = router
+            .process_request(&request, "akash-chat")
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, CwHoError::Auth { provider, .. } if provider == "AkashChat"));
+        let rendered = crate::error::render_error(&anyhow::Error::from(err));
+        assert!(rendered.contains("invalid api key"), "{}", rendered);
+        assert!(
+            rendered.contains(
+                "hint: check your AkashChat API key in api-keys.json or the AKASH_API_KEY env var"
+            ),
+            "{}",
+            rendered
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_in_flight_request() {
+        /// Never answers; flags when the pending request is dropped
+        #[derive(Default)]
+        struct HangingBackend {
+            started: tokio::sync::Notify,
+            aborted: Arc<std::sync::atomic::AtomicBool>,
+        }
+
+        struct AbortFlag(Arc<std::sync::atomic::AtomicBool>);
+
+        impl Drop for AbortFlag {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl HttpBackend for HangingBackend {
+            async fn send(
+                &self,
+                _request: reqwest::Request,
+            ) -> ho_std::error::HoResult<HttpResponse> {
+                let _flag = AbortFlag(self.aborted.clone());
+                self.started.notify_one();
+                std::future::pending().await
+            }
+        }
+
+        let backend = Arc::new(HangingBackend::default());
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        let router = Arc::new(router);
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+        let call = tokio::spawn({
+            let router = router.clone();
+            async move {
+                router
+                    .process_request_cancellable(&request, "akash-chat", cancel_rx)
+                    .await
+            }
+        });
+        backend.started.notified().await;
+        assert!(!backend.aborted.load(Ordering::SeqCst));
+
+        cancel_tx.send(true).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), call)
+            .await
+            .expect("cancelled request should return promptly")
+            .unwrap();
+        assert!(matches!(result, Err(CwHoError::Cancelled(_))));
+        assert!(backend.aborted.load(Ordering::SeqCst));
+    }
+
+    /// Successful akash completion carrying `content`
+    fn akash_completion(content: &str) -> HttpResponse {
+        HttpResponse::new(
+            reqwest::StatusCode::OK,
+            serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": content}}],
+            })
+            .to_string(),
+        )
+    }
+
+    /// Router that sends akash requests through `backend`
+    async fn akash_router(backend: Arc<FakeBackend>) -> LlmRouter {
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let mut router = LlmRouter::new(&config).await.unwrap().with_backend(backend);
+        router.api_keys.akash = Some("test-key".to_string());
+        router
+    }
+
+    #[tokio::test]
+    async fn test_openai_anthropic_and_grok_answer_with_full_responses() {
+        let openai = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": "hi"}}],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15},
+        });
+        let anthropic = serde_json::json!({
+            "content": [{"type": "text", "text": "hi"}],
+            "usage": {"input_tokens": 10, "output_tokens": 5},
+        });
+        for (provider, body) in [
+            (LlmModel::OpenAi, openai.clone()),
+            (LlmModel::Anthropic, anthropic),
+            (LlmModel::Grok, openai),
+        ] {
+            let backend = Arc::new(FakeBackend::default());
+            backend
+                .responses
+                .lock()
+                .unwrap()
+                .push_back(HttpResponse::new(reqwest::StatusCode::OK, body.to_string()));
+            let entity = provider.default_entity();
+            let config = test_router_config(vec![entity.clone()]);
+            let mut router = LlmRouter::new(&config)
+                .await
+                .unwrap()
+                .with_backend(backend.clone());
+            router.api_keys.openai = Some("test-key".to_string());
+            router.api_keys.anthropic = Some("test-key".to_string());
+            router.api_keys.grok = Some("test-key".to_string());
+
+            let request = PromptRequest {
+                model: entity.default_model.clone(),
+                provider: Some(entity.name.clone()),
+                messages: vec![PromptMessage {
+                    role: "user".to_string(),
+                    content: "hello".to_string(),
+                }],
+                llm_config: Some(LlmPromptConfig {
+                    temperature: 1,
+                    max_tokens: 100,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let response = router
+                .process_request(&request, &request.model)
+                .await
+                .unwrap();
+
+            assert_eq!(backend.urls.lock().unwrap().len(), 1);
+            assert_eq!(response.provider, provider.provider_key());
+            assert_eq!(response.response, ["hi"]);
+            assert_eq!(
+                response.tokens_used,
+                Some(TokenUsage {
+                    prompt: 10,
+                    completion: 5,
+                    total: 15,
+                })
+            );
+            assert_eq!(
+                response.cost,
+                Some(CostCalculator::calculate_cost(
+                    provider.provider_key(),
+                    &request.model,
+                    10,
+                    5
+                ))
+            );
+            assert!(response.latency_ms.is_some());
+            assert!(response.timestamp.is_some());
+            assert_eq!(
+                uuid::Uuid::from_slice(&response.id)
+                    .unwrap()
+                    .get_version_num(),
+                4
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_routed_to_anthropic_gets_default_sampling() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(HttpResponse::new(
+                reqwest::StatusCode::OK,
+                serde_json::json!({
+                    "content": [{"type": "text", "text": "hi"}],
+                    "usage": {"input_tokens": 10, "output_tokens": 5},
+                })
+                .to_string(),
+            ));
+        let config = test_router_config(vec![LlmModel::Anthropic.default_entity()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.anthropic = Some("test-key".to_string());
+
+        let task = CosmicTask {
+            prompt: "hello".to_string(),
+            ..Default::default()
+        };
+        let response = router.answer_task(&task, None).await.unwrap();
+
+        assert_eq!(response.response, ["hi"]);
+        let body = backend.bodies.lock().unwrap()[0].clone();
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_OUTPUT_TOKENS);
+        assert_eq!(body["temperature"], DEFAULT_TEMPERATURE);
+    }
+
+    #[tokio::test]
+    async fn test_request_max_tokens_clamped_to_configured_cap() {
+        let backend = Arc::new(FakeBackend::default());
+        let completion = HttpResponse::new(
+            reqwest::StatusCode::OK,
+            serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "hi"}}],
+            })
+            .to_string(),
+        );
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .extend([completion.clone(), completion]);
+        let entity = LlmEntity {
+            max_output_tokens: Some(512),
+            ..LlmModel::OpenAi.default_entity()
+        };
+        let config = test_router_config(vec![entity.clone()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.openai = Some("test-key".to_string());
+
+        let request = |max_tokens| PromptRequest {
+            model: entity.default_model.clone(),
+            provider: Some(entity.name.clone()),
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "hello".to_string(),
+            }],
+            llm_config: Some(LlmPromptConfig {
+                temperature: 1,
+                max_tokens,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        for max_tokens in [10_000, 0] {
+            let request = request(max_tokens);
+            router
+                .process_request(&request, &request.model)
+                .await
+                .unwrap();
+        }
+
+        let bodies = backend.bodies.lock().unwrap();
+        assert_eq!(bodies[0]["max_tokens"], 512);
+        assert_eq!(bodies[1]["max_tokens"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_takes_a_slot_on_the_entity_answering() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(akash_completion("answered by akash"));
+        let router = akash_router(backend).await;
+        router
+            .add_entity(LlmEntity {
+                enabled: false,
+                ..LlmModel::Grok.default_entity()
+            })
+            .unwrap();
+        router
+            .add_entity(LlmEntity {
+                max_concurrent_requests: Some(1),
+                ..LlmModel::AkashChat.default_entity()
+            })
+            .unwrap();
+
+        let request = PromptRequest {
+            provider: Some("Grok".to_string()),
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "who answers?".to_string(),
+            }],
+            ..Default::default()
+        };
+        router.process_request(&request, "grok-4").await.unwrap();
+
+        let queues = router.metrics().queues;
+        assert_eq!(queues.len(), 1);
+        assert_eq!(queues[0].provider, "AkashChat");
+        assert_eq!(queues[0].acquired, 1);
+    }
+
+    #[tokio::test]
+    async fn test_tools_rejected_for_model_without_tool_support() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(akash_completion("checked the weather"));
+        let router = akash_router(backend.clone()).await;
+        assert!(!LlmModel::AkashChat.capabilities().supports_tools);
+
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "weather in Lisbon?".to_string(),
+            }],
+            tools: vec![ToolDefinition {
+                name: "get_weather".to_string(),
+                description: "Current weather for a city".to_string(),
+                parameters: r#"{"type": "object", "properties": {"city": {"type": "string"}}}"#
+                    .to_string(),
+            }],
+            ..Default::default()
+        };
+        let err = router
+            .process_request(&request, "akash-chat")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CwHoError::InvalidRequest(_)), "{:?}", err);
+        assert_eq!(
+            err.to_string(),
+            "Invalid request: model akash-chat doesn't support tools"
+        );
+        assert!(backend.bodies.lock().unwrap().is_empty());
+
+        // An entity declaring tool support gets the tools sent along
+        let mut entity = LlmModel::AkashChat.default_entity();
+        entity.capabilities = Some(LlmCapabilities {
+            supports_tools: true,
+            ..entity.resolved_capabilities()
+        });
+        router.add_entity(entity).unwrap();
+        router
+            .process_request(&request, "akash-chat")
+            .await
+            .unwrap();
+        let sent = backend.bodies.lock().unwrap();
+        assert_eq!(sent[0]["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(
+            sent[0]["tools"][0]["function"]["parameters"]["properties"]["city"]["type"],
+            "string"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_object_mode_yields_parseable_output() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(akash_completion(
+                "Here you go:\n```json\n{\"status\": \"ready\"}\n```",
+            ));
+        let router = akash_router(backend.clone()).await;
+
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "is the node ready?".to_string(),
+            }],
+            response_format: Some(ResponseFormat::json_object()),
+            ..Default::default()
+        };
+        let response = router
+            .process_request(&request, "akash-chat")
+            .await
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&response.response[0]).unwrap();
+        assert_eq!(parsed["status"], "ready");
+
+        let sent = backend.bodies.lock().unwrap();
+        assert_eq!(sent[0]["response_format"]["type"], "json_object");
+        assert_eq!(sent[0]["messages"][0]["role"], "system");
+    }
+
+    #[tokio::test]
+    async fn test_session_request_replays_earlier_turns() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-session-{}", uuid::Uuid::new_v4()));
+        let storage = CwHoStorage::new(&dir).await.unwrap();
+        let backend = Arc::new(FakeBackend::default());
+        backend.responses.lock().unwrap().extend([
+            akash_completion("Paris"),
+            akash_completion("About two million"),
+        ]);
+        let router = akash_router(backend.clone()).await;
+        let ask = |content: &str| PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: content.to_string(),
+            }],
+            ..Default::default()
+        };
+
+        router
+            .route_request_in_session(
+                &storage,
+                "trip",
+                &ask("Capital of France?"),
+                "akash-chat",
+                tokio::sync::watch::channel(false).1,
+            )
+            .await
+            .unwrap();
+        router
+            .route_request_in_session(
+                &storage,
+                "trip",
+                &ask("Population?"),
+                "akash-chat",
+                tokio::sync::watch::channel(false).1,
+            )
+            .await
+            .unwrap();
+
+        let second = backend.bodies.lock().unwrap()[1].clone();
+        let contents: Vec<&str> = second["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(contents, vec!["Capital of France?", "Paris", "Population?"]);
+        assert_eq!(storage.session_history("trip").await.unwrap().len(), 4);
+        assert!(storage.session_history("other").await.unwrap().is_empty());
+
+        storage.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,102 @@
+//! On-disk log output configured by `LoggingConfig`
+use anyhow::{anyhow, bail, Context, Result};
+use camino::Utf8Path;
+use ho_std::prelude::LoggingConfig;
+use tracing::Subscriber;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{registry::LookupSpan, EnvFilter, Layer};
+
+/// Boxed layer so the file output can be added or left out at runtime
+pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+/// Daily-rotated file layer for `config.file`, or `None` when no file is configured.
+/// The guard flushes buffered lines on drop and must be held until shutdown.
+pub fn file_layer<S>(
+    home: &Utf8Path,
+    config: &LoggingConfig,
+    default_level: &str,
+) -> Result<Option<(BoxedLayer<S>, WorkerGuard)>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let Some(file) = config.file.as_deref().filter(|f| !f.is_empty()) else {
+        return Ok(None);
+    };
+    // absolute paths replace the home directory
+    let path = home.join(file);
+    let (dir, name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => (dir, name),
+        _ => bail!("log file {} is not a file path", path),
+    };
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create log directory {}", dir))?;
+
+    let level = match config.level.is_empty() {
+        true => default_level,
+        false => config.level.as_str(),
+    };
+    let filter =
+        EnvFilter::try_new(level).map_err(|e| anyhow!("invalid log level {}: {}", level, e))?;
+
+    let (writer, guard) =
+        tracing_appender::non_blocking(tracing_appender::rolling::daily(dir, name));
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(false);
+    let layer = match config.format.as_deref().unwrap_or("full") {
+        "full" => layer.with_filter(filter).boxed(),
+        "compact" => layer.compact().with_filter(filter).boxed(),
+        "json" => layer.json().with_filter(filter).boxed(),
+        other => bail!(
+            "unknown log format {}, expected full, compact or json",
+            other
+        ),
+    };
+    Ok(Some((layer, guard)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8PathBuf;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_file_layer_writes_log_file() {
+        let home = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("cw-ho-logs-{}", uuid::Uuid::new_v4())),
+        )
+        .unwrap();
+        let (layer, guard) = file_layer(&home, &LoggingConfig::new(), "info")
+            .unwrap()
+            .unwrap();
+
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("written to disk");
+            tracing::debug!("below the configured level");
+        });
+        // flush the background writer
+        drop(guard);
+
+        let log = std::fs::read_dir(home.join("logs"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.to_string_lossy().contains("cw-ho.log"))
+            .expect("rotated log file should exist");
+        let contents = std::fs::read_to_string(log).unwrap();
+        assert!(contents.contains("written to disk"));
+        assert!(!contents.contains("below the configured level"));
+
+        let disabled = LoggingConfig {
+            file: None,
+            ..LoggingConfig::new()
+        };
+        assert!(
+            file_layer::<tracing_subscriber::Registry>(&home, &disabled, "info")
+                .unwrap()
+                .is_none()
+        );
+        std::fs::remove_dir_all(home).ok();
+    }
+}
@@ -3,11 +3,15 @@ use std::fs;
 use anyhow::{Context, Result};
 use clap::Parser;
 
-use cw_ho::{start, Cli, Commands};
+use cw_ho::{logging::file_layer, start, Cli, Commands, CwHoConfig, OutputFormat};
 use ho_std::config::env::init_env;
+use ho_std::constants::CONFIG_FILE_NAME;
+use ho_std::traits::HoConfigTrait;
 
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -17,20 +21,57 @@ fn main() -> Result<()> {
         .with_context(|| format!("Failed to create home directory {}", cli.home))?;
 
     init_env();
-    // Initialize tracing
+    // Log to a file as well when the existing config asks for it
+    let logging = CwHoConfig::load(cli.home.join(CONFIG_FILE_NAME))
+        .ok()
+        .and_then(|mut config| {
+            if let Some(profile) = cli.profile {
+                profile.apply_logging(&mut config);
+            }
+            config.0.logging
+        });
+    let (file_log, _file_log_guard) = match &logging {
+        Some(logging) => file_layer(&cli.home, logging, cli.log_level())?.unzip(),
+        None => (None, None),
+    };
+
+    // Initialize tracing, keeping stdout clean for json output
+    let console_writer = match cli.output {
+        OutputFormat::Json => BoxMakeWriter::new(std::io::stderr),
+        OutputFormat::Text => BoxMakeWriter::new(std::io::stdout),
+    };
     tracing_subscriber::registry()
+        .with(file_log)
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| cli.log_level.clone().into()),
+            tracing_subscriber::fmt::layer()
+                .with_writer(console_writer)
+                .with_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| cli.log_level().into()),
+                ),
         )
-        .with(tracing_subscriber::fmt::layer())
         .init();
 
-    match cli.command {
-        Commands::Init(cmd) => cmd.init(cli.home.as_path())?,
-        Commands::Start { port } => start(cli, port)?,
-        Commands::ManageAuth(cmd) => cmd.exec(cli.home.as_path())?,
-    }
+    let output = cli.output;
+    let result = match cli.command {
+        Commands::Init(cmd) => cmd.init(cli.home.as_path(), output),
+        Commands::Start {
+            port,
+            bind,
+            ephemeral,
+            runtime,
+        } => start(cli, port, bind, ephemeral, runtime),
+        Commands::ManageAuth(cmd) => cmd.exec(cli.home.as_path(), output),
+        Commands::Bench(cmd) => cmd.exec(cli.home.as_path(), cli.profile, output),
+        Commands::Peers(cmd) => cmd.exec(cli.home.as_path(), output),
+        Commands::Export(cmd) => cmd.exec(cli.home.as_path(), output),
+        Commands::Config(cmd) => cmd.exec(cli.home.as_path(), output),
+        Commands::Replay(cmd) => cmd.exec(cli.home.as_path(), output),
+        Commands::Gc(cmd) => cmd.exec(cli.home.as_path(), output),
+    };
 
+    if let Err(e) = result {
+        output.emit_error(&e);
+        std::process::exit(1);
+    }
     Ok(())
 }
@@ -0,0 +1,634 @@
+//! Routes verified inbound network messages to per-type handlers
+use async_trait::async_trait;
+use commonware_cryptography::ed25519;
+use ho_std::commonware::error::CommonwareNetworkResult;
+use ho_std::commonware::identity::NodePubkey;
+use ho_std::constants::MAX_PEERS;
+use ho_std::prelude::*;
+use ho_std::traits::MessageHandler;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::network::events::NetworkEvents;
+use crate::network::manager::PeerInfo;
+use crate::network::topology::NetworkTopology;
+use crate::CwHoNetworkManifold;
+
+/// Registry of handlers keyed by message kind. The last registration for a kind wins.
+#[derive(Default, Clone)]
+pub struct MessageDispatcher {
+    handlers: HashMap<MessageKind, Arc<dyn MessageHandler>>,
+}
+
+impl MessageDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` for every message of `kind`
+    pub fn register(&mut self, kind: MessageKind, handler: Arc<dyn MessageHandler>) {
+        self.handlers.insert(kind, handler);
+    }
+
+    /// Whether a handler is registered for `kind`
+    pub fn handles(&self, kind: MessageKind) -> bool {
+        self.handlers.contains_key(&kind)
+    }
+
+    /// Hand `message` to its registered handler, returning any reply for the sender.
+    /// Messages without a handler are dropped.
+    pub async fn dispatch(
+        &self,
+        from: &NodePubkey,
+        message: &NetworkMessage,
+    ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+        let Some(kind) = message.message_type.as_ref().map(MessageKind::of) else {
+            debug!("📭 Ignoring empty message from {}", from.node_id());
+            return Ok(None);
+        };
+        match self.handlers.get(&kind) {
+            Some(handler) => handler.handle(from, message).await,
+            None => {
+                debug!("📭 No handler for {:?} from {}", kind, from.node_id());
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Answers liveness probes and records the sender as alive.
+/// A ping carrying a topology is a pong and is not answered, so two nodes never loop;
+/// it completes the round-trip measurement of the ping we sent that peer.
+pub struct PingHandler {
+    node_id: String,
+    topology: Arc<RwLock<NetworkTopology>>,
+    peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>>,
+}
+
+impl PingHandler {
+    pub fn new(
+        node_id: String,
+        topology: Arc<RwLock<NetworkTopology>>,
+        peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>>,
+    ) -> Self {
+        Self {
+            node_id,
+            topology,
+            peers,
+        }
+    }
+}
+
+#[async_trait]
+impl MessageHandler for PingHandler {
+    async fn handle(
+        &self,
+        from: &NodePubkey,
+        message: &NetworkMessage,
+    ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+        let Some(MessageType::TetrahedralPing(ping)) = &message.message_type else {
+            return Ok(None);
+        };
+        let now = chrono::Utc::now();
+        self.topology
+            .write()
+            .await
+            .touch(&from.node_id(), now.timestamp() as u64);
+        if ping.network_topology.is_some() {
+            let received = std::time::Instant::now();
+            if let Some(peer) = self.peers.write().await.get_mut(&from.0) {
+                if let Some(rtt) = peer.record_pong(received) {
+                    debug!("🏓 Round trip to {} took {:?}", from.node_id(), rtt);
+                }
+            }
+            return Ok(None);
+        }
+
+        let pong = TetrahedralPing {
+            from_node: self.node_id.clone(),
+            time: Some(pbjson_types::Timestamp {
+                seconds: now.timestamp(),
+                nanos: now.timestamp_subsec_nanos() as i32,
+            }),
+            network_topology: Some(self.topology.read().await.to_proto()),
+        };
+        Ok(Some(NetworkMessage {
+            message_type: Some(MessageType::TetrahedralPing(pong)),
+        }))
+    }
+}
+
+/// Disconnect reason given to nodes announcing once the peer table is full
+pub const PEER_LIMIT_REACHED: &str = "peer limit reached";
+
+/// How many peers are admitted, and who is admitted regardless
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerLimit {
+    pub max_peers: usize,
+    /// Keys of the configured bootstrap peers, admitted past the limit. Matched on the
+    /// key the connection authenticated with, never on an announced address.
+    pub bootstrap: HashSet<ed25519::PublicKey>,
+}
+
+impl Default for PeerLimit {
+    fn default() -> Self {
+        Self {
+            max_peers: MAX_PEERS as usize,
+            bootstrap: HashSet::new(),
+        }
+    }
+}
+
+impl PeerLimit {
+    /// The configured `limits.max_peers`, never above [`MAX_PEERS`]; unset or zero means
+    /// [`MAX_PEERS`]
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        let configured = config.limits.map(|l| l.max_peers).unwrap_or_default();
+        Self {
+            max_peers: match configured {
+                0 => MAX_PEERS,
+                max => max.min(MAX_PEERS),
+            } as usize,
+            bootstrap: config
+                .bootstrap_nodes()
+                .into_iter()
+                .map(|(key, _)| key.0)
+                .collect(),
+        }
+    }
+
+    /// Whether `peer` may join the `peers` already connected
+    pub fn admits(&self, peers: usize, peer: &ed25519::PublicKey) -> bool {
+        peers < self.max_peers || self.bootstrap.contains(peer)
+    }
+
+    /// Whether `peer`, not yet among `peers`, is turned away at its first message
+    pub fn refuses(
+        &self,
+        peers: &HashMap<ed25519::PublicKey, PeerInfo>,
+        peer: &ed25519::PublicKey,
+    ) -> bool {
+        !peers.contains_key(peer) && !self.admits(peers.len(), peer)
+    }
+}
+
+/// Registers announcing nodes as peers. Re-announcements refresh the advertised details
+/// but keep the connection time and latest round trip. A node whose protocol versions
+/// do not overlap ours, or a new node once the peer limit is reached, is dropped as a
+/// peer with a `PeerDisconnected` giving the reason, and its connection is closed.
+pub struct AnnounceHandler {
+    topology: Arc<RwLock<NetworkTopology>>,
+    peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>>,
+    /// Receives `TopologyChanged` when a peer joins or re-announces with new info
+    events: NetworkEvents,
+    /// Set from the network config when the network starts
+    limit: Arc<std::sync::RwLock<PeerLimit>>,
+    /// Peers whose connection should be closed
+    disconnects: Option<mpsc::UnboundedSender<ed25519::PublicKey>>,
+}
+
+impl AnnounceHandler {
+    pub fn new(
+        topology: Arc<RwLock<NetworkTopology>>,
+        peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>>,
+        events: NetworkEvents,
+    ) -> Self {
+        Self {
+            topology,
+            peers,
+            events,
+            limit: Default::default(),
+            disconnects: None,
+        }
+    }
+
+    /// Admit peers as `limit` allows, read on every announcement so it can be set later
+    pub fn with_limit(mut self, limit: Arc<std::sync::RwLock<PeerLimit>>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Close the connection of each peer dropped through `disconnects`
+    pub fn with_disconnects(
+        mut self,
+        disconnects: mpsc::UnboundedSender<ed25519::PublicKey>,
+    ) -> Self {
+        self.disconnects = Some(disconnects);
+        self
+    }
+
+    fn disconnected(&self, from: &NodePubkey, reason: String) {
+        if let Some(disconnects) = &self.disconnects {
+            let _ = disconnects.send(from.0.clone());
+        }
+        let _ = self.events.send(NetworkEvent {
+            event_type: Some(EventType::PeerDisconnected(PeerDisconnected {
+                peer_id: from.0.to_vec(),
+                reason,
+            })),
+        });
+    }
+}
+
+#[async_trait]
+impl MessageHandler for AnnounceHandler {
+    async fn handle(
+        &self,
+        from: &NodePubkey,
+        message: &NetworkMessage,
+    ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+        let Some(MessageType::NodeAnnounce(announce)) = &message.message_type else {
+            return Ok(None);
+        };
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut peers = self.peers.write().await;
+        let mut topology = self.topology.write().await;
+        if let Err(reason) = PeerInfo::negotiate_protocol(announce) {
+            warn!("🚫 Disconnecting {}: {}", from.node_id(), reason);
+            peers.remove(&from.0);
+            if topology.nodes.contains_key(&from.node_id()) {
+                let before = topology.clone();
+                topology.remove_node(&from.node_id());
+                let _ = self
+                    .events
+                    .send(CwHoNetworkManifold::topology_changed(&before, &topology));
+            }
+            self.disconnected(from, reason);
+            return Ok(None);
+        }
+        let peer = PeerInfo::from_announce(from.clone(), announce, now);
+        match peers.get_mut(&from.0) {
+            // Known peers are updated in place rather than replaced
+            Some(known) => {
+                known.address = peer.address;
+                known.protocol_version = peer.protocol_version;
+                if let Some(event) = known.reconcile(peer.node_info, &mut topology) {
+                    debug!("🔄 Peer {} re-announced with new info", from.node_id());
+                    let _ = self.events.send(event);
+                }
+            }
+            None if !self.limit.read().unwrap().admits(peers.len(), &from.0) => {
+                warn!(
+                    "🚫 Refusing {}: {} ({} peers)",
+                    from.node_id(),
+                    PEER_LIMIT_REACHED,
+                    peers.len()
+                );
+                self.disconnected(from, PEER_LIMIT_REACHED.to_string());
+            }
+            None => {
+                info!(
+                    "🤝 Peer {} connected, protocol v{}",
+                    peer.node_info.node_id, peer.protocol_version
+                );
+                let before = topology.clone();
+                topology.add_node(peer.node_info.clone());
+                peers.insert(from.0.clone(), peer);
+                let _ = self
+                    .events
+                    .send(CwHoNetworkManifold::topology_changed(&before, &topology));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ho_std::commonware::identity::NodePrivKey;
+    use ho_std::constants::PROTOCOL_VERSION;
+    use ho_std::types::cw_ho::network::v1::NetworkLimits;
+    use std::time::Duration;
+    use tokio::sync::{mpsc, Mutex};
+
+    /// Records every ping it is handed
+    #[derive(Default)]
+    struct RecordingHandler {
+        seen: Mutex<Vec<(String, TetrahedralPing)>>,
+    }
+
+    #[async_trait]
+    impl MessageHandler for RecordingHandler {
+        async fn handle(
+            &self,
+            from: &NodePubkey,
+            message: &NetworkMessage,
+        ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+            if let Some(MessageType::TetrahedralPing(ping)) = &message.message_type {
+                self.seen.lock().await.push((from.node_id(), ping.clone()));
+            }
+            Ok(None)
+        }
+    }
+
+    fn ping(topology: Option<ho_std::prelude::NetworkTopology>) -> NetworkMessage {
+        NetworkMessage {
+            message_type: Some(MessageType::TetrahedralPing(TetrahedralPing {
+                from_node: "peer".to_string(),
+                time: None,
+                network_topology: topology,
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_ping_handler_receives_decoded_ping() {
+        let peer = NodePrivKey::from_seed(7).id();
+        let recorder = Arc::new(RecordingHandler::default());
+        let mut dispatcher = MessageDispatcher::new();
+        dispatcher.register(MessageKind::TetrahedralPing, recorder.clone());
+
+        let reply = dispatcher.dispatch(&peer, &ping(None)).await.unwrap();
+        assert!(reply.is_none());
+        let seen = recorder.seen.lock().await;
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].0, peer.node_id());
+        assert_eq!(seen[0].1.from_node, "peer");
+
+        // Kinds without a handler are dropped rather than failing the pump
+        let request = NetworkMessage {
+            message_type: Some(MessageType::Request(Request::default())),
+        };
+        assert!(dispatcher
+            .dispatch(&peer, &request)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ping_handler_pongs_probes_only() {
+        let peer = NodePrivKey::from_seed(7).id();
+        let mut topology = NetworkTopology::new();
+        topology.add_node(NodeInfo {
+            node_id: peer.node_id(),
+            node_type: NodeType::Executor.as_str_name().to_string(),
+            online: false,
+            last_seen: 0,
+            capabilities: Vec::new(),
+        });
+        let topology = Arc::new(RwLock::new(topology));
+        let handler = PingHandler::new("me".to_string(), topology.clone(), Default::default());
+
+        let pong = handler.handle(&peer, &ping(None)).await.unwrap();
+        match pong.and_then(|m| m.message_type) {
+            Some(MessageType::TetrahedralPing(pong)) => {
+                assert_eq!(pong.from_node, "me");
+                assert!(pong.network_topology.is_some());
+            }
+            other => panic!("expected a pong, got {:?}", other),
+        }
+        assert_eq!(topology.read().await.online_nodes().len(), 1);
+
+        let answered = handler
+            .handle(&peer, &ping(Some(Default::default())))
+            .await
+            .unwrap();
+        assert!(answered.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ping_pong_round_updates_peer_rtt() {
+        let peer = NodePrivKey::from_seed(7).id();
+        let topology = Arc::new(RwLock::new(NetworkTopology::new()));
+        let peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>> = Default::default();
+        let announce = NetworkMessage {
+            message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                node_id: peer.node_id(),
+                role: NodeType::Executor.into(),
+                p2p_address: Some("10.0.0.7:26656".to_string()),
+                protocol_version: Some(99),
+                ..Default::default()
+            })),
+        };
+        let (events, _) = mpsc::unbounded_channel();
+        AnnounceHandler::new(topology.clone(), peers.clone(), NetworkEvents::new(events))
+            .handle(&peer, &announce)
+            .await
+            .unwrap();
+        {
+            let peers = peers.read().await;
+            let info = &peers[&peer.0];
+            assert_eq!(info.protocol_version, u32::from(PROTOCOL_VERSION));
+            assert_eq!(info.address, Some("10.0.0.7:26656".parse().unwrap()));
+            assert!(info.rtt.is_none());
+        }
+        assert_eq!(topology.read().await.online_nodes().len(), 1);
+
+        // The scheduler marks the ping as sent, the peer's pong completes the round
+        let sent = std::time::Instant::now() - std::time::Duration::from_millis(25);
+        peers.write().await.get_mut(&peer.0).unwrap().ping_sent = Some(sent);
+        let handler = PingHandler::new("me".to_string(), topology, peers.clone());
+        handler
+            .handle(&peer, &ping(Some(Default::default())))
+            .await
+            .unwrap();
+
+        let status = peers.read().await[&peer.0].to_proto();
+        let rtt = status.rtt_ms.expect("pong should record a round trip");
+        assert!(rtt >= 25.0, "rtt {}ms shorter than the ping's age", rtt);
+        assert_eq!(status.protocol_version, 1);
+
+        // A probe from the peer is answered without touching our measurement
+        handler.handle(&peer, &ping(None)).await.unwrap();
+        assert_eq!(peers.read().await[&peer.0].to_proto().rtt_ms, Some(rtt));
+    }
+
+    #[tokio::test]
+    async fn test_reannounce_reconciles_in_place() {
+        let peer = NodePrivKey::from_seed(7).id();
+        let topology = Arc::new(RwLock::new(NetworkTopology::new()));
+        let peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>> = Default::default();
+        let (events, mut changes) = mpsc::unbounded_channel();
+        let handler =
+            AnnounceHandler::new(topology.clone(), peers.clone(), NetworkEvents::new(events));
+        let announce = |capabilities: &[&str]| NetworkMessage {
+            message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                node_id: peer.node_id(),
+                role: NodeType::Executor.into(),
+                capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+                ..Default::default()
+            })),
+        };
+
+        handler
+            .handle(&peer, &announce(&["minimal"]))
+            .await
+            .unwrap();
+        let Some(EventType::TopologyChanged(joined)) = changes.try_recv().unwrap().event_type
+        else {
+            panic!("expected the new peer to change the topology");
+        };
+        assert_eq!(joined.diff.unwrap().added_nodes.len(), 1);
+        peers.write().await.get_mut(&peer.0).unwrap().rtt = Some(Duration::from_millis(5));
+        let connected_at = peers.read().await[&peer.0].connected_at;
+
+        // Announcing the same info again changes nothing worth reporting
+        handler
+            .handle(&peer, &announce(&["minimal"]))
+            .await
+            .unwrap();
+        assert!(changes.try_recv().is_err());
+
+        handler
+            .handle(&peer, &announce(&["minimal", "gpu"]))
+            .await
+            .unwrap();
+        let Some(EventType::TopologyChanged(changed)) = changes.try_recv().unwrap().event_type
+        else {
+            panic!("expected a topology change");
+        };
+        let diff = changed.diff.unwrap();
+        assert_eq!(diff.changed_nodes.len(), 1);
+        assert_eq!(diff.changed_nodes[0].capabilities, ["minimal", "gpu"]);
+        assert!(diff.added_nodes.is_empty());
+
+        // A node marked offline coming back with the same info is a change, as it is
+        // when the same announcement arrives by gossip
+        topology
+            .write()
+            .await
+            .nodes
+            .get_mut(&peer.node_id())
+            .unwrap()
+            .online = false;
+        handler
+            .handle(&peer, &announce(&["minimal", "gpu"]))
+            .await
+            .unwrap();
+        let Some(EventType::TopologyChanged(_)) = changes.try_recv().unwrap().event_type else {
+            panic!("expected the node coming back online to change the topology");
+        };
+        assert!(topology.read().await.nodes[&peer.node_id()].online);
+
+        // The existing records were updated, not replaced
+        let peers = peers.read().await;
+        assert_eq!(peers[&peer.0].connected_at, connected_at);
+        assert_eq!(peers[&peer.0].rtt, Some(Duration::from_millis(5)));
+        assert_eq!(
+            topology.read().await.nodes[&peer.node_id()].capabilities,
+            ["minimal", "gpu"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_protocol_disconnects_peer() {
+        let peer = NodePrivKey::from_seed(7).id();
+        let topology = Arc::new(RwLock::new(NetworkTopology::new()));
+        let peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>> = Default::default();
+        let (events, mut changes) = mpsc::unbounded_channel();
+        let (disconnects, mut closed) = mpsc::unbounded_channel();
+        let handler =
+            AnnounceHandler::new(topology.clone(), peers.clone(), NetworkEvents::new(events))
+                .with_disconnects(disconnects);
+        let announce = |min_protocol_version, protocol_version| NetworkMessage {
+            message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                node_id: peer.node_id(),
+                role: NodeType::Executor.into(),
+                protocol_version: Some(protocol_version),
+                min_protocol_version: Some(min_protocol_version),
+                ..Default::default()
+            })),
+        };
+
+        // A newer peer that still speaks our version negotiates down to it
+        handler.handle(&peer, &announce(1, 3)).await.unwrap();
+        assert_eq!(peers.read().await[&peer.0].protocol_version, 1);
+        assert!(changes.try_recv().is_ok());
+        assert!(closed.try_recv().is_err());
+
+        // Once it drops our version there is nothing left to speak
+        handler.handle(&peer, &announce(2, 3)).await.unwrap();
+        assert!(peers.read().await.is_empty());
+        assert!(topology.read().await.nodes.is_empty());
+        let Some(EventType::TopologyChanged(left)) = changes.try_recv().unwrap().event_type else {
+            panic!("expected the peer to leave the topology");
+        };
+        assert_eq!(left.diff.unwrap().removed_nodes, [peer.node_id()]);
+        let Some(EventType::PeerDisconnected(disconnected)) =
+            changes.try_recv().unwrap().event_type
+        else {
+            panic!("expected the peer to be disconnected");
+        };
+        assert_eq!(disconnected.peer_id, peer.0.to_vec());
+        assert_eq!(
+            disconnected.reason,
+            "incompatible protocol versions: peer speaks v2-v3, we speak v1-v1"
+        );
+        // Its connection is closed, not just forgotten
+        assert_eq!(closed.try_recv().unwrap(), peer.0);
+    }
+
+    #[tokio::test]
+    async fn test_peers_past_limit_are_refused_except_bootstrap() {
+        let topology = Arc::new(RwLock::new(NetworkTopology::new()));
+        let peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>> = Default::default();
+        let (events, mut changes) = mpsc::unbounded_channel();
+        let (closes, mut closed) = mpsc::unbounded_channel();
+        let bootstrap = NodePrivKey::from_seed(9).id();
+        let config = NetworkConfig {
+            limits: Some(NetworkLimits {
+                max_peers: 2,
+                ..Default::default()
+            }),
+            bootstrap_peers: vec![format!(
+                "{}@10.0.0.9:9000",
+                hex::encode(bootstrap.0.to_vec())
+            )],
+            ..Default::default()
+        };
+        let limit = PeerLimit::from_config(&config);
+        assert_eq!(limit.max_peers, 2);
+        let handler =
+            AnnounceHandler::new(topology.clone(), peers.clone(), NetworkEvents::new(events))
+                .with_limit(Arc::new(std::sync::RwLock::new(limit)))
+                .with_disconnects(closes);
+        let announce = |peer: &NodePubkey, address: &str| NetworkMessage {
+            message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                node_id: peer.node_id(),
+                role: NodeType::Executor.into(),
+                p2p_address: Some(address.to_string()),
+                ..Default::default()
+            })),
+        };
+
+        for seed in 1..=2 {
+            let peer = NodePrivKey::from_seed(seed).id();
+            handler
+                .handle(&peer, &announce(&peer, &format!("10.0.0.{}:9000", seed)))
+                .await
+                .unwrap();
+            assert!(changes.try_recv().is_ok());
+        }
+
+        // The third is one too many, even claiming the bootstrap peer's address
+        let extra = NodePrivKey::from_seed(3).id();
+        handler
+            .handle(&extra, &announce(&extra, "10.0.0.9:9000"))
+            .await
+            .unwrap();
+        assert_eq!(peers.read().await.len(), 2);
+        assert!(!topology.read().await.nodes.contains_key(&extra.node_id()));
+        let Some(EventType::PeerDisconnected(refused)) = changes.try_recv().unwrap().event_type
+        else {
+            panic!("expected the extra peer to be refused");
+        };
+        assert_eq!(refused.peer_id, extra.0.to_vec());
+        assert_eq!(refused.reason, PEER_LIMIT_REACHED);
+        assert_eq!(closed.try_recv().unwrap(), extra.0);
+
+        // The configured bootstrap key still gets in, whatever address it announces
+        handler
+            .handle(&bootstrap, &announce(&bootstrap, "10.0.0.99:9000"))
+            .await
+            .unwrap();
+        assert!(peers.read().await.contains_key(&bootstrap.0));
+        assert!(matches!(
+            changes.try_recv().unwrap().event_type,
+            Some(EventType::TopologyChanged(_))
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+//! Network event sender that also fans topology changes out to live subscribers
+use ho_std::constants::{MAX_TOPOLOGY_SUBSCRIBERS, TOPOLOGY_STREAM_BUFFER};
+use ho_std::prelude::*;
+use tokio::sync::{broadcast, mpsc};
+
+/// Sends `NetworkEvent`s to the manifold's subscriber, copying the diff of every
+/// `TopologyChanged` to each open topology stream
+#[derive(Clone)]
+pub struct NetworkEvents {
+    events: mpsc::UnboundedSender<NetworkEvent>,
+    topology: broadcast::Sender<TopologyDiff>,
+}
+
+impl NetworkEvents {
+    pub fn new(events: mpsc::UnboundedSender<NetworkEvent>) -> Self {
+        let (topology, _) = broadcast::channel(TOPOLOGY_STREAM_BUFFER);
+        Self { events, topology }
+    }
+
+    pub fn send(&self, event: NetworkEvent) -> Result<(), mpsc::error::SendError<NetworkEvent>> {
+        if let Some(EventType::TopologyChanged(TopologyChanged {
+            diff: Some(diff), ..
+        })) = &event.event_type
+        {
+            // No open streams is not an error
+            let _ = self.topology.send(diff.clone());
+        }
+        self.events.send(event)
+    }
+
+    /// Receive every topology diff from now on, `None` once [`MAX_TOPOLOGY_SUBSCRIBERS`]
+    /// streams are open. A receiver more than [`TOPOLOGY_STREAM_BUFFER`] diffs behind
+    /// sees `Lagged` and should be dropped.
+    pub fn subscribe_topology(&self) -> Option<broadcast::Receiver<TopologyDiff>> {
+        (self.topology.receiver_count() < MAX_TOPOLOGY_SUBSCRIBERS)
+            .then(|| self.topology.subscribe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topology_diffs_reach_capped_subscribers() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let events = NetworkEvents::new(tx);
+        let mut streams: Vec<_> = (0..MAX_TOPOLOGY_SUBSCRIBERS)
+            .map(|_| events.subscribe_topology().unwrap())
+            .collect();
+        assert!(events.subscribe_topology().is_none());
+
+        let diff = TopologyDiff {
+            removed_nodes: vec!["gone".to_string()],
+            ..Default::default()
+        };
+        events
+            .send(NetworkEvent {
+                event_type: Some(EventType::TopologyChanged(TopologyChanged {
+                    topology: None,
+                    diff: Some(diff.clone()),
+                })),
+            })
+            .unwrap();
+        events
+            .send(NetworkEvent {
+                event_type: Some(EventType::Error(NetworkError {
+                    error: "not topology".to_string(),
+                })),
+            })
+            .unwrap();
+        assert!(rx.try_recv().is_ok() && rx.try_recv().is_ok());
+        for stream in &mut streams {
+            assert_eq!(stream.try_recv().unwrap(), diff);
+            assert!(stream.try_recv().is_err());
+        }
+
+        // Closing a stream frees its slot
+        streams.pop();
+        assert!(events.subscribe_topology().is_some());
+    }
+}
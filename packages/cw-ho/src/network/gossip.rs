@@ -0,0 +1,378 @@
+//! Relays announcements past the announcing node's direct peers, so every node in a
+//! connected network learns of every other
+//!
+//! A node hearing an announcement from its originator wraps the signed original in a
+//! `GossipRelay` and passes it on. Each node receiving a relay checks the originator's
+//! signature, applies the announcement and passes the relay on to peers not yet on its
+//! path while hops remain. Each announcement carries a signed `seq`; a node keeps the
+//! newest seq of every originator and drops originals no newer than it or too old, so
+//! each node applies an announcement once however many paths it arrives by, and a relay
+//! cannot pass off an older announcement as new.
+use async_trait::async_trait;
+use commonware_cryptography::ed25519;
+use ho_std::commonware::error::{CommonwareNetworkError, CommonwareNetworkResult};
+use ho_std::commonware::identity::NodePubkey;
+use ho_std::constants::{CAPABILITY_GOSSIP, GOSSIP_MAX_AGE_MS, GOSSIP_SEEN_CAPACITY, GOSSIP_TTL};
+use ho_std::prelude::*;
+use ho_std::traits::MessageHandler;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, RwLock};
+use tracing::{debug, info};
+
+use crate::network::dispatch::PeerLimit;
+use crate::network::events::NetworkEvents;
+use crate::network::manager::PeerInfo;
+use crate::network::topology::NetworkTopology;
+use crate::CwHoNetworkManifold;
+
+/// A relay to send on, with the peers to send it to
+pub type Forward = (GossipRelay, Vec<ed25519::PublicKey>);
+
+/// Newest seq relayed per originator, forgetting the earliest seen originator past
+/// capacity
+struct SeenMessages {
+    order: VecDeque<String>,
+    latest: HashMap<String, u64>,
+    capacity: usize,
+}
+
+impl SeenMessages {
+    /// Remember `seq` from `origin`, false when one as new was remembered already
+    fn insert(&mut self, origin: &str, seq: u64) -> bool {
+        if let Some(latest) = self.latest.get_mut(origin) {
+            if *latest >= seq {
+                return false;
+            }
+            *latest = seq;
+            return true;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.latest.remove(&oldest);
+            }
+        }
+        self.order.push_back(origin.to_string());
+        self.latest.insert(origin.to_string(), seq);
+        true
+    }
+}
+
+/// The signed seq of a gossiped `message` announced within [`GOSSIP_MAX_AGE_MS`] of
+/// `now_ms`, `None` when it carries none or is stale
+fn fresh_seq(message: &NetworkMessage, now_ms: u64) -> Option<u64> {
+    let Some(MessageType::NodeAnnounce(announce)) = &message.message_type else {
+        return None;
+    };
+    announce
+        .seq
+        .filter(|seq| seq.saturating_add(GOSSIP_MAX_AGE_MS) >= now_ms)
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// A relay opened by [`Gossip::accept`]
+pub struct Accepted {
+    /// Node that signed the original
+    pub origin: NodePubkey,
+    pub message: NetworkMessage,
+    /// The relay to pass on, `None` once its hops are spent
+    pub onward: Option<GossipRelay>,
+}
+
+/// This node's side of the gossip: which originals it has relayed, and how far new
+/// relays travel
+pub struct Gossip {
+    node_id: String,
+    ttl: u32,
+    seen: Mutex<SeenMessages>,
+}
+
+impl Gossip {
+    pub fn new(node_id: String) -> Self {
+        Self {
+            node_id,
+            ttl: GOSSIP_TTL,
+            seen: Mutex::new(SeenMessages {
+                order: VecDeque::new(),
+                latest: HashMap::new(),
+                capacity: GOSSIP_SEEN_CAPACITY,
+            }),
+        }
+    }
+
+    /// Relay starting `original`, which its originator `from` sent us directly as
+    /// `message`. `None` for kinds that are not gossiped, messages `from` only passed
+    /// on, and announcements without a fresh seq newer than any relayed from `from`.
+    pub fn originate(
+        &self,
+        from: &NodePubkey,
+        message: &NetworkMessage,
+        original: SignedNetworkMessage,
+    ) -> Option<GossipRelay> {
+        let kind = message.message_type.as_ref().map(MessageKind::of)?;
+        if !kind.is_gossiped() || original.signer_key().ok().as_ref() != Some(from) {
+            return None;
+        }
+        let seq = fresh_seq(message, now_ms())?;
+        if !self.seen.lock().unwrap().insert(&from.node_id(), seq) {
+            return None;
+        }
+        Some(GossipRelay {
+            original: Some(original),
+            ttl: self.ttl,
+            path: vec![from.node_id(), self.node_id.clone()],
+        })
+    }
+
+    /// Open `relay`: the originator and its message to apply, and the relay to pass on
+    /// while hops remain. `None` for relays that looped back and originals without a
+    /// fresh seq newer than any seen from their originator. Fails when the original is
+    /// missing, forged or of a kind that is not gossiped.
+    pub fn accept(&self, relay: &GossipRelay) -> CommonwareNetworkResult<Option<Accepted>> {
+        if relay.path.contains(&self.node_id) {
+            debug!(
+                "🔄 Dropping gossip that already passed us: {:?}",
+                relay.path
+            );
+            return Ok(None);
+        }
+        let original = relay.original.as_ref().ok_or_else(|| {
+            CommonwareNetworkError::InvalidSignature("gossip relay without an original".to_string())
+        })?;
+        let origin = original.signer_key()?;
+        let message = original.verify(&origin)?;
+        let kind = message.message_type.as_ref().map(MessageKind::of);
+        if !kind.is_some_and(MessageKind::is_gossiped) {
+            return Err(CommonwareNetworkError::P2P(format!(
+                "{:?} from {} is not gossiped",
+                kind,
+                origin.node_id()
+            )));
+        }
+        let Some(seq) = fresh_seq(&message, now_ms()) else {
+            debug!("⏳ Dropping stale gossip from {}", origin.node_id());
+            return Ok(None);
+        };
+        if !self.seen.lock().unwrap().insert(&origin.node_id(), seq) {
+            debug!(
+                "🔁 Dropping repeated or older gossip from {}",
+                origin.node_id()
+            );
+            return Ok(None);
+        }
+
+        // Relays claiming more hops than we start with get ours
+        let hops = relay.ttl.min(self.ttl);
+        let onward = (hops > 1).then(|| GossipRelay {
+            original: Some(original.clone()),
+            ttl: hops - 1,
+            path: relay
+                .path
+                .iter()
+                .cloned()
+                .chain(std::iter::once(self.node_id.clone()))
+                .collect(),
+        });
+        Ok(Some(Accepted {
+            origin,
+            message,
+            onward,
+        }))
+    }
+
+    /// `relay` with up to `fan_out` of the `peers` that accept gossip and are not on its
+    /// path yet, `None` when there are none
+    pub fn forward(
+        relay: GossipRelay,
+        peers: &HashMap<ed25519::PublicKey, PeerInfo>,
+        fan_out: usize,
+    ) -> Option<Forward> {
+        let targets: Vec<_> = peers
+            .values()
+            .filter(|peer| {
+                peer.supports(CAPABILITY_GOSSIP) && !relay.path.contains(&peer.node_info.node_id)
+            })
+            .take(fan_out)
+            .map(|peer| peer.public_key.0.clone())
+            .collect();
+        (!targets.is_empty()).then_some((relay, targets))
+    }
+}
+
+/// Applies gossiped announcements to the topology and queues each relay to pass on.
+/// Nodes learned of this way join the topology only; they are not peers.
+pub struct GossipHandler {
+    gossip: Arc<Gossip>,
+    topology: Arc<RwLock<NetworkTopology>>,
+    peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>>,
+    /// Receives `TopologyChanged` when a relay brings a new node or new info
+    events: NetworkEvents,
+    /// Relays to pass on, sent by the manifold's gossip forwarder
+    outbox: mpsc::UnboundedSender<Forward>,
+    /// Caps how many peers each relay is passed to
+    limit: Arc<std::sync::RwLock<PeerLimit>>,
+}
+
+impl GossipHandler {
+    pub fn new(
+        gossip: Arc<Gossip>,
+        topology: Arc<RwLock<NetworkTopology>>,
+        peers: Arc<RwLock<HashMap<ed25519::PublicKey, PeerInfo>>>,
+        events: NetworkEvents,
+        outbox: mpsc::UnboundedSender<Forward>,
+    ) -> Self {
+        Self {
+            gossip,
+            topology,
+            peers,
+            events,
+            outbox,
+            limit: Default::default(),
+        }
+    }
+
+    /// Pass relays to at most `limit.max_peers` peers, read on every relay
+    pub fn with_limit(mut self, limit: Arc<std::sync::RwLock<PeerLimit>>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    async fn apply(&self, from: &NodePubkey, origin: NodePubkey, announce: &NodeAnnounce) {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let info = PeerInfo::from_announce(origin, announce, now).node_info;
+        let mut topology = self.topology.write().await;
+        let before = topology.changed_by(&info).then(|| topology.clone());
+        let node_id = info.node_id.clone();
+        topology.add_node(info);
+        if let Some(before) = before {
+            info!("📣 Learned of node {} through {}", node_id, from.node_id());
+            let _ = self
+                .events
+                .send(CwHoNetworkManifold::topology_changed(&before, &topology));
+        }
+    }
+}
+
+#[async_trait]
+impl MessageHandler for GossipHandler {
+    async fn handle(
+        &self,
+        from: &NodePubkey,
+        message: &NetworkMessage,
+    ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+        let Some(MessageType::GossipRelay(relay)) = &message.message_type else {
+            return Ok(None);
+        };
+        let Some(accepted) = self.gossip.accept(relay)? else {
+            return Ok(None);
+        };
+        if let Some(MessageType::NodeAnnounce(announce)) = &accepted.message.message_type {
+            self.apply(from, accepted.origin, announce).await;
+        }
+        if let Some(onward) = accepted.onward {
+            let fan_out = self.limit.read().unwrap().max_peers;
+            if let Some(forward) = Gossip::forward(onward, &*self.peers.read().await, fan_out) {
+                let _ = self.outbox.send(forward);
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ho_std::commonware::identity::NodePrivKey;
+    use ho_std::constants::MAX_PEERS;
+
+    #[tokio::test]
+    async fn test_announce_reaches_end_of_line_through_middle_once() {
+        let keys: Vec<NodePrivKey> = (1..=3).map(NodePrivKey::from_seed).collect();
+        let [a, b, c] = [0, 1, 2].map(|i| keys[i].id());
+        let peer = |key: &NodePubkey| {
+            let announce = NodeAnnounce {
+                node_id: key.node_id(),
+                capabilities: vec![CAPABILITY_GOSSIP.to_string()],
+                ..Default::default()
+            };
+            (
+                key.0.clone(),
+                PeerInfo::from_announce(key.clone(), &announce, 0),
+            )
+        };
+        // A line A–B–C: B is connected to both ends, C only to B
+        let b_peers = HashMap::from([peer(&a), peer(&c)]);
+        let c_peers = Arc::new(RwLock::new(HashMap::from([peer(&b)])));
+
+        let announce_at = |seq: u64| {
+            let announce = NetworkMessage {
+                message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                    node_id: a.node_id(),
+                    role: NodeType::Executor.into(),
+                    capabilities: vec!["minimal".to_string()],
+                    seq: Some(seq),
+                    ..Default::default()
+                })),
+            };
+            let signed = SignedNetworkMessage::sign(&announce, &keys[0]).unwrap();
+            (announce, signed)
+        };
+        let seq = now_ms();
+        let (announce, signed) = announce_at(seq);
+
+        // B hears A directly and passes the announcement to C alone
+        let b_gossip = Gossip::new(b.node_id());
+        let relay = b_gossip
+            .originate(&a, &announce, signed.clone())
+            .expect("an announcement from its originator is gossiped");
+        assert_eq!(relay.path, [a.node_id(), b.node_id()]);
+        let (relay, targets) = Gossip::forward(relay, &b_peers, MAX_PEERS as usize).unwrap();
+        assert_eq!(targets, [c.0.clone()]);
+        assert!(b_gossip.originate(&a, &announce, signed).is_none());
+        // Only a fresh announcement newer than the last one relayed goes out
+        let (older, older_signed) = announce_at(seq - 1);
+        assert!(b_gossip.originate(&a, &older, older_signed).is_none());
+        let (stale, stale_signed) = announce_at(seq - GOSSIP_MAX_AGE_MS - 60_000);
+        assert!(Gossip::new(b.node_id())
+            .originate(&a, &stale, stale_signed)
+            .is_none());
+        let (newer, newer_signed) = announce_at(seq + 1);
+        assert!(b_gossip.originate(&a, &newer, newer_signed).is_some());
+
+        let topology = Arc::new(RwLock::new(NetworkTopology::new()));
+        let (events, mut changes) = mpsc::unbounded_channel();
+        let (outbox, mut relayed) = mpsc::unbounded_channel();
+        let c_handler = GossipHandler::new(
+            Arc::new(Gossip::new(c.node_id())),
+            topology.clone(),
+            c_peers,
+            NetworkEvents::new(events),
+            outbox,
+        );
+        let message = NetworkMessage {
+            message_type: Some(MessageType::GossipRelay(relay)),
+        };
+        // The same relay arriving twice is applied once
+        for _ in 0..2 {
+            c_handler.handle(&b, &message).await.unwrap();
+        }
+
+        let Some(EventType::TopologyChanged(learned)) = changes.try_recv().unwrap().event_type
+        else {
+            panic!("expected C to learn of A");
+        };
+        let added = learned.diff.unwrap().added_nodes;
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].node_id, a.node_id());
+        assert!(changes.try_recv().is_err());
+        assert_eq!(
+            topology.read().await.nodes[&a.node_id()].capabilities,
+            ["minimal"]
+        );
+        // C's only peer is already on the path, so the relay ends there
+        assert!(relayed.try_recv().is_err());
+    }
+}
@@ -1,7 +1,6 @@
 //! Minimal network manager implementation using commonware libraries
 use commonware_codec::DecodeExt;
 use ho_std::commonware::error::{CommonwareNetworkError, CommonwareNetworkResult};
-use ho_std::llm::HoResult;
 use ho_std::prelude::*;
 
 use bytes::Bytes;
@@ -9,31 +8,145 @@ use commonware_cryptography::{ed25519, Signer};
 use commonware_runtime::{tokio::Context, Metrics, Spawner};
 
 use chrono;
-use ho_std::traits::{NetworkMessageTrait, NodeIdentityTrait};
-use std::borrow::Borrow;
+use ho_std::constants::{
+    CAPABILITY_GOSSIP, CAPABILITY_RPC, DEFAULT_NODE_STALE_AFTER_SECS, MIN_PROTOCOL_VERSION,
+    PEER_PING_INTERVAL_SECS, PEER_REQUEST_TIMEOUT_SECS, PROTOCOL_VERSION,
+};
+use ho_std::traits::{MessageHandler, NetworkConfigTrait, NetworkMessageTrait, NodeIdentityTrait};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::time;
-use tracing::info;
+use tracing::{debug, info, warn};
 
-use commonware_p2p::{authenticated, Manager, Recipients};
+use commonware_p2p::{authenticated, Blocker, Manager, Recipients};
 
 use governor::Quota;
 use std::num::NonZeroU32;
 
-use ho_std::commonware::identity::NodePubkey;
+use ho_std::commonware::identity::{NodePrivKey, NodePubkey};
 
+use crate::network::dispatch::{
+    AnnounceHandler, MessageDispatcher, PeerLimit, PingHandler, PEER_LIMIT_REACHED,
+};
+use crate::network::events::NetworkEvents;
+use crate::network::gossip::{Forward, Gossip, GossipHandler};
+use crate::network::queue::{OutboundMessage, PrioritySendQueue};
+use crate::network::rpc::{PendingRequests, ResponseHandler};
 use crate::network::topology::NetworkTopology;
 use crate::CwHoNetworkManifold;
 
+/// Capabilities this node announces
+const OWN_CAPABILITIES: &[&str] = &["minimal", CAPABILITY_RPC, CAPABILITY_GOSSIP];
+
 /// Peer information
 #[derive(Debug, Clone)]
 pub struct PeerInfo {
     pub public_key: NodePubkey,
     pub node_info: NodeInfo,
+    /// p2p socket address from the peer's announcement
+    pub address: Option<SocketAddr>,
+    /// Lower of our and the peer's protocol versions
+    pub protocol_version: u32,
+    /// Unix seconds of the peer's first announcement
+    pub connected_at: u64,
     pub last_seen: std::time::Instant,
+    /// Round trip of the latest answered ping
+    pub rtt: Option<Duration>,
+    /// When the outstanding ping was sent, cleared by its pong
+    pub ping_sent: Option<std::time::Instant>,
+}
+
+impl PeerInfo {
+    /// Peer first heard from through `announce`. Peers that predate protocol
+    /// versioning speak version 1.
+    pub fn from_announce(public_key: NodePubkey, announce: &NodeAnnounce, now: u64) -> Self {
+        let theirs = announce.protocol_version.unwrap_or(1);
+        Self {
+            node_info: NodeInfo {
+                node_id: public_key.node_id(),
+                node_type: announce.role().as_str_name().to_string(),
+                online: true,
+                last_seen: now,
+                capabilities: announce.capabilities.clone(),
+            },
+            public_key,
+            address: announce
+                .p2p_address
+                .as_deref()
+                .and_then(|address| address.parse().ok()),
+            protocol_version: theirs.min(PROTOCOL_VERSION.into()),
+            connected_at: now,
+            last_seen: std::time::Instant::now(),
+            rtt: None,
+            ping_sent: None,
+        }
+    }
+
+    /// Protocol version to speak with the node behind `announce`, or why there is none.
+    /// Nodes that do not announce the oldest version they speak still speak version 1.
+    pub fn negotiate_protocol(announce: &NodeAnnounce) -> Result<u32, String> {
+        let theirs = announce.protocol_version.unwrap_or(1);
+        let their_min = announce.min_protocol_version.unwrap_or(1).min(theirs);
+        let agreed = theirs.min(PROTOCOL_VERSION.into());
+        if agreed < u32::from(MIN_PROTOCOL_VERSION) || agreed < their_min {
+            return Err(format!(
+                "incompatible protocol versions: peer speaks v{}-v{}, we speak v{}-v{}",
+                their_min, theirs, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            ));
+        }
+        Ok(agreed)
+    }
+
+    /// Whether the peer announced `capability`
+    pub fn supports(&self, capability: &str) -> bool {
+        self.node_info.capabilities.iter().any(|c| c == capability)
+    }
+
+    /// Record a pong received `at`, measuring the round trip of the outstanding ping.
+    /// Unsolicited pongs leave the last measurement in place.
+    pub fn record_pong(&mut self, at: std::time::Instant) -> Option<Duration> {
+        let sent = self.ping_sent.take()?;
+        self.rtt = Some(at.saturating_duration_since(sent));
+        self.rtt
+    }
+
+    /// Fold `info` from a re-announcement into this peer and its topology entry in place.
+    /// Returns the `TopologyChanged` event when [`NetworkTopology::changed_by`] says so,
+    /// the same rule gossiped announcements are applied by.
+    pub fn reconcile(
+        &mut self,
+        info: NodeInfo,
+        topology: &mut NetworkTopology,
+    ) -> Option<NetworkEvent> {
+        self.last_seen = std::time::Instant::now();
+        self.node_info = NodeInfo {
+            node_id: self.node_info.node_id.clone(),
+            online: true,
+            ..info
+        };
+
+        let before = topology
+            .changed_by(&self.node_info)
+            .then(|| topology.clone());
+        topology.add_node(self.node_info.clone());
+        before.map(|before| CwHoNetworkManifold::topology_changed(&before, topology))
+    }
+
+    /// Snapshot served by the peers route and metrics
+    pub fn to_proto(&self) -> PeerStatus {
+        let now = chrono::Utc::now().timestamp() as u64;
+        PeerStatus {
+            node_info: Some(self.node_info.clone()),
+            address: self.address.map(|address| address.to_string()),
+            protocol_version: self.protocol_version,
+            connected_at: self.connected_at,
+            last_seen: now.saturating_sub(self.last_seen.elapsed().as_secs()),
+            rtt_ms: self.rtt.map(|rtt| rtt.as_secs_f64() * 1000.0),
+        }
+    }
 }
 
 impl CwHoNetworkManifold {
@@ -51,9 +164,9 @@ impl CwHoNetworkManifold {
 
         // Create event channel
         let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let event_tx = NetworkEvents::new(event_tx);
 
-        // We'll initialize the network components inside a spawned task
-        // For now, create empty containers that will be filled later
+        // Filled when start_network registers the channels
         let channel_senders = HashMap::new();
         let channel_receivers = HashMap::new();
 
@@ -62,12 +175,55 @@ impl CwHoNetworkManifold {
 
         // Add ourselves to the topology
         let our_info = NodeInfo {
-            node_id: identity.display_id(),
+            node_id: identity.node_id(),
             node_type: identity.node_type.clone(),
             online: true,
             last_seen: chrono::Utc::now().timestamp() as u64,
+            capabilities: OWN_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
         };
         topology.add_node(our_info);
+        let topology = Arc::new(RwLock::new(topology));
+
+        let peers = Arc::new(RwLock::new(HashMap::new()));
+        let peer_limit = Arc::new(std::sync::RwLock::new(PeerLimit::default()));
+        let (disconnect_tx, disconnect_rx) = mpsc::unbounded_channel();
+        let mut dispatcher = MessageDispatcher::new();
+        dispatcher.register(
+            MessageKind::TetrahedralPing,
+            Arc::new(PingHandler::new(
+                identity.node_id(),
+                topology.clone(),
+                peers.clone(),
+            )),
+        );
+        dispatcher.register(
+            MessageKind::NodeAnnounce,
+            Arc::new(
+                AnnounceHandler::new(topology.clone(), peers.clone(), event_tx.clone())
+                    .with_limit(peer_limit.clone())
+                    .with_disconnects(disconnect_tx.clone()),
+            ),
+        );
+        let gossip = Arc::new(Gossip::new(identity.node_id()));
+        let (gossip_tx, gossip_rx) = mpsc::unbounded_channel();
+        dispatcher.register(
+            MessageKind::GossipRelay,
+            Arc::new(
+                GossipHandler::new(
+                    gossip.clone(),
+                    topology.clone(),
+                    peers.clone(),
+                    event_tx.clone(),
+                    gossip_tx.clone(),
+                )
+                .with_limit(peer_limit.clone()),
+            ),
+        );
+        let pending_requests = Arc::new(PendingRequests::new());
+        dispatcher.register(
+            MessageKind::Response,
+            Arc::new(ResponseHandler::new(pending_requests.clone())),
+        );
 
         // Network will be started separately using start_network method
         // Background tasks and announcements will be handled there
@@ -77,8 +233,19 @@ impl CwHoNetworkManifold {
             network_running: Arc::new(RwLock::new(false)),
             channel_senders,
             channel_receivers,
-            peers: Arc::new(RwLock::new(HashMap::new())),
-            topology: Arc::new(RwLock::new(topology)),
+            send_queue: Arc::new(std::sync::Mutex::new(PrioritySendQueue::new())),
+            send_ready: Arc::new(tokio::sync::Notify::new()),
+            peers,
+            topology,
+            dispatcher: Arc::new(RwLock::new(dispatcher)),
+            pending_requests,
+            node_stale_after: Duration::from_secs(DEFAULT_NODE_STALE_AFTER_SECS),
+            peer_limit,
+            gossip,
+            gossip_tx,
+            gossip_rx: Some(gossip_rx),
+            disconnect_tx,
+            disconnect_rx: Some(disconnect_rx),
             event_tx,
             event_rx: Some(event_rx),
             shutdown: Arc::new(RwLock::new(false)),
@@ -95,6 +262,9 @@ impl CwHoNetworkManifold {
             .ok_or_else(|| CommonwareNetworkError::NotInitialized)?
             .clone();
 
+        self.node_stale_after = Duration::from_secs(config.node_stale_after_secs());
+        *self.peer_limit.write().unwrap() = PeerLimit::from_config(config);
+
         // Parse listen address
         let listen_addr = self.identity.p2p_address();
 
@@ -120,28 +290,41 @@ impl CwHoNetworkManifold {
             commonware_config,
         );
 
-        oracle
-            .update(0, vec![(public_key, listen_addr)].into())
-            .await;
+        // Bootstrap peers configured with their key are dialable from the start
+        let bootstrap = config
+            .bootstrap_nodes()
+            .into_iter()
+            .map(|(key, address)| (key.0, address))
+            .filter(|(key, _)| *key != public_key);
+        let known: Vec<_> = std::iter::once((public_key.clone(), listen_addr))
+            .chain(bootstrap)
+            .collect();
+        oracle.update(0, known.into()).await;
+
+        // Dropped peers are blocked, which closes their connection and refuses redials
+        if let Some(mut disconnects) = self.disconnect_rx.take() {
+            self.context
+                .with_label("disconnects")
+                .spawn(move |_| async move {
+                    while let Some(peer) = disconnects.recv().await {
+                        debug!(
+                            "🔌 Closing the connection to {}",
+                            NodePubkey(peer.clone()).node_id()
+                        );
+                        oracle.block(peer).await;
+                    }
+                });
+        }
 
         // Register channels and get senders/receivers
         let rate_quota = Quota::per_second(NonZeroU32::new(100).unwrap());
         let channels = config.channels.expect("channels does not exist");
-        // Channel 0: Discovery
-        let (_discovery_sender, _discovery_receiver) =
-            network.register(0, rate_quota, channels.discovery_buffer.try_into().unwrap());
-
-        // Channel 1: Tasks
-        let (_task_sender, _task_receiver) =
-            network.register(1, rate_quota, channels.task_buffer.try_into().unwrap());
-
-        // Channel 2: State
-        let (_state_sender, _state_receiver) =
-            network.register(2, rate_quota, channels.state_buffer.try_into().unwrap());
-
-        // Channel 3: Health
-        let (_health_sender, _health_receiver) =
-            network.register(3, rate_quota, channels.health_buffer.try_into().unwrap());
+        // Discovery, tasks, state and health, each buffered as configured
+        for (channel, buffer) in channels.buffers() {
+            let (sender, receiver) = network.register(channel.into(), rate_quota, buffer);
+            self.channel_senders.insert(channel, sender);
+            self.channel_receivers.insert(channel, receiver);
+        }
 
         // Start the network
         let network_handle = network.start();
@@ -149,9 +332,6 @@ impl CwHoNetworkManifold {
         // Store network handle for future shutdown
         *self.network_running.write().await = true;
 
-        // TODO: Store senders/receivers for use by the manager
-        // TODO: Start background message processing tasks
-
         // Spawn background task to monitor network
         let shutdown = self.shutdown.clone();
         let network_running = self.network_running.clone();
@@ -166,6 +346,8 @@ impl CwHoNetworkManifold {
             *network_running.write().await = false;
         });
 
+        self.start_background_tasks().await;
+
         info!("🌐 Network started in background");
         Ok(())
     }
@@ -189,79 +371,252 @@ impl CwHoNetworkManifold {
             ));
         }
 
-        let channel = msg.channel()?;
-        let bytes = self.serialize_message(&msg)?;
-
-        let sender = self.channel_senders.get_mut(&channel).ok_or_else(|| {
-            CommonwareNetworkError::ChannelError(format!("Channel {} not found", channel))
-        })?;
-
-        use commonware_p2p::Sender;
-        sender
-            .send(Recipients::Some(targets), bytes, false)
-            .await
-            .map_err(|e| CommonwareNetworkError::P2P(format!("{:?}", e)))?;
-
-        Ok(())
+        let recipients =
+            capable_recipients(&msg, Recipients::Some(targets), &peers).ok_or_else(|| {
+                CommonwareNetworkError::UnsupportedByPeer(format!(
+                    "no {} peer supports {:?}",
+                    role.as_str_name(),
+                    msg.message_type.as_ref().map(MessageKind::of)
+                ))
+            })?;
+        drop(peers);
+        self.enqueue(&msg, recipients)
     }
 
     /// Broadcast a message to all peers
     pub async fn broadcast(&mut self, msg: NetworkMessage) -> CommonwareNetworkResult<()> {
-        let channel = msg.channel()?;
-        let bytes = self.serialize_message(&msg)?;
-
         // TODO: Use commonware-broadcast for efficient dissemination
         // if msg.is_broadcast() {
         //     let mut broadcast = self.broadcast.write().await;
         //     // TODO: Implement broadcast integration
         // }
 
-        let sender = self.channel_senders.get_mut(&channel).ok_or_else(|| {
-            CommonwareNetworkError::ChannelError(format!("Channel {} not found", channel))
-        })?;
-
-        use commonware_p2p::Sender;
-        sender
-            .send(Recipients::All, bytes, false)
-            .await
-            .map_err(|e| CommonwareNetworkError::P2P(format!("{:?}", e)))?;
+        // Newer message kinds only reach the peers that announced support for them
+        let recipients = capable_recipients(&msg, Recipients::All, &*self.peers.read().await);
+        match recipients {
+            Some(recipients) => self.enqueue(&msg, recipients),
+            None => {
+                debug!(
+                    "📭 No peer supports {:?}, nothing to broadcast",
+                    msg.message_type.as_ref().map(MessageKind::of)
+                );
+                Ok(())
+            }
+        }
+    }
 
+    /// Sign a message and queue it for the send worker by priority
+    fn enqueue(
+        &self,
+        msg: &NetworkMessage,
+        recipients: Recipients<ed25519::PublicKey>,
+    ) -> CommonwareNetworkResult<()> {
+        let outbound = self.checked_outbound(msg, recipients)?;
+        Self::push(&self.send_queue, &self.send_ready, outbound);
         Ok(())
     }
 
-    /// Request data with timeout
-    pub async fn request(
-        &mut self,
-        peer: ed25519::PublicKey,
-        req: NetworkMessage,
-        timeout: Duration,
-    ) -> CommonwareNetworkResult<NetworkMessage> {
-        let msgtype = &req.message_type;
+    /// [`Self::outbound`], failing when no sender is registered for the message's channel
+    fn checked_outbound(
+        &self,
+        msg: &NetworkMessage,
+        recipients: Recipients<ed25519::PublicKey>,
+    ) -> CommonwareNetworkResult<OutboundMessage> {
+        let channel = msg.channel()?;
+        if !self.channel_senders.contains_key(&channel) {
+            return Err(CommonwareNetworkError::ChannelError(format!(
+                "Channel {} not found",
+                channel
+            )));
+        }
+        Self::outbound(&self.identity, msg, recipients)
+    }
 
-        let _request_id = match &msgtype.clone().expect("always will have MessageType") {
-            MessageType::Request(r) => &r.request_id,
-            _ => &uuid::Uuid::new_v4().to_string(),
+    /// Queue `outbound` by priority and wake the send worker
+    fn push(
+        queue: &std::sync::Mutex<PrioritySendQueue<OutboundMessage>>,
+        ready: &tokio::sync::Notify,
+        outbound: OutboundMessage,
+    ) {
+        queue
+            .lock()
+            .expect("send queue poisoned")
+            .push(outbound.priority, outbound);
+        ready.notify_one();
+    }
+
+    /// Send `request` to `peer` under a fresh `request_id`, resolving with the response that
+    /// carries the same id. Fails with `CollectorTimeout` when none arrives in time, and
+    /// with `UnsupportedByPeer` unless the peer announced [`CAPABILITY_RPC`].
+    /// The peer answers through whatever handler it registered for `MessageKind::Request`.
+    pub fn send_request(
+        &self,
+        peer: ed25519::PublicKey,
+        mut request: Request,
+    ) -> impl std::future::Future<Output = CommonwareNetworkResult<Response>> + Send + 'static {
+        let reply = self
+            .pending_requests
+            .register(&NodePubkey(peer.clone()), &mut request);
+        let msg = NetworkMessage {
+            message_type: Some(MessageType::Request(request)),
         };
-        // TODO: Register with collector
-        // let mut collector = self.collector.write().await;
-        // TODO: Implement collector integration for request-response
+        let outbound = self.checked_outbound(&msg, Recipients::One(peer.clone()));
+        let peers = self.peers.clone();
+        let queue = self.send_queue.clone();
+        let ready = self.send_ready.clone();
+        async move {
+            let outbound = outbound?;
+            if capable_recipients(&msg, Recipients::One(peer.clone()), &*peers.read().await)
+                .is_none()
+            {
+                return Err(CommonwareNetworkError::UnsupportedByPeer(format!(
+                    "{} did not announce {}",
+                    NodePubkey(peer).node_id(),
+                    CAPABILITY_RPC
+                )));
+            }
+            Self::push(&queue, &ready, outbound);
+            reply
+                .wait(Duration::from_secs(PEER_REQUEST_TIMEOUT_SECS))
+                .await
+        }
+    }
 
-        // Send request
-        let channel = req.channel()?;
-        let bytes = self.serialize_message(&req)?;
+    /// Route every inbound message of `kind` to `handler`, replacing any earlier handler
+    pub async fn register_handler(&self, kind: MessageKind, handler: Arc<dyn MessageHandler>) {
+        self.dispatcher.write().await.register(kind, handler);
+    }
 
-        let sender = self.channel_senders.get_mut(&channel).expect("yuh");
+    /// Handle `message` as if `from` had just sent it, as the receive loop does
+    #[cfg(test)]
+    pub(crate) async fn deliver(
+        &self,
+        from: &NodePubkey,
+        message: &NetworkMessage,
+    ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+        let admitted = Self::admit(
+            &from.0,
+            &*self.peers.read().await,
+            &self.peer_limit.read().unwrap(),
+            &self.disconnect_tx,
+            &self.event_tx,
+        );
+        if !admitted {
+            return Ok(None);
+        }
+        self.dispatcher.read().await.dispatch(from, message).await
+    }
 
-        use commonware_p2p::Sender;
-        sender.send(Recipients::One(peer), bytes, true).await?;
+    /// Handle `signed` as if `from` had just sent it, relaying it on like the receive loop
+    #[cfg(test)]
+    pub(crate) async fn deliver_signed(
+        &self,
+        from: &NodePubkey,
+        signed: &SignedNetworkMessage,
+    ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+        let bytes = Bytes::from(serde_json::to_vec(signed)?);
+        let admitted = Self::admit(
+            &from.0,
+            &*self.peers.read().await,
+            &self.peer_limit.read().unwrap(),
+            &self.disconnect_tx,
+            &self.event_tx,
+        );
+        if !admitted {
+            return Ok(None);
+        }
+        let msg = Self::deserialize_message(&bytes, &from.0, &*self.peers.read().await)?;
+        let reply = self.dispatcher.read().await.dispatch(from, &msg).await;
+        let fan_out = self.peer_limit.read().unwrap().max_peers;
+        Self::relay_original(
+            &self.gossip,
+            &self.gossip_tx,
+            &*self.peers.read().await,
+            fan_out,
+            from,
+            &msg,
+            &bytes,
+        );
+        reply
+    }
 
-        // Wait for response with timeout
-        tokio::time::timeout(timeout, async {
-            // TODO: Wait for response using collector
-            Err(CommonwareNetworkError::CollectorTimeout)
-        })
-        .await
-        .map_err(|_| CommonwareNetworkError::CollectorTimeout)?
+    /// Queue `msg`, which `from` sent us as `bytes`, for gossip to up to `fan_out` of
+    /// `peers` when it is an original [`Gossip::originate`] relays
+    fn relay_original(
+        gossip: &Gossip,
+        gossip_tx: &mpsc::UnboundedSender<Forward>,
+        peers: &HashMap<ed25519::PublicKey, PeerInfo>,
+        fan_out: usize,
+        from: &NodePubkey,
+        msg: &NetworkMessage,
+        bytes: &Bytes,
+    ) {
+        let gossiped = msg
+            .message_type
+            .as_ref()
+            .is_some_and(|m| MessageKind::of(m).is_gossiped());
+        if !gossiped {
+            return;
+        }
+        let relay = serde_json::from_slice(bytes)
+            .ok()
+            .and_then(|original| gossip.originate(from, msg, original));
+        if let Some(forward) = relay.and_then(|relay| Gossip::forward(relay, peers, fan_out)) {
+            let _ = gossip_tx.send(forward);
+        }
+    }
+
+    /// Whether anything `peer` sent may be handled. A peer that is not connected yet is
+    /// turned away once the peer table is full, unless it is a bootstrap peer: its
+    /// connection is closed and a `PeerDisconnected` emitted before its message is read.
+    fn admit(
+        peer: &ed25519::PublicKey,
+        peers: &HashMap<ed25519::PublicKey, PeerInfo>,
+        limit: &PeerLimit,
+        disconnects: &mpsc::UnboundedSender<ed25519::PublicKey>,
+        events: &NetworkEvents,
+    ) -> bool {
+        if !limit.refuses(peers, peer) {
+            return true;
+        }
+        warn!(
+            "🚫 Refusing {}: {} ({} peers)",
+            NodePubkey(peer.clone()).node_id(),
+            PEER_LIMIT_REACHED,
+            peers.len()
+        );
+        let _ = disconnects.send(peer.clone());
+        let _ = events.send(NetworkEvent {
+            event_type: Some(EventType::PeerDisconnected(PeerDisconnected {
+                peer_id: peer.to_vec(),
+                reason: PEER_LIMIT_REACHED.to_string(),
+            })),
+        });
+        false
+    }
+
+    /// Connected peers, ordered by node id
+    pub async fn peer_statuses(&self) -> Vec<PeerStatus> {
+        let peers = self.peers.read().await;
+        let mut peers: Vec<&PeerInfo> = peers.values().collect();
+        peers.sort_by(|a, b| a.node_info.node_id.cmp(&b.node_info.node_id));
+        peers.into_iter().map(PeerInfo::to_proto).collect()
+    }
+
+    /// Reconcile a re-announced `info` with what we hold for `public_key`, emitting
+    /// `TopologyChanged` only when something actually changed. Returns whether it did;
+    /// unknown peers are left to their first announcement.
+    pub async fn reconcile_peer(&self, public_key: &NodePubkey, info: NodeInfo) -> bool {
+        let mut peers = self.peers.write().await;
+        let Some(peer) = peers.get_mut(&public_key.0) else {
+            return false;
+        };
+        let event = peer.reconcile(info, &mut *self.topology.write().await);
+        let changed = event.is_some();
+        if let Some(event) = event {
+            let _ = self.event_tx.send(event);
+        }
+        changed
     }
 
     /// Get current network topology
@@ -269,6 +624,16 @@ impl CwHoNetworkManifold {
         self.topology.read().await.clone()
     }
 
+    /// The current topology and every diff after it, `None` when too many streams are
+    /// open. Subscribing comes first so no change is missed; one racing the snapshot
+    /// may show up in both.
+    pub async fn topology_stream(
+        &self,
+    ) -> Option<(NetworkTopology, broadcast::Receiver<TopologyDiff>)> {
+        let diffs = self.event_tx.subscribe_topology()?;
+        Some((self.get_topology().await, diffs))
+    }
+
     /// Subscribe to network events
     pub fn subscribe(&mut self) -> mpsc::UnboundedReceiver<NetworkEvent> {
         self.event_rx.take().expect("Event receiver already taken")
@@ -277,12 +642,16 @@ impl CwHoNetworkManifold {
     /// Announce this node to the network
     async fn announce_node(&mut self) -> CommonwareNetworkResult<()> {
         let msg = MessageType::NodeAnnounce(NodeAnnounce {
-            node_id: hex::encode(&self.identity.public_key.clone().expect("no pubkey set yet")),
+            node_id: self.identity.node_id(),
             role: NodeType::from_str_name(&self.identity.node_type.clone())
                 .expect("always have a valid node type")
                 .into(),
-            capabilities: vec!["minimal".to_string()], // Simplified capabilities
+            capabilities: OWN_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
             load_factor: 0.0.to_string(),
+            p2p_address: Some(self.identity.p2p_address().to_string()),
+            protocol_version: Some(PROTOCOL_VERSION.into()),
+            min_protocol_version: Some(MIN_PROTOCOL_VERSION.into()),
+            seq: Some(chrono::Utc::now().timestamp_millis() as u64),
         });
 
         self.broadcast(NetworkMessage {
@@ -300,18 +669,143 @@ impl CwHoNetworkManifold {
             }
         }
 
+        // Drain the outbound queue
+        self.spawn_send_worker();
+        self.spawn_gossip_forwarder();
+
         // Start periodic tasks
         self.spawn_periodic_tasks();
+        self.spawn_ping_scheduler();
+    }
+
+    /// Spawn the task that pings every peer to measure round-trip time.
+    /// `PingHandler` completes the measurement when the pong arrives.
+    fn spawn_ping_scheduler(&self) {
+        let peers = self.peers.clone();
+        let identity = self.identity.clone();
+        let queue = self.send_queue.clone();
+        let ready = self.send_ready.clone();
+        let shutdown = self.shutdown.clone();
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(PEER_PING_INTERVAL_SECS));
+
+            while !*shutdown.read().await {
+                interval.tick().await;
+
+                let sent_at = std::time::Instant::now();
+                let targets: Vec<ed25519::PublicKey> = peers
+                    .write()
+                    .await
+                    .iter_mut()
+                    .map(|(key, peer)| {
+                        peer.ping_sent = Some(sent_at);
+                        key.clone()
+                    })
+                    .collect();
+                if targets.is_empty() {
+                    continue;
+                }
+
+                let now = chrono::Utc::now();
+                let ping = NetworkMessage {
+                    message_type: Some(MessageType::TetrahedralPing(TetrahedralPing {
+                        from_node: identity.node_id(),
+                        time: Some(pbjson_types::Timestamp {
+                            seconds: now.timestamp(),
+                            nanos: now.timestamp_subsec_nanos() as i32,
+                        }),
+                        network_topology: None,
+                    })),
+                };
+                match Self::outbound(&identity, &ping, Recipients::Some(targets)) {
+                    Ok(outbound) => Self::push(&queue, &ready, outbound),
+                    Err(e) => warn!("🏓 Failed to queue peer pings: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Spawn the worker that sends queued messages in priority order
+    fn spawn_send_worker(&self) {
+        let mut senders = self.channel_senders.clone();
+        let queue = self.send_queue.clone();
+        let ready = self.send_ready.clone();
+        let shutdown = self.shutdown.clone();
+
+        tokio::spawn(async move {
+            while !*shutdown.read().await {
+                let next = queue.lock().expect("send queue poisoned").pop();
+                let Some(outbound) = next else {
+                    ready.notified().await;
+                    continue;
+                };
+                let Some(sender) = senders.get_mut(&outbound.channel) else {
+                    warn!(
+                        "📭 Dropping message for unknown channel {}",
+                        outbound.channel
+                    );
+                    continue;
+                };
+                use commonware_p2p::Sender;
+                if let Err(e) = sender
+                    .send(
+                        outbound.recipients,
+                        outbound.bytes,
+                        outbound.priority >= MessagePriority::High,
+                    )
+                    .await
+                {
+                    warn!(
+                        "📤 Failed to send {:?} message on channel {}: {:?}",
+                        outbound.priority, outbound.channel, e
+                    );
+                }
+            }
+        });
+    }
+
+    /// Spawn the task signing gossip relays and queueing them for the peers chosen for them
+    fn spawn_gossip_forwarder(&mut self) {
+        let Some(mut relays) = self.gossip_rx.take() else {
+            return;
+        };
+        let identity = self.identity.clone();
+        let queue = self.send_queue.clone();
+        let ready = self.send_ready.clone();
+        let shutdown = self.shutdown.clone();
+
+        tokio::spawn(async move {
+            while let Some((relay, targets)) = relays.recv().await {
+                if *shutdown.read().await {
+                    break;
+                }
+                let msg = NetworkMessage {
+                    message_type: Some(MessageType::GossipRelay(relay)),
+                };
+                match Self::outbound(&identity, &msg, Recipients::Some(targets)) {
+                    Ok(outbound) => Self::push(&queue, &ready, outbound),
+                    Err(e) => warn!("⚠️ Failed to relay gossip: {}", e),
+                }
+            }
+        });
     }
 
     /// Spawn handler for a specific channel
-    fn spawn_channel_handler(
-        &self,
-        channel: u8,
-        mut receiver: authenticated::lookup::Receiver<ed25519::PublicKey>,
-    ) {
+    fn spawn_channel_handler<R>(&self, channel: u8, mut receiver: R)
+    where
+        R: commonware_p2p::Receiver<PublicKey = ed25519::PublicKey>,
+    {
         let peers = self.peers.clone();
-        let _topology = self.topology.clone();
+        let topology = self.topology.clone();
+        let dispatcher = self.dispatcher.clone();
+        let identity = self.identity.clone();
+        let queue = self.send_queue.clone();
+        let ready = self.send_ready.clone();
+        let gossip = self.gossip.clone();
+        let gossip_tx = self.gossip_tx.clone();
+        let peer_limit = self.peer_limit.clone();
+        let disconnects = self.disconnect_tx.clone();
         let event_tx = self.event_tx.clone();
         let shutdown = self.shutdown.clone();
 
@@ -320,23 +814,117 @@ impl CwHoNetworkManifold {
                 use commonware_p2p::Receiver;
                 match receiver.recv().await {
                     Ok((peer_key, bytes)) => {
-                        // Process message
-                        if let Ok(msg) = Self::deserialize_message(&bytes) {
-                            // Update peer info
-                            if let Some(peer_info) = peers.read().await.get(peer_key.borrow()) {
-                                let mut peer_info = peer_info.clone();
-                                peer_info.last_seen = std::time::Instant::now();
-                                peers.write().await.insert(peer_key.clone(), peer_info);
+                        let admitted = Self::admit(
+                            &peer_key,
+                            &*peers.read().await,
+                            &peer_limit.read().unwrap(),
+                            &disconnects,
+                            &event_tx,
+                        );
+                        if !admitted {
+                            continue;
+                        }
+                        // Process message, dropping anything that fails signature checks
+                        let opened = {
+                            let known_peers = peers.read().await;
+                            Self::deserialize_message(&bytes, &peer_key, &known_peers)
+                        };
+                        let msg = match opened {
+                            Ok(msg) => msg,
+                            Err(e) => {
+                                warn!(
+                                    "🚫 Rejected message on channel {} from {}: {}",
+                                    channel,
+                                    hex::encode(peer_key.to_vec()),
+                                    e
+                                );
+                                continue;
                             }
-                            // Send event
-                            let _ = event_tx.send(NetworkEvent {
-                                event_type: Some(EventType::MessageReceived(MessageReceived {
-                                    from: peer_key.to_vec(),
-                                    message: Some(msg),
-                                    channel: channel.into(),
-                                })),
+                        };
+                        // Update peer info under a single write guard
+                        if let Some(peer_info) = peers.write().await.get_mut(&peer_key) {
+                            peer_info.last_seen = std::time::Instant::now();
+                        }
+                        // Any message proves the sender is alive
+                        let node_id = NodePubkey(peer_key.clone()).node_id();
+                        let now = chrono::Utc::now().timestamp() as u64;
+                        let mut topo = topology.write().await;
+                        // only an offline node can come back, so only then is a snapshot needed
+                        let before = topo
+                            .nodes
+                            .get(&node_id)
+                            .filter(|info| !info.online)
+                            .map(|_| topo.clone());
+                        if let (true, Some(before)) = (topo.touch(&node_id, now), before) {
+                            info!("🟢 Node {} is back online", node_id);
+                            let _ = event_tx.send(Self::topology_changed(&before, &topo));
+                        }
+                        drop(topo);
+                        // Hand off to the registered handler, answering on the same channel
+                        let from = NodePubkey(peer_key.clone());
+                        let reply = dispatcher
+                            .read()
+                            .await
+                            .dispatch(&from, &msg)
+                            .await
+                            .and_then(|reply| {
+                                reply
+                                    .map(|reply| {
+                                        Self::serialize_message(&identity, &reply)
+                                            .map(|bytes| (reply, bytes))
+                                    })
+                                    .transpose()
                             });
+                        match reply {
+                            Ok(Some((reply, bytes))) => {
+                                let recipients = capable_recipients(
+                                    &reply,
+                                    Recipients::One(peer_key.clone()),
+                                    &*peers.read().await,
+                                );
+                                match recipients {
+                                    Some(recipients) => Self::push(
+                                        &queue,
+                                        &ready,
+                                        OutboundMessage {
+                                            channel,
+                                            recipients,
+                                            bytes,
+                                            priority: reply.priority(),
+                                        },
+                                    ),
+                                    None => debug!(
+                                        "📭 Not replying to {}: it does not support {:?}",
+                                        node_id,
+                                        reply.message_type.as_ref().map(MessageKind::of)
+                                    ),
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!(
+                                "⚠️ Handler failed for message on channel {} from {}: {}",
+                                channel, node_id, e
+                            ),
                         }
+                        // Pass what the originator told us on to the peers it may not reach
+                        let fan_out = peer_limit.read().unwrap().max_peers;
+                        Self::relay_original(
+                            &gossip,
+                            &gossip_tx,
+                            &*peers.read().await,
+                            fan_out,
+                            &from,
+                            &msg,
+                            &bytes,
+                        );
+                        // Send event
+                        let _ = event_tx.send(NetworkEvent {
+                            event_type: Some(EventType::MessageReceived(MessageReceived {
+                                from: peer_key.to_vec(),
+                                message: Some(msg),
+                                channel: channel.into(),
+                            })),
+                        });
                     }
                     Err(e) => {
                         let _ = event_tx.send(NetworkEvent {
@@ -350,12 +938,26 @@ impl CwHoNetworkManifold {
         });
     }
 
+    /// Topology event carrying the current snapshot and its diff from `before`
+    pub(crate) fn topology_changed(
+        before: &NetworkTopology,
+        topology: &NetworkTopology,
+    ) -> NetworkEvent {
+        NetworkEvent {
+            event_type: Some(EventType::TopologyChanged(TopologyChanged {
+                topology: Some(topology.to_proto()),
+                diff: Some(before.diff(topology)),
+            })),
+        }
+    }
+
     /// Spawn periodic maintenance tasks
     fn spawn_periodic_tasks(&self) {
         let peers = self.peers.clone();
         let topology = self.topology.clone();
         let event_tx = self.event_tx.clone();
-        let _identity = self.identity.clone();
+        let own_id = self.identity.node_id();
+        let stale_after = self.node_stale_after.as_secs();
         let shutdown = self.shutdown.clone();
 
         // Periodic health check
@@ -389,20 +991,67 @@ impl CwHoNetworkManifold {
                         });
                     }
                 }
+                drop(peers_write);
+
+                // Flip silent nodes offline; we are always alive to ourselves
+                let now = chrono::Utc::now().timestamp() as u64;
+                let mut topo = topology.write().await;
+                topo.touch(&own_id, now);
+                let before = topo.clone();
+                let went_offline = topo.mark_stale(now, stale_after);
+                if !went_offline.is_empty() {
+                    warn!("🔴 Marked stale nodes offline: {:?}", went_offline);
+                    let _ = event_tx.send(Self::topology_changed(&before, &topo));
+                }
             }
         });
     }
 
-    /// Serialize a network message
-    fn serialize_message(&self, msg: &NetworkMessage) -> HoResult<Bytes> {
-        let json = serde_json::to_vec(msg)?;
+    /// Sign `msg` into an entry for the send queue
+    fn outbound(
+        identity: &NodeIdentity,
+        msg: &NetworkMessage,
+        recipients: Recipients<ed25519::PublicKey>,
+    ) -> CommonwareNetworkResult<OutboundMessage> {
+        Ok(OutboundMessage {
+            channel: msg.channel()?,
+            recipients,
+            bytes: Self::serialize_message(identity, msg)?,
+            priority: msg.priority(),
+        })
+    }
+
+    /// Serialize a network message, signed with our identity key
+    fn serialize_message(
+        identity: &NodeIdentity,
+        msg: &NetworkMessage,
+    ) -> CommonwareNetworkResult<Bytes> {
+        let private_key = identity
+            .private_key
+            .as_deref()
+            .and_then(NodePrivKey::from_bytes)
+            .ok_or(CommonwareNetworkError::NodePrivKeyNotFound)?;
+        let signed = SignedNetworkMessage::sign(msg, &private_key)?;
+        let json = serde_json::to_vec(&signed)?;
         Ok(Bytes::from(json))
     }
 
-    /// Deserialize a network message
-    fn deserialize_message(bytes: &Bytes) -> HoResult<NetworkMessage> {
-        let msg = serde_json::from_slice(bytes)?;
-        Ok(msg)
+    /// Deserialize a signed network message received from `from`.
+    /// The signer must be the sending peer itself or another known peer whose message is being relayed.
+    fn deserialize_message(
+        bytes: &Bytes,
+        from: &ed25519::PublicKey,
+        peers: &HashMap<ed25519::PublicKey, PeerInfo>,
+    ) -> CommonwareNetworkResult<NetworkMessage> {
+        let signed: SignedNetworkMessage = serde_json::from_slice(bytes)?;
+        let signer = signed.signer_key()?;
+        if &signer.0 != from && !peers.contains_key(&signer.0) {
+            return Err(CommonwareNetworkError::InvalidSignature(format!(
+                "unknown signer {}",
+                hex::encode(&signed.signer)
+            )));
+        }
+        signed.verify(&signer)
     }
 
     /// Shutdown the network manager
@@ -413,7 +1062,274 @@ impl CwHoNetworkManifold {
         // Mark network as not running
         *self.network_running.write().await = false;
 
+        // Wake the send worker so it observes the flag
+        self.send_ready.notify_one();
+
         // The commonware network will see the shutdown flag and gracefully stop
         // All spawned tasks (channel handlers, periodic tasks) will also see the flag and exit
     }
 }
+
+/// `recipients` narrowed to the peers that announced the capability `msg` requires,
+/// `None` when none are left. Kinds every protocol version decodes go out unchanged.
+pub(crate) fn capable_recipients(
+    msg: &NetworkMessage,
+    recipients: Recipients<ed25519::PublicKey>,
+    peers: &HashMap<ed25519::PublicKey, PeerInfo>,
+) -> Option<Recipients<ed25519::PublicKey>> {
+    let Some(capability) = msg
+        .message_type
+        .as_ref()
+        .and_then(|message| MessageKind::of(message).required_capability())
+    else {
+        return Some(recipients);
+    };
+    let capable = |key: &ed25519::PublicKey| peers.get(key).is_some_and(|p| p.supports(capability));
+    match recipients {
+        Recipients::One(key) => capable(&key).then_some(Recipients::One(key)),
+        Recipients::Some(keys) => {
+            let keys: Vec<_> = keys.into_iter().filter(|key| capable(key)).collect();
+            (!keys.is_empty()).then_some(Recipients::Some(keys))
+        }
+        Recipients::All => {
+            let keys: Vec<_> = peers.keys().filter(|key| capable(key)).cloned().collect();
+            (!keys.is_empty()).then_some(Recipients::Some(keys))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_lacking_capability_never_receives_gated_kind() {
+        let announced = |seed: u64, capabilities: &[&str]| {
+            let key = NodePrivKey::from_seed(seed).id();
+            let announce = NodeAnnounce {
+                capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+                ..Default::default()
+            };
+            (key.0.clone(), PeerInfo::from_announce(key, &announce, 0))
+        };
+        let (current, current_info) = announced(1, OWN_CAPABILITIES);
+        let (older, older_info) = announced(2, &["minimal"]);
+        let peers: HashMap<_, _> =
+            [(current.clone(), current_info), (older.clone(), older_info)].into();
+        let message = |message_type| NetworkMessage {
+            message_type: Some(message_type),
+        };
+        let targets =
+            |msg: &NetworkMessage, recipients| match capable_recipients(msg, recipients, &peers) {
+                Some(Recipients::Some(keys)) => keys,
+                Some(Recipients::One(key)) => vec![key],
+                Some(Recipients::All) => peers.keys().cloned().collect(),
+                None => Vec::new(),
+            };
+
+        for gated in [
+            message(MessageType::Request(Request::default())),
+            message(MessageType::Response(Response::default())),
+        ] {
+            assert_eq!(targets(&gated, Recipients::All), [current.clone()]);
+            assert_eq!(
+                targets(
+                    &gated,
+                    Recipients::Some(vec![older.clone(), current.clone()])
+                ),
+                [current.clone()]
+            );
+            assert!(targets(&gated, Recipients::One(older.clone())).is_empty());
+        }
+        // Kinds every version decodes still reach the older peer
+        let ping = message(MessageType::TetrahedralPing(TetrahedralPing::default()));
+        assert_eq!(targets(&ping, Recipients::One(older.clone())), [older]);
+    }
+
+    #[test]
+    fn test_full_peer_table_refuses_first_message_and_closes_connection() {
+        use commonware_runtime::tokio::{Config as RuntimeConfig, Runner};
+        use commonware_runtime::Runner as _;
+
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let mut manifold = CwHoNetworkManifold::new(&NodeIdentity::new(), context).await;
+            let mut events = manifold.event_rx.take().unwrap();
+            let mut closed = manifold.disconnect_rx.take().unwrap();
+            let bootstrap = NodePrivKey::from_seed(9).id();
+            let config = NetworkConfig {
+                limits: Some(ho_std::types::cw_ho::network::v1::NetworkLimits {
+                    max_peers: 1,
+                    ..Default::default()
+                }),
+                bootstrap_peers: vec![format!(
+                    "{}@10.0.0.9:9000",
+                    hex::encode(bootstrap.0.to_vec())
+                )],
+                ..Default::default()
+            };
+            *manifold.peer_limit.write().unwrap() = PeerLimit::from_config(&config);
+            let announce = |key: &NodePubkey| NetworkMessage {
+                message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                    node_id: key.node_id(),
+                    role: NodeType::Executor.into(),
+                    p2p_address: Some("10.0.0.9:9000".to_string()),
+                    ..Default::default()
+                })),
+            };
+
+            let first = NodePrivKey::from_seed(1).id();
+            manifold.deliver(&first, &announce(&first)).await.unwrap();
+            assert!(events.try_recv().is_ok());
+
+            // A stranger is turned away before its announcement is handled, however it
+            // describes itself, and its connection is closed
+            let stranger = NodePrivKey::from_seed(2).id();
+            manifold
+                .deliver(&stranger, &announce(&stranger))
+                .await
+                .unwrap();
+            let Some(EventType::PeerDisconnected(refused)) = events.try_recv().unwrap().event_type
+            else {
+                panic!("expected the stranger to be refused");
+            };
+            assert_eq!(refused.peer_id, stranger.0.to_vec());
+            assert_eq!(refused.reason, PEER_LIMIT_REACHED);
+            assert_eq!(closed.try_recv().unwrap(), stranger.0);
+            assert!(events.try_recv().is_err());
+
+            // Connected peers and the bootstrap key still get through
+            manifold.deliver(&first, &announce(&first)).await.unwrap();
+            manifold
+                .deliver(&bootstrap, &announce(&bootstrap))
+                .await
+                .unwrap();
+            assert_eq!(manifold.peer_statuses().await.len(), 2);
+            assert!(closed.try_recv().is_err());
+        });
+    }
+
+    /// Stands in for a p2p channel, yielding what the test sends it
+    #[derive(Debug)]
+    struct TestReceiver(mpsc::UnboundedReceiver<(ed25519::PublicKey, Bytes)>);
+
+    impl commonware_p2p::Receiver for TestReceiver {
+        type Error = std::io::Error;
+        type PublicKey = ed25519::PublicKey;
+
+        async fn recv(
+            &mut self,
+        ) -> Result<commonware_p2p::Message<ed25519::PublicKey>, Self::Error> {
+            self.0
+                .recv()
+                .await
+                .ok_or_else(|| std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn test_receive_loop_handles_known_peer_and_refreshes_last_seen() {
+        use commonware_runtime::tokio::{Config as RuntimeConfig, Runner};
+        use commonware_runtime::Runner as _;
+
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let mut manifold = CwHoNetworkManifold::new(&NodeIdentity::new(), context).await;
+            let mut events = manifold.event_rx.take().unwrap();
+            let key = NodePrivKey::from_seed(3);
+            let peer = key.id();
+            let announce = NetworkMessage {
+                message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                    node_id: peer.node_id(),
+                    role: NodeType::Executor.into(),
+                    ..Default::default()
+                })),
+            };
+            manifold.deliver(&peer, &announce).await.unwrap();
+            while events.try_recv().is_ok() {}
+            let seen = manifold.peers.read().await[&peer.0].last_seen;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+
+            let (sender, receiver) = mpsc::unbounded_channel();
+            manifold.spawn_channel_handler(1, TestReceiver(receiver));
+            // A pong, so the handler has nothing to answer
+            let pong = NetworkMessage {
+                message_type: Some(MessageType::TetrahedralPing(TetrahedralPing {
+                    from_node: peer.node_id(),
+                    network_topology: Some(Default::default()),
+                    ..Default::default()
+                })),
+            };
+            let signed = SignedNetworkMessage::sign(&pong, &key).unwrap();
+            sender
+                .send((
+                    peer.0.clone(),
+                    Bytes::from(serde_json::to_vec(&signed).unwrap()),
+                ))
+                .unwrap();
+
+            let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+                .await
+                .expect("the receive loop handles a known peer's message")
+                .unwrap();
+            let Some(EventType::MessageReceived(received)) = event.event_type else {
+                panic!("expected the pong to be received");
+            };
+            assert_eq!(received.from, peer.0.to_vec());
+            assert!(manifold.peers.read().await[&peer.0].last_seen > seen);
+        });
+    }
+
+    #[test]
+    fn test_received_announce_is_gossiped_once_per_fresh_seq() {
+        use commonware_runtime::tokio::{Config as RuntimeConfig, Runner};
+        use commonware_runtime::Runner as _;
+        use ho_std::constants::{CAPABILITY_GOSSIP, GOSSIP_MAX_AGE_MS};
+
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let mut manifold = CwHoNetworkManifold::new(&NodeIdentity::new(), context).await;
+            let mut relayed = manifold.gossip_rx.take().unwrap();
+            let keys: Vec<NodePrivKey> = (1..=2).map(NodePrivKey::from_seed).collect();
+            let [origin, listener] = [0, 1].map(|i| keys[i].id());
+            let announce = |key: &NodePubkey, seq: Option<u64>| NetworkMessage {
+                message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                    node_id: key.node_id(),
+                    role: NodeType::Executor.into(),
+                    capabilities: vec![CAPABILITY_GOSSIP.to_string()],
+                    seq,
+                    ..Default::default()
+                })),
+            };
+            let signed = |seq: u64| {
+                SignedNetworkMessage::sign(&announce(&origin, Some(seq)), &keys[0]).unwrap()
+            };
+            // A peer that takes gossip, so there is someone to relay to
+            manifold
+                .deliver(&listener, &announce(&listener, None))
+                .await
+                .unwrap();
+
+            let seq = chrono::Utc::now().timestamp_millis() as u64;
+            manifold
+                .deliver_signed(&origin, &signed(seq))
+                .await
+                .unwrap();
+            let (relay, targets) = relayed.try_recv().expect("a fresh announcement is relayed");
+            assert_eq!(relay.path[0], origin.node_id());
+            assert_eq!(targets, [listener.0.clone()]);
+
+            // Replays, older announcements and stale ones go no further, however they
+            // arrive; a newer one does
+            for seq in [seq, seq - 1, seq - GOSSIP_MAX_AGE_MS - 60_000] {
+                manifold
+                    .deliver_signed(&origin, &signed(seq))
+                    .await
+                    .unwrap();
+                assert!(relayed.try_recv().is_err());
+            }
+            manifold
+                .deliver_signed(&origin, &signed(seq + 1))
+                .await
+                .unwrap();
+            assert!(relayed.try_recv().is_ok());
+        });
+    }
+}
@@ -5,7 +5,14 @@
 //! tetrahedral topology.
 
 pub mod config;
+pub mod dispatch;
+pub mod events;
+pub mod gossip;
 pub mod manager;
+pub mod queue;
+pub mod rpc;
 pub mod topology;
 
+pub use dispatch::MessageDispatcher;
+pub use events::NetworkEvents;
 pub use topology::NetworkTopology;
@@ -0,0 +1,112 @@
+//! Priority-ordered outbound send queue for the network manifold
+use bytes::Bytes;
+use commonware_cryptography::ed25519;
+use commonware_p2p::Recipients;
+use ho_std::prelude::MessagePriority;
+use std::collections::VecDeque;
+
+/// Sends granted to each priority per scheduling round, highest first
+const PRIORITY_WEIGHTS: [u32; 4] = [8, 4, 2, 1];
+
+/// A signed message waiting for the send worker
+pub struct OutboundMessage {
+    pub channel: u8,
+    pub recipients: Recipients<ed25519::PublicKey>,
+    pub bytes: Bytes,
+    pub priority: MessagePriority,
+}
+
+/// Outbound queue ordered by priority. Uses weighted round-robin so bulk
+/// traffic still makes progress while control messages are flowing.
+pub struct PrioritySendQueue<T> {
+    queues: [VecDeque<T>; 4],
+    credits: [u32; 4],
+}
+
+impl<T> Default for PrioritySendQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PrioritySendQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            queues: Default::default(),
+            credits: PRIORITY_WEIGHTS,
+        }
+    }
+
+    fn slot(priority: MessagePriority) -> usize {
+        match priority {
+            MessagePriority::Critical => 0,
+            MessagePriority::High => 1,
+            MessagePriority::Normal | MessagePriority::Unspecified => 2,
+            MessagePriority::Low => 3,
+        }
+    }
+
+    /// Queue an item behind others of the same priority
+    pub fn push(&mut self, priority: MessagePriority, item: T) {
+        self.queues[Self::slot(priority)].push_back(item);
+    }
+
+    /// Next item to send, highest priority with remaining credit first
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        loop {
+            for slot in 0..self.queues.len() {
+                if self.credits[slot] > 0 && !self.queues[slot].is_empty() {
+                    self.credits[slot] -= 1;
+                    return self.queues[slot].pop_front();
+                }
+            }
+            // Every waiting priority has used its share, start a new round
+            self.credits = PRIORITY_WEIGHTS;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(VecDeque::is_empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_priority_jumps_low_backlog() {
+        let mut queue = PrioritySendQueue::new();
+        for i in 0..10 {
+            queue.push(MessagePriority::Low, format!("sync-{}", i));
+        }
+        queue.push(MessagePriority::Critical, "ping".to_string());
+        queue.push(MessagePriority::High, "announce".to_string());
+
+        assert_eq!(queue.pop().as_deref(), Some("ping"));
+        assert_eq!(queue.pop().as_deref(), Some("announce"));
+        assert_eq!(queue.pop().as_deref(), Some("sync-0"));
+        assert_eq!(queue.len(), 9);
+    }
+
+    #[test]
+    fn test_low_priority_is_not_starved() {
+        let mut queue = PrioritySendQueue::new();
+        queue.push(MessagePriority::Low, "sync");
+        for _ in 0..100 {
+            queue.push(MessagePriority::High, "request");
+        }
+
+        let position = std::iter::from_fn(|| queue.pop())
+            .position(|item| item == "sync")
+            .unwrap();
+        assert_eq!(position, PRIORITY_WEIGHTS[1] as usize);
+    }
+}
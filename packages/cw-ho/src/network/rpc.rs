@@ -0,0 +1,273 @@
+//! Correlates outbound `Request`s with the `Response` a peer sends back
+use async_trait::async_trait;
+use ho_std::commonware::error::{CommonwareNetworkError, CommonwareNetworkResult};
+use ho_std::commonware::identity::NodePubkey;
+use ho_std::prelude::*;
+use ho_std::traits::MessageHandler;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+/// A request waiting for its response: the peer it was sent to, and where to deliver it
+type Waiting = (NodePubkey, oneshot::Sender<Response>);
+
+/// Requests sent to peers that are still waiting for their response, by request id
+#[derive(Default)]
+pub struct PendingRequests {
+    waiting: Mutex<HashMap<String, Waiting>>,
+}
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stamp `request` with a fresh id and start waiting for the response carrying it,
+    /// which only `peer` may send
+    pub fn register(self: &Arc<Self>, peer: &NodePubkey, request: &mut Request) -> PendingReply {
+        request.request_id = uuid::Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel();
+        self.lock()
+            .insert(request.request_id.clone(), (peer.clone(), tx));
+        PendingReply {
+            request_id: request.request_id.clone(),
+            reply: rx,
+            pending: self.clone(),
+        }
+    }
+
+    /// Hand `response` from `from` to the request it answers. Returns false when nothing
+    /// is waiting for it, because it timed out or was never sent, and when `from` is not
+    /// the peer the request was sent to; the request then keeps waiting for that peer.
+    pub fn resolve(&self, from: &NodePubkey, response: Response) -> bool {
+        let mut waiting = self.lock();
+        let Entry::Occupied(entry) = waiting.entry(response.request_id.clone()) else {
+            return false;
+        };
+        let peer = &entry.get().0;
+        if peer != from {
+            warn!(
+                "🚫 Dropping response {} from {}: the request was sent to {}",
+                response.request_id,
+                from.node_id(),
+                peer.node_id()
+            );
+            return false;
+        }
+        let (_, reply) = entry.remove();
+        reply.send(response).is_ok()
+    }
+
+    /// Number of requests still waiting
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Waiting>> {
+        self.waiting.lock().expect("pending requests poisoned")
+    }
+}
+
+/// The response to one registered request. Dropping it stops waiting.
+pub struct PendingReply {
+    request_id: String,
+    reply: oneshot::Receiver<Response>,
+    pending: Arc<PendingRequests>,
+}
+
+impl PendingReply {
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// Wait up to `timeout` for the peer's response
+    pub async fn wait(mut self, timeout: Duration) -> CommonwareNetworkResult<Response> {
+        match tokio::time::timeout(timeout, &mut self.reply).await {
+            Ok(Ok(response)) => Ok(response),
+            _ => Err(CommonwareNetworkError::CollectorTimeout),
+        }
+    }
+}
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        self.pending.lock().remove(&self.request_id);
+    }
+}
+
+/// Resolves inbound responses against the requests waiting for them
+pub struct ResponseHandler {
+    pending: Arc<PendingRequests>,
+}
+
+impl ResponseHandler {
+    pub fn new(pending: Arc<PendingRequests>) -> Self {
+        Self { pending }
+    }
+}
+
+#[async_trait]
+impl MessageHandler for ResponseHandler {
+    async fn handle(
+        &self,
+        from: &NodePubkey,
+        message: &NetworkMessage,
+    ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+        let Some(MessageType::Response(response)) = &message.message_type else {
+            return Ok(None);
+        };
+        if !self.pending.resolve(from, response.clone()) {
+            debug!(
+                "📭 Dropping response {} from {}: no request of theirs is waiting for it",
+                response.request_id,
+                from.node_id()
+            );
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::dispatch::MessageDispatcher;
+    use ho_std::commonware::identity::NodePrivKey;
+    use tokio::sync::mpsc;
+
+    /// Answers every request with its own payload, echoing the request id
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MessageHandler for EchoHandler {
+        async fn handle(
+            &self,
+            _from: &NodePubkey,
+            message: &NetworkMessage,
+        ) -> CommonwareNetworkResult<Option<NetworkMessage>> {
+            let Some(MessageType::Request(request)) = &message.message_type else {
+                return Ok(None);
+            };
+            Ok(Some(NetworkMessage {
+                message_type: Some(MessageType::Response(Response {
+                    request_id: request.request_id.clone(),
+                    success: true,
+                    payload: request.payload.clone(),
+                })),
+            }))
+        }
+    }
+
+    fn request(task: &str) -> Request {
+        let task = pbjson_types::Value {
+            kind: Some(pbjson_types::value::Kind::StringValue(task.to_string())),
+        };
+        Request {
+            request_id: String::new(),
+            payload: Some(pbjson_types::Struct {
+                fields: [("task".to_string(), task)].into(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_resolves_with_correlated_response() {
+        let us = NodePrivKey::from_seed(1).id();
+        let peer = NodePrivKey::from_seed(2).id();
+        let pending = Arc::new(PendingRequests::new());
+        let mut ours = MessageDispatcher::new();
+        ours.register(
+            MessageKind::Response,
+            Arc::new(ResponseHandler::new(pending.clone())),
+        );
+        let mut theirs = MessageDispatcher::new();
+        theirs.register(MessageKind::Request, Arc::new(EchoHandler));
+
+        // An in-process peer answering over a channel, as the network would
+        let (to_peer, mut peer_inbox) = mpsc::unbounded_channel::<NetworkMessage>();
+        let peer_loop = tokio::spawn({
+            let (us, peer) = (us.clone(), peer.clone());
+            async move {
+                while let Some(message) = peer_inbox.recv().await {
+                    if let Some(reply) = theirs.dispatch(&us, &message).await.unwrap() {
+                        ours.dispatch(&peer, &reply).await.unwrap();
+                    }
+                }
+            }
+        });
+
+        let mut first = request("first");
+        let mut second = request("second");
+        let first_reply = pending.register(&peer, &mut first);
+        let second_reply = pending.register(&peer, &mut second);
+        assert_ne!(first.request_id, second.request_id);
+        assert_eq!(pending.len(), 2);
+
+        // Sent in reverse, each reply still lands on its own request
+        for sent in [second, first] {
+            to_peer
+                .send(NetworkMessage {
+                    message_type: Some(MessageType::Request(sent)),
+                })
+                .unwrap();
+        }
+        let first_id = first_reply.request_id().to_string();
+        let response = first_reply.wait(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(response.request_id, first_id);
+        assert_eq!(response.payload, request("first").payload);
+        let response = second_reply.wait(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(response.payload, request("second").payload);
+        assert!(pending.is_empty());
+
+        // Unanswered requests time out and stop waiting
+        drop(to_peer);
+        peer_loop.await.unwrap();
+        let mut unanswered = request("lost");
+        let lost = pending.register(&peer, &mut unanswered);
+        assert!(matches!(
+            lost.wait(Duration::from_millis(10)).await,
+            Err(CommonwareNetworkError::CollectorTimeout)
+        ));
+        assert!(pending.is_empty());
+        assert!(!pending.resolve(
+            &peer,
+            Response {
+                request_id: unanswered.request_id,
+                ..Default::default()
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_response_from_another_peer_is_rejected() {
+        let peer = NodePrivKey::from_seed(2).id();
+        let intruder = NodePrivKey::from_seed(3).id();
+        let pending = Arc::new(PendingRequests::new());
+        let handler = ResponseHandler::new(pending.clone());
+
+        let mut sent = request("private");
+        let reply = pending.register(&peer, &mut sent);
+        let answer = |payload: &str| NetworkMessage {
+            message_type: Some(MessageType::Response(Response {
+                request_id: sent.request_id.clone(),
+                success: true,
+                payload: request(payload).payload,
+            })),
+        };
+
+        // Another node guessing or replaying the id does not answer it
+        handler.handle(&intruder, &answer("forged")).await.unwrap();
+        assert_eq!(pending.len(), 1);
+
+        handler.handle(&peer, &answer("genuine")).await.unwrap();
+        let response = reply.wait(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(response.payload, request("genuine").payload);
+        assert!(pending.is_empty());
+    }
+}
@@ -1,7 +1,9 @@
 //! Network topology management
 //! // TODO: refactor into storage layer
 
+use crate::error::{CwHoError, Result};
 use ho_std::prelude::*;
+use ho_std::types::cw_ho::network::v1::Connection;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -26,6 +28,45 @@ impl NetworkTopology {
         self.nodes.insert(info.node_id.clone(), info);
     }
 
+    /// Whether adding `info` would change the topology: the node is unknown or offline,
+    /// or its type or capabilities differ. Other fields such as `last_seen` are no change.
+    pub fn changed_by(&self, info: &NodeInfo) -> bool {
+        self.nodes.get(&info.node_id).is_none_or(|known| {
+            !known.online
+                || known.node_type != info.node_type
+                || known.capabilities != info.capabilities
+        })
+    }
+
+    /// Add a node, refusing to overwrite one already registered under the same id
+    pub fn insert_new(&mut self, info: NodeInfo) -> Result<()> {
+        if self.nodes.contains_key(&info.node_id) {
+            return Err(CwHoError::DuplicateNode(info.node_id));
+        }
+        self.add_node(info);
+        Ok(())
+    }
+
+    /// Register a node from the ssh config under a fresh `{name}_{timestamp}_{suffix}` id.
+    /// The random suffix keeps nodes registered within the same second apart.
+    pub fn register_config_node(
+        &mut self,
+        name: &str,
+        node_type: NodeType,
+        now: u64,
+    ) -> Result<String> {
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let node_id = format!("{}_{}_{}", name, now, &suffix[..8]);
+        self.insert_new(NodeInfo {
+            node_id: node_id.clone(),
+            node_type: node_type.as_str_name().to_string(),
+            online: false,
+            last_seen: now,
+            capabilities: Vec::new(),
+        })?;
+        Ok(node_id)
+    }
+
     /// Remove a node from the topology
     pub fn remove_node(&mut self, node_id: &str) {
         self.nodes.remove(node_id);
@@ -60,6 +101,86 @@ impl NetworkTopology {
         self.nodes.values().filter(|info| info.online).collect()
     }
 
+    /// Record activity from a node, returning true if it was offline and is now back
+    pub fn touch(&mut self, node_id: &str, now: u64) -> bool {
+        match self.nodes.get_mut(node_id) {
+            Some(info) => {
+                info.last_seen = now;
+                !std::mem::replace(&mut info.online, true)
+            }
+            None => false,
+        }
+    }
+
+    /// Mark online nodes silent for longer than `stale_after` seconds as offline,
+    /// returning the ids that changed
+    pub fn mark_stale(&mut self, now: u64, stale_after: u64) -> Vec<String> {
+        self.nodes
+            .values_mut()
+            .filter(|info| info.online && now.saturating_sub(info.last_seen) > stale_after)
+            .map(|info| {
+                info.online = false;
+                info.node_id.clone()
+            })
+            .collect()
+    }
+
+    /// Wire representation used in topology events
+    pub fn to_proto(&self) -> ho_std::prelude::NetworkTopology {
+        ho_std::prelude::NetworkTopology {
+            nodes: self.nodes.values().cloned().collect(),
+            connections: self
+                .connections
+                .iter()
+                .map(|(from, to)| Connection {
+                    from_node_id: from.clone(),
+                    to_node_id: to.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Changes that turn `self` into `other`. Connections are undirected, and results are
+    /// sorted by node id so equal diffs compare equal.
+    pub fn diff(&self, other: &Self) -> TopologyDiff {
+        let mut diff = TopologyDiff::default();
+        for (id, info) in &other.nodes {
+            match self.nodes.get(id) {
+                None => diff.added_nodes.push(info.clone()),
+                Some(before) if before != info => diff.changed_nodes.push(info.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed_nodes = self
+            .nodes
+            .keys()
+            .filter(|id| !other.nodes.contains_key(*id))
+            .cloned()
+            .collect();
+
+        let connection = |(from, to): &(String, String)| Connection {
+            from_node_id: from.clone(),
+            to_node_id: to.clone(),
+        };
+        diff.added_connections = other
+            .connections
+            .iter()
+            .filter(|(from, to)| !self.has_connection(from, to))
+            .map(connection)
+            .collect();
+        diff.removed_connections = self
+            .connections
+            .iter()
+            .filter(|(from, to)| !other.has_connection(from, to))
+            .map(connection)
+            .collect();
+
+        diff.added_nodes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        diff.changed_nodes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        diff.removed_nodes.sort();
+        diff
+    }
+
     /// Check if the topology forms a complete tetrahedral structure
     pub fn is_complete_tetrahedron(&self) -> bool {
         let online_nodes = self.online_nodes();
@@ -131,3 +252,95 @@ impl Default for NetworkTopology {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_node_goes_offline_and_recovers() {
+        let mut topology = NetworkTopology::new();
+        topology.add_node(NodeInfo {
+            node_id: "quiet".to_string(),
+            node_type: NodeType::Executor.as_str_name().to_string(),
+            online: true,
+            last_seen: 1_000,
+            capabilities: Vec::new(),
+        });
+        topology.add_node(NodeInfo {
+            node_id: "chatty".to_string(),
+            node_type: NodeType::Referee.as_str_name().to_string(),
+            online: true,
+            last_seen: 1_100,
+            capabilities: Vec::new(),
+        });
+
+        assert_eq!(topology.mark_stale(1_150, 120), vec!["quiet".to_string()]);
+        assert_eq!(topology.online_nodes().len(), 1);
+        // Already offline nodes are not reported again
+        assert!(topology.mark_stale(1_160, 120).is_empty());
+
+        assert!(topology.touch("quiet", 1_170));
+        assert!(!topology.touch("quiet", 1_171));
+        assert_eq!(topology.online_nodes().len(), 2);
+        assert!(!topology.touch("unknown", 1_170));
+    }
+
+    #[test]
+    fn test_same_name_config_nodes_get_distinct_ids() {
+        let mut topology = NetworkTopology::new();
+        let first = topology
+            .register_config_node("gpu-box", NodeType::Executor, 1_000)
+            .unwrap();
+        let second = topology
+            .register_config_node("gpu-box", NodeType::Executor, 1_000)
+            .unwrap();
+        assert_ne!(first, second);
+        assert_eq!(topology.nodes.len(), 2);
+
+        let existing = topology.nodes[&first].clone();
+        assert!(matches!(
+            topology.insert_new(existing),
+            Err(CwHoError::DuplicateNode(id)) if id == first
+        ));
+    }
+
+    #[test]
+    fn test_diff_reports_only_what_changed() {
+        let node = |id: &str| NodeInfo {
+            node_id: id.to_string(),
+            node_type: NodeType::Executor.as_str_name().to_string(),
+            online: true,
+            last_seen: 1_000,
+            capabilities: Vec::new(),
+        };
+        let mut before = NetworkTopology::new();
+        before.add_node(node("a"));
+        before.add_node(node("b"));
+        before.add_connection("a".to_string(), "b".to_string());
+
+        let mut after = before.clone();
+        after.add_node(node("c"));
+        let diff = before.diff(&after);
+        assert_eq!(diff.added_nodes, vec![node("c")]);
+        assert_eq!(
+            diff,
+            TopologyDiff {
+                added_nodes: vec![node("c")],
+                ..Default::default()
+            }
+        );
+        assert_eq!(after.diff(&after), TopologyDiff::default());
+
+        // Removing a node drops its connections too; direction does not matter
+        after.remove_node("a");
+        after.add_connection("c".to_string(), "b".to_string());
+        after.mark_stale(2_000, 60);
+        let diff = before.diff(&after);
+        assert_eq!(diff.removed_nodes, vec!["a".to_string()]);
+        assert_eq!(diff.removed_connections.len(), 1);
+        assert_eq!(diff.added_connections[0].from_node_id, "c");
+        assert_eq!(diff.changed_nodes.len(), 1);
+        assert!(!diff.changed_nodes[0].online);
+    }
+}
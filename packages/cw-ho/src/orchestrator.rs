@@ -4,9 +4,13 @@
 //! incorporating cosmic/geometric principles and fractal recursion for AI agent orchestration.
 
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::FutureExt;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use std::{
     collections::HashMap,
+    panic::AssertUnwindSafe,
     process::Stdio,
     sync::Arc,
     time::{Duration, SystemTime},
@@ -14,24 +18,75 @@ use std::{
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     process::Child,
-    sync::RwLock,
+    sync::{watch, RwLock},
 };
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
+    artifacts::ArtifactStore,
     llm_providers::LLMRouter,
-    network::transports::ssh::SSHConnectionManager,
+    network::transports::ssh::{
+        retry_transient, ssh_retry_policy, SSHConnectionManager, ShellOutput,
+    },
     python::PythonExecutor,
     state::{cnidarium_store::SacredStateStore, SacredStateKey, SacredStateValue},
     types::{
         llm::{LLMProvider, LLMResponse},
         orch::*,
-        python::{AgentSpec, CosmicParameters, MetaPromptRequest, MetaPromptResponse},
+        python::{CosmicParameters, MetaPromptRequest, MetaPromptResponse},
         state::{AgentTask, GeometricMetadata, SandloopState, SandloopType, TetrahedralPosition},
     },
 };
-use ho_std::types::constants::*;
+use ho_std::{
+    orchestrate::{
+        golden_ratio_allocation_valid, panic_message, tetrahedral_coverage, SandloopConvergence,
+    },
+    prelude::{AgentSpec, LlmModel, NodeIdentity, OrchestrationReport, SshStepResult},
+    python::capabilities::PythonCapabilities,
+    traits::{LlmModelTrait, NodeIdentityTrait},
+    types::constants::*,
+    utils::shared_config_loader,
+};
+
+/// Report key of the workspace transfer and setup step
+const INSTALL_STEP: &str = "dev_environment_install";
+
+/// Run `command` in bash, a failure to even start it counting as a failed run
+async fn shell_output(command: &str) -> ShellOutput {
+    let output = tokio::process::Command::new(CMD_BASH)
+        .kill_on_drop(true)
+        .arg("-c")
+        .arg(command)
+        .output()
+        .await;
+    match output {
+        Ok(output) => output.into(),
+        Err(e) => ShellOutput {
+            stderr: format!("failed to run command: {}", e),
+            ..Default::default()
+        },
+    }
+}
+
+/// Active task counts reported by the metrics path, from a single snapshot so they always
+/// add up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActiveTaskCounts {
+    pub total: usize,
+    /// Tasks carrying fractal requirements
+    pub fractal: usize,
+    pub coordinator: usize,
+    pub executor: usize,
+    pub referee: usize,
+    pub development: usize,
+}
+
+/// Executes `CosmicTaskType::Custom` tasks registered under its name
+#[async_trait]
+pub trait CustomTaskHandler: Send + Sync {
+    async fn execute(&self, task: &CosmicTask) -> Result<serde_json::Value>;
+}
 
 /// Main Cosmic Orchestrator implementing AgentOrchestrator from Python
 pub struct CosmicOrchestrator {
@@ -43,17 +98,34 @@ pub struct CosmicOrchestrator {
     pub active_tasks: Arc<RwLock<HashMap<String, CosmicTask>>>,
     /// Golden ratio constant for resource allocation
     pub golden_ratio: f64,
+    /// Allowed deviation of the allocation ratio from `golden_ratio`
+    pub golden_ratio_tolerance: f64,
     /// Tetrahedral node positions
     pub tetrahedral_vertices: Vec<String>,
     /// Sacred state store for geometric state management
     pub sacred_store: Arc<SacredStateStore>,
-    /// Node ID for tetrahedral positioning
+    /// Canonical node id derived from the node's public key
     pub node_id: String,
+    /// Deadline for tasks without a timeout of their own, from `task_timeout_secs`
+    pub default_task_timeout: Option<Duration>,
+    /// Where large outputs such as generated code are written, from `artifact_dir`.
+    /// `None` keeps them inline in the task result
+    pub artifacts: Option<Arc<ArtifactStore>>,
+    /// Handlers for custom task types, by name
+    custom_handlers: RwLock<HashMap<String, Box<dyn CustomTaskHandler>>>,
+    /// Randomness behind fractal variants, from entropy unless fixed with `with_seed`
+    rng: std::sync::Mutex<StdRng>,
+    /// Cancellation signal observed by in-flight tasks
+    shutdown_tx: watch::Sender<bool>,
 }
 
+/// Error recorded on tasks cancelled by an orchestrator shutdown
+const TASK_CANCELLED_ERROR: &str = "cancelled by orchestrator shutdown";
+
 impl CosmicOrchestrator {
     /// Create a new cosmic orchestrator with sacred geometric storage
-    pub async fn new(src_path: &str, storage_path: &str, node_id: String) -> Result<Self> {
+    pub async fn new(src_path: &str, storage_path: &str, identity: &NodeIdentity) -> Result<Self> {
+        let node_id = identity.node_id();
         let llm_router = Arc::new(LLMRouter::new().context("Failed to initialize LLM router")?);
 
         let python_executor = Arc::new(
@@ -104,7 +176,7 @@ impl CosmicOrchestrator {
             "🌟 Cosmic Orchestrator initialized with tetrahedral vertices: {:?}",
             tetrahedral_vertices
         );
-        info!("📐 Golden ratio resource allocation: 1.618");
+        info!("📐 Golden ratio resource allocation: {}", GOLDEN_RATIO);
         info!("💾 Sacred State Store initialized at: {}", storage_path);
         info!(
             "🔷 Node registered as: {} at position: {:?}",
@@ -115,19 +187,37 @@ impl CosmicOrchestrator {
             llm_router,
             python_executor,
             active_tasks: Arc::new(RwLock::new(HashMap::new())),
-            golden_ratio: 1.618,
+            golden_ratio: GOLDEN_RATIO,
+            golden_ratio_tolerance: GOLDEN_RATIO_TOLERANCE,
             tetrahedral_vertices,
             sacred_store,
             node_id,
+            default_task_timeout: None,
+            artifacts: None,
+            custom_handlers: RwLock::new(HashMap::new()),
+            rng: std::sync::Mutex::new(StdRng::from_entropy()),
+            shutdown_tx: watch::channel(false).0,
         })
     }
 
-    /// Determine tetrahedral position from node ID
+    /// Draw fractal variants from a fixed seed instead of entropy, making expansions
+    /// reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = std::sync::Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Determine tetrahedral position from a canonical node id
     fn determine_tetrahedral_position_from_id(
         node_id: &str,
         vertices: &[String],
     ) -> TetrahedralPosition {
-        let index = node_id.len() % vertices.len();
+        // Canonical ids are hex digests, so the leading byte is uniformly spread
+        let lead = node_id
+            .get(..2)
+            .and_then(|b| u8::from_str_radix(b, 16).ok())
+            .unwrap_or_default();
+        let index = lead as usize % vertices.len();
         match index {
             0 => TetrahedralPosition::Coordinator,
             1 => TetrahedralPosition::Executor,
@@ -137,9 +227,21 @@ impl CosmicOrchestrator {
         }
     }
 
+    /// Execute tasks in dependency order, feeding each result into its dependents'
+    /// context and failing dependents of a failed task. Rejects cycles.
+    pub async fn submit_graph(&self, tasks: Vec<CosmicTask>) -> Result<Vec<CosmicTask>> {
+        Ok(ho_std::orchestrate::run_task_graph(tasks, |task| self.execute_task(task)).await?)
+    }
+
     /// Execute a cosmic task following geometric principles with sacred storage
     pub async fn execute_task(&self, task: CosmicTask) -> Result<CosmicTask> {
         let task_id = task.id.clone();
+        if *self.shutdown_tx.borrow() {
+            return Err(anyhow::anyhow!(
+                "Orchestrator is shutting down, rejecting task {}",
+                task_id
+            ));
+        }
         info!(
             "🚀 Executing cosmic task: {} (type: {:?}) (prompt: {:?})",
             task_id, task.task_type, task.prompt
@@ -184,38 +286,77 @@ impl CosmicOrchestrator {
             active_tasks.insert(task_id.clone(), updated_task.clone());
         }
 
-        // Execute task based on type, with potential fractal expansion
-        let result = if updated_task.fractal_requirements.is_some() {
-            self.execute_task_fractally(&updated_task).await
-        } else {
-            match updated_task.task_type {
-                CosmicTaskType::MetaPromptGeneration => {
-                    self.execute_meta_prompt_generation(&updated_task).await
-                }
-                CosmicTaskType::RecursiveOrchestration => {
-                    self.execute_recursive_orchestration(&updated_task).await
-                }
-                CosmicTaskType::FractalAgentCreation => {
-                    self.execute_fractal_agent_creation(&updated_task).await
-                }
-                CosmicTaskType::TetrahedralCoordination => {
-                    self.execute_tetrahedral_coordination(&updated_task).await
-                }
-                CosmicTaskType::GoldenRatioOptimization => {
-                    self.execute_golden_ratio_optimization(&updated_task).await
-                }
-                CosmicTaskType::SandloopExecution => {
-                    self.execute_sandloop_with_storage(&updated_task).await
-                }
-                CosmicTaskType::NetworkOrchestration => {
-                    self.execute_network_orchestration(&updated_task).await
+        // Execute task based on type, with potential fractal expansion.
+        // Dropping the dispatch on shutdown also kills any child processes it spawned,
+        // and a panicking executor fails the task instead of the worker.
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let dispatch = async {
+            if updated_task.fractal_requirements.is_some() {
+                self.execute_task_fractally(&updated_task).await
+            } else {
+                match updated_task.task_type {
+                    CosmicTaskType::MetaPromptGeneration => {
+                        self.execute_meta_prompt_generation(&updated_task).await
+                    }
+                    CosmicTaskType::RecursiveOrchestration => {
+                        self.execute_recursive_orchestration(&updated_task).await
+                    }
+                    CosmicTaskType::FractalAgentCreation => {
+                        self.execute_fractal_agent_creation(&updated_task).await
+                    }
+                    CosmicTaskType::TetrahedralCoordination => {
+                        self.execute_tetrahedral_coordination(&updated_task).await
+                    }
+                    CosmicTaskType::GoldenRatioOptimization => {
+                        self.execute_golden_ratio_optimization(&updated_task).await
+                    }
+                    CosmicTaskType::SandloopExecution => {
+                        self.execute_sandloop_with_storage(&updated_task).await
+                    }
+                    CosmicTaskType::NetworkOrchestration => {
+                        self.execute_network_orchestration(&updated_task).await
+                    }
+                    CosmicTaskType::CodeGeneration => {
+                        self.execute_code_generation(&updated_task).await
+                    }
+                    CosmicTaskType::DataProcessing => todo!(),
+                    CosmicTaskType::NetworkSyncronization => todo!(),
+                    CosmicTaskType::PromptRefinement => todo!(),
+                    CosmicTaskType::QualityAudit => todo!(),
+                    CosmicTaskType::Custom(ref name) => {
+                        self.execute_custom_task(name, &updated_task).await
+                    }
                 }
-                CosmicTaskType::CodeGeneration => todo!(),
-                CosmicTaskType::DataProcessing => todo!(),
-                CosmicTaskType::NetworkSyncronization => todo!(),
-                CosmicTaskType::PromptRefinement => todo!(),
-                CosmicTaskType::QualityAudit => todo!(),
-                CosmicTaskType::Custom(_) => todo!(),
+            }
+        };
+        let timeout = updated_task
+            .timeout_secs
+            .map(Duration::from_secs)
+            .or(self.default_task_timeout);
+        let deadline = async {
+            match timeout {
+                Some(limit) => tokio::time::sleep(limit).await,
+                None => std::future::pending().await,
+            }
+        };
+        let dispatch = AssertUnwindSafe(dispatch).catch_unwind().map(|outcome| {
+            outcome.unwrap_or_else(|panic| {
+                Err(anyhow::anyhow!(
+                    "{}: {}",
+                    TASK_PANICKED_ERROR,
+                    panic_message(panic.as_ref())
+                ))
+            })
+        });
+        let result = tokio::select! {
+            result = dispatch => result,
+            _ = shutdown_rx.wait_for(|stop| *stop) => {
+                warn!("🛑 Cosmic task cancelled by shutdown: {}", task_id);
+                Err(anyhow::anyhow!(TASK_CANCELLED_ERROR))
+            }
+            _ = deadline => {
+                warn!("⏰ Cosmic task timed out after {:?}: {}", timeout, task_id);
+                Err(anyhow::anyhow!(TASK_TIMED_OUT_ERROR))
             }
         };
 
@@ -268,13 +409,16 @@ impl CosmicOrchestrator {
             CosmicTaskType::GoldenRatioOptimization | CosmicTaskType::TetrahedralCoordination => {
                 TetrahedralPosition::Referee
             }
-            CosmicTaskType::FractalAgentCreation => TetrahedralPosition::Development,
-            CosmicTaskType::CodeGeneration => todo!(),
+            CosmicTaskType::FractalAgentCreation | CosmicTaskType::CodeGeneration => {
+                TetrahedralPosition::Development
+            }
             CosmicTaskType::DataProcessing => todo!(),
             CosmicTaskType::NetworkSyncronization => todo!(),
             CosmicTaskType::PromptRefinement => todo!(),
             CosmicTaskType::QualityAudit => todo!(),
-            CosmicTaskType::Custom(_) => todo!(),
+            // Custom tasks are run by whichever handler is registered, so they sit with
+            // the general-purpose executors
+            CosmicTaskType::Custom(_) => TetrahedralPosition::Executor,
         }
     }
 
@@ -313,6 +457,12 @@ impl CosmicOrchestrator {
         })
     }
 
+    /// Clamp a computed token budget to the provider's output limits
+    fn clamp_tokens(&self, provider: &str, requested: u32) -> u32 {
+        let model = LlmModel::from_provider_key(provider).unwrap_or(LlmModel::Custom);
+        self.llm_router.config().clamp_max_tokens(model, requested)
+    }
+
     /// Calculate geometric weight for a task based on fractal requirements
     fn calculate_geometric_weight(&self, task: &CosmicTask) -> f64 {
         if let Some(fractal_req) = &task.fractal_requirements {
@@ -404,7 +554,8 @@ impl CosmicOrchestrator {
 
         for i in 0..num_variants {
             let mut variant = base_action.clone();
-            variant.id = Uuid::new_v4();
+            variant.id =
+                uuid::Builder::from_random_bytes(self.rng.lock().unwrap().gen()).into_uuid();
 
             // Create fractal payload with scaled parameters
             let mut fractal_payload = base_action.payload.clone();
@@ -483,6 +634,8 @@ impl CosmicOrchestrator {
     /// Execute meta prompt generation using fractal principles
     async fn execute_meta_prompt_generation(&self, task: &CosmicTask) -> Result<serde_json::Value> {
         info!("🔮 Executing meta prompt generation with fractal recursion");
+        PythonCapabilities::detect()
+            .require("MetaPromptGeneration", &[TOOLS_METAPROMPT_GENERATOR])?;
 
         let cosmic_params = CosmicParameters {
             recursion_depth: task
@@ -560,7 +713,7 @@ impl CosmicOrchestrator {
             // Create recursive task
             let recursive_task = AgentTask {
                 id: Uuid::new_v4(),
-                node_id: "cosmic-orchestrator".to_string(),
+                node_id: self.node_id.clone(),
                 task_type: CosmicTaskType::MetaPromptGeneration,
                 status: CosmicTaskStatus::Pending,
                 payload: serde_json::json!({
@@ -613,22 +766,12 @@ impl CosmicOrchestrator {
     async fn execute_fractal_agent_creation(&self, task: &CosmicTask) -> Result<serde_json::Value> {
         info!("🎭 Creating fractal AI agents through recursive expansion!");
 
-        // Create base agent specification
-        let base_spec = AgentSpec {
-            agent_id: task.id.clone(),
-            agent_type: task.context.tetrahedral_position.clone(),
-            capabilities: vec![
-                COSMIC_ORCHESTRATION.to_string(),
-                FRACTAL_RECURSION.into(),
-                format!(
-                    "tetrahedral-{}",
-                    task.context.tetrahedral_position.to_lowercase()
-                ),
-            ],
-            execution_prompt: task.prompt.clone(),
-            tetrahedral_position: task.context.tetrahedral_position.clone(),
-            fractal_properties: HashMap::new(),
-        };
+        // Create base agent specification, rejecting unknown vertices up front
+        let base_spec = AgentSpec::new(
+            task.id.clone(),
+            &task.context.tetrahedral_position,
+            task.prompt.clone(),
+        )?;
 
         let recursion_depth = task
             .fractal_requirements
@@ -694,7 +837,11 @@ impl CosmicOrchestrator {
                     &vertex_prompt,
                     true, // use_fallback
                     None,
-                    Some(2048),
+                    Some(
+                        self.llm_router
+                            .config()
+                            .clamp_max_tokens(primary_provider, 2048),
+                    ),
                     Some(0.7),
                     None,
                 )
@@ -746,6 +893,71 @@ impl CosmicOrchestrator {
         }))
     }
 
+    /// Run `Custom(name)` tasks with `handler`, replacing any handler already registered
+    /// under `name`
+    pub async fn register_custom_handler(
+        &self,
+        name: impl Into<String>,
+        handler: Box<dyn CustomTaskHandler>,
+    ) {
+        self.custom_handlers
+            .write()
+            .await
+            .insert(name.into(), handler);
+    }
+
+    /// Hand a custom task to the handler registered under `name`
+    async fn execute_custom_task(
+        &self,
+        name: &str,
+        task: &CosmicTask,
+    ) -> Result<serde_json::Value> {
+        let handlers = self.custom_handlers.read().await;
+        let Some(handler) = handlers.get(name) else {
+            return Err(anyhow::anyhow!(
+                "no handler registered for custom task '{}'",
+                name
+            ));
+        };
+        info!("🧩 Executing custom task '{}'", name);
+        handler.execute(task).await
+    }
+
+    /// Generate code for the task's prompt. With an artifact store the code is written to
+    /// `code.txt` and the result references it instead of carrying it.
+    async fn execute_code_generation(&self, task: &CosmicTask) -> Result<serde_json::Value> {
+        info!("🧑‍💻 Executing code generation");
+        let Some(provider) = self.llm_router.get_primary_chain().first().cloned() else {
+            warn!("⏭️ No live LLM providers, skipping code generation");
+            return Ok(serde_json::json!({ "skipped": "no live providers" }));
+        };
+        let response = self
+            .llm_router
+            .route_request(
+                &provider,
+                &format!("Write the code for this task, code only:\n{}", task.prompt),
+                true,
+                None,
+                Some(self.clamp_tokens(provider.as_str(), 4096)),
+                Some(0.2),
+                None,
+            )
+            .await?;
+
+        let code = match &self.artifacts {
+            Some(artifacts) => {
+                let reference = artifacts.write(&task.id, "code.txt", &response.response)?;
+                serde_json::to_value(reference)?
+            }
+            None => response.response.clone().into(),
+        };
+        Ok(serde_json::json!({
+            "code": code,
+            "provider": response.provider,
+            "model": response.model,
+        }))
+    }
+
     /// Execute golden ratio optimization
     async fn execute_golden_ratio_optimization(
         &self,
@@ -754,11 +966,15 @@ impl CosmicOrchestrator {
         info!("📐 Executing golden ratio optimization (φ ≈ 1.618)");
 
         // Apply golden ratio to resource allocation
-        let primary_allocation = 0.618; // 61.8% to primary providers
-        let secondary_allocation = 0.382; // 38.2% to secondary providers
+        let primary_allocation = 1.0 / self.golden_ratio; // ~61.8% to primary providers
+        let secondary_allocation = 1.0 - primary_allocation; // ~38.2% to secondary providers
 
         let primary_providers = self.llm_router.get_primary_chain();
         let fallback_providers = self.llm_router.get_fallback_chain();
+        if primary_providers.is_empty() {
+            warn!("⏭️ No live LLM providers, skipping golden ratio optimization");
+            return Ok(serde_json::json!({ "skipped": "no live providers" }));
+        }
 
         let mut optimization_results = HashMap::new();
 
@@ -777,7 +993,7 @@ impl CosmicOrchestrator {
                     &adjusted_prompt,
                     false, // no fallback for primary
                     None,
-                    Some((2048.0 * weight) as u32),
+                    Some(self.clamp_tokens(provider.as_str(), (2048.0 * weight) as u32)),
                     Some(0.7),
                     None,
                 )
@@ -839,7 +1055,7 @@ impl CosmicOrchestrator {
                     &adjusted_prompt,
                     false,
                     None,
-                    Some((2048.0 * weight) as u32),
+                    Some(self.clamp_tokens(provider.as_str(), (2048.0 * weight) as u32)),
                     Some(0.7),
                     None,
                 )
@@ -884,6 +1100,13 @@ impl CosmicOrchestrator {
             .map(|fr| fr.recursion_depth)
             .unwrap_or(3);
 
+        let mut convergence = SandloopConvergence::new(
+            task.fractal_requirements
+                .as_ref()
+                .map(|fr| fr.sandloop_convergence())
+                .unwrap_or(DEFAULT_SANDLOOP_CONVERGENCE),
+        );
+
         let mut sandloop_results = Vec::new();
         let mut current_input = task.prompt.clone();
 
@@ -895,8 +1118,12 @@ impl CosmicOrchestrator {
             );
 
             // Select provider based on iteration (distribute across tetrahedral vertices)
-            let provider_index = iteration as usize % self.llm_router.get_primary_chain().len();
-            let provider = self.llm_router.get_primary_chain()[provider_index].clone();
+            let chain = self.llm_router.get_primary_chain();
+            if chain.is_empty() {
+                warn!("⏭️ No live LLM providers, ending sandloop early");
+                break;
+            }
+            let provider = chain[iteration as usize % chain.len()].clone();
 
             // Execute current iteration
             match self
@@ -906,7 +1133,7 @@ impl CosmicOrchestrator {
                     &current_input,
                     true, // use fallback
                     None,
-                    Some(2048),
+                    Some(self.clamp_tokens(provider.as_str(), 2048)),
                     Some(0.7),
                     None,
                 )
@@ -921,6 +1148,16 @@ impl CosmicOrchestrator {
                         "timestamp": response.timestamp
                     }));
 
+                    // Stop once successive outputs stop improving
+                    if convergence.observe(iteration + 1, &response.response) {
+                        info!(
+                            "🎯 Sandloop converged at iteration {}/{}",
+                            iteration + 1,
+                            loop_iterations
+                        );
+                        break;
+                    }
+
                     // Möbius strip: output becomes input for next iteration
                     current_input = format!(
                         "Building on previous iteration: {}\n\nNow execute: {}",
@@ -954,6 +1191,7 @@ impl CosmicOrchestrator {
         Ok(serde_json::json!({
             "sandloop_execution_complete": true,
             "iterations_completed": sandloop_results.len(),
+            "converged_at_iteration": convergence.converged_at,
             "mobius_continuity": true,
             "sandloop_results": sandloop_results,
             "golden_ratio_timing": true
@@ -964,61 +1202,32 @@ impl CosmicOrchestrator {
     async fn execute_network_orchestration(&self, task: &CosmicTask) -> Result<serde_json::Value> {
         info!("🌐 Executing network orchestration for node deployment");
 
-        let mut orchestration_results = serde_json::Map::new();
-
         // Extract target node information from task context
         let target_node = &task.context.dev_node;
         info!("🎯 Target node for orchestration: {}", target_node);
+        let mut report = OrchestrationReport::new(target_node);
 
         // Create persistent SSH connection manager
         let mut ssh_manager = SSHConnectionManager::new(target_node.to_string());
+        ssh_manager.preflight().await?;
 
         // Step 1: Establish persistent SSH connection
         info!("🔌 Step 1: Establishing persistent SSH connection");
-        match ssh_manager.connect().await {
-            Ok(()) => {
-                orchestration_results.insert(
-                    "ssh_connection_test".to_string(),
-                    serde_json::json!({
-                        "success": true,
-                        "target_node": target_node,
-                        "connection_type": "persistent",
-                        "test_type": "ssh_connection"
-                    }),
-                );
-            }
-            Err(e) => {
-                error!("❌ Persistent SSH connection failed: {}", e);
-                orchestration_results.insert(
-                    "ssh_connection_test".to_string(),
-                    serde_json::json!({
-                        "success": false,
-                        "error": e.to_string(),
-                        "connection_type": "persistent"
-                    }),
-                );
-                return Err(e);
-            }
+        if let Err(e) = ssh_manager.connect().await {
+            error!("❌ Persistent SSH connection failed: {}", e);
+            return Err(e);
         }
+        report.push(SshStepResult::ok("ssh_connection_test", "persistent"));
 
         // Step 2: Install/configure development environment using persistent SSH
         info!("🛠️  Step 2: Installing development environment on target node");
-        match self.install_dev_environment_via_ssh(&mut ssh_manager).await {
-            Ok(install_result) => {
-                orchestration_results.insert("dev_environment_install".to_string(), install_result);
-                info!("✅ Development environment installation completed");
-            }
-            Err(e) => {
-                error!("❌ Development environment installation failed: {}", e);
-                orchestration_results.insert(
-                    "dev_environment_install".to_string(),
-                    serde_json::json!({
-                        "success": false,
-                        "error": e.to_string()
-                    }),
-                );
-            }
+        let install = self.install_dev_environment_via_ssh(&mut ssh_manager).await;
+        let install = install.unwrap_or_else(|e| SshStepResult::failed(INSTALL_STEP, e));
+        match &install.error {
+            None => info!("✅ Development environment installation completed"),
+            Some(e) => error!("❌ Development environment installation failed: {}", e),
         }
+        report.push(install);
 
         // Close SSH connection before returning
         let _ = ssh_manager.close().await;
@@ -1043,44 +1252,26 @@ impl CosmicOrchestrator {
         //     }
         // }
 
-        // Calculate orchestration success rate
-        let total_steps = orchestration_results.len() as f64;
-        let successful_steps = orchestration_results
-            .values()
-            .filter(|result| {
-                result
-                    .get("success")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false)
-            })
-            .count() as f64;
-        let success_rate = if total_steps > 0.0 {
-            successful_steps / total_steps
-        } else {
-            0.0
-        };
+        let success_rate = report.success_rate();
 
         info!(
             "📊 Network orchestration completed with {:.1}% success rate",
             success_rate * 100.0
         );
 
-        Ok(serde_json::json!({
-            "network_orchestration_complete": true,
-            "target_node": target_node,
-            "success_rate": success_rate,
-            "steps_completed": successful_steps as u32,
-            "total_steps": total_steps as u32,
-            "orchestration_results": orchestration_results,
-            "golden_ratio_applied": true,
-            "tetrahedral_connectivity_established": success_rate >= 0.618 // Golden ratio threshold
-        }))
+        let mut summary = report.summary();
+        summary["network_orchestration_complete"] = true.into();
+        summary["golden_ratio_applied"] = true.into();
+        // Golden ratio threshold
+        summary["tetrahedral_connectivity_established"] = (success_rate >= 0.618).into();
+        Ok(summary)
     }
 
     /// Test SSH connection to target node.
     /// If response.success -> false, we error.
     async fn test_ssh_connection(&self, target_node: &str) -> Result<serde_json::Value> {
         info!("🔍 Testing SSH connection to node: {}", target_node);
+        PythonCapabilities::detect().require("SSH connection test", &[TOOLS_SSH_TRANSPORT])?;
 
         // Execute SSH connection test using Python script
         let ssh_test_command = format!(
@@ -1093,6 +1284,7 @@ impl CosmicOrchestrator {
         );
 
         let output = tokio::process::Command::new(CMD_BASH)
+            .kill_on_drop(true)
             .arg("-c")
             .arg(&ssh_test_command)
             .output()
@@ -1127,6 +1319,7 @@ impl CosmicOrchestrator {
 
         // Execute the installation script locally
         let output = tokio::process::Command::new(CMD_BASH)
+            .kill_on_drop(true)
             .arg(&TOOLS_LINUX_CONFIGURE)
             .current_dir(std::env::current_dir()?)
             .output()
@@ -1155,13 +1348,11 @@ impl CosmicOrchestrator {
     async fn install_dev_environment_via_ssh(
         &self,
         ssh_manager: &mut SSHConnectionManager,
-    ) -> Result<serde_json::Value> {
+    ) -> Result<SshStepResult> {
         info!("🛠️  Installing development environment via SSH using workspace transfer");
-        let ssh_config_content = tokio::fs::read_to_string(SSH_JSON_PATH)
-            .await
-            .context("Failed to read SSH config")?;
-        let ssh_config: serde_json::Value =
-            serde_json::from_str(&ssh_config_content).context("Failed to parse SSH config")?;
+        let ssh_config = shared_config_loader()
+            .json::<serde_json::Value, _>(SSH_JSON_PATH)
+            .context("Failed to load SSH config")?;
 
         let node_config = ssh_config.get(&ssh_manager.target_node).ok_or_else(|| {
             anyhow::anyhow!("Node {} not found in SSH config", ssh_manager.target_node)
@@ -1186,6 +1377,7 @@ impl CosmicOrchestrator {
         info!("🔧 Executing tar command: {}", tar_command);
 
         let tar_output = tokio::process::Command::new(CMD_BASH)
+            .kill_on_drop(true)
             .arg("-c")
             .arg(&tar_command)
             .output()
@@ -1201,24 +1393,25 @@ impl CosmicOrchestrator {
         }
 
         if !tar_output.status.success() {
-            return Ok(serde_json::json!({
-                "success": false,
-                "target_node": ssh_manager.target_node,
-                "installation_method": "ssh_workspace_transfer",
-                "error": format!("Workspace tar.gz creation failed: {} (stderr: {})", stdout.trim(), stderr.trim()),
-                "note": "Failed to create workspace tar.gz file"
-            }));
+            return Ok(SshStepResult::failed(
+                INSTALL_STEP,
+                format!(
+                    "Workspace tar.gz creation failed: {} (stderr: {})",
+                    stdout.trim(),
+                    stderr.trim()
+                ),
+            ));
         }
 
         // Verify the archive was actually created
         if !tokio::fs::try_exists(archive_path).await.unwrap_or(false) {
-            return Ok(serde_json::json!({
-                "success": false,
-                "target_node": ssh_manager.target_node,
-                "installation_method": "ssh_workspace_transfer",
-                "error": format!("Workspace tar.gz file does not exist at path: {}", archive_path),
-                "note": "Tar command succeeded but file was not created"
-            }));
+            return Ok(SshStepResult::failed(
+                INSTALL_STEP,
+                format!(
+                    "Workspace tar.gz file does not exist at path: {}",
+                    archive_path
+                ),
+            ));
         }
 
         info!(
@@ -1238,11 +1431,9 @@ impl CosmicOrchestrator {
         info!("🚀 Preparing to transfer workspace to remote host home directory");
 
         // TODO: GRAB FROM CONFIG FILE
-        let ssh_config_content = tokio::fs::read_to_string(SSH_JSON_PATH)
-            .await
-            .context("Failed to read SSH config")?;
-        let ssh_config: serde_json::Value =
-            serde_json::from_str(&ssh_config_content).context("Failed to parse SSH config")?;
+        let ssh_config = shared_config_loader()
+            .json::<serde_json::Value, _>(SSH_JSON_PATH)
+            .context("Failed to load SSH config")?;
 
         let node_config = ssh_config.get(&ssh_manager.target_node).ok_or_else(|| {
             anyhow::anyhow!("Node {} not found in SSH config", ssh_manager.target_node)
@@ -1349,28 +1540,29 @@ impl CosmicOrchestrator {
             scp_command.replace(password.unwrap_or(""), "********")
         );
 
-        let transfer_output = tokio::process::Command::new(CMD_BASH)
-            .arg("-c")
-            .arg(&scp_command)
-            .output()
-            .await?;
-
-        let scp_stdout = String::from_utf8_lossy(&transfer_output.stdout);
-        let scp_stderr = String::from_utf8_lossy(&transfer_output.stderr);
+        // Dropped connections and DNS hiccups are retried, refused logins fail at once
+        let retries = ssh_manager.transient_retries;
+        let (transfer_output, mut attempts) =
+            retry_transient(ssh_retry_policy(retries), || shell_output(&scp_command)).await;
+        let scp_stdout = &transfer_output.stdout;
+        let scp_stderr = &transfer_output.stderr;
 
-        if !scp_stdout.trim().is_empty() || !scp_stderr.trim().is_empty() {
-            info!("📤 SCP stdout: {}", scp_stdout.trim());
-            info!("⚠️ SCP stderr: {}", scp_stderr.trim());
+        if !scp_stdout.is_empty() || !scp_stderr.is_empty() {
+            info!("📤 SCP stdout: {}", scp_stdout);
+            info!("⚠️ SCP stderr: {}", scp_stderr);
         }
 
-        if !transfer_output.status.success() {
-            return Ok(serde_json::json!({
-                "success": false,
-                "target_node": ssh_manager.target_node,
-                "installation_method": "ssh_workspace_transfer",
-                "error": format!("Workspace tar.gz SCP transfer failed: {} (stderr: {})", scp_stdout.trim(), scp_stderr.trim()),
-                "note": "Failed to transfer workspace tar.gz to remote host via SCP"
-            }));
+        if !transfer_output.success {
+            return Ok(SshStepResult {
+                attempts: Some(attempts),
+                ..SshStepResult::failed(
+                    INSTALL_STEP,
+                    format!(
+                        "Workspace tar.gz SCP transfer failed after {} attempt(s): {} (stderr: {})",
+                        attempts, scp_stdout, scp_stderr
+                    ),
+                )
+            });
         }
 
         // Step 6: Create CW-AGENT directory and unpack tar.gz on remote host
@@ -1389,35 +1581,49 @@ impl CosmicOrchestrator {
             untar_command.replace("\"", "\\\"")
         );
 
-        let untar_output = tokio::process::Command::new(CMD_BASH)
-            .arg("-c")
-            .arg(&untar_ssh_command)
-            .output()
-            .await?;
+        let (untar_output, untar_attempts) = retry_transient(ssh_retry_policy(retries), || {
+            shell_output(&untar_ssh_command)
+        })
+        .await;
+        attempts += untar_attempts;
 
         // Log untar operation output
-        let untar_stdout = String::from_utf8_lossy(&untar_output.stdout);
-        let untar_stderr = String::from_utf8_lossy(&untar_output.stderr);
+        let untar_stdout = &untar_output.stdout;
+        let untar_stderr = &untar_output.stderr;
 
-        if !untar_stdout.trim().is_empty() {
-            info!("📤 Untar STDOUT: {}", untar_stdout.trim());
+        if !untar_stdout.is_empty() {
+            info!("📤 Untar STDOUT: {}", untar_stdout);
         }
-        if !untar_stderr.trim().is_empty() {
-            info!("⚠️ Untar STDERR: {}", untar_stderr.trim());
+        if !untar_stderr.is_empty() {
+            info!("⚠️ Untar STDERR: {}", untar_stderr);
         }
 
-        if !untar_output.status.success() {
-            return Ok(serde_json::json!({
-                "success": false,
-                "target_node": ssh_manager.target_node,
-                "installation_method": "ssh_workspace_transfer",
-                "error": format!("Workspace tar.gz extraction failed: {}", untar_stderr),
-                "note": "Failed to unpack workspace tar.gz on remote host"
-            }));
+        if !untar_output.success {
+            return Ok(SshStepResult {
+                attempts: Some(attempts),
+                ..SshStepResult::failed(
+                    INSTALL_STEP,
+                    format!(
+                        "Workspace tar.gz extraction failed after {} attempt(s): {}",
+                        untar_attempts, untar_stderr
+                    ),
+                )
+            });
         }
 
         info!("✅ Workspace tar.gz unpacked successfully");
 
+        // Step 6b: priv/* never travels in the archive, so give the node its own identity
+        let node_id = match ssh_manager.provision_node_key().await {
+            Ok(node_id) => node_id,
+            Err(e) => {
+                return Ok(SshStepResult::failed(
+                    INSTALL_STEP,
+                    format!("Node key provisioning failed: {}", e),
+                ));
+            }
+        };
+
         // Step 7: Execute setup script on remote host
         info!("🔧 Executing setup script on remote host");
 
@@ -1439,6 +1645,7 @@ impl CosmicOrchestrator {
 
         // Use spawn instead of output to get streaming output
         let mut child = tokio::process::Command::new(CMD_BASH)
+            .kill_on_drop(true)
             .arg("-c")
             .arg(&execute_ssh_command)
             .stdout(Stdio::piped())
@@ -1503,16 +1710,22 @@ impl CosmicOrchestrator {
             stderr_result.len()
         );
 
-        Ok(serde_json::json!({
-            "success": success,
-            "target_node": ssh_manager.target_node,
-            "installation_method": "ssh_workspace_tar_transfer",
-            "stdout": stdout_combined,
-            "stderr": stderr_combined,
-            "workspace_path": workspace_home,
-            "tar_size_bytes": archive_metadata.len(),
-            "note": "Development environment installed by transferring complete workspace tar.gz and executing setup script with real-time logging"
-        }))
+        info!(
+            "🆔 Node {} set up at {} as {} ({} byte archive)",
+            ssh_manager.target_node,
+            workspace_home,
+            node_id,
+            archive_metadata.len()
+        );
+        Ok(SshStepResult {
+            step: INSTALL_STEP.to_string(),
+            success,
+            stdout: stdout_combined,
+            stderr: stderr_combined,
+            error: (!success).then(|| format!("Setup script exited with {}", execute_output)),
+            // the setup script is not retried, so it adds its single run
+            attempts: Some(attempts + 1),
+        })
     }
 
     /// Deploy HO-Core API service to target node
@@ -1623,23 +1836,92 @@ impl CosmicOrchestrator {
 
     /// Calculate tetrahedral coverage for agent distribution
     pub fn calculate_tetrahedral_coverage(&self, agents: Vec<AgentSpec>) -> Result<f64> {
-        let mut vertex_counts = HashMap::new();
-        for vertex in &self.tetrahedral_vertices {
-            vertex_counts.insert(vertex.clone(), 0);
+        Ok(tetrahedral_coverage(&agents))
+    }
+
+    /// Cancel in-flight tasks, wait up to `grace` for them to persist their state,
+    /// force-terminate the rest and flush the sacred store
+    pub async fn shutdown(&self, grace: Duration) -> Result<OrchestratorShutdownSummary> {
+        let started = std::time::Instant::now();
+        let in_flight: Vec<String> = self
+            .list_active_tasks()
+            .await
+            .into_iter()
+            .filter(|t| t.status == CosmicTaskStatus::Running)
+            .map(|t| t.id)
+            .collect();
+        info!(
+            "🛑 Shutting down cosmic orchestrator with {} in-flight tasks (grace {:?})",
+            in_flight.len(),
+            grace
+        );
+        let _ = self.shutdown_tx.send(true);
+
+        // Wait for cancelled tasks to record their final state
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            let still_running = {
+                let active_tasks = self.active_tasks.read().await;
+                in_flight.iter().any(|id| {
+                    active_tasks
+                        .get(id)
+                        .is_some_and(|t| t.status == CosmicTaskStatus::Running)
+                })
+            };
+            if !still_running || tokio::time::Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
         }
 
-        // Count agents per tetrahedral vertex
-        for agent in agents {
-            if let Some(count) = vertex_counts.get_mut(&agent.tetrahedral_position) {
-                *count += 1;
+        let mut summary = OrchestratorShutdownSummary::default();
+        for task_id in in_flight {
+            let lingering = {
+                let mut active_tasks = self.active_tasks.write().await;
+                match active_tasks.get_mut(&task_id) {
+                    Some(task) if task.status == CosmicTaskStatus::Running => {
+                        task.status = CosmicTaskStatus::Failed;
+                        task.error = Some(TASK_CANCELLED_ERROR.to_string());
+                        task.updated_at = SystemTime::now();
+                        Some(task.clone())
+                    }
+                    _ => None,
+                }
+            };
+            match lingering {
+                Some(task) => {
+                    warn!("💀 Force-terminated cosmic task: {}", task_id);
+                    let key = SacredStateKey::Task {
+                        node_position: self.determine_tetrahedral_position(&task),
+                        task_id: Uuid::parse_str(&task_id).unwrap_or_else(|_| Uuid::new_v4()),
+                    };
+                    let value = SacredStateValue::TaskState {
+                        task: self.convert_to_agent_task(&task)?,
+                        fractal_level: task.context.fractal_level,
+                        geometric_weight: self.calculate_geometric_weight(&task),
+                    };
+                    if let Err(e) = self.sacred_store.store_state(key, value, None).await {
+                        error!("💥 Failed to persist terminated task {}: {}", task_id, e);
+                    }
+                    summary.terminated.push(task_id);
+                }
+                None => summary.drained.push(task_id),
             }
         }
 
-        // Calculate coverage as ratio of occupied vertices
-        let occupied_vertices = vertex_counts.values().filter(|&&count| count > 0).count();
-        let coverage = occupied_vertices as f64 / self.tetrahedral_vertices.len() as f64;
+        self.sacred_store
+            .commit()
+            .await
+            .context("Failed to flush Sacred State Store")?;
 
-        Ok(coverage)
+        summary.elapsed_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "✅ Cosmic orchestrator stopped: {} drained, {} terminated in {}ms",
+            summary.drained.len(),
+            summary.terminated.len(),
+            summary.elapsed_ms
+        );
+        Ok(summary)
     }
 
     /// Get active task status
@@ -1853,19 +2135,22 @@ impl CosmicOrchestrator {
                 params.get("fast_ratio").and_then(|v| v.as_f64()),
                 params.get("slow_ratio").and_then(|v| v.as_f64()),
             ) {
-                let actual_ratio = fast / slow;
-                let tolerance = 0.01;
-                return Ok((actual_ratio - self.golden_ratio).abs() < tolerance);
+                return Ok(golden_ratio_allocation_valid(
+                    fast,
+                    slow,
+                    self.golden_ratio_tolerance,
+                ));
             }
         }
 
         // Default validation - check if our configured ratio is correct
-        let expected_fast = 0.618;
-        let expected_slow = 0.382;
-        let actual_ratio = expected_fast / expected_slow;
-        let tolerance = 0.01;
-
-        Ok((actual_ratio - self.golden_ratio).abs() < tolerance)
+        let expected_fast = 1.0 / self.golden_ratio;
+        let expected_slow = 1.0 - expected_fast;
+        Ok(golden_ratio_allocation_valid(
+            expected_fast,
+            expected_slow,
+            self.golden_ratio_tolerance,
+        ))
     }
 
     /// Validate tetrahedral network connectivity
@@ -1954,35 +2239,48 @@ impl CosmicOrchestrator {
         Ok(serde_json::json!(storage_metrics))
     }
 
+    /// Copy of the active tasks, taken under a read lock that is released before returning
+    /// so slow readers never hold up task execution
+    pub async fn snapshot_active_tasks(&self) -> Vec<CosmicTask> {
+        self.active_tasks.read().await.values().cloned().collect()
+    }
+
+    /// Counts over one snapshot of the active tasks, all taken in a single pass
+    pub async fn active_task_counts(&self) -> ActiveTaskCounts {
+        let snapshot = self.snapshot_active_tasks().await;
+        let mut counts = ActiveTaskCounts {
+            total: snapshot.len(),
+            ..Default::default()
+        };
+        for task in &snapshot {
+            if task.fractal_requirements.is_some() {
+                counts.fractal += 1;
+            }
+            match self.determine_tetrahedral_position(task) {
+                TetrahedralPosition::Coordinator => counts.coordinator += 1,
+                TetrahedralPosition::Executor => counts.executor += 1,
+                TetrahedralPosition::Referee => counts.referee += 1,
+                TetrahedralPosition::Development => counts.development += 1,
+            }
+        }
+        counts
+    }
+
     /// Get sacred geometry metrics for monitoring
     pub async fn get_sacred_geometry_metrics(&self) -> Result<serde_json::Value> {
         let validation_result = self.validate_sacred_geometry().await?;
-
-        let active_tasks = self.active_tasks.read().await;
-        let task_count = active_tasks.len();
-        let fractal_task_count = active_tasks
-            .values()
-            .filter(|task| task.fractal_requirements.is_some())
-            .count();
+        let counts = self.active_task_counts().await;
 
         Ok(serde_json::json!({
             "sacred_geometry_health": validation_result,
             "task_metrics": {
-                "total_active_tasks": task_count,
-                "fractal_tasks": fractal_task_count,
+                "total_active_tasks": counts.total,
+                "fractal_tasks": counts.fractal,
                 "tetrahedral_distribution": {
-                    "coordinator_tasks": active_tasks.values().filter(|t|
-                        matches!(self.determine_tetrahedral_position(t), TetrahedralPosition::Coordinator)
-                    ).count(),
-                    "executor_tasks": active_tasks.values().filter(|t|
-                        matches!(self.determine_tetrahedral_position(t), TetrahedralPosition::Executor)
-                    ).count(),
-                    "referee_tasks": active_tasks.values().filter(|t|
-                        matches!(self.determine_tetrahedral_position(t), TetrahedralPosition::Referee)
-                    ).count(),
-                    "development_tasks": active_tasks.values().filter(|t|
-                        matches!(self.determine_tetrahedral_position(t), TetrahedralPosition::Development)
-                    ).count(),
+                    "coordinator_tasks": counts.coordinator,
+                    "executor_tasks": counts.executor,
+                    "referee_tasks": counts.referee,
+                    "development_tasks": counts.development,
                 }
             },
             "golden_ratio_constant": self.golden_ratio,
@@ -2021,12 +2319,12 @@ impl CosmicOrchestrator {
         Ok(())
     }
 
-    /// Register node from configuration file
+    /// Register node from configuration file, returning the generated node id
     pub async fn register_config_node(
         &self,
         config_node_name: &str,
         config_metadata: std::collections::HashMap<String, serde_json::Value>,
-    ) -> Result<()> {
+    ) -> Result<String> {
         // Determine tetrahedral position based on config node name
         let tetrahedral_position = match config_node_name {
             name if name.contains("coordinator") => TetrahedralPosition::Coordinator,
@@ -2051,8 +2349,15 @@ impl CosmicOrchestrator {
             }
         };
 
-        // Generate a unique node ID based on config name
-        let node_id = format!("{}_{}", config_node_name, chrono::Utc::now().timestamp());
+        // Generate a unique node ID based on config name, the random suffix keeps
+        // nodes registered within the same second from colliding
+        let suffix = Uuid::new_v4().simple().to_string();
+        let node_id = format!(
+            "{}_{}_{}",
+            config_node_name,
+            chrono::Utc::now().timestamp(),
+            &suffix[..8]
+        );
 
         self.sacred_store
             .register_node_from_config(
@@ -2068,51 +2373,7 @@ impl CosmicOrchestrator {
             "🔷 Registered config node: {} -> {} at position: {:?}",
             config_node_name, node_id, tetrahedral_position
         );
-        Ok(())
-    }
-
-    /// Load SSH config file and register all nodes in Sacred State Store  
-    pub async fn load_ssh_config_nodes(&self, ssh_config_path: &str) -> Result<usize> {
-        info!("📋 Loading SSH nodes from config: {}", ssh_config_path);
-
-        // Read SSH config file
-        let config_content = tokio::fs::read_to_string(ssh_config_path)
-            .await
-            .context("Failed to read SSH config file")?;
-
-        // Parse JSON config (matches our SSH config structure)
-        let config: serde_json::Value =
-            serde_json::from_str(&config_content).context("Failed to parse SSH config JSON")?;
-
-        let mut registered_count = 0;
-
-        // Process each node in the SSH config
-        if let Some(nodes) = config.as_object() {
-            for (node_name, node_config) in nodes {
-                // Extract node configuration
-                let mut metadata = std::collections::HashMap::new();
-
-                if let Some(obj) = node_config.as_object() {
-                    for (key, value) in obj {
-                        metadata.insert(key.clone(), value.clone());
-                    }
-                }
-
-                // Register the SSH node using existing method
-                match self.register_config_node(node_name, metadata).await {
-                    Ok(()) => {
-                        registered_count += 1;
-                        info!("✅ Registered SSH node: {}", node_name);
-                    }
-                    Err(e) => {
-                        warn!("❌ Failed to register SSH node {}: {}", node_name, e);
-                    }
-                }
-            }
-        }
-
-        info!("🎯 Registered {} SSH nodes from config", registered_count);
-        Ok(registered_count)
+        Ok(node_id)
     }
 }
 
@@ -2120,9 +2381,9 @@ impl CosmicOrchestrator {
 pub async fn create_cosmic_orchestrator(
     src_path: &str,
     storage_path: &str,
-    node_id: String,
+    identity: &NodeIdentity,
 ) -> Result<CosmicOrchestrator> {
-    CosmicOrchestrator::new(src_path, storage_path, node_id).await
+    CosmicOrchestrator::new(src_path, storage_path, identity).await
 }
 
 /// Helper function to create a cosmic task
@@ -2147,5 +2408,8 @@ pub fn create_cosmic_task(
         updated_at: SystemTime::now(),
         result: None,
         error: None,
+        depends_on: Vec::new(),
+        timeout_secs: None,
+        selection_strategy: None,
     }
 }
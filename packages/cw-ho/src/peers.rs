@@ -0,0 +1,76 @@
+//! `cw-ho peers`: peers of the running node, with their protocol version and round trip
+use crate::{load_config, OutputFormat};
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use ho_std::commonware::identity::NodePrivKey;
+use ho_std::prelude::*;
+use ho_std::routes::auth::signed_headers;
+use ho_std::traits::{HoConfigTrait, NodeIdentityTrait};
+
+#[derive(Debug, clap::Parser)]
+pub struct PeersCmd {
+    /// Base url of the node's API [default: http:// and the configured api address]
+    #[arg(long)]
+    pub url: Option<String>,
+}
+
+impl PeersCmd {
+    pub fn exec(&self, home: &Utf8Path, output: OutputFormat) -> Result<()> {
+        let config = load_config(home)?;
+        let identity = config.identity();
+        let key = identity
+            .private_key
+            .as_deref()
+            .and_then(NodePrivKey::from_bytes)
+            .context("node identity has no private key to sign the request with")?;
+        let url = match &self.url {
+            Some(url) => format!("{}/network/peers", url.trim_end_matches('/')),
+            None => format!("http://{}/network/peers", identity.api_address()),
+        };
+
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        let peers: Vec<PeerStatus> = tokio::runtime::Runtime::new()?.block_on(async {
+            let mut request = reqwest::Client::new().get(&url);
+            for (name, value) in signed_headers(&key, &[], timestamp) {
+                request = request.header(name, value);
+            }
+            let response = request
+                .send()
+                .await
+                .with_context(|| format!("Failed to reach {}", url))?
+                .error_for_status()?;
+            anyhow::Ok(response.json().await?)
+        })?;
+
+        output.emit(
+            &table(&peers),
+            serde_json::json!({ "command": "peers", "peers": peers }),
+        );
+        Ok(())
+    }
+}
+
+fn table(peers: &[PeerStatus]) -> Vec<String> {
+    if peers.is_empty() {
+        return vec!["No connected peers".to_string()];
+    }
+    let mut lines = vec![format!(
+        "{:<42} {:<12} {:<22} {:>5} {:>12} {:>9}",
+        "node id", "type", "address", "proto", "connected", "rtt ms"
+    )];
+    lines.extend(peers.iter().map(|peer| {
+        let node = peer.node_info.clone().unwrap_or_default();
+        format!(
+            "{:<42} {:<12} {:<22} {:>5} {:>12} {:>9}",
+            node.node_id,
+            node.node_type,
+            peer.address.as_deref().unwrap_or("-"),
+            peer.protocol_version,
+            peer.connected_at,
+            peer.rtt_ms
+                .map(|rtt| format!("{:.1}", rtt))
+                .unwrap_or_else(|| "-".to_string())
+        )
+    }));
+    lines
+}
@@ -0,0 +1,121 @@
+//! Default bundles selected with `--profile`
+use crate::CwHoConfig;
+use anyhow::{bail, Result};
+use ho_std::constants::LOG_FILE_PATH;
+use ho_std::traits::NodeIdentityTrait;
+
+/// Llm router timeout filled in by the dev profile
+const DEV_TIMEOUT_SECS: u64 = 10;
+/// Llm router timeout filled in by the prod profile
+const PROD_TIMEOUT_SECS: u64 = 60;
+
+/// Coherent set of defaults for a deployment. Profiles only fill fields the
+/// config leaves unset, explicit values always win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Profile {
+    /// Mock llm router, debug logging and short timeouts
+    Dev,
+    /// Real providers, file logging and strict validation
+    Prod,
+}
+
+impl Profile {
+    pub fn log_level(self) -> &'static str {
+        match self {
+            Profile::Dev => "debug",
+            Profile::Prod => "info",
+        }
+    }
+
+    /// Fill unset config fields with this profile's defaults. The prod profile
+    /// then rejects configs it cannot run with.
+    pub fn apply(self, config: &mut CwHoConfig) -> Result<()> {
+        self.apply_logging(config);
+
+        let llm = config.0.llm.get_or_insert_with(Default::default);
+        if llm.timeout_seconds == 0 {
+            llm.timeout_seconds = match self {
+                Profile::Dev => DEV_TIMEOUT_SECS,
+                Profile::Prod => PROD_TIMEOUT_SECS,
+            };
+        }
+        match self {
+            Profile::Dev => {
+                llm.mock.get_or_insert(true);
+                Ok(())
+            }
+            Profile::Prod => validate_prod(config),
+        }
+    }
+
+    /// Fill unset logging fields, kept separate so tracing can be set up before startup
+    pub fn apply_logging(self, config: &mut CwHoConfig) {
+        let logging = config.0.logging.get_or_insert_with(Default::default);
+        if logging.level.is_empty() {
+            logging.level = self.log_level().to_string();
+        }
+        if self == Profile::Prod && logging.file.is_none() {
+            logging.file = Some(LOG_FILE_PATH.to_string());
+        }
+    }
+}
+
+/// Checks the prod profile enforces on top of load-time validation
+fn validate_prod(config: &CwHoConfig) -> Result<()> {
+    let Some(llm) = config.llm.as_ref() else {
+        bail!("prod profile requires an llm router config");
+    };
+    if llm.mock == Some(true) {
+        bail!("prod profile does not allow the mock llm router");
+    }
+    if !llm.entities.iter().any(|e| e.enabled) {
+        bail!("prod profile requires at least one enabled llm provider");
+    }
+    match config.identity.as_ref() {
+        Some(identity) => identity.checked_api_port()?,
+        None => bail!("prod profile requires a node identity"),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LlmRouter;
+    use camino::Utf8Path;
+    use ho_std::prelude::*;
+    use ho_std::traits::HoConfigTrait;
+
+    #[tokio::test]
+    async fn test_dev_uses_mock_router_and_prod_needs_providers() {
+        let home = Utf8Path::new("/tmp/cw-ho-profile");
+        let mut dev = CwHoConfig::new(home);
+        Profile::Dev.apply(&mut dev).unwrap();
+        assert_eq!(dev.llm().mock, Some(true));
+
+        let router = LlmRouter::new(dev.llm()).await.unwrap();
+        let request = PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: "ping".to_string(),
+            }],
+            ..Default::default()
+        };
+        let response = router.process_request(&request, "gpt-4o").await.unwrap();
+        assert_eq!(response.provider, "mock");
+
+        // explicit values are kept
+        let mut explicit = CwHoConfig::new(home);
+        explicit.0.llm.as_mut().unwrap().mock = Some(false);
+        Profile::Dev.apply(&mut explicit).unwrap();
+        assert_eq!(explicit.llm().mock, Some(false));
+
+        let mut prod = CwHoConfig::new(home);
+        for entity in &mut prod.0.llm.as_mut().unwrap().entities {
+            entity.enabled = false;
+        }
+        let err = Profile::Prod.apply(&mut prod).unwrap_err();
+        assert!(err.to_string().contains("enabled llm provider"));
+        assert!(prod.logging.as_ref().unwrap().file.is_some());
+    }
+}
@@ -0,0 +1,280 @@
+//! `cw-ho replay`: re-run a stored task's prompt and compare the outcome with the original
+use crate::{load_config, CwHoStorage, LlmRouter, OutputFormat};
+use anyhow::{bail, Context, Result};
+use camino::Utf8Path;
+use ho_std::prelude::*;
+use ho_std::traits::HoConfigTrait;
+use pbjson_types::{value::Kind, Struct, Value};
+use std::collections::BTreeSet;
+
+#[derive(Debug, clap::Parser)]
+pub struct ReplayCmd {
+    /// Id of the stored task to re-run
+    pub task_id: String,
+    /// Answer with the mock router instead of the configured providers, for a
+    /// deterministic replay
+    #[arg(long)]
+    pub mock: bool,
+}
+
+impl ReplayCmd {
+    pub fn exec(&self, home: &Utf8Path, output: OutputFormat) -> Result<()> {
+        let mut config = load_config(home)?;
+        if self.mock {
+            config.0.llm.get_or_insert_with(Default::default).mock = Some(true);
+        }
+
+        let (original, replayed) = tokio::runtime::Runtime::new()?.block_on(async {
+            let storage = CwHoStorage::from_config(config.storage())
+                .await
+                .context("Failed to open storage; stop the node before replaying its tasks")?;
+            let original = load_task(&storage, &self.task_id).await;
+            storage.close().await;
+            let original = original?;
+            let router = LlmRouter::new(config.llm()).await?;
+            let replayed = replay_task(&router, &original).await;
+            anyhow::Ok((original, replayed))
+        })?;
+
+        let diff = diff_results(original.result.as_ref(), replayed.result.as_ref());
+        let mut lines = vec![
+            format!("task {}", original.id),
+            format!(
+                "status   {} -> {}",
+                original.status().as_str_name(),
+                replayed.status().as_str_name()
+            ),
+        ];
+        if let Some(error) = &replayed.error {
+            lines.push(format!("error    {}", error));
+        }
+        for (label, fields) in [
+            ("missing", &diff.missing),
+            ("added", &diff.added),
+            ("changed", &diff.changed),
+        ] {
+            if !fields.is_empty() {
+                lines.push(format!("{:<8} {}", label, fields.join(", ")));
+            }
+        }
+        output.emit(
+            &lines,
+            serde_json::json!({
+                "command": "replay",
+                "original": original,
+                "replayed": replayed,
+                "diff": diff,
+            }),
+        );
+        Ok(())
+    }
+}
+
+/// Stored task `id`, with a clear error when retention has swept it
+async fn load_task(storage: &CwHoStorage, id: &str) -> Result<CosmicTask> {
+    if let Some(task) = storage.get_task(id).await? {
+        return Ok(task);
+    }
+    if storage.get_task_summary(id).await?.is_some() {
+        bail!(
+            "task {} was swept by task retention; only its summary is kept",
+            id
+        );
+    }
+    bail!("no stored task {}", id)
+}
+
+/// Run `task`'s prompt again through `router`, on the provider and model its result
+/// records so the two answers compare. A task without a recorded provider is routed with
+/// its selection strategy, or the router's default when it sets none. The stored task is
+/// left untouched; the replay is returned as a copy with a fresh status, result and error.
+pub async fn replay_task(router: &LlmRouter, task: &CosmicTask) -> CosmicTask {
+    let mut replayed = CosmicTask {
+        result: None,
+        error: None,
+        ..task.clone()
+    };
+    let answer = match original_selection(task) {
+        Some((provider, model)) => router.answer_task_from(task, provider, &model).await,
+        None => router.answer_task(task, None).await,
+    };
+    match answer {
+        Ok(response) => {
+            replayed.set_status(CosmicTaskStatus::Completed);
+            replayed.result = Some(prompt_result(&response));
+        }
+        Err(e) => {
+            replayed.set_status(CosmicTaskStatus::Failed);
+            replayed.error = Some(e.to_string());
+        }
+    }
+    replayed.updated_at = Some(chrono::Utc::now().into());
+    replayed
+}
+
+/// Provider and model that answered `task`, as its result records them
+fn original_selection(task: &CosmicTask) -> Option<(LlmModel, String)> {
+    let fields = &task.result.as_ref()?.fields;
+    let text = |name: &str| match &fields.get(name)?.kind {
+        Some(Kind::StringValue(text)) => Some(text.clone()),
+        _ => None,
+    };
+    let provider = LlmRouter::provider_named(&text("provider")?)?;
+    Some((provider, text("model")?))
+}
+
+/// Result recorded for a prompt task answered by `response`
+pub fn prompt_result(response: &PromptResponse) -> Struct {
+    let text = |s: &str| Value {
+        kind: Some(Kind::StringValue(s.to_string())),
+    };
+    Struct {
+        fields: [
+            ("provider".to_string(), text(&response.provider)),
+            ("model".to_string(), text(&response.model)),
+            ("response".to_string(), text(&response.response.join("\n"))),
+        ]
+        .into(),
+    }
+}
+
+/// Compare two task results field by field; an absent result has no fields
+pub fn diff_results(original: Option<&Struct>, replayed: Option<&Struct>) -> ResultDiff {
+    let empty = Default::default();
+    let original = &original.unwrap_or(&empty).fields;
+    let replayed = &replayed.unwrap_or(&empty).fields;
+    let names: BTreeSet<&String> = original.keys().chain(replayed.keys()).collect();
+
+    let mut diff = ResultDiff::default();
+    for name in names {
+        match (original.get(name), replayed.get(name)) {
+            (Some(_), None) => diff.missing.push(name.clone()),
+            (None, Some(_)) => diff.added.push(name.clone()),
+            (Some(before), Some(after)) if before != after => diff.changed.push(name.clone()),
+            _ => {}
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_router_config, FakeBackend};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_replaying_completed_task_yields_comparable_result() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(HttpResponse::new(
+                reqwest::StatusCode::OK,
+                serde_json::json!({
+                    "choices": [{"message": {"role": "assistant", "content": "a tetrahedron"}}],
+                })
+                .to_string(),
+            ));
+        let entity = LlmModel::OpenAi.default_entity();
+        // The router's default is AkashChat; the replay still goes to OpenAI
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity(), entity.clone()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        router.api_keys.openai = Some("test-key".to_string());
+        let storage = CwHoStorage::memory();
+
+        // A task completed earlier, by a provider that answered differently
+        let mut task = AgentSpec::new(
+            "replay-me".to_string(),
+            "coordinator",
+            "name a platonic solid".to_string(),
+        )
+        .unwrap()
+        .to_task();
+        task.set_status(CosmicTaskStatus::Completed);
+        task.result = Some(prompt_result(&PromptResponse {
+            provider: "openai".to_string(),
+            model: entity.default_model.clone(),
+            response: vec!["a cube".to_string()],
+            ..Default::default()
+        }));
+        storage.store_task(&task).await.unwrap();
+
+        let original = load_task(&storage, "replay-me").await.unwrap();
+        let replayed = replay_task(&router, &original).await;
+        assert_eq!(replayed.id, original.id);
+        assert_eq!(replayed.status(), CosmicTaskStatus::Completed);
+        assert!(replayed.error.is_none());
+
+        let diff = diff_results(original.result.as_ref(), replayed.result.as_ref());
+        assert!(diff.same_shape(), "{:?}", diff);
+        assert_eq!(diff.changed, ["response"]);
+        assert_eq!(
+            *backend.urls.lock().unwrap(),
+            ["https://api.openai.com/v1/chat/completions"]
+        );
+        // Replaying leaves the stored task as it was
+        assert_eq!(storage.get_task("replay-me").await.unwrap(), Some(task));
+
+        let err = load_task(&storage, "never-stored").await.unwrap_err();
+        assert_eq!(err.to_string(), "no stored task never-stored");
+    }
+
+    #[tokio::test]
+    async fn test_replaying_anthropic_task_is_answered_by_anthropic() {
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(HttpResponse::new(
+                reqwest::StatusCode::OK,
+                serde_json::json!({
+                    "content": [{"type": "text", "text": "a tetrahedron"}],
+                    "usage": {"input_tokens": 10, "output_tokens": 5},
+                })
+                .to_string(),
+            ));
+        let entity = LlmModel::Anthropic.default_entity();
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity(), entity.clone()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.anthropic = Some("test-key".to_string());
+
+        let mut task = AgentSpec::new(
+            "replay-anthropic".to_string(),
+            "coordinator",
+            "name a platonic solid".to_string(),
+        )
+        .unwrap()
+        .to_task();
+        task.set_status(CosmicTaskStatus::Completed);
+        task.result = Some(prompt_result(&PromptResponse {
+            provider: "anthropic".to_string(),
+            model: entity.default_model.clone(),
+            response: vec!["a cube".to_string()],
+            ..Default::default()
+        }));
+
+        let replayed = replay_task(&router, &task).await;
+        assert_eq!(
+            replayed.status(),
+            CosmicTaskStatus::Completed,
+            "{:?}",
+            replayed.error
+        );
+        let diff = diff_results(task.result.as_ref(), replayed.result.as_ref());
+        assert_eq!(diff.changed, ["response"]);
+        assert_eq!(
+            *backend.urls.lock().unwrap(),
+            [ho_std::constants::ANTHROPIC_MESSAGE_URL]
+        );
+    }
+}
@@ -1,34 +1,99 @@
 use ho_std::{
+    constants::{
+        DEFAULT_SSH_REGISTRATION_CONCURRENCY, LLM_MODEL_HEADER, LLM_PROVIDER_HEADER,
+        MAX_REQUEST_ID_LEN, REQUEST_ID_HEADER, TASK_SHUTDOWN_GRACE_SECS,
+    },
+    orchestrate::panic_message,
     prelude::*,
-    routes::AuthLayer,
+    python::capabilities::PythonCapabilities,
+    routes::{ApiError, ApiJson, AuthLayer},
     traits::{HoConfigTrait, NodeIdentityTrait},
-    transports::ssh::SSHConnectionManager,
+    transports::ssh::{ssh_config_path, SSHConnectionManager},
+    utils::shared_config_loader,
 };
 
-use crate::{error::*, AppState, CwHoConfig, CwHoNetworkManifold, CwHoStorage, LlmRouter};
+use crate::{
+    artifacts::ArtifactStore, error::*, geometry, network::topology::NetworkTopology,
+    ssh_nodes::load_ssh_config_nodes, tasks::TaskRunner, AppState, CwHoConfig, CwHoNetworkManifold,
+    CwHoStorage, LlmRouter,
+};
 use axum::{
-    extract::{Query, State},
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
     Json, Router,
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use commonware_runtime::tokio::Context;
 use std::{ops::Deref, sync::Arc, time::Instant};
 use tokio::net::TcpListener;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::{error, info};
+use tokio::sync::{broadcast, watch};
+use tower_http::{catch_panic::CatchPanicLayer, cors::CorsLayer, trace::TraceLayer};
+use tracing::{debug, error, info, warn, Instrument};
+use uuid::Uuid;
 
 pub struct Server {
-    state: AppState,
+    pub(crate) state: AppState,
 }
 
 impl Server {
-    pub async fn new(config: CwHoConfig, context: Context) -> Result<Self> {
+    /// Start a node on `config`, reading the files kept beside it, such as the SSH node
+    /// map, from `home`
+    pub async fn new(
+        config: CwHoConfig,
+        home: &Utf8Path,
+        context: Context,
+        ephemeral: bool,
+    ) -> Result<Self> {
         config.validate()?;
         let config_clone = config.clone();
         // STORAGE_INIT
-        let storage = Arc::new(CwHoStorage::new(&config.storage().data_dir).await?);
+        let storage = match ephemeral {
+            true => Arc::new(CwHoStorage::memory()),
+            false => Arc::new(CwHoStorage::from_config(config.storage()).await?),
+        };
+        // TASK_RETENTION
+        if let Some(retention_secs) = config.storage().task_retention_secs {
+            let sweeper = storage.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                    ho_std::constants::TASK_SWEEP_INTERVAL_SECS,
+                ));
+                loop {
+                    interval.tick().await;
+                    let now = chrono::Utc::now().timestamp();
+                    if let Err(e) = sweeper.sweep_tasks(retention_secs, now).await {
+                        error!("🧹 Task sweep failed: {}", e);
+                    }
+                }
+            });
+        }
+        // SSH_NODES
+        let ssh_config = ssh_config_path(home);
+        if ssh_config.exists() {
+            let concurrency = config
+                .ssh_registration_concurrency
+                .map_or(DEFAULT_SSH_REGISTRATION_CONCURRENCY, |n| n as usize);
+            if let Err(e) =
+                load_ssh_config_nodes(&storage, ssh_config.as_std_path(), concurrency).await
+            {
+                warn!("⚠️ Failed to load SSH nodes from {}: {}", ssh_config, e);
+            }
+        }
         // LLM_ROUTER_INIT
         let llm_config = config.llm();
-        let llm_router = Arc::new(LlmRouter::new(llm_config.deref()).await?);
+        let llm_router = Arc::new(
+            LlmRouter::new(llm_config.deref())
+                .await?
+                .with_egress(egress_policy(&config, home)),
+        );
+        // Warm up providers in the background so startup is never blocked on them
+        let warm_router = llm_router.clone();
+        tokio::spawn(async move { warm_router.warm_up().await });
         // NETWORK MANIFOLD
         let mut network_manifold =
             CwHoNetworkManifold::new(config.identity().clone(), context).await;
@@ -37,18 +102,57 @@ impl Server {
         network_manifold.start_network(config.network()).await?;
         info!("🌐 Network manager initialized and started");
 
+        // PYTHON_PROBE
+        let python = PythonCapabilities::detect();
+        for unavailable in python.unavailable() {
+            warn!("🐍 {}", unavailable);
+        }
+
+        // TASK_RUNNER
+        let tasks = TaskRunner::new(storage.clone(), llm_router.clone())
+            .with_default_timeout(config.task_timeout_secs.map(std::time::Duration::from_secs))
+            .with_artifacts(ArtifactStore::from_config(&config));
+
         let state = AppState {
             storage,
             llm_router,
             network_manifold: Arc::new(tokio::sync::Mutex::new(network_manifold)),
             start_time: Instant::now(),
             config: config_clone,
+            python,
+            config_path: None,
+            home: home.to_path_buf(),
+            tasks: Arc::new(tasks),
         };
 
         Ok(Self { state })
     }
 
+    /// Save runtime changes such as the default LLM entity to the config file at `path`
+    pub fn with_config_path(mut self, path: Utf8PathBuf) -> Self {
+        self.state.config_path = Some(path);
+        self
+    }
+
     pub async fn run(self, port: u16) -> Result<()> {
+        let host = &self.state.config.network().listen_address;
+        // IPv6 hosts such as `::` from `--bind` need brackets before the port
+        let addr = match host.parse::<std::net::IpAddr>() {
+            Ok(ip) => std::net::SocketAddr::new(ip, port).to_string(),
+            Err(_) => format!("{}:{}", host, port),
+        };
+        info!("🌐 Server listening on {}", addr);
+        let listener = TcpListener::bind(&addr).await?;
+        self.serve(listener, shutdown_signal()).await
+    }
+
+    /// Serve every route on `listener` until `shutdown` resolves, then drain in-flight
+    /// requests, stop the network and flush storage
+    pub async fn serve(
+        self,
+        listener: TcpListener,
+        shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+    ) -> Result<()> {
         // Use the new generic route structure from ho-std
         let (public_router, protected_router) = ho_std::define_routes! {
             public_routes: [
@@ -58,31 +162,129 @@ impl Server {
                 { path: "/api/prompts", method: get, handler: handle_query },
                 { path: "/orchestrate/bootstrap", method: post, handler: handle_bootstrap },
                 { path: "/api/prompt", method: post, handler: handle_prompt },
+                { path: "/prompts/import", method: post, handler: handle_import },
                 { path: "/orchestrate/fractal", method: post, handler: handle_fractal_hoe_creation },
                 { path: "/orchestrate/prune", method: post, handler: handle_prune },
+                { path: "/orchestrate/tasks", method: post, handler: handle_tasks },
                 { path: "/network/topology", method: get, handler: handle_network_topology },
+                { path: "/network/peers", method: get, handler: handle_network_peers },
+                { path: "/topology/stream", method: get, handler: handle_topology_stream },
+                { path: "/metrics", method: get, handler: handle_metrics },
+                { path: "/geometry", method: get, handler: handle_geometry },
+                { path: "/geometry/validate", method: post, handler: handle_geometry_validate },
+                { path: "/llm/providers", method: get, handler: handle_llm_providers },
+                { path: "/llm/default", method: post, handler: handle_llm_default },
+                { path: "/llm/models", method: get, handler: handle_llm_models },
+                { path: "/tasks/{id}/artifacts", method: get, handler: handle_task_artifacts },
+                { path: "/tasks/{id}/artifacts/{name}", method: get, handler: handle_task_artifact },
             ]
         };
-        let addr = format!("{}:{}", self.state.config.network().listen_address, port);
+        let state = self.state.clone();
+        // Running tasks are cancelled as soon as shutdown starts, so the requests
+        // waiting on them can drain
+        let tasks = self.state.tasks.clone();
+        let shutdown = async move {
+            shutdown.await;
+            tasks
+                .shutdown(std::time::Duration::from_secs(TASK_SHUTDOWN_GRACE_SECS))
+                .await;
+        };
         axum::serve(
-            TcpListener::bind(&addr).await?,
+            listener,
             Router::new()
                 .merge(public_router)
                 .merge(protected_router.route_layer(AuthLayer))
                 .layer(CorsLayer::permissive())
                 .layer(TraceLayer::new_for_http())
+                .layer(CatchPanicLayer::custom(panic_response))
+                .layer(axum::middleware::from_fn(correlate_request))
                 .with_state(self.state),
         )
+        .with_graceful_shutdown(shutdown)
         .await
         .map_err(|e| CwHoError::Config(format!("Server error: {}", e)))?;
-        info!("🌐 Server listening on {}", addr);
+
+        // In-flight requests have drained; stop the network and flush storage
+        info!("🛑 Shutting down node services");
+        state.network_manifold.lock().await.shutdown().await;
+        state.storage.close().await;
+        info!("👋 Shutdown complete");
         Ok(())
     }
 }
 
+/// Resolves on ctrl-c or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("🛑 Shutdown signal received, draining connections");
+}
+
+/// Tag each request with a correlation id: the client's `x-request-id` when usable, a fresh
+/// uuid otherwise. The id replaces the request's header for handlers to read, is recorded on
+/// a span around everything the request logs, and is echoed on the response and in the body
+/// of an [`ApiError`].
+async fn correlate_request(
+    mut request: axum::extract::Request,
+    next: Next,
+) -> axum::response::Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&id).expect("request ids are visible ascii");
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, value.clone());
+
+    let span = tracing::info_span!("request", request_id = %id);
+    let response = next.run(request).instrument(span).await;
+    let mut response = ApiError::with_request_id(response, &id);
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+/// A handler panic answers 500 in the error envelope instead of dropping the connection
+fn panic_response(panic: Box<dyn std::any::Any + Send + 'static>) -> axum::response::Response {
+    error!("💥 Request handler panicked: {}", panic_message(&*panic));
+    ApiError::internal("INTERNAL_ERROR", "request handler panicked").into_response()
+}
+
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Correlation id [`correlate_request`] left on the request
+fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
 async fn handle_fractal_hoe_creation(// State(_state): State<AppState>,
     // Json(request): Json<PromptRequest>,
-) -> Json<serde_json::Value> {
+) -> ApiError {
     info!("🌀 Creating fractal hoe");
     //TODO: boostrap new node via desired method
     // Create persistent SSH connection manager
@@ -95,13 +297,26 @@ async fn handle_fractal_hoe_creation(// State(_state): State<AppState>,
     // Close SSH connection before returning
     // let _ = ssh_manager.close().await;
 
-    Json(error_json("Currently unimplemented", "INVALID_PROMPT"))
+    ApiError::new(
+        StatusCode::NOT_IMPLEMENTED,
+        "UNIMPLEMENTED",
+        "Currently unimplemented",
+    )
+}
+
+/// Egress allowlist from the config, defaulting to the providers and the SSH nodes under
+/// `home`
+fn egress_policy(config: &CwHoConfig, home: &Utf8Path) -> EgressPolicy {
+    let ssh_config = shared_config_loader()
+        .json::<serde_json::Value, _>(ssh_config_path(home))
+        .ok();
+    EgressPolicy::from_config(config, ssh_config.as_ref())
 }
 
 async fn handle_bootstrap(
-    State(..): State<AppState>,
-    Json(request): Json<BootstrapRequest>,
-) -> Json<serde_json::Value> {
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<BootstrapRequest>,
+) -> std::result::Result<Json<BootstrapResponse>, ApiError> {
     let start_time = Instant::now();
     let id = uuid::Uuid::new_v4();
     let target_node = request.target_node.clone();
@@ -109,9 +324,28 @@ async fn handle_bootstrap(
     // TODO: handle bootstrap via method:
     // /hoe.network.v1.bootstrap.types: (transport connections for nodes,bootstrapping types used in functions for traits )
 
+    if let Err(e) = state
+        .python
+        .require("bootstrap", &[ho_std::constants::TOOLS_SSH_TRANSPORT])
+    {
+        error!("Bootstrap refused for node {}: {}", target_node, e);
+        return Err(ApiError::internal("PYTHON_UNAVAILABLE", e.to_string()));
+    }
+
     // Create persistent SSH connection manager
     info!("🚀 Starting bootstrap process for node: {}", target_node);
-    let mut ssh_manager = SSHConnectionManager::new(target_node.clone());
+    let max_archive_bytes = state
+        .config
+        .max_workspace_archive_bytes
+        .unwrap_or(ho_std::constants::DEFAULT_MAX_WORKSPACE_ARCHIVE_BYTES);
+    let transient_retries = state
+        .config
+        .ssh_transient_retries
+        .unwrap_or(ho_std::constants::DEFAULT_SSH_TRANSIENT_RETRIES);
+    let mut ssh_manager = SSHConnectionManager::new(target_node.clone(), &state.home)
+        .with_egress(egress_policy(&state.config, &state.home))
+        .with_max_archive_bytes(max_archive_bytes)
+        .with_transient_retries(transient_retries);
 
     match ssh_manager.bootstrap_node().await {
         Ok(bootstrap_summary) => {
@@ -132,7 +366,7 @@ async fn handle_bootstrap(
                 duration_ms: start_time.elapsed().as_millis() as u64,
             };
 
-            Json(serde_json::to_value(response).unwrap())
+            Ok(Json(response))
         }
         Err(e) => {
             error!("Bootstrap failed for node {}: {}", target_node, e);
@@ -140,9 +374,9 @@ async fn handle_bootstrap(
             // Close SSH connection before returning error
             let _ = ssh_manager.close().await;
 
-            Json(error_json(
-                &format!("Bootstrap failed: {}", e),
+            Err(ApiError::internal(
                 "BOOTSTRAP_ERROR",
+                format!("Bootstrap failed: {}", e),
             ))
         }
     }
@@ -150,7 +384,7 @@ async fn handle_bootstrap(
 
 async fn handle_prune(// State(state): State<AppState>,
     // Json(_request): Json<PromptRequest>,
-) -> Json<serde_json::Value> {
+) -> ApiError {
     //TODO: prune all non-coordinator nodes storage state by bradcasting its cnardium state to up to the coordinator node.
     info!("🔌 Step 1: snapshot, prepend metadata & broadcast to coordinator node");
     info!("🔌 Step 2: Dump snapshot of state and broadcast to coordinator node");
@@ -164,82 +398,278 @@ async fn handle_prune(// State(state): State<AppState>,
     //     Ok(_) => {}
     //     Err(_e) => return Json(error_json("CwHoStorage prune failed", "STORAGE_ERROR")),
     // };
-    Json(error_json("Currently unimplemented", "INVALID_PROMPT"))
+    ApiError::new(
+        StatusCode::NOT_IMPLEMENTED,
+        "UNIMPLEMENTED",
+        "Currently unimplemented",
+    )
 }
 
-async fn handle_prompt(// State(state): State<AppState>,
-    // Json(request): Json<PromptRequest>,
-) -> Json<serde_json::Value> {
-    // let start_time = Instant::now();
-    // let id = Uuid::new_v4();
-
-    // let prompt = serde_json::to_string(&request.messages).unwrap();
-
-    // // Validate request
-    // if request.messages.is_empty() {
-    //     return Json(error_json(
-    //         "Prompt messages cannot be empty",
-    //         "INVALID_PROMPT",
-    //     ));
-    // }
-
-    // // Route to LLM
-    // let model = &request.model;
-
-    // match state.llm_router.process_request(&request, model).await {
-    //     Ok(llm_response) => {
-    //         let duration = start_time.elapsed();
-
-    //         let response = PromptResponse {
-    //             id: id.into(),
-    //             prompt,
-    //             response: llm_response.response,
-    //             model: model.to_string(),
-    //             timestamp: None, // TODO: Fix timestamp conversion
-    //             tokens_used: llm_response.tokens_used,
-    //             provider: "default".to_string(),
-    //             cost: Some(0.0),
-    //             latency_ms: Some(duration.as_millis() as u64),
-    //             // context: request.context.clone(),
-    //         };
-
-    //         // Store to Cnidarium with original request context
-    //         if let Err(e) = state
-    //             .storage
-    //             .store_prompt_with_context(&response, Some(&request))
-    //             .await
-    //         {
-    //             error!("Failed to store prompt to storage: {}", e);
-    //             // Continue anyway - we don't want to fail the request due to storage issues
-    //         }
-
-    //         Json(serde_json::to_value(response).unwrap())
-    //     }
-    //     Err(e) => {
-    //         error!("LLM processing failed: {}", e);
-    //         Json(error_json(
-    //             &format!("LLM processing failed: {}", e),
-    //             "LLM_ERROR",
-    //         ))
-    //     }
-    // }
-    Json(serde_json::to_value("{}").unwrap())
+/// Run a task graph in dependency order, answering with every task in its final state
+async fn handle_tasks(
+    State(state): State<AppState>,
+    ApiJson(tasks): ApiJson<Vec<CosmicTask>>,
+) -> std::result::Result<Json<Vec<CosmicTask>>, ApiError> {
+    Ok(Json(state.tasks.run(tasks).await?))
+}
+
+/// Cancels an LLM call when dropped while the call is still running, which happens when
+/// the client disconnects and axum drops the handler
+struct CancelOnDrop(watch::Sender<bool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        // the call's receiver is gone once it finished
+        if !self.0.is_closed() {
+            info!("🔌 Client disconnected, cancelling its LLM call");
+            let _ = self.0.send(true);
+        }
+    }
+}
+
+/// Route a prompt to an LLM provider. The `x-llm-provider` and `x-llm-model` headers, or
+/// the body's `provider` and `model`, override the default selection. A prompt whose
+/// context names a session is answered as that session's next turn.
+async fn handle_prompt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ApiJson(mut request): ApiJson<PromptRequest>,
+) -> std::result::Result<Json<PromptResponse>, ApiError> {
+    let start_time = Instant::now();
+    if request.messages.is_empty() {
+        return Err(ApiError::bad_request(
+            "INVALID_PROMPT",
+            "Prompt messages cannot be empty",
+        ));
+    }
+
+    let (provider, model) = requested_selection(&headers, &request);
+    let (provider, model) = match state
+        .llm_router
+        .resolve_selection(provider.as_deref(), model.as_deref())
+    {
+        Ok(selection) => selection,
+        Err(e) => return Err(ApiError::bad_request("INVALID_PROVIDER", e.to_string())),
+    };
+    request.provider = Some(provider.as_str_name().to_string());
+    request.model = model.clone();
+
+    // The call runs in its own task; if the client disconnects, axum drops this handler and
+    // the guard cancels the call instead of leaving the provider request running
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    let _disconnect = CancelOnDrop(cancel_tx);
+    let router = state.llm_router.clone();
+    let storage = state.storage.clone();
+    let call_request = request.clone();
+    let session_id = request.context.as_ref().and_then(|c| c.session_id.clone());
+    // The call keeps the request's span, so router logs carry its correlation id
+    let call = tokio::spawn(
+        async move {
+            match session_id {
+                Some(session_id) => {
+                    router
+                        .route_request_in_session(
+                            &storage,
+                            &session_id,
+                            &call_request,
+                            &model,
+                            cancel_rx,
+                        )
+                        .await
+                }
+                None => {
+                    router
+                        .process_request_cancellable(&call_request, &model, cancel_rx)
+                        .await
+                }
+            }
+        }
+        .in_current_span(),
+    );
+    let result = match call.await {
+        Ok(result) => result,
+        Err(e) => Err(CwHoError::LlmEntity(format!("LLM call panicked: {}", e))),
+    };
+
+    match result {
+        Ok(mut response) => {
+            response.id = Uuid::new_v4().as_bytes().to_vec();
+            response.latency_ms = Some(start_time.elapsed().as_millis() as u64);
+            response.request_id = request_id(&headers);
+            // A storage failure should not fail the request
+            match state.llm_router.retained(provider, &response) {
+                Some(record) => {
+                    if let Err(e) = state
+                        .storage
+                        .store_prompt_with_context(&record, Some(&request))
+                        .await
+                    {
+                        error!("Failed to store prompt to storage: {}", e);
+                    }
+                }
+                None => debug!(
+                    "🙈 Not storing the response from {}, its entity keeps no responses",
+                    response.provider
+                ),
+            }
+            Ok(Json(response))
+        }
+        Err(e) => {
+            error!("LLM processing failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Provider and model a prompt asked for, headers taking precedence over body fields
+fn requested_selection(
+    headers: &HeaderMap,
+    request: &PromptRequest,
+) -> (Option<String>, Option<String>) {
+    let pick = |header: &str, body: Option<&str>| {
+        headers
+            .get(header)
+            .and_then(|value| value.to_str().ok())
+            .or(body)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    (
+        pick(LLM_PROVIDER_HEADER, request.provider.as_deref()),
+        pick(LLM_MODEL_HEADER, Some(request.model.as_str())),
+    )
+}
+
+/// Configured LLM entities, flagging the one requests without a provider go to
+async fn handle_llm_providers(State(state): State<AppState>) -> Json<Vec<LlmEntityStatus>> {
+    Json(state.llm_router.entity_statuses())
+}
+
+/// Models each enabled entity serves, for clients populating model pickers.
+/// `?live=true` leaves out providers the last warm-up marked down.
+async fn handle_llm_models(
+    State(state): State<AppState>,
+    Query(query): Query<LlmModelsQuery>,
+) -> Json<Vec<LlmProviderModels>> {
+    Json(state.llm_router.model_listing(query.live.unwrap_or(false)))
+}
+
+/// Make an enabled entity the default for requests naming no provider. The change is live
+/// at once and saved to the config file, so the node keeps it across restarts.
+async fn handle_llm_default(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<SetDefaultEntityRequest>,
+) -> std::result::Result<Json<serde_json::Value>, ApiError> {
+    let applied = LlmRouter::provider_named(request.model.trim())
+        .ok_or_else(|| CwHoError::InvalidRequest(format!("unknown LLM provider {}", request.model)))
+        .and_then(|model| state.llm_router.set_default_entity(model).map(|()| model));
+    let model = match applied {
+        Ok(model) => model,
+        Err(e) => return Err(ApiError::bad_request("INVALID_PROVIDER", e.to_string())),
+    };
+    if let Some(path) = &state.config_path {
+        if let Err(e) = save_default_entity(path, model) {
+            error!("Failed to save the default LLM entity to {}: {}", path, e);
+            return Err(ApiError::internal(
+                "CONFIG_WRITE_FAILED",
+                format!("Default set for this run but not saved: {}", e),
+            ));
+        }
+    }
+    Ok(Json(
+        serde_json::json!({ "default_entity": model.as_str_name() }),
+    ))
+}
+
+/// Record `model` as the default entity in the config file at `path`. The file is re-read
+/// so profile defaults and other in-memory overrides are not written back with it.
+fn save_default_entity(path: &Utf8Path, model: LlmModel) -> Result<()> {
+    let mut config = CwHoConfig::load(path)?;
+    config
+        .0
+        .llm
+        .get_or_insert_with(Default::default)
+        .update_default_entity(model);
+    config.save(path)?;
+    Ok(())
+}
+
+/// The node's artifact store, or the error answered when `artifact_dir` is unset
+fn artifact_store(state: &AppState) -> std::result::Result<ArtifactStore, ApiError> {
+    ArtifactStore::from_config(&state.config).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "ARTIFACTS_DISABLED",
+            "This node keeps task outputs inline",
+        )
+        .with_hint(Some("set artifact_dir in the node config".to_string()))
+    })
+}
+
+/// Artifacts task `id` wrote, by name and size
+async fn handle_task_artifacts(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> std::result::Result<Json<Vec<ArtifactInfo>>, ApiError> {
+    Ok(Json(artifact_store(&state)?.list(&task_id)?))
+}
+
+/// Download task `id`'s artifact `name` as text
+async fn handle_task_artifact(
+    State(state): State<AppState>,
+    Path((task_id, name)): Path<(String, String)>,
+) -> std::result::Result<Response, ApiError> {
+    match artifact_store(&state)?.read(&task_id, &name)? {
+        Some(contents) => Ok((
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            contents,
+        )
+            .into_response()),
+        None => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "ARTIFACT_NOT_FOUND",
+            format!("Task {} has no artifact {}", task_id, name),
+        )),
+    }
 }
 
 async fn handle_query(
     State(state): State<AppState>,
-    Query(query): Query<QueryRequest>,
-) -> Json<serde_json::Value> {
+    Query(mut query): Query<QueryRequest>,
+) -> std::result::Result<Json<Vec<PromptResponse>>, ApiError> {
+    // A malformed time range is the caller's mistake, not a failed query
+    if let Err(e) = query.resolve_time_range(chrono::Utc::now()) {
+        return Err(ApiError::bad_request("INVALID_QUERY", e.to_string()));
+    }
     match state.storage.query_prompts(&query).await {
-        Ok(prompts) => {
-            Json(serde_json::to_value(prompts).unwrap_or_else(|_| serde_json::json!([])))
-        }
+        Ok(prompts) => Ok(Json(prompts)),
         Err(e) => {
             error!("Query failed: {}", e);
-            Json(error_json(&format!("Query failed: {}", e), "QUERY_ERROR"))
+            Err(ApiError::internal(
+                "QUERY_ERROR",
+                format!("Query failed: {}", e),
+            ))
+        }
+    }
+}
+/// Bulk import of newline-delimited `PromptResponse` records, parsed as the body streams in
+async fn handle_import(
+    State(state): State<AppState>,
+    body: axum::body::Body,
+) -> std::result::Result<Json<serde_json::Value>, ApiError> {
+    match state.storage.import_prompts(body.into_data_stream()).await {
+        Ok(report) => Ok(Json(serde_json::to_value(report).unwrap_or_default())),
+        Err(e) => {
+            error!("Import failed: {}", e);
+            Err(ApiError::internal(
+                "IMPORT_ERROR",
+                format!("Import failed: {}", e),
+            ))
         }
     }
 }
+
 async fn handle_auth(State(state): State<AppState>) -> Json<()> {
     Json(())
 }
@@ -269,9 +699,28 @@ async fn handle_health(State(state): State<AppState>) -> Json<HealthResponse> {
         uptime_seconds: uptime,
         storage_status,
         network_status: Some(network_status),
+        llm_providers: state.llm_router.readiness(),
     })
 }
 
+async fn handle_metrics(State(state): State<AppState>) -> Json<NodeMetrics> {
+    let peers = state.network_manifold.lock().await.peer_statuses().await;
+    let llm = state.llm_router.metrics();
+    Json(NodeMetrics {
+        queues: llm.queues,
+        peers,
+        prompt_tokens: llm.prompt_tokens,
+        completion_tokens: llm.completion_tokens,
+        latency: llm.latency,
+        reliability: llm.reliability,
+        tasks: Some(state.tasks.active_task_counts()),
+    })
+}
+
+async fn handle_network_peers(State(state): State<AppState>) -> Json<Vec<PeerStatus>> {
+    Json(state.network_manifold.lock().await.peer_statuses().await)
+}
+
 async fn handle_network_topology(State(state): State<AppState>) -> Json<serde_json::Value> {
     let network_manifold = state.network_manifold.lock().await;
     let topology = network_manifold.get_topology().await;
@@ -279,10 +728,566 @@ async fn handle_network_topology(State(state): State<AppState>) -> Json<serde_js
     Json(serde_json::json!({
         "topology": topology,
         "node_identity": {
-            "node_id": identity.display_id(),
+            "node_id": identity.node_id(),
+            "public_key": identity.display_id(),
             "node_type": identity.node_type,
             "p2p_address": identity.p2p_address(),
             "api_address": identity.api_address(),
         }
     }))
 }
+
+async fn handle_topology_stream(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> axum::response::Response {
+    let stream = state.network_manifold.lock().await.topology_stream().await;
+    match stream {
+        Some((snapshot, diffs)) => {
+            ws.on_upgrade(move |socket| stream_topology(socket, snapshot, diffs))
+        }
+        None => ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "TOO_MANY_STREAMS",
+            "too many topology streams open",
+        )
+        .into_response(),
+    }
+}
+
+/// Send `snapshot`, then each diff as a JSON text frame until the client leaves or
+/// falls too far behind
+async fn stream_topology(
+    mut socket: WebSocket,
+    snapshot: NetworkTopology,
+    mut diffs: broadcast::Receiver<TopologyDiff>,
+) {
+    let snapshot = serde_json::to_string(&snapshot.to_proto()).unwrap_or_default();
+    if socket.send(Message::Text(snapshot.into())).await.is_err() {
+        return;
+    }
+    loop {
+        tokio::select! {
+            diff = diffs.recv() => match diff {
+                Ok(diff) => {
+                    let frame = serde_json::to_string(&diff).unwrap_or_default();
+                    if socket.send(Message::Text(frame.into())).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("🐢 Dropping topology stream {} diffs behind", missed);
+                    let _ = socket
+                        .send(Message::Close(Some(CloseFrame {
+                            code: close_code::AGAIN,
+                            reason: "too far behind".into(),
+                        })))
+                        .await;
+                    return;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            // Client frames carry nothing; only its leaving matters
+            received = socket.recv() => match received {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Topology snapshot and storage health error the geometry diagnostics are computed from
+async fn geometry_inputs(state: &AppState) -> (NetworkTopology, Option<String>) {
+    let topology = state.network_manifold.lock().await.get_topology().await;
+    let storage_error = state.storage.health_check().await.err();
+    (topology, storage_error.map(|e| e.to_string()))
+}
+
+async fn handle_geometry(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let (topology, storage_error) = geometry_inputs(&state).await;
+    Json(geometry::sacred_geometry_metrics(
+        &state.config.identity().node_id(),
+        &topology,
+        storage_error.as_deref(),
+    ))
+}
+
+async fn handle_geometry_validate(State(state): State<AppState>) -> Json<serde_json::Value> {
+    info!("🔍 Validating sacred geometric invariants");
+    let (topology, storage_error) = geometry_inputs(&state).await;
+    Json(geometry::validate_sacred_geometry(
+        &topology,
+        storage_error.as_deref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_router_config, TestServer};
+    use commonware_runtime::tokio::{Config as RuntimeConfig, Runner};
+    use commonware_runtime::Runner as _;
+    use ho_std::constants::ARTIFACT_INLINE_MAX_BYTES;
+    use ho_std::llm::HoError;
+    use ho_std::traits::{HttpBackend, LlmModelTrait};
+    use pbjson_types::value::Kind;
+
+    #[test]
+    fn test_selection_headers_override_body() {
+        let request = PromptRequest {
+            model: "gpt-4o".to_string(),
+            provider: Some("openai".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            requested_selection(&HeaderMap::new(), &request),
+            (Some("openai".to_string()), Some("gpt-4o".to_string()))
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(LLM_PROVIDER_HEADER, "akash_chat".parse().unwrap());
+        headers.insert(LLM_MODEL_HEADER, "DeepSeek-R1-0528".parse().unwrap());
+        assert_eq!(
+            requested_selection(&headers, &request),
+            (
+                Some("akash_chat".to_string()),
+                Some("DeepSeek-R1-0528".to_string())
+            )
+        );
+
+        assert_eq!(
+            requested_selection(&HeaderMap::new(), &PromptRequest::default()),
+            (None, None)
+        );
+    }
+
+    /// Log output collected in memory
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Logs from every thread, captured by a global subscriber installed on first use.
+    /// The server runs on the runtime's worker threads, out of reach of a thread-local one.
+    fn captured_logs() -> &'static CapturedLogs {
+        static LOGS: std::sync::OnceLock<CapturedLogs> = std::sync::OnceLock::new();
+        LOGS.get_or_init(|| {
+            let logs = CapturedLogs::default();
+            let writer = logs.clone();
+            tracing::subscriber::set_global_default(
+                tracing_subscriber::fmt()
+                    .with_writer(move || writer.clone())
+                    .with_ansi(false)
+                    .finish(),
+            )
+            .expect("no other test installs a global subscriber");
+            logs
+        })
+    }
+
+    #[test]
+    fn test_request_id_reaches_stored_record_and_logs() {
+        let logs = captured_logs();
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let server = TestServer::start(context).await;
+            let prompt = PromptRequest {
+                messages: vec![PromptMessage {
+                    role: "user".to_string(),
+                    content: "trace me".to_string(),
+                }],
+                ..Default::default()
+            };
+
+            let response = server
+                .post_signed_with(
+                    "/api/prompt",
+                    &prompt,
+                    &[(REQUEST_ID_HEADER, "trace-me-42")],
+                )
+                .await;
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            assert_eq!(response.headers()[REQUEST_ID_HEADER], "trace-me-42");
+            let answered: PromptResponse = response.json().await.unwrap();
+            assert_eq!(answered.request_id.as_deref(), Some("trace-me-42"));
+
+            let stored: Vec<PromptResponse> = server
+                .get_signed("/api/prompts")
+                .await
+                .json()
+                .await
+                .unwrap();
+            let record = stored.iter().find(|p| p.id == answered.id).unwrap();
+            assert_eq!(record.request_id.as_deref(), Some("trace-me-42"));
+            let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+            assert!(
+                logs.lines()
+                    .any(|line| line.contains("request_id=trace-me-42")
+                        && line.contains("stored prompt")),
+                "{}",
+                logs
+            );
+
+            // Without a usable id from the client one is generated
+            for sent in [None, Some("x".repeat(MAX_REQUEST_ID_LEN + 1))] {
+                let headers: Vec<(&str, &str)> = sent
+                    .iter()
+                    .map(|sent| (REQUEST_ID_HEADER, sent.as_str()))
+                    .collect();
+                let response = server
+                    .post_signed_with("/api/prompt", &prompt, &headers)
+                    .await;
+                let echoed = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+                assert!(Uuid::parse_str(echoed).is_ok(), "{}", echoed);
+            }
+        });
+    }
+
+    #[test]
+    fn test_default_entity_change_is_saved() {
+        let home = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("cw-ho-default-{}", Uuid::new_v4())),
+        )
+        .unwrap();
+        std::fs::create_dir_all(&home).unwrap();
+        let path = home.join(ho_std::constants::CONFIG_FILE_NAME);
+        CwHoConfig::new(&home).save(&path).unwrap();
+
+        save_default_entity(&path, LlmModel::OpenAi).unwrap();
+        let saved = CwHoConfig::load(&path).unwrap();
+        assert_eq!(saved.llm().configured_default(), Some(LlmModel::OpenAi));
+        std::fs::remove_dir_all(home).ok();
+    }
+
+    #[test]
+    fn test_topology_stream_sends_snapshot_then_diffs() {
+        use futures::StreamExt;
+        use ho_std::commonware::identity::NodePrivKey;
+
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let server = TestServer::start(context).await;
+            let me = server.network().lock().await.get_topology().await;
+            let mut client = server.websocket_signed("/topology/stream").await;
+            let frame = client.next().await.unwrap().unwrap().into_text().unwrap();
+            let snapshot: ho_std::prelude::NetworkTopology = serde_json::from_str(&frame).unwrap();
+            assert_eq!(snapshot, me.to_proto());
+            assert_eq!(snapshot.nodes.len(), 1);
+
+            // A peer joining adds its node, which reaches the stream as a diff
+            let peer = NodePrivKey::from_seed(3).id();
+            let announce = NetworkMessage {
+                message_type: Some(MessageType::NodeAnnounce(NodeAnnounce {
+                    node_id: peer.node_id(),
+                    role: NodeType::Executor.into(),
+                    ..Default::default()
+                })),
+            };
+            server
+                .network()
+                .lock()
+                .await
+                .deliver(&peer, &announce)
+                .await
+                .unwrap();
+            let frame = client.next().await.unwrap().unwrap().into_text().unwrap();
+            let diff: TopologyDiff = serde_json::from_str(&frame).unwrap();
+            assert_eq!(diff.added_nodes.len(), 1);
+            assert_eq!(diff.added_nodes[0].node_id, peer.node_id());
+            assert!(diff.removed_nodes.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_dropped_handler_cancels_running_call() {
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        drop(CancelOnDrop(cancel_tx));
+        assert!(*cancel_rx.borrow());
+
+        // A finished call has dropped its receiver, so there is nothing to cancel
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        drop(cancel_rx);
+        drop(CancelOnDrop(cancel_tx));
+    }
+
+    /// Fails every request the way the reqwest backend does when a provider never answers
+    struct TimedOutBackend;
+
+    #[async_trait::async_trait]
+    impl HttpBackend for TimedOutBackend {
+        async fn send(&self, _request: reqwest::Request) -> ho_std::error::HoResult<HttpResponse> {
+            Err(HoError::Timeout("operation timed out".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_response_from_entity_keeping_no_responses_is_not_stored() {
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let config = LlmRouterConfig {
+                mock: Some(true),
+                ..test_router_config(vec![
+                    LlmEntity {
+                        store_responses: Some(false),
+                        ..LlmModel::AkashChat.default_entity()
+                    },
+                    LlmModel::OllamaLocal.default_entity(),
+                ])
+            };
+            let router = LlmRouter::new(&config).await.unwrap();
+            let server = TestServer::with_router(context, router).await;
+            let prompt = |provider: LlmModel, content: &str| PromptRequest {
+                messages: vec![PromptMessage {
+                    role: "user".to_string(),
+                    content: content.to_string(),
+                }],
+                provider: Some(provider.as_str_name().to_string()),
+                ..Default::default()
+            };
+
+            // The caller still gets the answer
+            let sensitive = server
+                .post_signed("/api/prompt", &prompt(LlmModel::AkashChat, "secret"))
+                .await;
+            assert_eq!(sensitive.status(), reqwest::StatusCode::OK);
+            let sensitive: PromptResponse = sensitive.json().await.unwrap();
+            assert_eq!(sensitive.response, ["mock response to: secret"]);
+            let kept: PromptResponse = server
+                .post_signed("/api/prompt", &prompt(LlmModel::OllamaLocal, "public"))
+                .await
+                .json()
+                .await
+                .unwrap();
+
+            let stored: Vec<PromptResponse> = server
+                .get_signed("/api/prompts")
+                .await
+                .json()
+                .await
+                .unwrap();
+            let ids: Vec<&Vec<u8>> = stored.iter().map(|p| &p.id).collect();
+            assert_eq!(ids, [&kept.id]);
+        });
+    }
+
+    #[test]
+    fn test_requests_without_newer_fields_are_accepted() {
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let server = TestServer::start(context).await;
+            // A client from before tools existed
+            let prompt = serde_json::json!({
+                "messages": [{"role": "user", "content": "hello"}],
+                "model": "",
+            });
+            let answered = server.post_signed("/api/prompt", &prompt).await;
+            assert_eq!(answered.status(), reqwest::StatusCode::OK);
+            let answered: PromptResponse = answered.json().await.unwrap();
+            assert_eq!(answered.response, ["mock response to: hello"]);
+
+            // ... or before task dependencies
+            let tasks = serde_json::json!([{
+                "id": "solo",
+                "task_type": 0,
+                "status": 0,
+                "prompt": "hello",
+            }]);
+            let handled = server.post_signed("/orchestrate/tasks", &tasks).await;
+            assert_eq!(handled.status(), reqwest::StatusCode::OK);
+        });
+    }
+
+    #[test]
+    fn test_provider_timeout_answers_502_in_error_envelope() {
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+            let mut router = LlmRouter::new(&config)
+                .await
+                .unwrap()
+                .with_backend(Arc::new(TimedOutBackend));
+            router.api_keys.akash = Some("test-key".to_string());
+            let server = TestServer::with_router(context, router).await;
+
+            let prompt = PromptRequest {
+                messages: vec![PromptMessage {
+                    role: "user".to_string(),
+                    content: "ping".to_string(),
+                }],
+                provider: Some(LlmModel::AkashChat.as_str_name().to_string()),
+                ..Default::default()
+            };
+            let response = server.post_signed("/api/prompt", &prompt).await;
+            assert_eq!(response.status(), reqwest::StatusCode::BAD_GATEWAY);
+            let echoed = response.headers()[REQUEST_ID_HEADER]
+                .to_str()
+                .unwrap()
+                .to_string();
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["error"]["code"], "PROVIDER_TIMEOUT");
+            assert!(
+                body["error"]["message"]
+                    .as_str()
+                    .unwrap()
+                    .contains("operation timed out"),
+                "{}",
+                body
+            );
+            assert_eq!(body["error"]["request_id"], echoed.as_str());
+
+            // Auth failures come back in the same envelope
+            let unsigned = server.get("/api/prompts").await;
+            assert_eq!(unsigned.status(), reqwest::StatusCode::UNAUTHORIZED);
+            let body: serde_json::Value = unsigned.json().await.unwrap();
+            assert_eq!(body["error"]["code"], "MISSING_SIGNATURE");
+            assert!(body["error"]["request_id"].is_string());
+
+            // So do bodies the handler can't read
+            let unreadable = server
+                .post_signed("/api/prompt", &serde_json::json!({ "model": "" }))
+                .await;
+            assert_eq!(
+                unreadable.status(),
+                reqwest::StatusCode::UNPROCESSABLE_ENTITY
+            );
+            let body: serde_json::Value = unreadable.json().await.unwrap();
+            assert_eq!(body["error"]["code"], "INVALID_JSON");
+            assert!(
+                body["error"]["message"]
+                    .as_str()
+                    .unwrap()
+                    .contains("missing field `messages`"),
+                "{}",
+                body
+            );
+            assert!(body["error"]["request_id"].is_string());
+        });
+    }
+
+    #[test]
+    fn test_task_artifacts_are_listed_and_downloaded() {
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let server = TestServer::start(context).await;
+            // The mock echoes the prompt, so the answer is too large to keep inline
+            let prompt = "fn main() {}\n".repeat(ARTIFACT_INLINE_MAX_BYTES);
+            let task = CosmicTask {
+                id: "codegen-1".to_string(),
+                prompt: prompt.clone(),
+                ..Default::default()
+            };
+            let handled: Vec<CosmicTask> = server
+                .post_signed("/orchestrate/tasks", &vec![task])
+                .await
+                .json()
+                .await
+                .unwrap();
+            let answer = format!("mock response to: {}", prompt);
+            let result = handled[0].result.as_ref().unwrap();
+            let Some(Kind::StructValue(reference)) = &result.fields["response"].kind else {
+                panic!("response was kept inline: {:?}", result.fields["response"]);
+            };
+            assert_eq!(
+                reference.fields["bytes"].kind,
+                Some(Kind::NumberValue(answer.len() as f64))
+            );
+
+            let listed: Vec<serde_json::Value> = server
+                .get_signed("/tasks/codegen-1/artifacts")
+                .await
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(
+                listed,
+                [serde_json::json!({ "name": "response.txt", "bytes": answer.len() })]
+            );
+
+            let download = server
+                .get_signed("/tasks/codegen-1/artifacts/response.txt")
+                .await;
+            assert_eq!(download.status(), reqwest::StatusCode::OK);
+            assert_eq!(download.text().await.unwrap(), answer);
+
+            let missing = server
+                .get_signed("/tasks/codegen-1/artifacts/other.txt")
+                .await;
+            assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+            let body: serde_json::Value = missing.json().await.unwrap();
+            assert_eq!(body["error"]["code"], "ARTIFACT_NOT_FOUND");
+        });
+    }
+
+    #[test]
+    fn test_task_graph_runs_through_the_route() {
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let server = TestServer::start(context).await;
+            let task = |id: &str, depends_on: &[&str]| CosmicTask {
+                id: id.to_string(),
+                prompt: format!("step {}", id),
+                depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+                ..Default::default()
+            };
+            let tasks = vec![task("second", &["first"]), task("first", &[])];
+
+            let answered = server.post_signed("/orchestrate/tasks", &tasks).await;
+            assert_eq!(answered.status(), reqwest::StatusCode::OK);
+            let handled: Vec<CosmicTask> = answered.json().await.unwrap();
+            let ids: Vec<&str> = handled.iter().map(|t| t.id.as_str()).collect();
+            assert_eq!(ids, ["first", "second"]);
+            assert!(handled
+                .iter()
+                .all(|t| t.status() == CosmicTaskStatus::Completed));
+            assert_eq!(
+                handled[1].result.as_ref().unwrap().fields["response"],
+                pbjson_types::Value {
+                    kind: Some(pbjson_types::value::Kind::StringValue(
+                        "mock response to: step second".to_string()
+                    )),
+                }
+            );
+
+            let cycle = vec![task("a", &["b"]), task("b", &["a"])];
+            let rejected = server.post_signed("/orchestrate/tasks", &cycle).await;
+            assert_eq!(rejected.status(), reqwest::StatusCode::BAD_REQUEST);
+        });
+    }
+
+    #[test]
+    fn test_prompts_in_a_session_are_recorded_as_its_turns() {
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let server = TestServer::start(context).await;
+            let ask = |content: &str| PromptRequest {
+                messages: vec![PromptMessage {
+                    role: "user".to_string(),
+                    content: content.to_string(),
+                }],
+                context: Some(PromptContext {
+                    session_id: Some("trip".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+
+            for question in ["Capital of France?", "Population?"] {
+                let answered = server.post_signed("/api/prompt", &ask(question)).await;
+                assert_eq!(answered.status(), reqwest::StatusCode::OK);
+            }
+
+            let history = server.storage().session_history("trip").await.unwrap();
+            let contents: Vec<&str> = history.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(
+                contents,
+                [
+                    "Capital of France?",
+                    "mock response to: Capital of France?",
+                    "Population?",
+                    "mock response to: Population?",
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,156 @@
+//! Nodes of the ssh node map, registered in storage under generated ids
+//!
+//! Each node takes the tetrahedral vertex its name mentions. Large maps are registered a
+//! bounded number of nodes at a time, and a node that fails is reported alongside the
+//! rest rather than aborting them.
+use crate::error::{CwHoError, Result};
+use crate::CwHoStorage;
+use futures::StreamExt;
+use ho_std::prelude::*;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Vertex of the node named `name`: the role its name mentions, otherwise one picked
+/// by hashing the name
+pub fn config_position(name: &str) -> TetrahedralPosition {
+    match name {
+        name if name.contains("coordinator") => TetrahedralPosition::Coordinator,
+        name if name.contains("executor") => TetrahedralPosition::Executor,
+        name if name.contains("referee") => TetrahedralPosition::Referee,
+        name if name.contains("development") || name.contains("dev") => {
+            TetrahedralPosition::Development
+        }
+        _ => {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            name.hash(&mut hasher);
+            match hasher.finish() % 4 {
+                0 => TetrahedralPosition::Coordinator,
+                1 => TetrahedralPosition::Executor,
+                2 => TetrahedralPosition::Referee,
+                _ => TetrahedralPosition::Development,
+            }
+        }
+    }
+}
+
+/// Register the node `name` of the ssh node map, whose entry is `config`
+pub async fn register_config_node(
+    storage: &CwHoStorage,
+    name: &str,
+    config: &serde_json::Value,
+) -> Result<ConfigNode> {
+    let config = config.as_object().ok_or_else(|| {
+        CwHoError::InvalidRequest("node config must be a JSON object".to_string())
+    })?;
+    // The random suffix keeps nodes registered within the same second from colliding
+    let suffix = Uuid::new_v4().simple().to_string();
+    let node = ConfigNode {
+        name: name.to_string(),
+        node_id: format!(
+            "{}_{}_{}",
+            name,
+            chrono::Utc::now().timestamp(),
+            &suffix[..8]
+        ),
+        position: config_position(name).into(),
+        host: config
+            .get("host")
+            .and_then(|host| host.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    storage.store_config_node(&node).await?;
+    Ok(node)
+}
+
+/// Register every node of the ssh node map at `path`, up to `concurrency` at once
+pub async fn load_ssh_config_nodes(
+    storage: &CwHoStorage,
+    path: &Path,
+    concurrency: usize,
+) -> Result<SshRegistrationReport> {
+    info!("📋 Loading SSH nodes from config: {}", path.display());
+    let nodes: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&tokio::fs::read(path).await?)?;
+
+    let outcomes: Vec<(String, Result<ConfigNode>)> = futures::stream::iter(nodes)
+        .map(|(name, config)| async move {
+            let outcome = register_config_node(storage, &name, &config).await;
+            (name, outcome)
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut report = SshRegistrationReport::default();
+    for (name, outcome) in outcomes {
+        match outcome {
+            Ok(node) => {
+                info!("✅ Registered SSH node: {} as {}", name, node.node_id);
+                report.registered.push(node);
+            }
+            Err(e) => {
+                warn!("❌ Failed to register SSH node {}: {}", name, e);
+                report.failed.push(ConfigNodeFailure {
+                    name,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    // Completion order varies with concurrency; report by node name
+    report.registered.sort_by(|a, b| a.name.cmp(&b.name));
+    report.failed.sort_by(|a, b| a.name.cmp(&b.name));
+
+    info!(
+        "🎯 Registered {} SSH nodes from config, {} failed",
+        report.registered.len(),
+        report.failed.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ssh_nodes_register_concurrently_and_report_failures() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-ssh-nodes-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = serde_json::Map::new();
+        for i in 0..20 {
+            config.insert(
+                format!("executor-{:02}", i),
+                serde_json::json!({ "host": format!("10.0.0.{}", i), "username": "ubuntu" }),
+            );
+        }
+        config.insert("broken".to_string(), serde_json::json!("not a node"));
+        let path = dir.join("ssh-config.json");
+        std::fs::write(&path, serde_json::to_vec(&config).unwrap()).unwrap();
+
+        let storage = CwHoStorage::memory();
+        let report = load_ssh_config_nodes(&storage, &path, 8).await.unwrap();
+        assert_eq!(report.registered.len(), 20);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].name, "broken");
+        assert_eq!(report.registered[3].host, "10.0.0.3");
+        assert_eq!(
+            report.registered[3].position(),
+            TetrahedralPosition::Executor
+        );
+        // Nodes registered in the same second still get distinct ids
+        let ids: std::collections::HashSet<&String> =
+            report.registered.iter().map(|node| &node.node_id).collect();
+        assert_eq!(ids.len(), 20);
+        assert_eq!(storage.config_nodes().await.unwrap().len(), 20);
+
+        // Loading the map again replaces the registrations rather than adding to them
+        load_ssh_config_nodes(&storage, &path, 8).await.unwrap();
+        assert_eq!(storage.config_nodes().await.unwrap().len(), 20);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
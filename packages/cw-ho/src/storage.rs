@@ -1,20 +1,76 @@
 use crate::{
     error::{CwHoError, Result},
+    wal::{KeyWrite, WriteAheadLog},
     CwHoStorage,
 };
 
 use cnidarium::{StateRead, StateWrite, Storage as CnidariumStorage};
+use commonware_cryptography::{blake3, Hasher};
 use futures::StreamExt;
 use ho_std::prelude::*;
 use ho_std::traits::StorageConfigTrait;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 const PROMPT_PREFIX: &str = "prompts/";
 const SESSION_INDEX_PREFIX: &str = "sessions/";
 const USER_INDEX_PREFIX: &str = "users/";
+const THREAD_INDEX_PREFIX: &str = "threads/";
 const TIMESTAMP_INDEX_PREFIX: &str = "timestamps/";
+/// Conversation messages, keyed `conversations/{session_id}/{sequence}`
+const CONVERSATION_PREFIX: &str = "conversations/";
+/// Full task states, keyed by task id
+const TASK_PREFIX: &str = "tasks/";
+/// What remains of a task once the retention sweep drops its payload
+const TASK_SUMMARY_PREFIX: &str = "task_summaries/";
+/// Deduplicated prompt texts, keyed by their blake3 digest
+const PROMPT_BODY_PREFIX: &str = "prompt_bodies/";
+/// Digest of the prompt text a deduplicated record left out, keyed by prompt id
+const PROMPT_REF_PREFIX: &str = "prompt_refs/";
+/// Nodes registered from the ssh node map, keyed by their name in the map
+const CONFIG_NODE_PREFIX: &str = "config_nodes/";
+/// Index entries, each holding the raw id of the prompt it points at
+const INDEX_PREFIXES: [&str; 4] = [
+    SESSION_INDEX_PREFIX,
+    USER_INDEX_PREFIX,
+    THREAD_INDEX_PREFIX,
+    TIMESTAMP_INDEX_PREFIX,
+];
+/// Key prefixes captured by snapshots
+const SNAPSHOT_PREFIXES: [&str; 11] = [
+    PROMPT_PREFIX,
+    SESSION_INDEX_PREFIX,
+    USER_INDEX_PREFIX,
+    THREAD_INDEX_PREFIX,
+    TIMESTAMP_INDEX_PREFIX,
+    CONVERSATION_PREFIX,
+    TASK_PREFIX,
+    TASK_SUMMARY_PREFIX,
+    PROMPT_BODY_PREFIX,
+    PROMPT_REF_PREFIX,
+    CONFIG_NODE_PREFIX,
+];
+/// Records the pid of the process holding the data dir
+const PID_FILE: &str = "cw-ho.pid";
+/// Write-ahead log of commits, inside the data dir
+const WAL_FILE: &str = "cw-ho.wal";
+
+/// Where a [`CwHoStorage`] keeps its records, chosen by [`StorageBackend`]
+pub(crate) enum RecordStore {
+    Cnidarium(CnidariumStorage),
+    /// Gone once the node stops
+    Memory(std::sync::RwLock<MemoryRecords>),
+}
+
+#[derive(Default)]
+pub(crate) struct MemoryRecords {
+    /// Commits made so far
+    version: u64,
+    records: BTreeMap<String, Vec<u8>>,
+}
 
 impl StorageConfigTrait for CwHoStorage {
     fn data_dir(&self) -> &str {
@@ -43,6 +99,15 @@ impl CwHoStorage {
         let path = data_dir.as_ref();
         std::fs::create_dir_all(path)?;
 
+        // Refuse to open a data dir owned by another live process
+        let pid_path = path.join(PID_FILE);
+        if let Some(pid) = Self::lock_holder(&pid_path) {
+            return Err(CwHoError::StorageLocked {
+                path: path.display().to_string(),
+                pid,
+            });
+        }
+
         info!("📂 Initializing Cnidarium storage at: {}", path.display());
         // Define substore prefixes to align with multistore routing
         let prefixes = vec![
@@ -53,9 +118,210 @@ impl CwHoStorage {
 
         let cnidarium = CnidariumStorage::load(path.to_path_buf(), prefixes)
             .await
-            .map_err(|e| CwHoError::Storage(e.into()))?;
+            .map_err(|e| {
+                CwHoError::Storage(anyhow::Error::from(e).context(format!(
+                    "Failed to open storage at {}; check that no other cw-ho process is using it",
+                    path.display()
+                )))
+            })?;
+
+        std::fs::write(&pid_path, std::process::id().to_string())?;
+
+        let mut storage = Self {
+            backend: RecordStore::Cnidarium(cnidarium),
+            data_dir: Some(path.to_path_buf()),
+            snapshot_ratio: std::sync::Mutex::new(None),
+            dedupe_prompts: false,
+            wal: None,
+            recovery: WalRecovery::default(),
+            conversation_writes: Default::default(),
+        };
+
+        // Finish the commits a crash interrupted before accepting new ones
+        let (wal, uncommitted, recovery) = WriteAheadLog::open(&path.join(WAL_FILE))?;
+        for writes in uncommitted {
+            storage.apply(writes).await?;
+        }
+        let wal = Arc::new(wal);
+        Self::with_wal(&wal, WriteAheadLog::reset).await?;
+        if recovery != WalRecovery::default() {
+            info!(
+                "🩹 Storage recovered from its write-ahead log: {} interrupted commits replayed, {} torn entries dropped",
+                recovery.replayed, recovery.torn
+            );
+        }
+        storage.wal = Some(wal);
+        storage.recovery = recovery;
+        Ok(storage)
+    }
+
+    /// What replaying the write-ahead log did when the store was opened
+    pub fn recovery(&self) -> WalRecovery {
+        self.recovery
+    }
+
+    /// Keep records in process memory only, nothing is written to disk
+    pub fn memory() -> Self {
+        warn!("⚠️ Using in-memory storage, data will not be persisted");
+        Self {
+            backend: RecordStore::Memory(Default::default()),
+            data_dir: None,
+            snapshot_ratio: std::sync::Mutex::new(None),
+            dedupe_prompts: false,
+            wal: None,
+            recovery: WalRecovery::default(),
+            conversation_writes: Default::default(),
+        }
+    }
+
+    /// Open the backend `config` selects, cnidarium under its data dir by default
+    pub async fn from_config(config: &StorageConfig) -> Result<Self> {
+        let storage = match config.backend() {
+            StorageBackend::Cnidarium => Self::new(&config.data_dir).await?,
+            StorageBackend::Memory => Self::memory(),
+        };
+        Ok(storage.with_prompt_dedup(config.dedupe_prompts.unwrap_or(false)))
+    }
+
+    /// Store each distinct prompt text once, referenced by digest from the records using it.
+    /// Records stored either way are read back the same.
+    pub fn with_prompt_dedup(mut self, enabled: bool) -> Self {
+        self.dedupe_prompts = enabled;
+        self
+    }
+
+    /// Pid of another live process holding the data dir, clearing stale pid files
+    fn lock_holder(pid_path: &PathBuf) -> Option<u32> {
+        let pid = std::fs::read_to_string(pid_path)
+            .ok()?
+            .trim()
+            .parse::<u32>()
+            .ok()?;
+        if pid == std::process::id() {
+            return None;
+        }
+        if Self::process_alive(pid) {
+            return Some(pid);
+        }
+
+        warn!(
+            "🧹 Removing stale storage lock {} left by process {}",
+            pid_path.display(),
+            pid
+        );
+        let _ = std::fs::remove_file(pid_path);
+        None
+    }
+
+    #[cfg(target_os = "linux")]
+    fn process_alive(pid: u32) -> bool {
+        Path::new("/proc").join(pid.to_string()).exists()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn process_alive(pid: u32) -> bool {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(true)
+    }
+
+    async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match &self.backend {
+            RecordStore::Cnidarium(store) => store
+                .latest_snapshot()
+                .get_raw(key)
+                .await
+                .map_err(CwHoError::Storage),
+            RecordStore::Memory(memory) => Ok(memory.read().unwrap().records.get(key).cloned()),
+        }
+    }
+
+    /// Every record whose key starts with `prefix`, in key order
+    async fn prefix_raw(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        match &self.backend {
+            RecordStore::Cnidarium(store) => {
+                let snapshot = store.latest_snapshot();
+                let mut entries = snapshot.prefix_raw(prefix);
+                let mut records = Vec::new();
+                while let Some(entry) = entries.next().await {
+                    records.push(entry.map_err(CwHoError::Storage)?);
+                }
+                Ok(records)
+            }
+            RecordStore::Memory(memory) => Ok(memory
+                .read()
+                .unwrap()
+                .records
+                .range(prefix.to_string()..)
+                .take_while(|(key, _)| key.starts_with(prefix))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()),
+        }
+    }
+
+    /// Apply puts, and deletes where the value is `None`, as one commit. The writes are
+    /// logged first, so a crash leaves them either fully stored or redone on next open.
+    async fn commit(&self, writes: Vec<KeyWrite>) -> Result<()> {
+        let Some(wal) = &self.wal else {
+            return self.apply(writes).await;
+        };
+        let (seq, writes) =
+            Self::with_wal(wal, move |wal| Ok((wal.append(&writes)?, writes))).await?;
+        let applied = self.apply(writes).await;
+        // A failed commit is settled too; it must not land behind the caller's back
+        Self::with_wal(wal, move |wal| wal.done(seq)).await?;
+        applied
+    }
+
+    /// Run `f` on the write-ahead log off the async runtime, as each call syncs the log
+    async fn with_wal<T: Send + 'static>(
+        wal: &Arc<WriteAheadLog>,
+        f: impl FnOnce(&WriteAheadLog) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let wal = wal.clone();
+        tokio::task::spawn_blocking(move || f(&wal))
+            .await
+            .map_err(std::io::Error::from)?
+    }
 
-        Ok(Self { cnidarium })
+    /// Commit `writes` to the backend without logging them
+    async fn apply(&self, writes: Vec<KeyWrite>) -> Result<()> {
+        match &self.backend {
+            RecordStore::Cnidarium(store) => {
+                let mut delta = cnidarium::StateDelta::new(store.latest_snapshot());
+                for (key, value) in writes {
+                    match value {
+                        Some(value) => delta.put_raw(key, value),
+                        None => delta.delete(key),
+                    }
+                }
+                store
+                    .commit(delta)
+                    .await
+                    .map_err(|e| CwHoError::Storage(e.into()))?;
+            }
+            RecordStore::Memory(memory) => {
+                let mut memory = memory.write().unwrap();
+                for (key, value) in writes {
+                    match value {
+                        Some(value) => memory.records.insert(key, value),
+                        None => memory.records.remove(&key),
+                    };
+                }
+                memory.version += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Version of the latest committed state
+    fn version(&self) -> u64 {
+        match &self.backend {
+            RecordStore::Cnidarium(store) => store.latest_snapshot().version(),
+            RecordStore::Memory(memory) => memory.read().unwrap().version,
+        }
     }
 
     pub async fn store_prompt_with_context(
@@ -63,14 +329,62 @@ impl CwHoStorage {
         prompt: &PromptResponse,
         original_request: Option<&PromptRequest>,
     ) -> Result<()> {
-        let mut delta = cnidarium::StateDelta::new(self.cnidarium.latest_snapshot());
         let id = hex::encode(prompt.id.clone());
+        let prompt_key = format!("{}{}", PROMPT_PREFIX, id);
+        let writes = self.prompt_writes(prompt, original_request).await?;
+
+        debug!("Storing prompt {} with timestamp index", id);
+
+        // Commit the changes
+        self.commit(writes).await?;
+
+        info!(
+            "💾 Successfully stored prompt: {} with key: {}",
+            id, prompt_key
+        );
+
+        // Debug: Let's try to immediately read it back to verify storage
+        match self.get_raw(&prompt_key).await {
+            Ok(Some(_)) => info!("✅ Verified prompt {} can be read back immediately", id),
+            Ok(None) => warn!("⚠️ Prompt {} not found immediately after storage", id),
+            Err(e) => warn!("❌ Error reading prompt {} back: {}", id, e),
+        }
+
+        Ok(())
+    }
+
+    /// Every put storing `prompt`: the record, its indexes, and with prompt dedup the
+    /// prompt text under its digest
+    async fn prompt_writes(
+        &self,
+        prompt: &PromptResponse,
+        original_request: Option<&PromptRequest>,
+    ) -> Result<Vec<KeyWrite>> {
+        let mut writes = Vec::new();
+        let id = hex::encode(prompt.id.clone());
+        let mut record = prompt.clone();
+        // The response's own context wins over the request it came with
+        if record.context.is_none() {
+            record.context = original_request.and_then(|request| request.context.clone());
+        }
+        if self.dedupe_prompts && !prompt.prompt.is_empty() {
+            let digest = blake3::Blake3::hash(prompt.prompt.as_bytes()).to_string();
+            let body_key = format!("{}{}", PROMPT_BODY_PREFIX, digest);
+            if self.get_raw(&body_key).await?.is_none() {
+                writes.push((body_key, Some(prompt.prompt.clone().into_bytes())));
+            }
+            writes.push((
+                format!("{}{}", PROMPT_REF_PREFIX, id),
+                Some(digest.into_bytes()),
+            ));
+            record.prompt.clear();
+        }
         // Serialize the prompt response
-        let prompt_data = serde_json::to_vec(prompt)?;
+        let prompt_data = serde_json::to_vec(&record)?;
         let prompt_key = format!("{}{}", PROMPT_PREFIX, id.clone());
 
         // Store the main prompt record
-        delta.put_raw(prompt_key.clone(), prompt_data);
+        writes.push((prompt_key, Some(prompt_data)));
 
         // Create indexes for efficient querying
         let timestamp_key = format!(
@@ -82,77 +396,267 @@ impl CwHoStorage {
                 .nanos,
             id
         );
-        delta.put_raw(timestamp_key, prompt.id.clone());
-
-        // Create context-based indexes if original request is provided
-        if let Some(request) = original_request {
-            if let Some(ref context) = request.context {
-                // Index by session_id if present
-                if let Some(ref session_id) = context.session_id {
-                    let session_key = format!("{}{}:{}", SESSION_INDEX_PREFIX, session_id, id);
-                    delta.put_raw(session_key, prompt.id.clone());
-                    debug!("Created session index for {}: {}", session_id, id);
-                }
+        writes.push((timestamp_key, Some(prompt.id.clone())));
 
-                // Index by user_id if present
-                if let Some(ref user_id) = context.user_id {
-                    let user_key = format!("{}{}:{}", USER_INDEX_PREFIX, user_id, id);
-                    delta.put_raw(user_key, prompt.id.clone());
-                    debug!("Created user index for {}: {}", user_id, id);
-                }
+        // Create context-based indexes if the response has a context
+        if let Some(ref context) = record.context {
+            // Index by session_id if present
+            if let Some(ref session_id) = context.session_id {
+                let session_key = format!("{}{}:{}", SESSION_INDEX_PREFIX, session_id, id);
+                writes.push((session_key, Some(prompt.id.clone())));
+                debug!("Created session index for {}: {}", session_id, id);
+            }
+
+            // Index by user_id if present
+            if let Some(ref user_id) = context.user_id {
+                let user_key = format!("{}{}:{}", USER_INDEX_PREFIX, user_id, id);
+                writes.push((user_key, Some(prompt.id.clone())));
+                debug!("Created user index for {}: {}", user_id, id);
+            }
+
+            // Index by thread_id if present
+            if let Some(ref thread_id) = context.thread_id {
+                let thread_key = format!("{}{}:{}", THREAD_INDEX_PREFIX, thread_id, id);
+                writes.push((thread_key, Some(prompt.id.clone())));
+                debug!("Created thread index for {}: {}", thread_id, id);
             }
         }
 
-        debug!("Storing prompt {} with timestamp index", id);
+        Ok(writes)
+    }
 
-        // Commit the changes
-        self.cnidarium
-            .commit(delta)
-            .await
-            .map_err(|e| CwHoError::Storage(e.into()))?;
+    // Backward compatibility method
+    pub async fn store_prompt(&self, prompt: &PromptResponse) -> Result<()> {
+        self.store_prompt_with_context(prompt, None).await
+    }
+
+    /// Whether a prompt with this raw id is already stored
+    pub async fn has_prompt(&self, id: &[u8]) -> Result<bool> {
+        let prompt_key = format!("{}{}", PROMPT_PREFIX, hex::encode(id));
+        Ok(self.get_raw(&prompt_key).await?.is_some())
+    }
+
+    /// Store newline-delimited `PromptResponse` records as they arrive. Lines that fail
+    /// to parse or store are reported without aborting, and ids already stored are skipped.
+    pub async fn import_prompts<S, B, E>(&self, body: S) -> Result<ImportPromptsResponse>
+    where
+        S: futures::Stream<Item = std::result::Result<B, E>>,
+        B: AsRef<[u8]>,
+        E: std::fmt::Display,
+    {
+        let mut body = std::pin::pin!(body);
+        let mut report = ImportPromptsResponse::default();
+        let mut pending: Vec<u8> = Vec::new();
+        let mut line = 0;
+
+        loop {
+            let chunk = match body.next().await {
+                Some(chunk) => chunk.map_err(|e| {
+                    CwHoError::InvalidRequest(format!("failed to read import body: {}", e))
+                })?,
+                None => break,
+            };
+            pending.extend_from_slice(chunk.as_ref());
+            while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                let record: Vec<u8> = pending.drain(..=end).collect();
+                line += 1;
+                self.import_line(line, &record, &mut report).await;
+            }
+        }
+        // last line without a trailing newline
+        if !pending.is_empty() {
+            line += 1;
+            self.import_line(line, &pending, &mut report).await;
+        }
 
         info!(
-            "💾 Successfully stored prompt: {} with key: {}",
-            id, prompt_key
+            "📥 Imported {} prompts ({} skipped, {} failed)",
+            report.imported, report.skipped, report.failed
         );
+        Ok(report)
+    }
 
-        // Debug: Let's try to immediately read it back to verify storage
-        match self
-            .get_prompt(&Uuid::from_slice(&prompt.id).unwrap())
-            .await
-        {
-            Ok(Some(_)) => info!("✅ Verified prompt {} can be read back immediately", id),
-            Ok(None) => warn!("⚠️ Prompt {} not found immediately after storage", id),
-            Err(e) => warn!("❌ Error reading prompt {} back: {}", id, e),
+    async fn import_line(&self, line: u64, record: &[u8], report: &mut ImportPromptsResponse) {
+        let record = record.trim_ascii();
+        if record.is_empty() {
+            return;
+        }
+        let stored = async {
+            let prompt: PromptResponse = serde_json::from_slice(record)?;
+            // store_prompt expects a uuid id and a timestamp for its indexes
+            Uuid::from_slice(&prompt.id)
+                .map_err(|_| CwHoError::InvalidRequest("id must be a 16 byte uuid".to_string()))?;
+            if prompt.timestamp.is_none() {
+                return Err(CwHoError::InvalidRequest("missing timestamp".to_string()));
+            }
+            if self.has_prompt(&prompt.id).await? {
+                return Ok(false);
+            }
+            self.store_prompt(&prompt).await?;
+            Ok::<_, CwHoError>(true)
+        };
+        match stored.await {
+            Ok(true) => report.imported += 1,
+            Ok(false) => report.skipped += 1,
+            Err(e) => {
+                warn!("Skipping import line {}: {}", line, e);
+                report.failed += 1;
+                report.errors.push(ImportLineError {
+                    line,
+                    error: e.to_string(),
+                });
+            }
         }
+    }
 
+    /// Messages recorded for a session, oldest first
+    pub async fn session_history(&self, session_id: &str) -> Result<Vec<PromptMessage>> {
+        let prefix = format!("{}{}/", CONVERSATION_PREFIX, session_id);
+        let mut messages = Vec::new();
+        for (key, value) in self.prefix_raw(&prefix).await? {
+            messages.push((key, serde_json::from_slice::<PromptMessage>(&value)?));
+        }
+        // zero-padded sequence numbers sort in turn order
+        messages.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(messages.into_iter().map(|(_, message)| message).collect())
+    }
+
+    /// Append messages to a session's conversation. Appends run one at a time, so
+    /// concurrent ones each land whole instead of overwriting each other's sequence numbers.
+    pub async fn append_session_messages(
+        &self,
+        session_id: &str,
+        messages: &[PromptMessage],
+    ) -> Result<()> {
+        let _writing = self.conversation_writes.lock().await;
+        let next = self.session_history(session_id).await?.len();
+        let mut writes = Vec::new();
+        for (i, message) in messages.iter().enumerate() {
+            let key = format!("{}{}/{:020}", CONVERSATION_PREFIX, session_id, next + i);
+            writes.push((key, Some(serde_json::to_vec(message)?)));
+        }
+        self.commit(writes).await?;
+        debug!(
+            "💬 Recorded {} messages for session {}",
+            messages.len(),
+            session_id
+        );
         Ok(())
     }
 
-    // Backward compatibility method
-    pub async fn store_prompt(&self, prompt: &PromptResponse) -> Result<()> {
-        self.store_prompt_with_context(prompt, None).await
+    /// Record a task's current state, replacing any earlier one
+    pub async fn store_task(&self, task: &CosmicTask) -> Result<()> {
+        self.commit(vec![(
+            format!("{}{}", TASK_PREFIX, task.id),
+            Some(serde_json::to_vec(task)?),
+        )])
+        .await
+    }
+
+    /// Full state of a task that has not been swept
+    pub async fn get_task(&self, id: &str) -> Result<Option<CosmicTask>> {
+        match self.get_raw(&format!("{}{}", TASK_PREFIX, id)).await? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a node registered from the ssh node map, replacing its earlier registration
+    pub async fn store_config_node(&self, node: &ConfigNode) -> Result<()> {
+        self.commit(vec![(
+            format!("{}{}", CONFIG_NODE_PREFIX, node.name),
+            Some(serde_json::to_vec(node)?),
+        )])
+        .await
+    }
+
+    /// Every node registered from the ssh node map, by name
+    pub async fn config_nodes(&self) -> Result<Vec<ConfigNode>> {
+        self.prefix_raw(CONFIG_NODE_PREFIX)
+            .await?
+            .into_iter()
+            .map(|(_, value)| Ok(serde_json::from_slice(&value)?))
+            .collect()
+    }
+
+    /// Summary left behind by the retention sweep
+    pub async fn get_task_summary(&self, id: &str) -> Result<Option<TaskSummary>> {
+        match self
+            .get_raw(&format!("{}{}", TASK_SUMMARY_PREFIX, id))
+            .await?
+        {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace completed and failed tasks last updated more than `retention_secs` before
+    /// `now` with a [`TaskSummary`]. Pending and running tasks are never touched.
+    /// Returns how many tasks were swept.
+    pub async fn sweep_tasks(&self, retention_secs: u64, now: i64) -> Result<u64> {
+        let cutoff = now.saturating_sub(retention_secs as i64);
+        let mut writes = Vec::new();
+        let mut swept = 0;
+
+        for (key, value) in self.prefix_raw(TASK_PREFIX).await? {
+            let task: CosmicTask = serde_json::from_slice(&value)?;
+            let finished = matches!(
+                task.status(),
+                CosmicTaskStatus::Completed | CosmicTaskStatus::Failed
+            );
+            let updated = task.updated_at.or(task.created_at).map(|t| t.seconds);
+            if !finished || updated.is_none_or(|updated| updated > cutoff) {
+                continue;
+            }
+
+            let outcome = serde_json::to_vec(&(&task.result, &task.error))?;
+            let summary = TaskSummary {
+                id: task.id.clone(),
+                status: task.status,
+                created_at: task.created_at,
+                updated_at: task.updated_at,
+                result_digest: blake3::Blake3::hash(&outcome).to_string(),
+            };
+            writes.push((
+                format!("{}{}", TASK_SUMMARY_PREFIX, task.id),
+                Some(serde_json::to_vec(&summary)?),
+            ));
+            writes.push((key, None));
+            swept += 1;
+        }
+
+        if swept > 0 {
+            self.commit(writes).await?;
+            info!(
+                "🧹 Swept {} tasks older than {}s into summaries",
+                swept, retention_secs
+            );
+        }
+        Ok(swept)
     }
 
     pub async fn get_prompt(&self, id: &Uuid) -> Result<Option<PromptResponse>> {
-        let snapshot = self.cnidarium.latest_snapshot();
-        let prompt_key = format!("{}{}", PROMPT_PREFIX, id);
+        // Records are stored under the hex of the id bytes, not the hyphenated uuid
+        let prompt_key = format!("{}{}", PROMPT_PREFIX, hex::encode(id.as_bytes()));
 
-        match snapshot.get_raw(&prompt_key).await {
+        match self.get_raw(&prompt_key).await {
             Ok(Some(data)) => {
-                let prompt: PromptResponse = serde_json::from_slice(&data)?;
+                let mut prompt: PromptResponse = serde_json::from_slice(&data)?;
+                self.rehydrate_prompt(&mut prompt).await?;
                 Ok(Some(prompt))
             }
             Ok(None) => Ok(None),
             Err(e) => {
                 warn!("Failed to get prompt {}: {}", id, e);
-                Err(CwHoError::Storage(e.into()))
+                Err(e)
             }
         }
     }
 
     pub async fn query_prompts(&self, query: &QueryRequest) -> Result<Vec<PromptResponse>> {
-        let snapshot = self.cnidarium.latest_snapshot();
+        let mut query = query.clone();
+        query.resolve_time_range(chrono::Utc::now())?;
+        let query = &query;
         let mut results = Vec::new();
         let limit = query.limit.unwrap_or(100).min(1000); // Cap at 1000
 
@@ -163,53 +667,43 @@ impl CwHoStorage {
 
         // For now, let's implement a simple approach that scans all prompts
         // We'll use the prompt prefix to get all stored prompts
-        let mut prompt_stream = snapshot.prefix_raw(PROMPT_PREFIX);
         let mut count = 0;
         let mut total_entries = 0;
 
-        while let Some(entry_result) = prompt_stream.next().await {
+        for (key, value) in self.prefix_raw(PROMPT_PREFIX).await? {
             total_entries += 1;
             if count >= limit {
                 break;
             }
 
-            match entry_result {
-                Ok((key, value)) => {
-                    let key_str = String::from_utf8_lossy(&key.as_bytes());
+            debug!(
+                "📋 Found entry with key: {}, value size: {} bytes",
+                key,
+                value.len()
+            );
+
+            // Deserialize the prompt response
+            match serde_json::from_slice::<PromptResponse>(&value) {
+                Ok(prompt) => {
+                    let id = hex::encode(prompt.id.clone()).to_string();
+                    debug!("✅ Successfully deserialized prompt: {}", id);
+
+                    // Apply filters
+                    let matches_filters = self.matches_query_filters(&prompt, query);
                     debug!(
-                        "📋 Found entry with key: {}, value size: {} bytes",
-                        key_str,
-                        value.len()
+                        "🔍 Prompt {} matches filters: {}",
+                        id.to_string(),
+                        matches_filters
                     );
 
-                    // Deserialize the prompt response
-                    match serde_json::from_slice::<PromptResponse>(&value) {
-                        Ok(prompt) => {
-                            let id = hex::encode(prompt.id.clone()).to_string();
-                            debug!("✅ Successfully deserialized prompt: {}", id);
-
-                            // Apply filters
-                            let matches_filters = self.matches_query_filters(&prompt, query);
-                            debug!(
-                                "🔍 Prompt {} matches filters: {}",
-                                id.to_string(),
-                                matches_filters
-                            );
-
-                            if matches_filters {
-                                results.push(prompt);
-                                count += 1;
-                                info!("➕ Added prompt to results, count now: {}", count);
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to deserialize prompt from key {}: {}", key_str, e);
-                        }
+                    if matches_filters {
+                        results.push(prompt);
+                        count += 1;
+                        info!("➕ Added prompt to results, count now: {}", count);
                     }
                 }
                 Err(e) => {
-                    warn!("Error reading from storage stream: {}", e);
-                    continue;
+                    warn!("Failed to deserialize prompt from key {}: {}", key, e);
                 }
             }
         }
@@ -225,6 +719,10 @@ impl CwHoStorage {
                 .then_with(|| b_ts.nanos.cmp(&a_ts.nanos))
         });
 
+        for prompt in &mut results {
+            self.rehydrate_prompt(prompt).await?;
+        }
+
         info!(
             "🔍 Query scanned {} total entries, returned {} results",
             total_entries,
@@ -233,84 +731,686 @@ impl CwHoStorage {
         Ok(results)
     }
 
+    /// Put back the prompt text a deduplicated record was stored without
+    async fn rehydrate_prompt(&self, prompt: &mut PromptResponse) -> Result<()> {
+        let ref_key = format!("{}{}", PROMPT_REF_PREFIX, hex::encode(&prompt.id));
+        let Some(digest) = self.get_raw(&ref_key).await? else {
+            return Ok(());
+        };
+        let body_key = format!("{}{}", PROMPT_BODY_PREFIX, String::from_utf8_lossy(&digest));
+        match self.get_raw(&body_key).await? {
+            Some(body) => prompt.prompt = String::from_utf8_lossy(&body).into_owned(),
+            None => warn!(
+                "⚠️ Prompt body {} of {} is missing",
+                String::from_utf8_lossy(&digest),
+                hex::encode(&prompt.id)
+            ),
+        }
+        Ok(())
+    }
+
     fn matches_query_filters(&self, prompt: &PromptResponse, query: &QueryRequest) -> bool {
-        let prompt_nano = prompt.timestamp.expect("should have a time").nanos;
+        let instant = |ts: pbjson_types::Timestamp| (ts.seconds, ts.nanos);
+        let prompt_time = instant(prompt.timestamp.expect("should have a time"));
         // Apply time filters if specified
-        let matches_time_filter = match (query.start_time, query.end_time) {
-            (Some(start), Some(end)) => {
-                prompt_nano >= start.nanos
-                    && prompt.timestamp.expect("must have timestamp").nanos <= end.nanos
-            }
-            (Some(start), None) => prompt_nano >= start.nanos,
-            (None, Some(end)) => prompt_nano <= end.nanos,
-            (None, None) => true,
-        };
+        let matches_time_filter = query
+            .start_time
+            .is_none_or(|start| prompt_time >= instant(start))
+            && query.end_time.is_none_or(|end| prompt_time <= instant(end));
 
         if !matches_time_filter {
             return false;
         }
 
-        // Apply session_id filter if specified
-        if let Some(ref query_session_id) = query.session_id {
-            // if let Some(ref context) = prompt.context {
-            //     if let Some(ref session_id) = context.session_id {
-            //         if session_id != query_session_id {
-            //             return false;
-            //         }
-            //     } else {
-            //         return false; // No session_id in prompt, but filter requires it
-            //     }
-            // } else {
-            //     return false; // No context in prompt, but filter requires session_id
-            // }
-        }
-
-        // Apply user_id filter if specified
-        if let Some(ref query_user_id) = query.user_id {
-            // if let Some(ref context) = prompt.context {
-            //     if let Some(ref user_id) = context.user_id {
-            //         if user_id != query_user_id {
-            //             return false;
-            //         }
-            //     } else {
-            //         return false; // No user_id in prompt, but filter requires it
-            //     }
-            // } else {
-            //     return false; // No context in prompt, but filter requires user_id
-            // }
-        }
-
-        true
+        // Context filters match only responses carrying that id
+        let context = prompt.context.clone().unwrap_or_default();
+        [
+            (&query.session_id, &context.session_id),
+            (&query.user_id, &context.user_id),
+            (&query.thread_id, &context.thread_id),
+        ]
+        .into_iter()
+        .all(|(wanted, actual)| wanted.is_none() || wanted == actual)
     }
 
     pub async fn health_check(&self) -> Result<()> {
-        // Try to get the latest snapshot to verify storage is accessible
-        let _snapshot = self.cnidarium.latest_snapshot();
-
         // Try a simple read operation
         let test_key = "health_check";
-        let snapshot = self.cnidarium.latest_snapshot();
 
-        match snapshot.get_raw(test_key).await {
+        match self.get_raw(test_key).await {
             Ok(_) => Ok(()), // Whether it exists or not, storage is accessible
             Err(e) => {
                 warn!("Storage health check failed: {}", e);
-                Err(CwHoError::Storage(e.into()))
+                Err(e)
+            }
+        }
+    }
+    /// Flush the database and release the data dir lock
+    pub async fn close(&self) {
+        match (&self.backend, &self.data_dir) {
+            (RecordStore::Cnidarium(store), Some(data_dir)) => {
+                store.clone().release().await;
+                let _ = std::fs::remove_file(data_dir.join(PID_FILE));
+                info!("💾 Storage closed at {}", data_dir.display());
+            }
+            _ => info!("💾 In-memory storage closed, its records are discarded"),
+        }
+    }
+
+    /// Delete the prompts stored before `cutoff`, then the oldest ones until prompts, their
+    /// indexes and references take at most `target_bytes`. Returns how many were deleted;
+    /// prompt bodies they leave unreferenced go with the next [`compact`](Self::compact).
+    pub async fn prune_storage(
+        &self,
+        cutoff: Option<chrono::DateTime<chrono::Utc>>,
+        target_bytes: Option<u64>,
+    ) -> Result<u64> {
+        // Every record owned by a prompt, by prompt id, with their total size
+        let mut owned: std::collections::HashMap<String, (Vec<String>, u64)> = Default::default();
+        let mut own = |id: String, key: String, bytes: usize| {
+            let entry = owned.entry(id).or_default();
+            entry.1 += (key.len() + bytes) as u64;
+            entry.0.push(key);
+        };
+        for prefix in INDEX_PREFIXES {
+            for (key, value) in self.prefix_raw(prefix).await? {
+                own(hex::encode(&value), key, value.len());
+            }
+        }
+        for (key, value) in self.prefix_raw(PROMPT_REF_PREFIX).await? {
+            own(key[PROMPT_REF_PREFIX.len()..].to_string(), key, value.len());
+        }
+        let mut prompts = Vec::new();
+        for (key, value) in self.prefix_raw(PROMPT_PREFIX).await? {
+            // records without a readable timestamp sort first, as the oldest
+            let time = serde_json::from_slice::<PromptResponse>(&value)
+                .ok()
+                .and_then(|prompt| prompt.timestamp)
+                .map(|t| (t.seconds, t.nanos))
+                .unwrap_or_default();
+            let id = key[PROMPT_PREFIX.len()..].to_string();
+            own(id.clone(), key, value.len());
+            prompts.push((time, id));
+        }
+        prompts.sort();
+
+        let cutoff = cutoff.map(|c| (c.timestamp(), c.timestamp_subsec_nanos() as i32));
+        let mut total: u64 = owned.values().map(|(_, bytes)| bytes).sum();
+        let mut writes = Vec::new();
+        let mut pruned = 0;
+        // oldest first, so once one prompt stays every later one does too
+        for (time, id) in prompts {
+            let expired = cutoff.is_some_and(|cutoff| time < cutoff);
+            let oversized = target_bytes.is_some_and(|target| total > target);
+            if !expired && !oversized {
+                break;
+            }
+            let (keys, bytes) = owned.remove(&id).unwrap_or_default();
+            writes.extend(keys.into_iter().map(|key| (key, None)));
+            total = total.saturating_sub(bytes);
+            pruned += 1;
+        }
+
+        if pruned > 0 {
+            self.commit(writes).await?;
+            info!("✂️ Pruned {} prompts", pruned);
+        }
+        Ok(pruned)
+    }
+
+    /// Delete records nothing points at any more: index entries and references whose
+    /// prompt is gone, and prompt bodies no reference uses. Returns how many were deleted.
+    pub async fn compact(&self) -> Result<u64> {
+        let prompts: std::collections::HashSet<String> = self
+            .prefix_raw(PROMPT_PREFIX)
+            .await?
+            .into_iter()
+            .map(|(key, _)| key[PROMPT_PREFIX.len()..].to_string())
+            .collect();
+        let mut writes = Vec::new();
+        for prefix in INDEX_PREFIXES {
+            for (key, id) in self.prefix_raw(prefix).await? {
+                if !prompts.contains(&hex::encode(&id)) {
+                    writes.push((key, None));
+                }
+            }
+        }
+        let mut used = std::collections::HashSet::new();
+        for (key, digest) in self.prefix_raw(PROMPT_REF_PREFIX).await? {
+            match prompts.contains(&key[PROMPT_REF_PREFIX.len()..]) {
+                true => {
+                    used.insert(String::from_utf8_lossy(&digest).into_owned());
+                }
+                false => writes.push((key, None)),
+            }
+        }
+        for (key, _) in self.prefix_raw(PROMPT_BODY_PREFIX).await? {
+            if !used.contains(&key[PROMPT_BODY_PREFIX.len()..]) {
+                writes.push((key, None));
             }
         }
+
+        let compacted = writes.len() as u64;
+        if compacted > 0 {
+            self.commit(writes).await?;
+            info!("🗜️ Compacted {} unreferenced records", compacted);
+        }
+        Ok(compacted)
     }
-    pub async fn prune_storage(&self) -> Result<()> {
-        unimplemented!();
+
+    /// Bytes of every record snapshots capture, keys included
+    pub async fn stored_bytes(&self) -> Result<u64> {
+        let mut bytes = 0;
+        for prefix in SNAPSHOT_PREFIXES {
+            for (key, value) in self.prefix_raw(prefix).await? {
+                bytes += (key.len() + value.len()) as u64;
+            }
+        }
+        Ok(bytes)
     }
 
-    pub async fn create_snapshot(&self) -> Result<()> {
+    /// Capture every stored record into a gzip-compressed snapshot
+    pub async fn create_snapshot(&self) -> Result<StorageSnapshot> {
         // Create a named snapshot for backup/recovery
         let snapshot_name = format!("snapshot_{}", chrono::Utc::now().timestamp());
+        let version = self.version();
+
+        let mut data = std::collections::HashMap::new();
+        for prefix in SNAPSHOT_PREFIXES {
+            data.extend(self.prefix_raw(prefix).await?);
+        }
+
+        let mut snapshot = StorageSnapshot {
+            id: snapshot_name,
+            created_at: Some(chrono::Utc::now().into()),
+            version,
+            data,
+            ..Default::default()
+        };
+        snapshot.compress()?;
+
+        let ratio = snapshot.compression_ratio();
+        *self.snapshot_ratio.lock().unwrap() = ratio;
+        info!(
+            "📸 Created snapshot {}: {} raw bytes at ratio {:.3} (Kepler target {})",
+            snapshot.id,
+            snapshot.raw_size_bytes.unwrap_or_default(),
+            ratio.unwrap_or(1.0),
+            ho_std::constants::KEPLER_PACKING_DENSITY
+        );
+        Ok(snapshot)
+    }
 
-        // TODO: ensure we are accurately taking the snapshots (needs tests)
-        let _snapshot = self.cnidarium.latest_snapshot();
-        info!("📸 Created logical snapshot: {}", snapshot_name);
+    /// Write a snapshot's records back into storage, decompressing it first
+    pub async fn restore_from_snapshot(&self, snapshot: &StorageSnapshot) -> Result<()> {
+        let mut snapshot = snapshot.clone();
+        snapshot.decompress()?;
 
+        let restored = snapshot.data.len();
+        self.commit(
+            snapshot
+                .data
+                .into_iter()
+                .map(|(key, value)| (key, Some(value)))
+                .collect(),
+        )
+        .await?;
+
+        info!("♻️ Restored {} records from {}", restored, snapshot.id);
         Ok(())
     }
+
+    /// Storage metrics, including the compression achieved by the latest snapshot
+    /// and the prompt text saved by deduplication
+    pub async fn metrics(&self) -> Result<StorageMetrics> {
+        let bodies: std::collections::HashMap<String, u64> = self
+            .prefix_raw(PROMPT_BODY_PREFIX)
+            .await?
+            .into_iter()
+            .map(|(key, body)| {
+                (
+                    key[PROMPT_BODY_PREFIX.len()..].to_string(),
+                    body.len() as u64,
+                )
+            })
+            .collect();
+        // Every reference would have stored its body inline
+        let referenced: u64 = self
+            .prefix_raw(PROMPT_REF_PREFIX)
+            .await?
+            .iter()
+            .filter_map(|(_, digest)| bodies.get(String::from_utf8_lossy(digest).as_ref()))
+            .sum();
+        Ok(StorageMetrics {
+            total_entries: self.count_prefix(PROMPT_PREFIX).await?,
+            compression_ratio: *self.snapshot_ratio.lock().unwrap(),
+            tasks_retained: Some(self.count_prefix(TASK_PREFIX).await?),
+            tasks_swept: Some(self.count_prefix(TASK_SUMMARY_PREFIX).await?),
+            prompt_bodies: Some(bodies.len() as u64),
+            prompt_dedup_saved_bytes: Some(referenced.saturating_sub(bodies.values().sum())),
+            ..Default::default()
+        })
+    }
+
+    /// Number of records under `prefix`
+    async fn count_prefix(&self, prefix: &str) -> Result<u64> {
+        Ok(self.prefix_raw(prefix).await?.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::test_router_config;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_locked_data_dir_reports_holder() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-locked-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // pid 1 is always alive and never us
+        std::fs::write(dir.join(PID_FILE), "1").unwrap();
+
+        match CwHoStorage::new(&dir).await {
+            Err(CwHoError::StorageLocked { path, pid }) => {
+                assert_eq!(pid, 1);
+                assert_eq!(path, dir.display().to_string());
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("locked data dir should not open"),
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_session_appends_all_land() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-appends-{}", Uuid::new_v4()));
+        let storage = CwHoStorage::new(&dir).await.unwrap();
+        let turn = |i: usize| {
+            [0, 1].map(|j| PromptMessage {
+                role: "user".to_string(),
+                content: format!("turn {} message {}", i, j),
+            })
+        };
+        let turns: Vec<_> = (0..8).map(turn).collect();
+        futures::future::try_join_all(
+            turns
+                .iter()
+                .map(|turn| storage.append_session_messages("busy", turn)),
+        )
+        .await
+        .unwrap();
+
+        let history = storage.session_history("busy").await.unwrap();
+        assert_eq!(history.len(), 16);
+        // Each append stays in one piece
+        for pair in history.chunks(2) {
+            assert_eq!(
+                pair[0].content.replace("message 0", "message 1"),
+                pair[1].content
+            );
+        }
+
+        storage.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_stores_without_touching_disk() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-memory-{}", Uuid::new_v4()));
+        let config = StorageConfig {
+            data_dir: dir.display().to_string(),
+            backend: Some(StorageBackend::Memory.into()),
+            ..Default::default()
+        };
+        let storage = CwHoStorage::from_config(&config).await.unwrap();
+
+        let id = Uuid::new_v4();
+        let prompt = PromptResponse {
+            id: id.as_bytes().to_vec(),
+            response: vec!["kept in memory".to_string()],
+            timestamp: Some(chrono::Utc::now().into()),
+            ..Default::default()
+        };
+        storage.store_prompt(&prompt).await.unwrap();
+        storage
+            .append_session_messages("s1", &[PromptMessage::default()])
+            .await
+            .unwrap();
+
+        assert!(storage.has_prompt(id.as_bytes()).await.unwrap());
+        let stored = storage.get_prompt(&id).await.unwrap().unwrap();
+        assert_eq!(stored.response, prompt.response);
+        let prompts = storage
+            .query_prompts(&QueryRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].response, prompt.response);
+        assert_eq!(storage.session_history("s1").await.unwrap().len(), 1);
+        assert_eq!(storage.metrics().await.unwrap().total_entries, 1);
+        assert!(storage.health_check().await.is_ok());
+
+        storage.close().await;
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_since_excludes_older_prompts() {
+        let storage = CwHoStorage::memory();
+        let now = chrono::Utc::now();
+        let prompt = |text: &str, age: chrono::Duration| PromptResponse {
+            id: Uuid::new_v4().as_bytes().to_vec(),
+            response: vec![text.to_string()],
+            timestamp: Some((now - age).into()),
+            ..Default::default()
+        };
+        storage
+            .store_prompt(&prompt("old", chrono::Duration::hours(2)))
+            .await
+            .unwrap();
+        storage
+            .store_prompt(&prompt("recent", chrono::Duration::minutes(5)))
+            .await
+            .unwrap();
+
+        let query = |since: &str, until: Option<&str>| QueryRequest {
+            since: Some(since.to_string()),
+            until: until.map(str::to_string),
+            ..Default::default()
+        };
+        let recent = storage.query_prompts(&query("1h", None)).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].response, ["recent"]);
+
+        let until = (now - chrono::Duration::hours(1)).to_rfc3339();
+        let older = storage
+            .query_prompts(&query("1d", Some(&until)))
+            .await
+            .unwrap();
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].response, ["old"]);
+
+        assert!(storage.query_prompts(&query("soon", None)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_response_is_retrievable_by_its_request_thread() {
+        let config = LlmRouterConfig {
+            mock: Some(true),
+            ..test_router_config(vec![])
+        };
+        let router = crate::LlmRouter::new(&config).await.unwrap();
+        let storage = CwHoStorage::memory();
+        let ask = |thread: &str| PromptRequest {
+            messages: vec![PromptMessage {
+                role: "user".to_string(),
+                content: format!("hello from {}", thread),
+            }],
+            context: Some(PromptContext {
+                session_id: Some("s1".to_string()),
+                user_id: Some("alice".to_string()),
+                thread_id: Some(thread.to_string()),
+            }),
+            ..Default::default()
+        };
+        for thread in ["t1", "t2"] {
+            let mut response = router
+                .process_request(&ask(thread), "gpt-4o")
+                .await
+                .unwrap();
+            response.id = Uuid::new_v4().as_bytes().to_vec();
+            response.timestamp = Some(chrono::Utc::now().into());
+            assert_eq!(response.context, ask(thread).context);
+            storage.store_prompt(&response).await.unwrap();
+        }
+
+        let by_thread = |thread: &str| QueryRequest {
+            thread_id: Some(thread.to_string()),
+            ..Default::default()
+        };
+        let t1 = storage.query_prompts(&by_thread("t1")).await.unwrap();
+        assert_eq!(t1.len(), 1);
+        assert_eq!(t1[0].response, ["mock response to: hello from t1"]);
+        assert!(storage
+            .query_prompts(&by_thread("t3"))
+            .await
+            .unwrap()
+            .is_empty());
+        let by_user = QueryRequest {
+            user_id: Some("alice".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(storage.query_prompts(&by_user).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_close_releases_lock() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-close-{}", Uuid::new_v4()));
+        let storage = CwHoStorage::new(&dir).await.unwrap();
+        assert!(dir.join(PID_FILE).exists());
+
+        storage.close().await;
+        assert!(!dir.join(PID_FILE).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_wal_completes_commit_interrupted_by_crash() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-wal-{}", Uuid::new_v4()));
+        let storage = CwHoStorage::new(&dir).await.unwrap();
+        let id = Uuid::new_v4();
+        let prompt = PromptResponse {
+            id: id.as_bytes().to_vec(),
+            response: vec!["logged, never committed".to_string()],
+            timestamp: Some(chrono::Utc::now().into()),
+            ..Default::default()
+        };
+
+        // Crash between the log append and the commit, mid-way through the next append
+        let writes = storage.prompt_writes(&prompt, None).await.unwrap();
+        storage.wal.as_ref().unwrap().append(&writes).unwrap();
+        assert!(storage.get_prompt(&id).await.unwrap().is_none());
+        storage.close().await;
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.join(WAL_FILE))
+            .unwrap();
+        log.write_all(b"{\"kind\":\"intent\",\"seq\":1,\"wri")
+            .unwrap();
+
+        let reopened = CwHoStorage::new(&dir).await.unwrap();
+        assert_eq!(
+            reopened.recovery(),
+            WalRecovery {
+                replayed: 1,
+                torn: 1
+            }
+        );
+        let stored = reopened.get_prompt(&id).await.unwrap().unwrap();
+        assert_eq!(stored.response, prompt.response);
+        // The indexes landed with the record
+        let listed = reopened
+            .query_prompts(&QueryRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        reopened.close().await;
+
+        // Recovery emptied the log, so nothing is replayed twice
+        let again = CwHoStorage::new(&dir).await.unwrap();
+        assert_eq!(again.recovery(), WalRecovery::default());
+        again.close().await;
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip_is_compressed() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-snapshot-{}", Uuid::new_v4()));
+        let storage = CwHoStorage::new(&dir).await.unwrap();
+        let id = Uuid::new_v4();
+        let prompt = PromptResponse {
+            id: id.as_bytes().to_vec(),
+            response: vec!["the same words again and again ".repeat(50)],
+            timestamp: Some(chrono::Utc::now().into()),
+            ..Default::default()
+        };
+        storage.store_prompt(&prompt).await.unwrap();
+
+        let snapshot = storage.create_snapshot().await.unwrap();
+        assert!(snapshot.data.is_empty());
+        let compressed = snapshot.compressed_data.as_ref().unwrap().len() as f64;
+        let raw = snapshot.raw_size_bytes.unwrap() as f64;
+        assert_eq!(snapshot.compression_ratio(), Some(compressed / raw));
+        assert!(compressed < raw);
+        assert_eq!(
+            storage.metrics().await.unwrap().compression_ratio,
+            snapshot.compression_ratio()
+        );
+        storage.close().await;
+
+        let restored_dir = std::env::temp_dir().join(format!("cw-ho-restore-{}", Uuid::new_v4()));
+        let restored = CwHoStorage::new(&restored_dir).await.unwrap();
+        restored.restore_from_snapshot(&snapshot).await.unwrap();
+        let prompts = restored
+            .query_prompts(&QueryRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].id, prompt.id);
+        assert_eq!(prompts[0].response, prompt.response);
+        restored.close().await;
+
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&restored_dir);
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_malformed_line_and_is_idempotent() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-import-{}", Uuid::new_v4()));
+        let storage = CwHoStorage::new(&dir).await.unwrap();
+        let record = |text: &str| {
+            serde_json::to_string(&PromptResponse {
+                id: Uuid::new_v4().as_bytes().to_vec(),
+                response: vec![text.to_string()],
+                timestamp: Some(chrono::Utc::now().into()),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let body = format!("{}\n{{\"id\": \n{}\n", record("first"), record("third"));
+        // split mid-record so lines span chunks
+        let (head, tail) = body.split_at(body.len() / 2);
+        let chunks = || {
+            futures::stream::iter(vec![
+                Ok::<_, std::io::Error>(head.as_bytes().to_vec()),
+                Ok(tail.as_bytes().to_vec()),
+            ])
+        };
+
+        let report = storage.import_prompts(chunks()).await.unwrap();
+        assert_eq!((report.imported, report.skipped, report.failed), (2, 0, 1));
+        assert_eq!(report.errors[0].line, 2);
+
+        let again = storage.import_prompts(chunks()).await.unwrap();
+        assert_eq!((again.imported, again.skipped, again.failed), (0, 2, 1));
+
+        storage.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_prunes_finished_tasks_past_ttl() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-tasks-{}", Uuid::new_v4()));
+        let storage = CwHoStorage::new(&dir).await.unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let task = |id: &str, status: CosmicTaskStatus, age: i64| CosmicTask {
+            id: id.to_string(),
+            status: status.into(),
+            prompt: "a long prompt payload ".repeat(20),
+            created_at: Some(pbjson_types::Timestamp {
+                seconds: now - age,
+                nanos: 0,
+            }),
+            updated_at: Some(pbjson_types::Timestamp {
+                seconds: now - age,
+                nanos: 0,
+            }),
+            ..Default::default()
+        };
+        storage
+            .store_task(&task("old-done", CosmicTaskStatus::Completed, 7_200))
+            .await
+            .unwrap();
+        storage
+            .store_task(&task("old-running", CosmicTaskStatus::Running, 7_200))
+            .await
+            .unwrap();
+        storage
+            .store_task(&task("fresh-done", CosmicTaskStatus::Completed, 10))
+            .await
+            .unwrap();
+
+        assert_eq!(storage.sweep_tasks(3_600, now).await.unwrap(), 1);
+        assert!(storage.get_task("old-done").await.unwrap().is_none());
+        let summary = storage.get_task_summary("old-done").await.unwrap().unwrap();
+        assert_eq!(summary.status(), CosmicTaskStatus::Completed);
+        assert!(!summary.result_digest.is_empty());
+        assert!(storage.get_task("old-running").await.unwrap().is_some());
+        assert!(storage.get_task("fresh-done").await.unwrap().is_some());
+
+        let metrics = storage.metrics().await.unwrap();
+        assert_eq!(
+            (metrics.tasks_retained, metrics.tasks_swept),
+            (Some(2), Some(1))
+        );
+        // A second sweep has nothing left to do
+        assert_eq!(storage.sweep_tasks(3_600, now).await.unwrap(), 0);
+
+        storage.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_shared_prompt_body_is_stored_once() {
+        let storage = CwHoStorage::memory().with_prompt_dedup(true);
+        let system_prompt = "You coordinate the tetrahedral loop. ".repeat(50);
+        for i in 0..100 {
+            let prompt = PromptResponse {
+                id: Uuid::new_v4().as_bytes().to_vec(),
+                prompt: system_prompt.clone(),
+                response: vec![format!("reply {}", i)],
+                timestamp: Some(chrono::Utc::now().into()),
+                ..Default::default()
+            };
+            storage.store_prompt(&prompt).await.unwrap();
+        }
+
+        assert_eq!(storage.count_prefix(PROMPT_BODY_PREFIX).await.unwrap(), 1);
+        for (_, record) in storage.prefix_raw(PROMPT_PREFIX).await.unwrap() {
+            let record: PromptResponse = serde_json::from_slice(&record).unwrap();
+            assert!(record.prompt.is_empty());
+        }
+        let prompts = storage
+            .query_prompts(&QueryRequest {
+                limit: Some(1000),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(prompts.len(), 100);
+        assert!(prompts.iter().all(|p| p.prompt == system_prompt));
+
+        let metrics = storage.metrics().await.unwrap();
+        assert_eq!(metrics.total_entries, 100);
+        assert_eq!(metrics.prompt_bodies, Some(1));
+        assert_eq!(
+            metrics.prompt_dedup_saved_bytes,
+            Some(99 * system_prompt.len() as u64)
+        );
+    }
 }
@@ -0,0 +1,639 @@
+//! Runs cosmic tasks submitted to this node
+//!
+//! Tasks run in dependency order, each answering its prompt through the LLM router, or
+//! for custom tasks through the handler registered under the name they give. Sandloop
+//! tasks feed each answer back as the next prompt until successive answers converge. A
+//! task's state is stored when it starts and again when it ends, and a node shutting
+//! down cancels the tasks still running so each ends in a stored, failed state.
+use crate::artifacts::ArtifactStore;
+use crate::error::{CwHoError, Result};
+use crate::replay::prompt_result;
+use crate::{CwHoStorage, LlmRouter};
+use async_trait::async_trait;
+use ho_std::constants::{
+    DEFAULT_RECURSION_DEPTH, DEFAULT_SANDLOOP_CONVERGENCE, TASK_CANCELLED_ERROR,
+};
+use ho_std::orchestrate::{
+    fail_task, run_catching_panics, run_task_graph, run_with_timeout, SandloopConvergence,
+};
+use ho_std::prelude::*;
+use pbjson_types::value::Kind;
+use pbjson_types::{Struct, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, RwLock};
+use tracing::{error, info, warn};
+
+/// Runs the `Custom` tasks that name it in `custom_handler`
+#[async_trait]
+pub trait CustomTaskHandler: Send + Sync {
+    /// The result to complete `task` with
+    async fn execute(&self, task: &CosmicTask) -> Result<Struct>;
+}
+
+pub struct TaskRunner {
+    storage: Arc<CwHoStorage>,
+    llm_router: Arc<LlmRouter>,
+    /// How long tasks setting no timeout of their own may run, unlimited when `None`
+    default_timeout: Option<Duration>,
+    /// Where large result fields are moved, `None` keeps them inline
+    artifacts: Option<Arc<ArtifactStore>>,
+    /// Handlers for custom tasks, by name
+    custom_handlers: RwLock<HashMap<String, Box<dyn CustomTaskHandler>>>,
+    /// Tasks running now, by id
+    active: std::sync::RwLock<HashMap<String, CosmicTask>>,
+    /// Turns true once the node starts shutting down
+    shutdown: watch::Sender<bool>,
+}
+
+impl TaskRunner {
+    pub fn new(storage: Arc<CwHoStorage>, llm_router: Arc<LlmRouter>) -> Self {
+        Self {
+            storage,
+            llm_router,
+            default_timeout: None,
+            artifacts: None,
+            custom_handlers: Default::default(),
+            active: Default::default(),
+            shutdown: watch::channel(false).0,
+        }
+    }
+
+    /// Fail tasks setting no timeout of their own once `timeout` elapses
+    pub fn with_default_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// Move result fields too large to keep inline to `artifacts`
+    pub fn with_artifacts(mut self, artifacts: Option<ArtifactStore>) -> Self {
+        self.artifacts = artifacts.map(Arc::new);
+        self
+    }
+
+    /// Run custom tasks naming `name` with `handler`, replacing any handler already
+    /// registered under it
+    pub async fn register_custom_handler(
+        &self,
+        name: impl Into<String>,
+        handler: Box<dyn CustomTaskHandler>,
+    ) {
+        self.custom_handlers
+            .write()
+            .await
+            .insert(name.into(), handler);
+    }
+
+    /// Copies of the tasks running now
+    pub fn active_tasks(&self) -> Vec<CosmicTask> {
+        self.active.read().unwrap().values().cloned().collect()
+    }
+
+    /// Counts over one copy of the running tasks, so the lock is held only for the copy
+    /// and the counts always add up
+    pub fn active_task_counts(&self) -> ActiveTaskCounts {
+        let snapshot = self.active_tasks();
+        let mut counts = ActiveTaskCounts {
+            total: snapshot.len() as u64,
+            ..Default::default()
+        };
+        for task in &snapshot {
+            if task.fractal_requirements.is_some() {
+                counts.fractal += 1;
+            }
+            if task.timeout().or(self.default_timeout).is_some() {
+                counts.with_deadline += 1;
+            }
+        }
+        counts
+    }
+
+    /// Run `tasks` in dependency order, returning each in its final state as stored.
+    /// Fails without running any of them when the graph has duplicate ids, unknown
+    /// dependencies or a cycle.
+    pub async fn run(&self, tasks: Vec<CosmicTask>) -> Result<Vec<CosmicTask>> {
+        run_task_graph(tasks, |task| self.run_one(task))
+            .await
+            .map_err(|e| CwHoError::InvalidRequest(e.to_string()))
+    }
+
+    async fn run_one(&self, mut task: CosmicTask) -> Result<CosmicTask> {
+        if *self.shutdown.borrow() {
+            return Err(CwHoError::Cancelled(format!(
+                "node is shutting down, not starting task {}",
+                task.id
+            )));
+        }
+        task.set_status(CosmicTaskStatus::Running);
+        task.updated_at = Some(chrono::Utc::now().into());
+        self.storage.store_task(&task).await?;
+        self.active
+            .write()
+            .unwrap()
+            .insert(task.id.clone(), task.clone());
+
+        // A panicking execution fails its task rather than leaving it running forever
+        let mut shutdown = self.shutdown.subscribe();
+        let outcome = tokio::select! {
+            outcome = run_with_timeout(task.clone(), self.default_timeout, |task, _| {
+                run_catching_panics(task, |task| self.execute(task))
+            }) => outcome,
+            _ = shutdown.wait_for(|stopping| *stopping) => {
+                warn!("🛑 Task {} cancelled by shutdown", task.id);
+                Ok(fail_task(task.clone(), TASK_CANCELLED_ERROR.to_string()))
+            }
+        };
+        let done = outcome.unwrap_or_else(|e| fail_task(task.clone(), e.to_string()));
+
+        let stored = self.storage.store_task(&done).await;
+        self.active.write().unwrap().remove(&done.id);
+        stored?;
+        Ok(done)
+    }
+
+    /// Answer `task`'s prompt, completing it with the answer as its result. Tasks with a
+    /// deadline fall back through the live chain within it. Custom tasks are completed
+    /// by their handler instead, and sandloop tasks by their last iteration's answer.
+    async fn execute(&self, mut task: CosmicTask) -> Result<CosmicTask> {
+        let mut result = match task.task_type() {
+            OrchestrateTask::Custom => self.execute_custom(&task).await?,
+            OrchestrateTask::Sandloop => self.execute_sandloop(&task).await?,
+            _ => {
+                let deadline = task.timeout().or(self.default_timeout);
+                prompt_result(&self.llm_router.answer_task(&task, deadline).await?)
+            }
+        };
+        if let Some(artifacts) = self.artifacts.clone() {
+            let task_id = task.id.clone();
+            let (moved, offloaded) = tokio::task::spawn_blocking(move || {
+                let moved = artifacts.offload(&task_id, &mut result)?;
+                Ok::<_, CwHoError>((moved, result))
+            })
+            .await
+            .map_err(std::io::Error::from)??;
+            if moved > 0 {
+                info!(
+                    "📦 Moved {} result fields of task {} to artifacts",
+                    moved, task.id
+                );
+            }
+            result = offloaded;
+        }
+        task.result = Some(result);
+        task.set_status(CosmicTaskStatus::Completed);
+        task.updated_at = Some(chrono::Utc::now().into());
+        Ok(task)
+    }
+
+    /// Hand a custom task to the handler it names
+    async fn execute_custom(&self, task: &CosmicTask) -> Result<Struct> {
+        let name = task.custom_handler.as_deref().unwrap_or_default();
+        let handlers = self.custom_handlers.read().await;
+        let handler = handlers
+            .get(name)
+            .ok_or_else(|| CwHoError::NoCustomHandler(name.to_string()))?;
+        info!("🧩 Running custom task {} with handler '{}'", task.id, name);
+        handler.execute(task).await
+    }
+
+    /// Answer `task` up to `recursion_depth` times, each time building on the previous
+    /// answer, stopping early once two successive answers are at least the task's
+    /// convergence threshold similar. The result is the last answer along with
+    /// `iterations_completed` and, when it stopped early, `converged_at_iteration`.
+    async fn execute_sandloop(&self, task: &CosmicTask) -> Result<Struct> {
+        let fractal = task.fractal_requirements.as_ref();
+        let iterations = fractal
+            .map(|fr| fr.recursion_depth)
+            .filter(|depth| *depth > 0)
+            .unwrap_or(DEFAULT_RECURSION_DEPTH);
+        let mut convergence = SandloopConvergence::new(
+            fractal
+                .map(|fr| fr.sandloop_convergence())
+                .unwrap_or(DEFAULT_SANDLOOP_CONVERGENCE),
+        );
+        let deadline = task.timeout().or(self.default_timeout);
+
+        let mut step = task.clone();
+        let mut completed = 0;
+        let mut last = None;
+        for iteration in 1..=iterations {
+            info!(
+                "🔄 Sandloop iteration {}/{} of task {}",
+                iteration, iterations, task.id
+            );
+            let response = self.llm_router.answer_task(&step, deadline).await?;
+            completed = iteration;
+            let answer = response.response.join("\n");
+            let converged = convergence.observe(iteration, &answer);
+            last = Some(response);
+            if converged {
+                info!(
+                    "🎯 Sandloop of task {} converged at iteration {}/{}",
+                    task.id, iteration, iterations
+                );
+                break;
+            }
+            step.prompt = format!(
+                "Building on previous iteration: {}\n\nNow execute: {}",
+                answer,
+                if iteration + 1 < iterations {
+                    &task.prompt
+                } else {
+                    "Synthesize final result"
+                }
+            );
+        }
+
+        let number = |n: u32| Value {
+            kind: Some(Kind::NumberValue(n as f64)),
+        };
+        let mut result = last.as_ref().map(prompt_result).unwrap_or_default();
+        result
+            .fields
+            .insert("iterations_completed".to_string(), number(completed));
+        if let Some(converged_at) = convergence.converged_at {
+            result
+                .fields
+                .insert("converged_at_iteration".to_string(), number(converged_at));
+        }
+        Ok(result)
+    }
+
+    /// Cancel the tasks running now and refuse new ones, then wait up to `grace` for the
+    /// cancelled tasks to store their final state. Tasks still running at the deadline
+    /// are stored as failed here and reported as terminated.
+    pub async fn shutdown(&self, grace: Duration) -> OrchestratorShutdownSummary {
+        let started = Instant::now();
+        let in_flight: Vec<String> = self.active.read().unwrap().keys().cloned().collect();
+        info!(
+            "🛑 Stopping {} running tasks (grace {:?})",
+            in_flight.len(),
+            grace
+        );
+        self.shutdown.send_replace(true);
+
+        let deadline = tokio::time::Instant::now() + grace;
+        while !self.active.read().unwrap().is_empty() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let lingering: Vec<CosmicTask> = self
+            .active
+            .write()
+            .unwrap()
+            .drain()
+            .map(|(_, t)| t)
+            .collect();
+        let mut summary = OrchestratorShutdownSummary::default();
+        for task in lingering {
+            warn!("💀 Task {} did not stop in time", task.id);
+            let task = fail_task(task, TASK_CANCELLED_ERROR.to_string());
+            if let Err(e) = self.storage.store_task(&task).await {
+                error!("💥 Failed to store terminated task {}: {}", task.id, e);
+            }
+            summary.terminated.push(task.id);
+        }
+        summary.drained = in_flight
+            .into_iter()
+            .filter(|id| !summary.terminated.contains(id))
+            .collect();
+        summary.elapsed_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "✅ Tasks stopped: {} drained, {} terminated in {}ms",
+            summary.drained.len(),
+            summary.terminated.len(),
+            summary.elapsed_ms
+        );
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{test_router_config, FakeBackend};
+    use ho_std::constants::TASK_PANICKED_ERROR;
+    use ho_std::traits::HttpBackend;
+
+    /// Provider that never answers
+    struct Stalled;
+
+    /// Provider whose client panics on every request
+    struct Exploding;
+
+    #[async_trait::async_trait]
+    impl HttpBackend for Exploding {
+        async fn send(&self, _request: reqwest::Request) -> ho_std::error::HoResult<HttpResponse> {
+            panic!("provider client exploded")
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HttpBackend for Stalled {
+        async fn send(&self, _request: reqwest::Request) -> ho_std::error::HoResult<HttpResponse> {
+            std::future::pending().await
+        }
+    }
+
+    fn task(id: &str) -> CosmicTask {
+        CosmicTask {
+            id: id.to_string(),
+            prompt: "summarise the network".to_string(),
+            status: CosmicTaskStatus::Pending.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_cancels_running_task_and_stores_its_state() {
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(Arc::new(Stalled));
+        router.api_keys.akash = Some("test-key".to_string());
+        let storage = Arc::new(CwHoStorage::memory());
+        let runner = Arc::new(TaskRunner::new(storage.clone(), Arc::new(router)));
+
+        let running = tokio::spawn({
+            let runner = runner.clone();
+            async move { runner.run(vec![task("stuck")]).await }
+        });
+        while runner.active_tasks().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let stored = storage.get_task("stuck").await.unwrap().unwrap();
+        assert_eq!(stored.status(), CosmicTaskStatus::Running);
+
+        let summary = runner.shutdown(Duration::from_secs(5)).await;
+        assert_eq!(summary.drained, ["stuck"]);
+        assert!(summary.terminated.is_empty());
+
+        let handled = running.await.unwrap().unwrap();
+        assert_eq!(handled[0].status(), CosmicTaskStatus::Failed);
+        assert_eq!(handled[0].error.as_deref(), Some(TASK_CANCELLED_ERROR));
+        assert_eq!(
+            storage.get_task("stuck").await.unwrap(),
+            Some(handled[0].clone())
+        );
+
+        // Nothing new starts once shutdown began
+        let late = runner.run(vec![task("late")]).await.unwrap();
+        assert_eq!(late[0].status(), CosmicTaskStatus::Failed);
+        assert!(storage.get_task("late").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_active_task_counts_come_from_one_snapshot() {
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let router = LlmRouter::new(&config).await.unwrap();
+        let runner = TaskRunner::new(Arc::new(CwHoStorage::memory()), Arc::new(router));
+        let fractal = CosmicTask {
+            fractal_requirements: Some(Default::default()),
+            timeout_secs: Some(30),
+            ..task("fractal")
+        };
+        for task in [task("plain"), fractal] {
+            runner.active.write().unwrap().insert(task.id.clone(), task);
+        }
+
+        assert_eq!(
+            runner.active_task_counts(),
+            ActiveTaskCounts {
+                total: 2,
+                fractal: 1,
+                with_deadline: 1,
+            }
+        );
+        // Counting released the lock
+        runner.active.try_write().unwrap().clear();
+        assert_eq!(runner.active_task_counts(), ActiveTaskCounts::default());
+    }
+
+    #[tokio::test]
+    async fn test_panicking_task_is_stored_failed() {
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(Arc::new(Exploding));
+        router.api_keys.akash = Some("test-key".to_string());
+        let storage = Arc::new(CwHoStorage::memory());
+        let runner = TaskRunner::new(storage.clone(), Arc::new(router));
+
+        let done = runner.run(vec![task("boom")]).await.unwrap();
+        assert_eq!(done[0].status(), CosmicTaskStatus::Failed);
+        let error = done[0].error.as_deref().unwrap();
+        assert!(error.starts_with(TASK_PANICKED_ERROR), "{}", error);
+        assert!(error.contains("provider client exploded"), "{}", error);
+        assert_eq!(
+            storage.get_task("boom").await.unwrap(),
+            Some(done[0].clone())
+        );
+        assert!(runner.active_tasks().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_task_with_deadline_falls_back_to_next_provider() {
+        let backend = Arc::new(FakeBackend::default());
+        backend.responses.lock().unwrap().extend([
+            HttpResponse::new(reqwest::StatusCode::UNAUTHORIZED, "bad key"),
+            HttpResponse::new(
+                reqwest::StatusCode::OK,
+                serde_json::json!({
+                    "choices": [{"message": {"role": "assistant", "content": "from openai"}}],
+                })
+                .to_string(),
+            ),
+        ]);
+        let entity = |model: LlmModel, priority: u32| LlmEntity {
+            priority,
+            max_retries: 0,
+            ..model.default_entity()
+        };
+        let config = test_router_config(vec![
+            entity(LlmModel::AkashChat, 3),
+            entity(LlmModel::OpenAi, 2),
+        ]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        router.api_keys.openai = Some("test-key".to_string());
+        let runner = TaskRunner::new(Arc::new(CwHoStorage::memory()), Arc::new(router))
+            .with_default_timeout(Some(Duration::from_secs(30)));
+
+        let mut prioritised = task("fallback");
+        prioritised.selection_strategy = Some(ModelSelectionStrategy::Priority.into());
+        let done = runner.run(vec![prioritised]).await.unwrap();
+        assert_eq!(
+            done[0].status(),
+            CosmicTaskStatus::Completed,
+            "{:?}",
+            done[0].error
+        );
+        let result = done[0].result.as_ref().unwrap();
+        assert_eq!(
+            result.fields["response"].kind,
+            Some(Kind::StringValue("from openai".to_string()))
+        );
+        assert_eq!(backend.urls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_custom_task_runs_registered_handler_or_fails_cleanly() {
+        struct Echo;
+        #[async_trait]
+        impl CustomTaskHandler for Echo {
+            async fn execute(&self, task: &CosmicTask) -> Result<Struct> {
+                Ok(Struct {
+                    fields: [(
+                        "echo".to_string(),
+                        pbjson_types::Value {
+                            kind: Some(Kind::StringValue(task.prompt.clone())),
+                        },
+                    )]
+                    .into(),
+                })
+            }
+        }
+
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let router = LlmRouter::new(&config).await.unwrap();
+        let runner = TaskRunner::new(Arc::new(CwHoStorage::memory()), Arc::new(router));
+        runner.register_custom_handler("echo", Box::new(Echo)).await;
+        let custom = |id: &str, handler: &str| CosmicTask {
+            task_type: OrchestrateTask::Custom.into(),
+            custom_handler: Some(handler.to_string()),
+            ..task(id)
+        };
+
+        let done = runner
+            .run(vec![custom("echoed", "echo"), custom("orphan", "unknown")])
+            .await
+            .unwrap();
+        assert_eq!(done[0].status(), CosmicTaskStatus::Completed);
+        assert_eq!(
+            done[0].result.as_ref().unwrap().fields["echo"].kind,
+            Some(Kind::StringValue("summarise the network".to_string()))
+        );
+        // An unregistered name fails its task instead of panicking the runner
+        assert_eq!(done[1].status(), CosmicTaskStatus::Failed);
+        assert_eq!(
+            done[1].error.as_deref(),
+            Some("no handler registered for custom task 'unknown'")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sandloop_task_stops_once_answers_converge() {
+        let answer = |content: &str| {
+            HttpResponse::new(
+                reqwest::StatusCode::OK,
+                serde_json::json!({
+                    "choices": [{"message": {"role": "assistant", "content": content}}],
+                })
+                .to_string(),
+            )
+        };
+        let backend = Arc::new(FakeBackend::default());
+        backend.responses.lock().unwrap().extend([
+            answer("the network has three nodes"),
+            answer("The network has three nodes."),
+            answer("never asked for"),
+        ]);
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let mut router = LlmRouter::new(&config)
+            .await
+            .unwrap()
+            .with_backend(backend.clone());
+        router.api_keys.akash = Some("test-key".to_string());
+        let runner = TaskRunner::new(Arc::new(CwHoStorage::memory()), Arc::new(router));
+        let sandloop = CosmicTask {
+            task_type: OrchestrateTask::Sandloop.into(),
+            fractal_requirements: Some(FractalRequirements {
+                recursion_depth: 5,
+                ..Default::default()
+            }),
+            ..task("looped")
+        };
+
+        let done = runner.run(vec![sandloop]).await.unwrap();
+        assert_eq!(
+            done[0].status(),
+            CosmicTaskStatus::Completed,
+            "{:?}",
+            done[0].error
+        );
+        let fields = &done[0].result.as_ref().unwrap().fields;
+        assert_eq!(
+            fields["response"].kind,
+            Some(Kind::StringValue(
+                "The network has three nodes.".to_string()
+            ))
+        );
+        assert_eq!(
+            fields["iterations_completed"].kind,
+            Some(Kind::NumberValue(2.0))
+        );
+        assert_eq!(
+            fields["converged_at_iteration"].kind,
+            Some(Kind::NumberValue(2.0))
+        );
+        // The second iteration built on the first answer, and no third one ran
+        let bodies = backend.bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 2);
+        let prompt = bodies[1]["messages"][0]["content"].as_str().unwrap();
+        assert!(
+            prompt.starts_with("Building on previous iteration: the network has three nodes"),
+            "{}",
+            prompt
+        );
+    }
+
+    #[tokio::test]
+    async fn test_long_answer_to_task_with_colon_id_is_offloaded() {
+        let long = "x".repeat(ho_std::constants::ARTIFACT_INLINE_MAX_BYTES + 1);
+        let backend = Arc::new(FakeBackend::default());
+        backend
+            .responses
+            .lock()
+            .unwrap()
+            .push_back(HttpResponse::new(
+                reqwest::StatusCode::OK,
+                serde_json::json!({
+                    "choices": [{"message": {"role": "assistant", "content": long}}],
+                })
+                .to_string(),
+            ));
+        let config = test_router_config(vec![LlmModel::AkashChat.default_entity()]);
+        let mut router = LlmRouter::new(&config).await.unwrap().with_backend(backend);
+        router.api_keys.akash = Some("test-key".to_string());
+        let dir = std::env::temp_dir().join(format!("cw-ho-artifacts-{}", uuid::Uuid::new_v4()));
+        let artifacts = ArtifactStore::new(camino::Utf8PathBuf::from_path_buf(dir).unwrap());
+        let runner = TaskRunner::new(Arc::new(CwHoStorage::memory()), Arc::new(router))
+            .with_artifacts(Some(artifacts));
+
+        let done = runner.run(vec![task("job:1")]).await.unwrap();
+        assert_eq!(
+            done[0].status(),
+            CosmicTaskStatus::Completed,
+            "{:?}",
+            done[0].error
+        );
+        let response = &done[0].result.as_ref().unwrap().fields["response"];
+        assert!(
+            matches!(response.kind, Some(Kind::StructValue(_))),
+            "{:?}",
+            response
+        );
+        let stored = runner
+            .artifacts
+            .as_ref()
+            .unwrap()
+            .read("job:1", "response.txt");
+        assert_eq!(stored.unwrap(), Some(long.into_bytes()));
+    }
+}
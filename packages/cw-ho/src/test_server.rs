@@ -0,0 +1,272 @@
+//! Serves a real [`Server`] on an ephemeral port, so tests exercise routes, auth and state
+//! wiring end to end
+use crate::artifacts::ArtifactStore;
+use crate::server::Server;
+use crate::tasks::TaskRunner;
+use crate::{AppState, CwHoConfig, CwHoNetworkManifold, CwHoStorage, LlmRouter};
+use camino::{Utf8Path, Utf8PathBuf};
+use commonware_runtime::tokio::Context;
+use ho_std::commonware::identity::NodePrivKey;
+use ho_std::error::{HoError, HoResult};
+use ho_std::prelude::*;
+use ho_std::python::capabilities::PythonCapabilities;
+use ho_std::routes::auth::signed_headers;
+use ho_std::traits::{HoConfigTrait, HttpBackend};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+/// A node serving on `127.0.0.1:0` with in-memory storage and the mock LLM router.
+/// Dropping it shuts the server down.
+pub(crate) struct TestServer {
+    addr: SocketAddr,
+    /// Signs requests to protected routes
+    key: NodePrivKey,
+    client: reqwest::Client,
+    /// The node's in-memory storage
+    storage: Arc<CwHoStorage>,
+    /// The node's network, not started
+    network: Arc<tokio::sync::Mutex<CwHoNetworkManifold>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl TestServer {
+    pub async fn start(context: Context) -> Self {
+        let router = LlmRouter::new(test_config(&test_home()).llm())
+            .await
+            .unwrap();
+        Self::with_router(context, router).await
+    }
+
+    /// Like [`start`](Self::start), answering prompts with `router` instead of the mock
+    pub async fn with_router(context: Context, router: LlmRouter) -> Self {
+        let home = test_home();
+        let config = test_config(&home);
+        let storage = Arc::new(CwHoStorage::memory());
+        let llm_router = Arc::new(router);
+        let network = Arc::new(tokio::sync::Mutex::new(
+            CwHoNetworkManifold::new(config.identity(), context).await,
+        ));
+        let state = AppState {
+            tasks: Arc::new(
+                TaskRunner::new(storage.clone(), llm_router.clone())
+                    .with_artifacts(ArtifactStore::from_config(&config)),
+            ),
+            storage: storage.clone(),
+            llm_router,
+            network_manifold: network.clone(),
+            start_time: Instant::now(),
+            config,
+            python: PythonCapabilities::detect(),
+            config_path: None,
+            home,
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown, stopped) = oneshot::channel();
+        tokio::spawn(Server { state }.serve(listener, async {
+            let _ = stopped.await;
+        }));
+
+        Self {
+            addr,
+            key: NodePrivKey::from_seed(42),
+            client: reqwest::Client::new(),
+            storage,
+            network,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn storage(&self) -> &CwHoStorage {
+        &self.storage
+    }
+
+    pub fn network(&self) -> &tokio::sync::Mutex<CwHoNetworkManifold> {
+        &self.network
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// GET `path` without signing, as a public route is called
+    pub async fn get(&self, path: &str) -> reqwest::Response {
+        self.client.get(self.url(path)).send().await.unwrap()
+    }
+
+    /// GET a protected `path`, signed over the empty body
+    pub async fn get_signed(&self, path: &str) -> reqwest::Response {
+        self.signed(self.client.get(self.url(path)), Vec::new())
+            .await
+    }
+
+    /// POST `body` as JSON to a protected `path`, signed over the encoded body
+    pub async fn post_signed(&self, path: &str, body: &impl serde::Serialize) -> reqwest::Response {
+        self.post_signed_with(path, body, &[]).await
+    }
+
+    /// Like [`post_signed`](Self::post_signed), sending `headers` too
+    pub async fn post_signed_with(
+        &self,
+        path: &str,
+        body: &impl serde::Serialize,
+        headers: &[(&str, &str)],
+    ) -> reqwest::Response {
+        let body = serde_json::to_vec(body).unwrap();
+        let mut request = self
+            .client
+            .post(self.url(path))
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        self.signed(request, body).await
+    }
+
+    /// Open a websocket on a protected `path`, the upgrade signed over the empty body
+    pub async fn websocket_signed(
+        &self,
+        path: &str,
+    ) -> tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>
+    {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+        let mut request = format!("ws://{}{}", self.addr, path)
+            .into_client_request()
+            .unwrap();
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        for (name, value) in signed_headers(&self.key, &[], timestamp) {
+            request.headers_mut().insert(name, value.parse().unwrap());
+        }
+        tokio_tungstenite::connect_async(request).await.unwrap().0
+    }
+
+    async fn signed(
+        &self,
+        mut request: reqwest::RequestBuilder,
+        body: Vec<u8>,
+    ) -> reqwest::Response {
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        for (name, value) in signed_headers(&self.key, &body, timestamp) {
+            request = request.header(name, value);
+        }
+        request.body(body).send().await.unwrap()
+    }
+}
+
+/// A fresh temp home, not created until something is written under it
+fn test_home() -> Utf8PathBuf {
+    Utf8PathBuf::from_path_buf(
+        std::env::temp_dir().join(format!("cw-ho-server-{}", uuid::Uuid::new_v4())),
+    )
+    .unwrap()
+}
+
+/// Default config under `home`, with the mock LLM router
+fn test_config(home: &Utf8Path) -> CwHoConfig {
+    let mut config = CwHoConfig::new(home);
+    config.0.llm.as_mut().unwrap().mock = Some(true);
+    config
+}
+
+/// LLM router config for tests: `entities`, a keys file that does not exist, and a short
+/// timeout
+pub(crate) fn test_router_config(entities: Vec<LlmEntity>) -> LlmRouterConfig {
+    LlmRouterConfig {
+        api_keys_file: "does-not-exist.json".to_string(),
+        entities,
+        timeout_seconds: 5,
+        ..Default::default()
+    }
+}
+
+/// Replays canned responses in order and records the requested urls and json bodies
+#[derive(Default)]
+pub(crate) struct FakeBackend {
+    pub responses: std::sync::Mutex<std::collections::VecDeque<HttpResponse>>,
+    pub urls: std::sync::Mutex<Vec<String>>,
+    pub bodies: std::sync::Mutex<Vec<serde_json::Value>>,
+}
+
+#[async_trait::async_trait]
+impl HttpBackend for FakeBackend {
+    async fn send(&self, request: reqwest::Request) -> HoResult<HttpResponse> {
+        self.urls.lock().unwrap().push(request.url().to_string());
+        if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+            self.bodies
+                .lock()
+                .unwrap()
+                .push(serde_json::from_slice(body).unwrap());
+        }
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| HoError::Network("no canned response left".to_string()))
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use commonware_runtime::tokio::{Config as RuntimeConfig, Runner};
+    use commonware_runtime::Runner as _;
+
+    #[test]
+    fn test_server_serves_health_and_signed_prompt() {
+        Runner::new(RuntimeConfig::new()).start(|context| async move {
+            let server = TestServer::start(context).await;
+            assert_ne!(server.addr().port(), 0);
+
+            let health: HealthResponse = server.get("/health").await.json().await.unwrap();
+            assert_eq!(health.status, "ok");
+            assert_eq!(health.storage_status, "healthy");
+
+            let prompt = PromptRequest {
+                messages: vec![PromptMessage {
+                    role: "user".to_string(),
+                    content: "ping".to_string(),
+                }],
+                ..Default::default()
+            };
+            // Protected routes turn away unsigned requests
+            let unsigned = reqwest::Client::new()
+                .post(server.url("/api/prompt"))
+                .json(&prompt)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(unsigned.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+            let answered = server.post_signed("/api/prompt", &prompt).await;
+            assert_eq!(answered.status(), reqwest::StatusCode::OK);
+            let answered: PromptResponse = answered.json().await.unwrap();
+            assert_eq!(answered.response, ["mock response to: ping"]);
+
+            // The answer went through the same storage the query route reads
+            let stored: Vec<PromptResponse> = server
+                .get_signed("/api/prompts")
+                .await
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(stored.len(), 1);
+            assert_eq!(stored[0].id, answered.id);
+        });
+    }
+}
@@ -0,0 +1,145 @@
+//! Write-ahead log making storage commits all-or-nothing across crashes
+//!
+//! Each commit is logged as an intent and synced before it reaches the store, then marked
+//! done. Intents a crash left without their mark are committed again when the store is
+//! next opened. Writes carry whole values, so redoing one that did land is harmless.
+use crate::error::Result;
+use ho_std::prelude::WalRecovery;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A put, or a delete when the value is `None`
+pub type KeyWrite = (String, Option<Vec<u8>>);
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Entry {
+    /// Writes about to be committed
+    Intent { seq: u64, writes: Vec<LoggedWrite> },
+    /// The intent with this seq was committed, or failed and must not be redone
+    Done { seq: u64 },
+}
+
+#[derive(Serialize, Deserialize)]
+struct LoggedWrite {
+    key: String,
+    /// Hex-encoded value, absent for a delete
+    value: Option<String>,
+}
+
+pub struct WriteAheadLog {
+    state: Mutex<WalState>,
+}
+
+struct WalState {
+    file: File,
+    next_seq: u64,
+    /// Intents appended and not yet marked done
+    pending: HashSet<u64>,
+}
+
+impl WriteAheadLog {
+    /// Open the log at `path`, along with the write batches a crash left uncommitted,
+    /// oldest first. Commit those, then [`reset`](Self::reset) the log.
+    pub fn open(path: &Path) -> Result<(Self, Vec<Vec<KeyWrite>>, WalRecovery)> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        let mut intents = BTreeMap::new();
+        let mut recovery = WalRecovery::default();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(Entry::Intent { seq, writes }) => {
+                    intents.insert(seq, writes);
+                }
+                Ok(Entry::Done { seq }) => {
+                    intents.remove(&seq);
+                }
+                Err(_) => recovery.torn += 1,
+            }
+        }
+
+        let mut uncommitted = Vec::new();
+        for writes in intents.into_values() {
+            uncommitted.push(
+                writes
+                    .into_iter()
+                    .map(|write| {
+                        let value = write.value.map(hex::decode).transpose()?;
+                        Ok((write.key, value))
+                    })
+                    .collect::<std::result::Result<Vec<_>, hex::FromHexError>>()
+                    .map_err(|e| anyhow::anyhow!("corrupt write-ahead log value: {}", e))?,
+            );
+        }
+        recovery.replayed = uncommitted.len() as u64;
+
+        let wal = Self {
+            state: Mutex::new(WalState {
+                file,
+                next_seq: 0,
+                pending: HashSet::new(),
+            }),
+        };
+        Ok((wal, uncommitted, recovery))
+    }
+
+    /// Empty the log once everything it held is committed
+    pub fn reset(&self) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        state.file.set_len(0)?;
+        state.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Durably record `writes` as about to be committed, returning the seq to mark done
+    pub fn append(&self, writes: &[KeyWrite]) -> Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        let writes = writes
+            .iter()
+            .map(|(key, value)| LoggedWrite {
+                key: key.clone(),
+                value: value.as_ref().map(hex::encode),
+            })
+            .collect();
+        state.write(&Entry::Intent { seq, writes })?;
+        state.pending.insert(seq);
+        Ok(seq)
+    }
+
+    /// Mark the intent `seq` as settled. The log is emptied whenever nothing is in flight,
+    /// so it stays as small as the commits running at once.
+    pub fn done(&self, seq: u64) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.pending.remove(&seq);
+        if state.pending.is_empty() {
+            state.file.set_len(0)?;
+            state.file.sync_data()?;
+            return Ok(());
+        }
+        state.write(&Entry::Done { seq })
+    }
+}
+
+impl WalState {
+    fn write(&mut self, entry: &Entry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
@@ -172,5 +172,6 @@ pub fn create_advanced_fractal_requirements() -> ho_std::types::cw_ho::v1::Fract
             "fractal_coherence_maintained".to_string(),
             "sacred_proportions".to_string(),
         ],
+        convergence_threshold: Some(0.95),
     }
 }
@@ -42,8 +42,14 @@ pub enum CommonwareNetworkError {
     #[error("Peer not found: {0}")]
     PeerNotFound(String),
 
+    #[error("Peer does not support message: {0}")]
+    UnsupportedByPeer(String),
+
     #[error("Channel error: {0}")]
     ChannelError(String),
+
+    #[error("Invalid message signature: {0}")]
+    InvalidSignature(String),
 }
 
 pub type CommonwareNetworkResult<T> = std::result::Result<T, CommonwareNetworkError>;
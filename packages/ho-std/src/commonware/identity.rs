@@ -16,6 +16,16 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 // Use proto types
 
+/// Bytes of the public key digest kept in a node id
+const NODE_ID_LEN: usize = 20;
+
+/// Canonical node id: hex of the truncated sha256 digest of the public key bytes
+pub fn node_id_from_public_key(public_key: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(public_key);
+    hex::encode(&digest[..NODE_ID_LEN])
+}
+
 impl NodeIdentityTrait for NodeIdentity {
     type HostOS = HostOs;
     type NodeType = NodeType;
@@ -77,12 +87,28 @@ impl NodeIdentityTrait for NodeIdentity {
         format!("{}:{}", self.host, self.api_port)
     }
 
+    /// Get the API port, rejecting values that do not fit a u16
+    fn checked_api_port(&self) -> super::error::CommonwareNetworkResult<u16> {
+        u16::try_from(self.api_port).map_err(|_| {
+            super::error::CommonwareNetworkError::ConfigError(format!(
+                "api_port {} is out of range for u16",
+                self.api_port
+            ))
+        })
+    }
+
     /// Get a display-friendly identifier
     fn display_id(&self) -> String {
         hex::encode(self.public_key())
     }
 
-    /// Get private key from environment variable or generate a new one
+    /// Stable node id derived from the ed25519 public key
+    fn node_id(&self) -> String {
+        node_id_from_public_key(self.public_key())
+    }
+
+    /// Get private key from environment variable, then a provisioned key file,
+    /// or generate a new one
     fn get_private_key_from_env() -> NodePrivKey {
         // Try to get private key from environment variable
         if let Ok(hex_string) = std::env::var("NODE_PRIVATE_KEY") {
@@ -92,6 +118,17 @@ impl NodeIdentityTrait for NodeIdentity {
             eprintln!("Warning: Invalid private key in NODE_PRIVATE_KEY, generating new key");
         }
 
+        // Key written by bootstrap provisioning
+        if let Ok(hex_string) = std::fs::read_to_string(crate::constants::NODE_KEY_PATH) {
+            if let Some(private_key) = NodePrivKey::from_hex(hex_string.trim()) {
+                return private_key;
+            }
+            eprintln!(
+                "Warning: Invalid private key in {}, generating new key",
+                crate::constants::NODE_KEY_PATH
+            );
+        }
+
         // Generate a new random private key if env var not found or invalid
         let mut rng = rand::rngs::OsRng;
         NodePrivKey::new(&mut rng)
@@ -134,6 +171,11 @@ impl<'de> Deserialize<'de> for NodePubkey {
 }
 
 impl NodePubkey {
+    /// Canonical node id for this key, matching `NodeIdentity::node_id`
+    pub fn node_id(&self) -> String {
+        node_id_from_public_key(&self.0.to_vec())
+    }
+
     /// Verify `sig` on `msg` using the given *optional* namespace.
     ///
     /// This mirrors the contract of `Signer::sign`: the namespace is
@@ -364,6 +406,19 @@ mod tests {
         assert!(!b.id().verify(Some(TEST_NS), msg, &sig));
     }
 
+    #[test]
+    fn node_id_is_stable_per_key() {
+        let a = NodePrivKey::from_seed(1);
+        let b = NodePrivKey::from_seed(2);
+
+        let mut identity = NodeIdentity::default();
+        identity.set_keypair(a.clone());
+        assert_eq!(identity.node_id(), a.id().node_id());
+        assert_eq!(identity.node_id(), NodePrivKey::from_seed(1).id().node_id());
+        assert_eq!(identity.node_id().len(), NODE_ID_LEN * 2);
+        assert_ne!(a.id().node_id(), b.id().node_id());
+    }
+
     #[test]
     fn public_key_serialisation() {
         let node = NodePrivKey::new(&mut OsRng);
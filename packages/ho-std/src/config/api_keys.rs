@@ -1,3 +1,5 @@
+use crate::constants::REDACTED;
+use crate::llm::env_placeholder;
 use crate::prelude::*;
 use crate::traits::LlmModelTrait;
 use anyhow::{Context, Result};
@@ -9,6 +11,202 @@ use termion::event::{Event, Key, MouseButton, MouseEvent};
 use termion::input::{MouseTerminal, TermRead};
 use termion::raw::IntoRawMode;
 use termion::{clear, color, cursor, style};
+use tracing::warn;
+
+/// Short spellings accepted for provider keys besides case, separator and proto name variants
+const PROVIDER_KEY_ALIASES: &[(&str, LlmModel)] = &[
+    ("akash", LlmModel::AkashChat),
+    ("ollama", LlmModel::OllamaLocal),
+];
+
+/// Providers without a provider type of their own whose api keys the router still reads
+pub const EXTRA_PROVIDER_KEYS: [&str; 2] = ["qwen", "venice"];
+
+/// Canonical `provider_key` for a hand-written one. Case and `_`/`-` separators are ignored,
+/// so `OpenAI`, `open_ai` and `AkashChat` resolve, as do the [`PROVIDER_KEY_ALIASES`] and
+/// the [`EXTRA_PROVIDER_KEYS`].
+pub fn canonical_provider_key(key: &str) -> Option<&'static str> {
+    let squashed = squash_key(key);
+    known_models()
+        .find(|model| {
+            [model.provider_key(), model.as_str_name()]
+                .iter()
+                .any(|name| squash_key(name) == squashed)
+        })
+        .or_else(|| {
+            PROVIDER_KEY_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == squashed)
+                .map(|(_, model)| *model)
+        })
+        .map(|model| model.provider_key())
+        .or_else(|| {
+            EXTRA_PROVIDER_KEYS
+                .into_iter()
+                .find(|extra| squash_key(extra) == squashed)
+        })
+}
+
+fn squash_key(key: &str) -> String {
+    key.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn known_models() -> impl Iterator<Item = LlmModel> {
+    (0..).map_while(|i| LlmModel::try_from(i).ok())
+}
+
+/// Canonical key for `key`, warning when it was spelled differently
+fn resolve_provider_key(key: &str) -> Result<&'static str> {
+    let canonical = canonical_provider_key(key).ok_or_else(|| {
+        let valid: Vec<&str> = known_models()
+            .map(|m| m.provider_key())
+            .chain(EXTRA_PROVIDER_KEYS)
+            .collect();
+        anyhow::anyhow!(
+            "unknown provider {:?}, expected one of: {}",
+            key,
+            valid.join(", ")
+        )
+    })?;
+    if canonical != key {
+        warn!("🔑 Reading provider {:?} as {:?}", key, canonical);
+    }
+    Ok(canonical)
+}
+
+impl ApiKeysIssue {
+    pub fn new(kind: ApiKeysIssueKind, provider: &str) -> Self {
+        Self {
+            kind: kind.into(),
+            provider: provider.to_string(),
+            model: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiKeysIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let provider = &self.provider;
+        match self.kind() {
+            ApiKeysIssueKind::NoModels => write!(f, "{} is enabled but lists no models", provider),
+            ApiKeysIssueKind::DefaultModelNotListed => write!(
+                f,
+                "{}'s default model {:?} is not among its models",
+                provider,
+                self.model()
+            ),
+            ApiKeysIssueKind::DefaultProviderDisabled => {
+                write!(f, "default provider {} is not enabled", provider)
+            }
+            ApiKeysIssueKind::Unspecified => write!(f, "{} is misconfigured", provider),
+        }
+    }
+}
+
+/// Closes every api keys file error, as the file is usually easier regenerated than fixed
+const REGENERATE_HINT: &str = "re-run `cw-ho init llm-api-keys` to regenerate it";
+
+/// A provider entry of an api keys file that would not load, by provider and field
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("provider {provider:?}: {field} {problem}")]
+pub struct ApiKeysFieldError {
+    pub provider: String,
+    pub field: String,
+    pub problem: String,
+}
+
+/// Whether `value` has the JSON shape of `template`: the same kind, a whole number where
+/// the template holds one, and list items shaped like the template's first item
+fn fits(template: &serde_json::Value, value: &serde_json::Value) -> bool {
+    use serde_json::Value;
+    match (template, value) {
+        (Value::Number(template), Value::Number(value)) => !template.is_u64() || value.is_u64(),
+        (Value::Array(template), Value::Array(items)) => template
+            .first()
+            .is_none_or(|template| items.iter().all(|item| fits(template, item))),
+        (template, value) => json_kind(template) == json_kind(value),
+    }
+}
+
+/// The shape of `template` as a field error names it
+fn describe(template: &serde_json::Value) -> String {
+    use serde_json::Value;
+    match template {
+        Value::Number(n) if n.is_u64() => "a whole number".to_string(),
+        Value::Bool(_) => "true or false".to_string(),
+        Value::Array(items) => match items.first() {
+            Some(Value::String(_)) => "a list of strings".to_string(),
+            _ => "a list".to_string(),
+        },
+        other => json_kind(other).to_string(),
+    }
+}
+
+fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "a list",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// Missing or mistyped provider entity fields of a parsed api keys file, by provider name.
+/// The fields and their shapes come from [`LlmEntity`] itself: every field that is not
+/// optional is required, shaped like the entity `cw-ho init` writes for Ollama.
+pub fn entity_field_errors(file: &serde_json::Value) -> Vec<ApiKeysFieldError> {
+    let Some(providers) = file.get("providers").and_then(|p| p.as_object()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<&String> = providers.keys().collect();
+    names.sort();
+
+    let required = serde_json::to_value(LlmEntity::default()).unwrap_or_default();
+    let example = serde_json::to_value(LlmModel::OllamaLocal.default_entity()).unwrap_or_default();
+    let required: Vec<(&String, &serde_json::Value)> = required
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, default)| !default.is_null())
+        .map(|(field, default)| (field, example.get(field).unwrap_or(default)))
+        .collect();
+
+    let mut errors = Vec::new();
+    for name in names {
+        let error = |field: &str, problem: String| ApiKeysFieldError {
+            provider: name.clone(),
+            field: field.to_string(),
+            problem,
+        };
+        let entity = match providers[name].get("entity") {
+            None | Some(serde_json::Value::Null) => continue,
+            Some(entity) => entity,
+        };
+        let Some(fields) = entity.as_object() else {
+            errors.push(error(
+                "entity",
+                format!("must be an object, found {}", json_kind(entity)),
+            ));
+            continue;
+        };
+        for (field, template) in &required {
+            let problem = match fields.get(*field) {
+                None => "is missing".to_string(),
+                Some(value) if !fits(template, value) => {
+                    format!("must be {}, found {}", describe(template), json_kind(value))
+                }
+                Some(_) => continue,
+            };
+            errors.push(error(&format!("entity.{}", field), problem));
+        }
+    }
+    errors
+}
 
 impl ApiKeysJson {
     /// Create a new default configuration with ollama_local enabled
@@ -18,7 +216,7 @@ impl ApiKeysJson {
         // Add ollama_local by default (no API key needed)
         let ollama = LlmModel::OllamaLocal;
         providers.insert(
-            "ollama_local".to_string(),
+            ollama.provider_key().to_string(),
             ProviderWithAuth {
                 api_key: None,
                 entity: Some(ollama.default_entity()),
@@ -58,6 +256,7 @@ impl ApiKeysJson {
                     "✅ Restrict file permissions: chmod 600 api-keys.json".to_string(),
                 ],
             }),
+            default_provider: Some(ollama.provider_key().to_string()),
         }
     }
 
@@ -66,12 +265,89 @@ impl ApiKeysJson {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read API keys file: {}", path.as_str()))?;
 
-        let config: ApiKeysJson = serde_json::from_str(&content)
-            .with_context(|| format!("Failed to parse API keys JSON from: {}", path.as_str()))?;
+        // Parsed in two steps so a bad entry is named by provider and field rather than by
+        // a serde byte offset
+        let file: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+            anyhow::anyhow!(
+                "API keys file {} is not valid JSON: {}; fix it or {}",
+                path.as_str(),
+                e,
+                REGENERATE_HINT
+            )
+        })?;
+        let invalid = |problems: Vec<String>| {
+            let listed: Vec<String> = problems.iter().map(|p| format!("  - {}", p)).collect();
+            anyhow::anyhow!(
+                "API keys file {} has invalid provider settings:\n{}\nFix the fields above or {}",
+                path.as_str(),
+                listed.join("\n"),
+                REGENERATE_HINT
+            )
+        };
+        let errors = entity_field_errors(&file);
+        if !errors.is_empty() {
+            return Err(invalid(errors.iter().map(ToString::to_string).collect()));
+        }
+        let mut config: ApiKeysJson = serde_json::from_value(file).with_context(|| {
+            format!(
+                "Failed to parse API keys JSON from: {}; {}",
+                path.as_str(),
+                REGENERATE_HINT
+            )
+        })?;
+        config
+            .normalize_provider_keys()
+            .with_context(|| format!("Invalid provider in API keys file: {}", path.as_str()))?;
+
+        // A disabled default only costs a fallback to another provider, so it is reported
+        // rather than refused
+        let (fallbacks, issues): (Vec<_>, Vec<_>) = config
+            .validate()
+            .into_iter()
+            .partition(|issue| issue.kind() == ApiKeysIssueKind::DefaultProviderDisabled);
+        for issue in &fallbacks {
+            warn!("🔑 {}", issue);
+        }
+        if !issues.is_empty() {
+            return Err(invalid(issues.iter().map(ToString::to_string).collect()));
+        }
 
         Ok(config)
     }
 
+    /// Copy safe to share: every api key becomes [`REDACTED`], except `${ENV_VAR}`
+    /// references, which name a secret without holding it
+    pub fn redacted(&self) -> Self {
+        let mut redacted = self.clone();
+        for provider in redacted.providers.values_mut() {
+            if let Some(key) = provider
+                .api_key
+                .as_mut()
+                .filter(|key| !key.is_empty() && env_placeholder(key).is_none())
+            {
+                *key = REDACTED.to_string();
+            }
+        }
+        redacted
+    }
+
+    /// Rewrite provider keys and the default provider to their canonical `provider_key`,
+    /// rejecting unknown providers and two keys naming the same one
+    pub fn normalize_provider_keys(&mut self) -> Result<()> {
+        let mut providers = HashMap::new();
+        for (key, provider) in std::mem::take(&mut self.providers) {
+            let canonical = resolve_provider_key(&key)?;
+            if providers.insert(canonical.to_string(), provider).is_some() {
+                anyhow::bail!("provider {} is configured more than once", canonical);
+            }
+        }
+        self.providers = providers;
+        if let Some(default) = self.default_provider.as_mut() {
+            *default = resolve_provider_key(default)?.to_string();
+        }
+        Ok(())
+    }
+
     /// Save configuration to file
     pub fn save(&self, path: &Utf8PathBuf) -> Result<()> {
         let json =
@@ -91,10 +367,92 @@ impl ApiKeysJson {
 
         Ok(())
     }
+
+    /// Problems with the provider settings: enabled providers without models or with a
+    /// default model outside them, and a disabled default provider. [`load`](Self::load)
+    /// refuses files with any but the last.
+    pub fn validate(&self) -> Vec<ApiKeysIssue> {
+        let mut enabled: Vec<(&String, &LlmEntity)> = self
+            .providers
+            .iter()
+            .filter_map(|(key, provider)| Some((key, provider.entity.as_ref()?)))
+            .filter(|(_, entity)| entity.enabled)
+            .collect();
+        enabled.sort_by_key(|(key, _)| *key);
+
+        let mut issues = Vec::new();
+        for (key, entity) in &enabled {
+            if entity.models.is_empty() {
+                issues.push(ApiKeysIssue::new(ApiKeysIssueKind::NoModels, key));
+            } else if !entity.models.contains(&entity.default_model) {
+                issues.push(ApiKeysIssue {
+                    model: Some(entity.default_model.clone()),
+                    ..ApiKeysIssue::new(ApiKeysIssueKind::DefaultModelNotListed, key)
+                });
+            }
+        }
+        if let Some(default) = &self.default_provider {
+            if !enabled.iter().any(|(key, _)| *key == default) {
+                issues.push(ApiKeysIssue::new(
+                    ApiKeysIssueKind::DefaultProviderDisabled,
+                    default,
+                ));
+            }
+        }
+        issues
+    }
+
+    /// Human readable changes between this config and `next`:
+    /// providers being enabled or disabled, and a changed default
+    pub fn diff(&self, next: &ApiKeysJson) -> Vec<String> {
+        let enabled = |config: &ApiKeysJson, key: &str| {
+            config
+                .providers
+                .get(key)
+                .and_then(|p| p.entity.as_ref())
+                .is_some_and(|e| e.enabled)
+        };
+
+        let mut keys: Vec<&String> = self.providers.keys().chain(next.providers.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        let mut changes = Vec::new();
+        for key in keys {
+            match (enabled(self, key), enabled(next, key)) {
+                (false, true) => changes.push(format!("+ enable {}", key)),
+                (true, false) => changes.push(format!("- disable {}", key)),
+                _ => {}
+            }
+        }
+        if self.default_provider != next.default_provider {
+            changes.push(format!(
+                "~ default: {} -> {}",
+                self.default_provider.as_deref().unwrap_or("none"),
+                next.default_provider.as_deref().unwrap_or("none")
+            ));
+        }
+        changes
+    }
+}
+
+/// Write the confirmed config to `path`, or return its JSON without touching the file when `dry_run` is set
+pub fn finish_api_keys(
+    config: &ApiKeysJson,
+    path: &Utf8PathBuf,
+    dry_run: bool,
+) -> Result<Option<String>> {
+    if dry_run {
+        let json =
+            serde_json::to_string_pretty(config).context("Failed to serialize API keys config")?;
+        return Ok(Some(json));
+    }
+    config.save(path)?;
+    Ok(None)
 }
 
 /// Get environment variable name for a provider
-fn get_env_var_name(provider: LlmModel) -> &'static str {
+pub fn get_env_var_name(provider: LlmModel) -> &'static str {
     use crate::constants::*;
 
     match provider {
@@ -108,19 +466,6 @@ fn get_env_var_name(provider: LlmModel) -> &'static str {
     }
 }
 
-/// Get provider key name (lowercase identifier)
-fn get_provider_key(provider: LlmModel) -> &'static str {
-    match provider {
-        LlmModel::AkashChat => "akash_chat",
-        LlmModel::OllamaLocal => "ollama_local",
-        LlmModel::KimiResearch => "kimi",
-        LlmModel::Grok => "grok",
-        LlmModel::OpenAi => "openai",
-        LlmModel::Anthropic => "anthropic",
-        LlmModel::Custom => "custom",
-    }
-}
-
 /// Provider menu item
 #[derive(Clone)]
 struct ProviderMenuItem {
@@ -145,11 +490,17 @@ enum ConfigStep {
     SelectProviders,
     ConfigureProviders(usize), // Index in selected providers list
     SelectDefaultProvider,
+    Confirm(Box<ApiKeysJson>),
     Done,
 }
 
-/// Interactive CLI for configuring API keys using termion TUI - 3 step process
-pub fn configure_api_keys_interactive(api_keys_path: &Utf8PathBuf) -> Result<()> {
+/// Interactive CLI for configuring API keys using termion TUI - 3 step process,
+/// followed by a confirmation screen listing the changes before anything is written.
+/// With `dry_run` the file is left alone and the resulting JSON is returned instead.
+pub fn configure_api_keys_interactive(
+    api_keys_path: &Utf8PathBuf,
+    dry_run: bool,
+) -> Result<Option<String>> {
     // Load existing config or create new one
     let mut config = if api_keys_path.exists() {
         ApiKeysJson::load(api_keys_path)?
@@ -184,7 +535,7 @@ pub fn configure_api_keys_interactive(api_keys_path: &Utf8PathBuf) -> Result<()>
 
     // Mark already enabled providers as selected
     for provider in &mut all_providers {
-        let key = get_provider_key(provider.model);
+        let key = provider.model.provider_key();
         if let Some(cfg) = config.providers.get(key) {
             provider.selected = cfg.entity.clone().expect("dange").enabled;
         }
@@ -193,6 +544,9 @@ pub fn configure_api_keys_interactive(api_keys_path: &Utf8PathBuf) -> Result<()>
     let mut cursor_pos: usize = 0;
     let mut step = ConfigStep::SelectProviders;
     let mut default_provider_index: usize = 0;
+    // Why the last attempt to leave the default selection was refused
+    let mut issues: Vec<ApiKeysIssue> = Vec::new();
+    let mut dry_run_json = None;
 
     // Create events iterator once
     let mut events = stdin.events();
@@ -224,7 +578,15 @@ pub fn configure_api_keys_interactive(api_keys_path: &Utf8PathBuf) -> Result<()>
                     .filter(|p| p.selected)
                     .cloned()
                     .collect();
-                draw_select_default(&mut stdout, &selected_providers, default_provider_index)?;
+                draw_select_default(
+                    &mut stdout,
+                    &selected_providers,
+                    default_provider_index,
+                    &issues,
+                )?;
+            }
+            ConfigStep::Confirm(pending) => {
+                draw_confirm(&mut stdout, &config.diff(pending), dry_run)?;
             }
             ConfigStep::Done => {
                 running = false;
@@ -305,6 +667,7 @@ pub fn configure_api_keys_interactive(api_keys_path: &Utf8PathBuf) -> Result<()>
                             // Go back
                             step = ConfigStep::SelectProviders;
                             cursor_pos = 0;
+                            issues.clear();
                         }
                         Event::Key(Key::Up) => {
                             if default_provider_index > 0 {
@@ -319,9 +682,31 @@ pub fn configure_api_keys_interactive(api_keys_path: &Utf8PathBuf) -> Result<()>
                             }
                         }
                         Event::Key(Key::Char('\n')) | Event::Key(Key::Char('s')) => {
-                            // Save configuration
-                            save_configuration(&mut config, &all_providers)?;
-                            config.save(api_keys_path)?;
+                            // Stage the configuration for review
+                            let default = all_providers
+                                .iter()
+                                .filter(|p| p.selected)
+                                .nth(default_provider_index)
+                                .map(|p| p.model);
+                            let mut pending = config.clone();
+                            save_configuration(&mut pending, &all_providers, default)?;
+                            // Stay here, listing the problems, until they are fixed
+                            issues = pending.validate();
+                            if issues.is_empty() {
+                                step = ConfigStep::Confirm(Box::new(pending));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                ConfigStep::Confirm(pending) => {
+                    match evt {
+                        Event::Key(Key::Char('q')) | Event::Key(Key::Esc) => {
+                            // Go back to default selection
+                            step = ConfigStep::SelectDefaultProvider;
+                        }
+                        Event::Key(Key::Char('\n')) | Event::Key(Key::Char('y')) => {
+                            dry_run_json = finish_api_keys(pending, api_keys_path, dry_run)?;
                             step = ConfigStep::Done;
                         }
                         _ => {}
@@ -342,7 +727,7 @@ pub fn configure_api_keys_interactive(api_keys_path: &Utf8PathBuf) -> Result<()>
     )?;
     stdout.flush()?;
 
-    Ok(())
+    Ok(dry_run_json)
 }
 
 /// Draw Step 1: Select Providers
@@ -543,6 +928,7 @@ fn draw_select_default<W: Write>(
     stdout: &mut W,
     providers: &[ProviderMenuItem],
     cursor: usize,
+    issues: &[ApiKeysIssue],
 ) -> Result<()> {
     write!(stdout, "{}{}", clear::All, cursor::Goto(1, 1))?;
 
@@ -603,6 +989,20 @@ fn draw_select_default<W: Write>(
         )?;
     }
 
+    // Problems refusing the save
+    if !issues.is_empty() {
+        write!(stdout, "\r\n")?;
+    }
+    for issue in issues {
+        write!(
+            stdout,
+            "{}✗ {}{}\r\n",
+            color::Fg(color::Red),
+            issue,
+            style::Reset
+        )?;
+    }
+
     write!(stdout, "\r\n")?;
     write!(
         stdout,
@@ -616,20 +1016,92 @@ fn draw_select_default<W: Write>(
     Ok(())
 }
 
+/// Draw the confirmation screen with the pending changes
+fn draw_confirm<W: Write>(stdout: &mut W, changes: &[String], dry_run: bool) -> Result<()> {
+    write!(stdout, "{}{}", clear::All, cursor::Goto(1, 1))?;
+
+    // Title
+    write!(
+        stdout,
+        "{}{}╔══════════════════════════════════════════════════════════════════════╗\r\n",
+        color::Fg(color::Cyan),
+        style::Bold
+    )?;
+    write!(
+        stdout,
+        "║  {}🔧 Review Changes{}                                                 ║\r\n",
+        color::Fg(color::Yellow),
+        color::Fg(color::Cyan)
+    )?;
+    write!(
+        stdout,
+        "╚══════════════════════════════════════════════════════════════════════╝{}\r\n",
+        style::Reset
+    )?;
+    write!(stdout, "\r\n")?;
+
+    if changes.is_empty() {
+        write!(
+            stdout,
+            "{}No provider or default changes{}\r\n",
+            color::Fg(color::LightBlack),
+            style::Reset
+        )?;
+    }
+    for change in changes {
+        let fg = match change.chars().next() {
+            Some('+') => format!("{}", color::Fg(color::Green)),
+            Some('-') => format!("{}", color::Fg(color::Red)),
+            _ => format!("{}", color::Fg(color::Yellow)),
+        };
+        write!(stdout, "  {}{}{}\r\n", fg, change, style::Reset)?;
+    }
+
+    let action = if dry_run {
+        "print the resulting JSON (dry run)"
+    } else {
+        "save configuration"
+    };
+    write!(stdout, "\r\n")?;
+    write!(
+        stdout,
+        "{}{}Press Enter to {} | ESC to go back{}",
+        color::Fg(color::LightBlack),
+        style::Italic,
+        action,
+        style::Reset
+    )?;
+
+    stdout.flush()?;
+    Ok(())
+}
+
 /// Save the configuration
-fn save_configuration(config: &mut ApiKeysJson, providers: &[ProviderMenuItem]) -> Result<()> {
+fn save_configuration(
+    config: &mut ApiKeysJson,
+    providers: &[ProviderMenuItem],
+    default: Option<LlmModel>,
+) -> Result<()> {
+    if let Some(model) = default {
+        config.default_provider = Some(model.provider_key().to_string());
+    }
+
     for provider in providers {
         if !provider.selected {
             // Disable non-selected providers
-            let key = get_provider_key(provider.model);
-            if let Some(cfg) = config.providers.get_mut(key) {
-                cfg.entity.clone().unwrap().enabled = false;
+            let key = provider.model.provider_key();
+            if let Some(entity) = config
+                .providers
+                .get_mut(key)
+                .and_then(|cfg| cfg.entity.as_mut())
+            {
+                entity.enabled = false;
             }
             continue;
         }
 
         // Create configuration for selected providers
-        let key = get_provider_key(provider.model);
+        let key = provider.model.provider_key();
 
         let api_key = if !matches!(provider.model, LlmModel::OllamaLocal) {
             Some(format!("${{{}}}", get_env_var_name(provider.model)))
@@ -647,3 +1119,182 @@ fn save_configuration(config: &mut ApiKeysJson, providers: &[ProviderMenuItem])
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_missing_default_model_is_named_in_load_error() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-api-keys-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.join("api-keys.json")).unwrap();
+        let mut entity = serde_json::to_value(LlmModel::OpenAi.default_entity()).unwrap();
+        entity.as_object_mut().unwrap().remove("default_model");
+        let file = serde_json::json!({
+            "providers": { "openai": { "api_key": "${OPENAI_API_KEY}", "entity": entity } }
+        });
+        std::fs::write(&path, file.to_string()).unwrap();
+
+        assert_eq!(
+            entity_field_errors(&file),
+            [ApiKeysFieldError {
+                provider: "openai".to_string(),
+                field: "entity.default_model".to_string(),
+                problem: "is missing".to_string(),
+            }]
+        );
+        let error = ApiKeysJson::load(&path).unwrap_err().to_string();
+        assert!(
+            error.contains("provider \"openai\": entity.default_model is missing"),
+            "{}",
+            error
+        );
+        assert!(error.contains("init llm-api-keys"), "{}", error);
+
+        // An enabled provider whose default model is not among its models is refused by
+        // the same load
+        let mut entity = LlmModel::OpenAi.default_entity();
+        entity.enabled = true;
+        entity.default_model = "gpt-missing".to_string();
+        let file = serde_json::json!({
+            "providers": { "openai": { "api_key": null, "entity": entity } }
+        });
+        std::fs::write(&path, file.to_string()).unwrap();
+        assert!(entity_field_errors(&file).is_empty());
+        let error = ApiKeysJson::load(&path).unwrap_err().to_string();
+        assert!(
+            error.contains("openai's default model \"gpt-missing\" is not among its models"),
+            "{}",
+            error
+        );
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_dry_run_leaves_file_untouched() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-api-keys-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.join("api-keys.json")).unwrap();
+        let current = ApiKeysJson::new();
+        current.save(&path).unwrap();
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        let mut providers = vec![ProviderMenuItem::new(LlmModel::OpenAi, "OpenAI")];
+        providers[0].selected = true;
+        let mut pending = current.clone();
+        save_configuration(&mut pending, &providers, Some(LlmModel::OpenAi)).unwrap();
+
+        let changes = current.diff(&pending);
+        assert!(changes.contains(&format!("+ enable {}", LlmModel::OpenAi.provider_key())));
+        assert!(changes.iter().any(|c| c.starts_with("~ default:")));
+
+        let json = finish_api_keys(&pending, &path, true).unwrap().unwrap();
+        assert_eq!(serde_json::from_str::<ApiKeysJson>(&json).unwrap(), pending);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+
+        assert!(finish_api_keys(&pending, &path, false).unwrap().is_none());
+        assert_eq!(ApiKeysJson::load(&path).unwrap(), pending);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_disabled_default_provider_is_rejected() {
+        // OpenAI was enabled before but is deselected while still chosen as the default
+        let mut current = ApiKeysJson::new();
+        current.providers.insert(
+            LlmModel::OpenAi.provider_key().to_string(),
+            ProviderWithAuth {
+                api_key: None,
+                entity: Some(LlmModel::OpenAi.default_entity()),
+            },
+        );
+        assert!(current.validate().is_empty());
+
+        let mut providers = vec![
+            ProviderMenuItem::new(LlmModel::OllamaLocal, "Ollama"),
+            ProviderMenuItem::new(LlmModel::OpenAi, "OpenAI"),
+        ];
+        providers[0].selected = true;
+        let mut pending = current.clone();
+        save_configuration(&mut pending, &providers, Some(LlmModel::OpenAi)).unwrap();
+        assert_eq!(
+            pending.validate(),
+            [ApiKeysIssue::new(
+                ApiKeysIssueKind::DefaultProviderDisabled,
+                LlmModel::OpenAi.provider_key()
+            )]
+        );
+
+        // Models are checked on every enabled provider
+        let ollama = LlmModel::OllamaLocal.provider_key();
+        let entity = pending
+            .providers
+            .get_mut(ollama)
+            .and_then(|p| p.entity.as_mut())
+            .unwrap();
+        entity.default_model = "missing".to_string();
+        pending.default_provider = Some(ollama.to_string());
+        assert_eq!(
+            pending.validate(),
+            [ApiKeysIssue {
+                model: Some("missing".to_string()),
+                ..ApiKeysIssue::new(ApiKeysIssueKind::DefaultModelNotListed, ollama)
+            }]
+        );
+        let entity = pending
+            .providers
+            .get_mut(ollama)
+            .and_then(|p| p.entity.as_mut())
+            .unwrap();
+        entity.models.clear();
+        assert_eq!(
+            pending.validate()[0].to_string(),
+            format!("{} is enabled but lists no models", ollama)
+        );
+    }
+
+    #[test]
+    fn test_provider_key_aliases_resolve_on_load() {
+        for key in ["OpenAI", "openai", "open_ai", "Open-AI"] {
+            assert_eq!(canonical_provider_key(key), Some("openai"));
+        }
+        assert_eq!(canonical_provider_key("AkashChat"), Some("akash_chat"));
+        assert_eq!(canonical_provider_key("ollama"), Some("ollama_local"));
+        assert_eq!(canonical_provider_key("KimiResearch"), Some("kimi"));
+        assert_eq!(canonical_provider_key("Venice"), Some("venice"));
+        assert_eq!(canonical_provider_key("openrouter"), None);
+
+        let dir = std::env::temp_dir().join(format!("cw-ho-api-keys-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = Utf8PathBuf::from_path_buf(dir.join("api-keys.json")).unwrap();
+        let write = |providers: &[&str], default: &str| {
+            let mut config = ApiKeysJson::new();
+            config.providers = providers
+                .iter()
+                .map(|key| (key.to_string(), ProviderWithAuth::default()))
+                .collect();
+            config.default_provider = Some(default.to_string());
+            std::fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+        };
+
+        for spelling in ["OpenAI", "openai"] {
+            write(&[spelling], spelling);
+            let loaded = ApiKeysJson::load(&path).unwrap();
+            assert_eq!(
+                loaded.providers.keys().collect::<Vec<_>>(),
+                [LlmModel::OpenAi.provider_key()]
+            );
+            assert_eq!(loaded.default_provider.as_deref(), Some("openai"));
+        }
+
+        write(&["OpenAI", "openai"], "openai");
+        assert!(ApiKeysJson::load(&path).is_err());
+
+        write(&["openrouter"], "openai");
+        let err = format!("{:#}", ApiKeysJson::load(&path).unwrap_err());
+        assert!(err.contains("unknown provider \"openrouter\""), "{}", err);
+        assert!(err.contains("akash_chat"), "{}", err);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -1,30 +1,54 @@
 pub mod api_keys;
 pub mod env;
 
+use std::net::SocketAddr;
+
 use crate::commonware::error::{CommonwareNetworkError, CommonwareNetworkResult};
+use crate::commonware::identity::NodePubkey;
+use crate::constants::*;
 use crate::prelude::*;
+use crate::traits::NetworkConfigTrait;
 use crate::types::cw_ho::network::v1::{ChannelConfig, NetworkLimits};
 
-impl crate::traits::NetworkConfigTrait for NetworkConfig {
+impl HoConfig {
+    /// Copy safe to share: the node's private key is dropped. Provider api keys live in
+    /// the api keys file, see [`ApiKeysJson::redacted`].
+    pub fn redacted(&self) -> Self {
+        let mut redacted = self.clone();
+        if let Some(identity) = redacted.identity.as_mut() {
+            identity.private_key = None;
+        }
+        redacted
+    }
+}
+
+impl NetworkConfigTrait for NetworkConfig {
     /// Validate the network config.
     fn validate(&self) -> CommonwareNetworkResult<()> {
-        if self.channels.is_none() {};
-        if self.connection_timeout_ms < 100 {}
-        // Basic validation - could be expanded
-        if self.listen_port == 0 {
+        check_listen_port(self.listen_port)?;
+        if self.listen_address.is_empty() {
             return Err(CommonwareNetworkError::ConfigError(
-                "Listen port must be non-zero".to_string(),
+                "Listen address must be set".to_string(),
             ));
         }
-        if self.listen_address == "" {};
+        check_connection_timeout(self.connection_timeout_ms)?;
+        for peer in &self.bootstrap_peers {
+            parse_peer(peer)?;
+        }
 
         if let Some(l) = self.limits {
-            if l.connection_timeout < 100 || l.max_message_size > 100000000 || l.max_peers > 10 {
+            check_max_peers(l.max_peers)?;
+            if l.connection_timeout < MIN_CONNECTION_TIMEOUT_MS as u64
+                || l.max_message_size > 100000000
+            {
                 return Err(CommonwareNetworkError::ConfigError(
                     "Issue with nodes ports, please update".to_string(),
                 ));
             }
         }
+        if let Some(channels) = self.channels {
+            check_channel_buffers(&channels)?;
+        }
         Ok(())
     }
 
@@ -40,6 +64,10 @@ impl crate::traits::NetworkConfigTrait for NetworkConfig {
         &self.listen_address
     }
 
+    fn max_peers(&self) -> u32 {
+        self.limits().max_peers
+    }
+
     fn connection_timeout_ms(&self) -> u32 {
         self.connection_timeout_ms
     }
@@ -48,6 +76,11 @@ impl crate::traits::NetworkConfigTrait for NetworkConfig {
         self.enable_discovery
     }
 
+    fn node_stale_after_secs(&self) -> u64 {
+        self.node_stale_after_secs
+            .unwrap_or(crate::constants::DEFAULT_NODE_STALE_AFTER_SECS)
+    }
+
     fn from_toml(&self) -> toml::Table {
         // Basic TOML representation
         let mut table = toml::Table::new();
@@ -85,12 +118,17 @@ impl crate::traits::NetworkConfigTrait for NetworkConfig {
             node_type: NodeType::Executor.into(),
             bootstrap_peers: Default::default(),
             known_peers: Default::default(),
-            listen_port: 69699,
+            listen_port: DEFAULT_LISTEN_PORT.into(),
             listen_address: "127.0.0.1".to_owned(),
-            connection_timeout_ms: 1313131313,
+            connection_timeout_ms: DEFAULT_CONNECTION_TIMEOUT_MS,
             enable_discovery: true,
-            limits: Some(NetworkLimits::default()),
+            limits: Some(NetworkLimits {
+                max_message_size: MAX_MESSAGE_SIZE as u32,
+                max_peers: MAX_PEERS,
+                connection_timeout: DEFAULT_CONNECTION_TIMEOUT_MS.into(),
+            }),
             channels: Some(ChannelConfig::new()),
+            node_stale_after_secs: None,
         }
     }
 
@@ -99,6 +137,182 @@ impl crate::traits::NetworkConfigTrait for NetworkConfig {
     }
 }
 
+impl NetworkConfig {
+    /// Bootstrap peers configured with their public key, skipping entries that give
+    /// an address alone or do not parse
+    pub fn bootstrap_nodes(&self) -> Vec<(NodePubkey, SocketAddr)> {
+        self.bootstrap_peers
+            .iter()
+            .filter_map(|peer| match parse_peer(peer) {
+                Ok((Some(key), address)) => Some((key, address)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Chained construction of a [`NetworkConfig`], starting from valid defaults. Setters
+/// reject bad values as they are given and [`build`](Self::build) validates the whole.
+#[derive(Debug, Clone)]
+pub struct NetworkConfigBuilder {
+    config: NetworkConfig,
+}
+
+impl Default for NetworkConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetworkConfigBuilder {
+    pub fn new() -> Self {
+        Self {
+            config: NetworkConfig::new(),
+        }
+    }
+
+    pub fn node_type(mut self, node_type: NodeType) -> Self {
+        self.config.node_type = node_type.into();
+        self
+    }
+
+    pub fn listen_address(mut self, address: impl Into<String>) -> Self {
+        self.config.listen_address = address.into();
+        self
+    }
+
+    pub fn listen_port(mut self, port: u32) -> CommonwareNetworkResult<Self> {
+        check_listen_port(port)?;
+        self.config.listen_port = port;
+        Ok(self)
+    }
+
+    pub fn max_peers(mut self, max_peers: u32) -> CommonwareNetworkResult<Self> {
+        check_max_peers(max_peers)?;
+        self.config
+            .limits
+            .get_or_insert_with(Default::default)
+            .max_peers = max_peers;
+        Ok(self)
+    }
+
+    /// Peers dialed at startup, each a `host:port` socket address, prefixed with the
+    /// peer's hex public key and `@` to dial it and admit it past `max_peers`
+    pub fn bootstrap_peers<I, S>(mut self, peers: I) -> CommonwareNetworkResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let peers: Vec<String> = peers.into_iter().map(Into::into).collect();
+        for peer in &peers {
+            parse_peer(peer)?;
+        }
+        self.config.bootstrap_peers = peers;
+        Ok(self)
+    }
+
+    pub fn connection_timeout_ms(mut self, timeout_ms: u32) -> CommonwareNetworkResult<Self> {
+        check_connection_timeout(timeout_ms)?;
+        self.config.connection_timeout_ms = timeout_ms;
+        Ok(self)
+    }
+
+    pub fn enable_discovery(mut self, enabled: bool) -> Self {
+        self.config.enable_discovery = enabled;
+        self
+    }
+
+    pub fn node_stale_after_secs(mut self, secs: u64) -> Self {
+        self.config.node_stale_after_secs = Some(secs);
+        self
+    }
+
+    /// Buffer sizes of the discovery, task, state and health channels
+    pub fn channels(mut self, channels: ChannelConfig) -> CommonwareNetworkResult<Self> {
+        check_channel_buffers(&channels)?;
+        self.config.channels = Some(channels);
+        Ok(self)
+    }
+
+    /// The config, once it passes [`NetworkConfigTrait::validate`]
+    pub fn build(self) -> CommonwareNetworkResult<NetworkConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+fn check_listen_port(port: u32) -> CommonwareNetworkResult<()> {
+    if port == 0 || port > u16::MAX as u32 {
+        return Err(CommonwareNetworkError::ConfigError(format!(
+            "Listen port {} must be between 1 and {}",
+            port,
+            u16::MAX
+        )));
+    }
+    Ok(())
+}
+
+fn check_max_peers(max_peers: u32) -> CommonwareNetworkResult<()> {
+    if max_peers == 0 || max_peers > MAX_PEERS {
+        return Err(CommonwareNetworkError::ConfigError(format!(
+            "Max peers {} must be between 1 and {}",
+            max_peers, MAX_PEERS
+        )));
+    }
+    Ok(())
+}
+
+fn check_connection_timeout(timeout_ms: u32) -> CommonwareNetworkResult<()> {
+    if !(MIN_CONNECTION_TIMEOUT_MS..=MAX_CONNECTION_TIMEOUT_MS).contains(&timeout_ms) {
+        return Err(CommonwareNetworkError::ConfigError(format!(
+            "Connection timeout {}ms must be between {}ms and {}ms",
+            timeout_ms, MIN_CONNECTION_TIMEOUT_MS, MAX_CONNECTION_TIMEOUT_MS
+        )));
+    }
+    Ok(())
+}
+
+fn check_channel_buffers(channels: &ChannelConfig) -> CommonwareNetworkResult<()> {
+    for (name, buffer) in [
+        ("discovery_buffer", channels.discovery_buffer),
+        ("task_buffer", channels.task_buffer),
+        ("state_buffer", channels.state_buffer),
+        ("health_buffer", channels.health_buffer),
+    ] {
+        if buffer == 0 {
+            return Err(CommonwareNetworkError::ConfigError(format!(
+                "Channel {} must be at least 1",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A bootstrap peer entry, `host:port` or `<hex public key>@host:port`. Only entries
+/// naming their key identify the peer behind the address.
+fn parse_peer(peer: &str) -> CommonwareNetworkResult<(Option<NodePubkey>, SocketAddr)> {
+    let (key, address) = match peer.split_once('@') {
+        Some((key, address)) => {
+            let key = NodePubkey::from_hex(key).ok_or_else(|| {
+                CommonwareNetworkError::ConfigError(format!(
+                    "Bootstrap peer {:?} does not start with a hex public key",
+                    peer
+                ))
+            })?;
+            (Some(key), address)
+        }
+        None => (None, peer),
+    };
+    let address = address.parse().map_err(|_| {
+        CommonwareNetworkError::ConfigError(format!(
+            "Bootstrap peer {:?} is not a host:port address",
+            peer
+        ))
+    })?;
+    Ok((key, address))
+}
+
 impl ChannelConfig {
     fn new() -> Self {
         Self {
@@ -108,4 +322,176 @@ impl ChannelConfig {
             health_buffer: 50,
         }
     }
+
+    /// Buffer size of each channel id the network manager registers: discovery 0,
+    /// tasks 1, state 2, health 3
+    pub fn buffers(&self) -> [(u8, usize); 4] {
+        [
+            (0, self.discovery_buffer as usize),
+            (1, self.task_buffer as usize),
+            (2, self.state_buffer as usize),
+            (3, self.health_buffer as usize),
+        ]
+    }
+}
+
+impl LoggingConfig {
+    /// Info level, daily-rotated file under `<home>/logs`
+    pub fn new() -> Self {
+        Self {
+            level: "info".to_string(),
+            file: Some(crate::constants::LOG_FILE_PATH.to_string()),
+            format: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::LlmModelTrait;
+
+    #[test]
+    fn test_builder_defaults_are_valid_and_bad_values_rejected() {
+        let config = NetworkConfigBuilder::new().build().unwrap();
+        assert_eq!(config.listen_port, u32::from(DEFAULT_LISTEN_PORT));
+        assert_eq!(config.max_peers(), MAX_PEERS);
+        assert!(config.validate().is_ok());
+
+        assert!(NetworkConfigBuilder::new().listen_port(69699).is_err());
+        assert!(NetworkConfigBuilder::new().listen_port(0).is_err());
+        assert!(NetworkConfigBuilder::new()
+            .max_peers(MAX_PEERS + 1)
+            .is_err());
+        assert!(NetworkConfigBuilder::new()
+            .bootstrap_peers(["10.0.0.7"])
+            .is_err());
+        assert!(NetworkConfigBuilder::new()
+            .connection_timeout_ms(0)
+            .is_err());
+
+        let config = NetworkConfigBuilder::new()
+            .node_type(NodeType::Coordinator)
+            .listen_port(4000)
+            .and_then(|b| b.max_peers(3))
+            .and_then(|b| b.bootstrap_peers(["10.0.0.7:26656", "[::1]:26656"]))
+            .and_then(NetworkConfigBuilder::build)
+            .unwrap();
+        assert_eq!(config.listen_port, 4000);
+        assert_eq!(config.max_peers(), 3);
+        assert_eq!(config.bootstrap_peers.len(), 2);
+        // Only entries naming their key identify a bootstrap node
+        assert!(config.bootstrap_nodes().is_empty());
+
+        let key = crate::commonware::identity::NodePrivKey::from_seed(9).id();
+        let config = NetworkConfigBuilder::new()
+            .bootstrap_peers([
+                format!("{}@10.0.0.9:26656", hex::encode(key.0.to_vec())),
+                "10.0.0.7:26656".to_string(),
+            ])
+            .and_then(NetworkConfigBuilder::build)
+            .unwrap();
+        assert_eq!(
+            config.bootstrap_nodes(),
+            [(key, "10.0.0.9:26656".parse().unwrap())]
+        );
+        assert!(NetworkConfigBuilder::new()
+            .bootstrap_peers(["not-a-key@10.0.0.9:26656"])
+            .is_err());
+
+        // Fields set directly are still caught at build
+        let mut builder = NetworkConfigBuilder::new();
+        builder.config.listen_address.clear();
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn test_redacted_config_drops_secrets_keeps_structure() {
+        let private_key = vec![0xAB; 32];
+        let config = HoConfig {
+            network: Some(NetworkConfigBuilder::new().build().unwrap()),
+            identity: Some(NodeIdentity {
+                host: "10.0.0.7".to_string(),
+                public_key: Some(vec![0xCD; 32]),
+                private_key: Some(private_key.clone()),
+                ..Default::default()
+            }),
+            llm: Some(LlmRouterConfig {
+                entities: vec![LlmModel::OpenAi.default_entity()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let redacted = config.redacted();
+        let identity = redacted.identity.as_ref().unwrap();
+        assert_eq!(identity.private_key, None);
+        assert_eq!(identity.public_key, Some(vec![0xCD; 32]));
+        assert_eq!(identity.host, "10.0.0.7");
+        assert_eq!(redacted.network, config.network);
+        assert_eq!(redacted.llm, config.llm);
+        let dumped = toml::to_string_pretty(&redacted).unwrap();
+        assert!(!dumped.contains("private_key"), "{}", dumped);
+
+        let with_key = |key: &str| ProviderWithAuth {
+            api_key: Some(key.to_string()),
+            entity: Some(LlmModel::OpenAi.default_entity()),
+        };
+        let mut api_keys = ApiKeysJson::new();
+        api_keys.providers = [
+            ("openai".to_string(), with_key("sk-live-secret")),
+            ("anthropic".to_string(), with_key("${ANTHROPIC_API_KEY}")),
+        ]
+        .into();
+        api_keys.default_provider = Some("openai".to_string());
+        let dumped = serde_json::to_string(&api_keys.redacted()).unwrap();
+        assert!(!dumped.contains("sk-live-secret"), "{}", dumped);
+        assert!(dumped.contains(REDACTED));
+        assert!(dumped.contains("${ANTHROPIC_API_KEY}"));
+        let redacted = api_keys.redacted();
+        assert_eq!(redacted.default_provider.as_deref(), Some("openai"));
+        assert_eq!(
+            redacted.providers["openai"].entity,
+            api_keys.providers["openai"].entity
+        );
+    }
+
+    #[test]
+    fn test_task_channel_is_buffered_by_configured_task_buffer() {
+        use crate::traits::network::NetworkMessageTrait;
+        use crate::types::cw_ho::network::v1::TaskCoordination;
+
+        let channels = ChannelConfig {
+            task_buffer: 7,
+            ..ChannelConfig::new()
+        };
+        let config = NetworkConfigBuilder::new()
+            .channels(channels)
+            .and_then(NetworkConfigBuilder::build)
+            .unwrap();
+
+        // Task messages go out on the channel registered with the task buffer
+        let task = NetworkMessage {
+            message_type: Some(MessageType::TaskCoordination(TaskCoordination::default())),
+        };
+        let task_channel = task.channel().unwrap();
+        let buffers = config.channels.unwrap().buffers();
+        assert_eq!(
+            buffers.iter().find(|(channel, _)| *channel == task_channel),
+            Some(&(task_channel, 7))
+        );
+
+        let empty = ChannelConfig {
+            health_buffer: 0,
+            ..ChannelConfig::new()
+        };
+        let err = NetworkConfigBuilder::new().channels(empty).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            CommonwareNetworkError::ConfigError("Channel health_buffer must be at least 1".into())
+                .to_string()
+        );
+        let mut config = NetworkConfigBuilder::new().build().unwrap();
+        config.channels = Some(empty);
+        assert!(config.validate().is_err());
+    }
 }
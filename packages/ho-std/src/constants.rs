@@ -5,8 +5,15 @@ use std::collections::HashMap;
 //
 pub const CONFIG_FILE_NAME: &str = "config.toml";
 pub const LLM_API_KEYS_FILE: &str = "api-keys.json";
+/// Stands in for a secret in configs printed for sharing
+pub const REDACTED: &str = "<redacted>";
 pub const ENV_VARIABLES_FILE: &str = ".env";
 pub const DATA_FOLDER_NAME: &str = "memories";
+/// Under `--home`, where large task outputs are written
+pub const ARTIFACTS_FOLDER_NAME: &str = "artifacts";
+/// Task result strings longer than this move to an artifact file when an artifact dir is set
+pub const ARTIFACT_INLINE_MAX_BYTES: usize = 4 * 1024;
+pub const LOG_FILE_PATH: &str = "logs/cw-ho.log";
 pub const OPENAI_API_KEY: &str = "OPENAI_API_KEY";
 pub const ANTHROPIC_API_KEY: &str = "ANTHROPIC_API_KEY";
 pub const GROK_API_KEY: &str = "GROK_API_KEY";
@@ -22,6 +29,10 @@ pub const AKASH_CHAT: &str = "akashchat";
 pub const KIMI: &str = "kimi";
 pub const QUEN: &str = "qwen";
 pub const VENICE: &str = "venice";
+/// Llm request timeout written into new configs and entities
+pub const DEFAULT_LLM_TIMEOUT_SECS: u64 = 60;
+/// Longest llm request timeout a config may set, ten minutes
+pub const MAX_LLM_TIMEOUT_SECS: u64 = 600;
 
 pub const KIMI_RESEARCH_MODELS: &[&str] = &["kimi_research"];
 pub const GROK_MODELS: &[&str] = &["grok"];
@@ -44,37 +55,126 @@ pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
 pub const ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com/v1";
 pub const ANTHROPIC_MESSAGE_URL: &str = "https://api.anthropic.com/v1/messages";
 
+// Largest completion (in tokens) each provider accepts per request
+pub const AKASH_CHAT_MAX_OUTPUT_TOKENS: u32 = 8_192;
+pub const KIMI_RESEARCH_MAX_OUTPUT_TOKENS: u32 = 8_192;
+pub const GROK_MAX_OUTPUT_TOKENS: u32 = 16_384;
+pub const OLLAMA_LOCAL_MAX_OUTPUT_TOKENS: u32 = 4_096;
+pub const OPENAI_MAX_OUTPUT_TOKENS: u32 = 16_384;
+pub const ANTHROPIC_MAX_OUTPUT_TOKENS: u32 = 8_192;
+pub const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 4_096;
+// Sampling temperature of requests that set none
+pub const DEFAULT_TEMPERATURE: u32 = 1;
+// Estimated tokens of stored conversation replayed into an in-session request
+pub const SESSION_HISTORY_TOKEN_BUDGET: u32 = 8_000;
+// Bucket bounds of the prompt and completion token histograms on /metrics
+pub const TOKEN_HISTOGRAM_BOUNDS: &[u64] = &[64, 256, 1_024, 4_096, 16_384, 65_536];
+// Bucket bounds of the per-provider latency histograms on /metrics, in ms
+pub const LATENCY_HISTOGRAM_BOUNDS_MS: &[u64] =
+    &[100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000];
+
 pub const OLLAMA_LOCAL_HOST: &str = "localhost";
 pub const OLLAMA_LOCAL_PORT: u16 = 11_434;
 // the default recursion depth is deliberately modest – deep recursion
 // can explode memory usage if the rest of the pipeline isn’t tuned.
 pub const DEFAULT_RECURSION_DEPTH: u32 = 2;
-pub const GOLDEN_RATIO: f32 = 1.618033988749894;
+/// φ at full f64 precision, the single source for every golden-ratio computation
+pub const GOLDEN_RATIO: f64 = 1.618_033_988_749_895;
+/// Default allowed deviation of an allocation's fast/slow ratio from φ
+pub const GOLDEN_RATIO_TOLERANCE: f64 = 0.01;
+/// Densest sphere packing, the compression target advertised for snapshots
+pub const KEPLER_PACKING_DENSITY: f64 = 0.74048;
 pub const TETRAHEDRAL_VERTICES: usize = 4;
 pub const FRACTAL_MAX_DEPTH: u32 = 10;
+/// Similarity between consecutive sandloop outputs treated as converged
+pub const DEFAULT_SANDLOOP_CONVERGENCE: f64 = 0.9;
 pub const PROTOCOL_VERSION: u8 = 1;
+/// Oldest protocol version this node still speaks; peers whose range misses ours are dropped
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
+/// Capability a peer announces to receive `Request`s and `Response`s
+pub const CAPABILITY_RPC: &str = "rpc";
+/// Capability a peer announces to receive `GossipRelay`s
+pub const CAPABILITY_GOSSIP: &str = "gossip";
 pub const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024; // 10 MiB;
+pub const NETWORK_MESSAGE_NAMESPACE: &[u8] = b"cw-ho-network-message";
+pub const DEFAULT_NODE_STALE_AFTER_SECS: u64 = 120;
+pub const DEFAULT_LISTEN_PORT: u16 = 26_656;
+/// Most peers a node may hold connections to
+pub const MAX_PEERS: u32 = 10;
+pub const DEFAULT_CONNECTION_TIMEOUT_MS: u32 = 5_000;
+/// Bounds on a configured connection timeout, the upper one being five minutes
+pub const MIN_CONNECTION_TIMEOUT_MS: u32 = 100;
+pub const MAX_CONNECTION_TIMEOUT_MS: u32 = 300_000;
+/// How often connected peers are pinged to measure round-trip time
+pub const PEER_PING_INTERVAL_SECS: u64 = 15;
+/// How long a request sent to a peer waits for its response
+pub const PEER_REQUEST_TIMEOUT_SECS: u64 = 30;
+/// Hops a gossiped announcement travels past its originator's direct peers
+pub const GOSSIP_TTL: u32 = 6;
+/// Originators whose newest gossiped announcement is remembered to drop repeats and
+/// older ones, the earliest seen forgotten first
+pub const GOSSIP_SEEN_CAPACITY: usize = 1_024;
+/// Age past which a gossiped announcement is dropped as stale, bounding replays of
+/// announcements whose originator was forgotten
+pub const GOSSIP_MAX_AGE_MS: u64 = 5 * 60 * 1_000;
+/// How often a node with task retention configured sweeps expired task states
+pub const TASK_SWEEP_INTERVAL_SECS: u64 = 300;
+/// Most clients streaming topology changes at once
+pub const MAX_TOPOLOGY_SUBSCRIBERS: usize = 16;
+/// Topology diffs buffered per stream; a client falling further behind is dropped
+pub const TOPOLOGY_STREAM_BUFFER: usize = 64;
+/// Error recorded on tasks that outlive their timeout
+pub const TASK_TIMED_OUT_ERROR: &str = "timed out";
+/// Prefix of the error recorded on tasks whose execution panicked
+pub const TASK_PANICKED_ERROR: &str = "executor panicked";
+/// Error recorded on tasks cancelled because the node is shutting down
+pub const TASK_CANCELLED_ERROR: &str = "cancelled by node shutdown";
+/// How long a stopping node waits for cancelled tasks to store their final state
+pub const TASK_SHUTDOWN_GRACE_SECS: u64 = 5;
 
 // WORKSPACE RELATED
 pub const CNARDIUM_STORAGE: &str = "./data/cnardium";
 pub const WORKSPACE: &str = "../../src";
 pub const WORKSPACE_HOME: &str = "~/CW-AGENT";
 pub const WORKSPACE_ARCHIVE_PATH: &str = "./workspace.tar.gz";
+/// Largest workspace archive transferred to a bootstrapping node unless configured
+pub const DEFAULT_MAX_WORKSPACE_ARCHIVE_BYTES: u64 = 512 * 1024 * 1024;
+/// Entries named when an archive is rejected for its size
+pub const ARCHIVE_LARGEST_ENTRIES: usize = 10;
 
 // TOOLS RELATED
 pub const TOOLS_LINUX_CONFIGURE: &str = "tools/linux/configure.sh";
 pub const TOOLS_SSH_TRANSPORT: &str = "tools/ssh/transport.py";
 pub const TOOLS_METAPROMPT_GENERATOR: &str = "/tools/python/prompt_generator.py";
 
+// HTTP API
+/// Request headers choosing the provider and model of a prompt, overriding the body fields
+pub const LLM_PROVIDER_HEADER: &str = "x-llm-provider";
+pub const LLM_MODEL_HEADER: &str = "x-llm-model";
+/// Correlation id of an HTTP request, taken from the client or generated, and echoed back
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longest client-supplied request id kept; longer ones are replaced
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
 // SSH RELATED
 pub const SSH_JSON_PATH: &str = "priv/ssh-config.json";
+/// Hex node key provisioned onto bootstrapped nodes, relative to the workspace
+pub const NODE_KEY_PATH: &str = "priv/node.key";
 pub const SSH_TEMPLATE_PATH: &str = "templates/ssh-config.json";
 pub const SSH_TEMPLATE_FLAG: &str = "--config templates/ssh-config.json";
+/// Retries of an ssh or scp run that failed on a dropped connection or DNS hiccup
+pub const DEFAULT_SSH_TRANSIENT_RETRIES: u32 = 3;
+/// First and longest wait between those retries
+pub const SSH_RETRY_BASE_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+pub const SSH_RETRY_MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+/// Nodes of the ssh node map registered at once when the config sets no concurrency
+pub const DEFAULT_SSH_REGISTRATION_CONCURRENCY: usize = 8;
 pub const DEFAULT_CONFIG_FILE_PATH: &str = "priv/config.toml";
 
 // COMMANDS
 pub const CMD_BASH: &str = "bash";
 pub const CMD_PYTHON3: &str = "python3";
+pub const CMD_TAR: &str = "tar";
 pub const CMD_WSL: &str = "wsl bash -c";
 
 pub const DEFAULT_PROVIDERS_NODE_ACCESS: &[&str] = &["akash_chat", "anthropic", "grok"];
@@ -135,3 +235,18 @@ pub const FRACTAL_RECURSION: &str = "fractal-recursion";
 pub const GEOMETRIC_VALIDATION: &str = "geometric-validation";
 pub const TETRAHEDRAL_CONNECTIVITY: &str = "tetrahedral-connectivity";
 pub const GOLDEN_RATIO_SCALING: &str = "golden-ratio-scaling";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_ratio_is_phi_at_full_precision() {
+        assert_eq!(GOLDEN_RATIO, (1.0 + 5f64.sqrt()) / 2.0);
+        // φ² = φ + 1
+        assert!((GOLDEN_RATIO * GOLDEN_RATIO - GOLDEN_RATIO - 1.0).abs() < f64::EPSILON * 4.0);
+        // An allocation split exactly at 1/φ sits inside the default tolerance
+        let fast = 1.0 / GOLDEN_RATIO;
+        assert!((fast / (1.0 - fast) - GOLDEN_RATIO).abs() < GOLDEN_RATIO_TOLERANCE);
+    }
+}
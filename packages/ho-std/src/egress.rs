@@ -0,0 +1,192 @@
+//! Egress allowlist: the hosts a node may reach for LLM requests and SSH/SCP
+use crate::error::{HoError, HoResult};
+use crate::prelude::{EgressPolicy, HoConfig, LlmModel, LlmRouterConfig};
+use crate::traits::LlmModelTrait;
+use reqwest::Url;
+use serde_json::Value;
+use tracing::warn;
+
+impl EgressPolicy {
+    /// Policy allowing exactly `patterns`: hosts, `*.domain` wildcards, `*` for any host,
+    /// or URLs whose host is taken. Ports are ignored.
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut patterns: Vec<String> = patterns
+            .into_iter()
+            .filter_map(|p| normalize_pattern(p.as_ref()))
+            .collect();
+        patterns.sort();
+        patterns.dedup();
+        Self { patterns }
+    }
+
+    /// Hosts of every configured provider endpoint, including each provider's built-in one
+    pub fn for_providers(llm: &LlmRouterConfig) -> Self {
+        Self::new(provider_urls(llm))
+    }
+
+    /// The configured allowlist. Without one, the provider endpoints and the host of every
+    /// node in `ssh_config` are allowed.
+    pub fn from_config(config: &HoConfig, ssh_config: Option<&Value>) -> Self {
+        if let Some(egress) = config
+            .egress
+            .as_ref()
+            .filter(|e| !e.allowed_hosts.is_empty())
+        {
+            return Self::new(&egress.allowed_hosts);
+        }
+        let mut hosts = config.llm.as_ref().map(provider_urls).unwrap_or_default();
+        hosts.extend(
+            ssh_config
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .filter_map(|(_, node)| Some(node.get("host")?.as_str()?.to_string())),
+        );
+        Self::new(hosts)
+    }
+
+    /// Normalized patterns, sorted
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn allows(&self, host: &str) -> bool {
+        let host = normalize_host(host);
+        !host.is_empty()
+            && self.patterns.iter().any(|pattern| match pattern.as_str() {
+                "*" => true,
+                pattern => match pattern.strip_prefix("*.") {
+                    Some(domain) => host
+                        .strip_suffix(domain)
+                        .is_some_and(|sub| sub.ends_with('.')),
+                    None => host == pattern,
+                },
+            })
+    }
+
+    /// Reject `host` unless allowed, writing an audit line for every blocked attempt.
+    /// `purpose` names the connection in the log and error, e.g. `llm` or `ssh`.
+    pub fn check(&self, purpose: &str, host: &str) -> HoResult<()> {
+        if self.allows(host) {
+            return Ok(());
+        }
+        warn!(
+            target: "audit",
+            "🚫 Blocked {} egress to {}: host is not on the allowlist",
+            purpose,
+            host
+        );
+        Err(HoError::Network(format!(
+            "{} egress to {} blocked: host is not on the allowlist [{}]",
+            purpose,
+            host,
+            self.patterns.join(", ")
+        )))
+    }
+
+    /// [`check`](Self::check) the host of `url`
+    pub fn check_url(&self, purpose: &str, url: &Url) -> HoResult<()> {
+        self.check(purpose, url.host_str().unwrap_or_default())
+    }
+}
+
+/// Configured base urls plus the built-in endpoint of each configured provider
+fn provider_urls(llm: &LlmRouterConfig) -> Vec<String> {
+    llm.entities
+        .iter()
+        .flat_map(|entity| {
+            let builtin = LlmModel::from_str_name(&entity.name).and_then(|m| m.default_base_url());
+            [Some(entity.base_url.clone()), builtin]
+        })
+        .flatten()
+        .filter(|url| !url.is_empty())
+        .collect()
+}
+
+fn normalize_pattern(pattern: &str) -> Option<String> {
+    let pattern = pattern.trim();
+    let host = match pattern.contains("://") {
+        true => Url::parse(pattern).ok()?.host_str()?.to_string(),
+        // drop a `:port` suffix, leaving bare IPv6 addresses alone
+        false => match pattern.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') && port.parse::<u16>().is_ok() => {
+                host.to_string()
+            }
+            _ => pattern.to_string(),
+        },
+    };
+    let host = normalize_host(&host);
+    (!host.is_empty()).then_some(host)
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{EgressConfig, LlmEntity};
+
+    #[test]
+    fn test_patterns_match_hosts_and_subdomains() {
+        let policy = EgressPolicy::new([
+            "api.openai.com",
+            "*.Example.org",
+            "https://llm.internal:8443/v1",
+            "10.0.0.7:22",
+        ]);
+        assert!(policy.allows("API.openai.com"));
+        assert!(!policy.allows("openai.com"));
+        assert!(policy.allows("eu.models.example.org"));
+        assert!(!policy.allows("example.org"));
+        assert!(!policy.allows("badexample.org"));
+        assert!(policy.allows("llm.internal"));
+        assert!(policy.allows("10.0.0.7"));
+        assert!(!policy.allows(""));
+        assert!(EgressPolicy::new(["*"]).allows("anything.test"));
+
+        let err = policy.check("llm", "evil.test").unwrap_err();
+        assert!(err.to_string().contains("evil.test"));
+        assert!(policy.check("ssh", "10.0.0.7").is_ok());
+    }
+
+    #[test]
+    fn test_defaults_to_providers_and_ssh_nodes() {
+        let custom = LlmEntity {
+            name: LlmModel::Custom.as_str_name().to_string(),
+            base_url: "https://llm.internal.example/v1".to_string(),
+            ..Default::default()
+        };
+        let mut config = HoConfig {
+            llm: Some(LlmRouterConfig {
+                entities: vec![LlmModel::OpenAi.default_entity(), custom],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let ssh = serde_json::json!({"node-1": {"host": "192.168.1.100"}});
+
+        let policy = EgressPolicy::from_config(&config, Some(&ssh));
+        assert!(policy.allows("api.openai.com"));
+        assert!(policy.allows("llm.internal.example"));
+        assert!(policy.allows("192.168.1.100"));
+        assert!(!policy.allows("api.anthropic.com"));
+
+        // An explicit allowlist replaces the defaults
+        config.egress = Some(EgressConfig {
+            allowed_hosts: vec!["*.openai.com".to_string()],
+        });
+        let policy = EgressPolicy::from_config(&config, Some(&ssh));
+        assert!(policy.allows("api.openai.com"));
+        assert!(!policy.allows("192.168.1.100"));
+    }
+}
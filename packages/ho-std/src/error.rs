@@ -12,6 +12,9 @@ pub enum HoError {
     #[error("Network error: {0}")]
     Network(String),
 
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
     #[error("Storage error: {0}")]
     Storage(String),
 
@@ -397,6 +397,8 @@ pub mod custom_llm {
                 }),
                 cost: Some(0.001),
                 latency_ms: Some(150),
+                request_id: None,
+                context: None,
                 // context: todo!(),
             })
         }
@@ -480,22 +482,26 @@ pub mod extension_trait_usage {
     pub fn demonstrate_cosmic_context() {
         println!("🌌 Demonstrating CosmicContext extension traits");
 
-        let context = CosmicContext {
+        let mut context = CosmicContext {
             task_id: "cosmic-task-42".to_string(),
             user_input: "Execute fractal orchestration with golden ratio scaling".to_string(),
             current_step: 1,
             total_steps: 7,
             fractal_level: 3,
-            golden_ratio_state: 1.618.to_string(),
+            golden_ratio_state: String::new(),
             previous_responses: vec![],
             cosmic_metadata: std::collections::HashMap::new(),
         };
+        context.set_golden_ratio_state(crate::constants::GOLDEN_RATIO);
 
         println!("📋 Task ID: {}", context.task_id);
         println!("📝 User Input: {}", context.user_input);
         println!("📊 Steps: {}/{}", context.current_step, context.total_steps);
         println!("🌀 Fractal Level: {}", context.fractal_level);
-        println!("✨ Golden Ratio State: {}", context.golden_ratio_state);
+        println!(
+            "✨ Golden Ratio State: {}",
+            context.golden_ratio_state_f64()
+        );
     }
 
     /// Demonstrates using FractalRequirements extension methods
@@ -511,6 +517,7 @@ pub mod extension_trait_usage {
             fractal_coherence: 0.9,
             expansion_criteria: vec!["complexity".to_string(), "elegance".to_string()],
             context: None,
+            convergence_threshold: None,
         };
 
         println!("🔄 Recursion Depth: {}", requirements.recursion_depth);
@@ -584,6 +591,9 @@ pub mod generic_functions {
             model: "test-model".to_string(),
             context: None,
             llm_config: None,
+            response_format: None,
+            provider: None,
+            tools: Vec::new(),
         };
 
         // Process the request
@@ -28,6 +28,7 @@ pub mod commonware;
 pub mod config;
 pub mod constants;
 pub mod deploy;
+pub mod egress;
 pub mod error;
 pub mod examples;
 pub mod llm;
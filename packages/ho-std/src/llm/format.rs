@@ -0,0 +1,197 @@
+use crate::error::{HoError, HoResult};
+use crate::prelude::{ResponseFormat, ResponseFormatType};
+use serde_json::{json, Value};
+
+impl ResponseFormat {
+    pub fn text() -> Self {
+        Self::default()
+    }
+
+    pub fn json_object() -> Self {
+        Self {
+            format_type: ResponseFormatType::JsonObject.into(),
+            json_schema: None,
+        }
+    }
+
+    pub fn json_schema(schema: &Value) -> Self {
+        Self {
+            format_type: ResponseFormatType::JsonSchema.into(),
+            json_schema: Some(schema.to_string()),
+        }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format_type() != ResponseFormatType::Text
+    }
+
+    /// Parsed schema for `JsonSchema` formats
+    pub fn schema(&self) -> HoResult<Option<Value>> {
+        match (self.format_type(), self.json_schema.as_deref()) {
+            (ResponseFormatType::JsonSchema, Some(schema)) => serde_json::from_str(schema)
+                .map(Some)
+                .map_err(|e| HoError::Llm(format!("invalid response json schema: {}", e))),
+            (ResponseFormatType::JsonSchema, None) => Err(HoError::Llm(
+                "json schema response format without a schema".to_string(),
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// `response_format` body parameter for OpenAI-compatible chat completions
+    pub fn openai_param(&self) -> HoResult<Option<Value>> {
+        Ok(match self.format_type() {
+            ResponseFormatType::Text => None,
+            ResponseFormatType::JsonObject => Some(json!({"type": "json_object"})),
+            ResponseFormatType::JsonSchema => Some(json!({
+                "type": "json_schema",
+                "json_schema": {"name": "response", "schema": self.schema()?},
+            })),
+        })
+    }
+
+    /// System instruction describing the format. Sent to every provider: those without a
+    /// JSON mode rely on it, and OpenAI's JSON mode requires the prompt to mention JSON.
+    pub fn instruction(&self) -> HoResult<Option<String>> {
+        Ok(match self.format_type() {
+            ResponseFormatType::Text => None,
+            ResponseFormatType::JsonObject => Some(
+                "Respond with a single valid JSON object only, without markdown fences or commentary."
+                    .to_string(),
+            ),
+            ResponseFormatType::JsonSchema => Some(format!(
+                "Respond with a single valid JSON value only, without markdown fences or commentary. \
+                 It must satisfy this JSON schema: {}",
+                self.schema()?.unwrap_or_default()
+            )),
+        })
+    }
+
+    /// Check a completion against the format, repairing the common ways models wrap JSON.
+    /// Returns the completion as compact JSON for JSON formats and unchanged for text.
+    pub fn enforce(&self, completion: &str) -> HoResult<String> {
+        if !self.is_json() {
+            return Ok(completion.to_string());
+        }
+        let value = repair_json(completion)
+            .ok_or_else(|| HoError::Llm(format!("completion is not valid JSON: {}", completion)))?;
+        match self.schema()? {
+            Some(schema) => validate_schema(&value, &schema, "$")?,
+            None if !value.is_object() => {
+                return Err(HoError::Llm(format!(
+                    "expected a JSON object, got: {}",
+                    value
+                )))
+            }
+            None => {}
+        }
+        Ok(value.to_string())
+    }
+}
+
+/// Parse JSON out of a completion, tolerating markdown fences and surrounding prose
+pub fn repair_json(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    // ```json ... ``` fences
+    if let Some(start) = text.find("```") {
+        let body = &text[start + 3..];
+        let body = body.strip_prefix("json").unwrap_or(body);
+        if let Some(end) = body.find("```") {
+            if let Ok(value) = serde_json::from_str(body[..end].trim()) {
+                return Some(value);
+            }
+        }
+    }
+    // outermost object or array embedded in prose
+    ['{', '[']
+        .into_iter()
+        .zip(['}', ']'])
+        .filter_map(|(open, close)| Some((text.find(open)?, text.rfind(close)?)))
+        .filter(|(start, end)| start < end)
+        .find_map(|(start, end)| serde_json::from_str(&text[start..=end]).ok())
+}
+
+/// Validate the `type`, `required`, `properties`, `items` and `enum` keywords
+fn validate_schema(value: &Value, schema: &Value, path: &str) -> HoResult<()> {
+    let fail = |reason: String| Err(HoError::Llm(format!("{} {}", path, reason)));
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+        if !matches {
+            return fail(format!("should be {}, got {}", expected, value));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return fail(format!("{} is not one of {:?}", value, allowed));
+        }
+    }
+    if let Some(object) = value.as_object() {
+        for key in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !object.contains_key(key) {
+                return fail(format!("is missing required field {}", key));
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, property) in properties {
+                if let Some(field) = object.get(key) {
+                    validate_schema(field, property, &format!("{}.{}", path, key))?;
+                }
+            }
+        }
+    }
+    if let (Some(items), Some(schema)) = (value.as_array(), schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_schema(item, schema, &format!("{}[{}]", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enforce_repairs_and_validates_json() {
+        let fenced = "Sure! Here it is:\n```json\n{\"name\": \"ergors\", \"stars\": 3}\n```";
+        let object = ResponseFormat::json_object();
+        assert_eq!(
+            object.enforce(fenced).unwrap(),
+            r#"{"name":"ergors","stars":3}"#
+        );
+        assert!(object.enforce("no json here").is_err());
+        assert!(object.enforce("[1, 2]").is_err());
+
+        let schema = ResponseFormat::json_schema(&json!({
+            "type": "object",
+            "required": ["name", "stars"],
+            "properties": {"stars": {"type": "integer"}},
+        }));
+        assert!(schema.enforce(fenced).is_ok());
+        assert!(schema.enforce(r#"{"name": "ergors"}"#).is_err());
+        assert!(schema
+            .enforce(r#"{"name": "ergors", "stars": "three"}"#)
+            .is_err());
+
+        assert_eq!(ResponseFormat::text().enforce("plain").unwrap(), "plain");
+    }
+}
@@ -0,0 +1,296 @@
+use crate::error::{HoError, HoResult};
+use crate::prelude::{HttpHeader, HttpResponse, TlsConfig};
+use crate::traits::HttpBackend;
+use async_trait::async_trait;
+use reqwest::{Certificate, Client, Identity, StatusCode};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tracing::warn;
+
+impl HttpResponse {
+    /// Response with a status and canned body, mostly for fake backends
+    pub fn new(status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: status.as_u16().into(),
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Add a `name: value` header after any received before
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push(HttpHeader {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// The status code, a 500 for one out of range
+    pub fn status(&self) -> StatusCode {
+        u16::try_from(self.status)
+            .ok()
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// First value of header `name`, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_str())
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// HTTP client for a provider, applying its TLS settings. Certificates are always
+/// verified unless `danger_accept_invalid_certs` is set, which is warned about.
+pub fn build_client(timeout: Duration, tls: Option<&TlsConfig>) -> HoResult<Client> {
+    let config = |e: String| HoError::Config(e);
+    let read = |path: &str| {
+        std::fs::read(path).map_err(|e| config(format!("Failed to read {}: {}", path, e)))
+    };
+    let mut builder = Client::builder().timeout(timeout);
+    if let Some(tls) = tls {
+        if let Some(path) = tls.ca_bundle.as_deref() {
+            let certs = Certificate::from_pem_bundle(&read(path)?)
+                .map_err(|e| config(format!("Invalid CA bundle {}: {}", path, e)))?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        match (tls.client_cert.as_deref(), tls.client_key.as_deref()) {
+            (Some(cert), Some(key)) => {
+                // rustls reads the identity from one PEM holding both
+                let mut pem = read(cert)?;
+                pem.extend(read(key)?);
+                let identity = Identity::from_pem(&pem)
+                    .map_err(|e| config(format!("Invalid client certificate {}: {}", cert, e)))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(config(
+                    "client_cert and client_key must be set together".to_string(),
+                ))
+            }
+        }
+        if tls.danger_accept_invalid_certs.unwrap_or(false) {
+            warn!("⚠️ TLS certificate verification is DISABLED for this provider, use only in development");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    }
+    builder
+        .build()
+        .map_err(|e| config(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// Default backend sending requests over a `reqwest::Client`.
+/// Requests under a scoped base url use that scope's client instead.
+#[derive(Debug, Clone)]
+pub struct ReqwestBackend {
+    client: Client,
+    scoped: Vec<(reqwest::Url, Client)>,
+}
+
+/// Whether `url` is at or below `base`: same scheme, host and port, and a path under
+/// base's path at a segment boundary
+fn in_scope(base: &reqwest::Url, url: &reqwest::Url) -> bool {
+    let path = base.path().trim_end_matches('/');
+    base.scheme() == url.scheme()
+        && base.host_str() == url.host_str()
+        && base.port_or_known_default() == url.port_or_known_default()
+        && url
+            .path()
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+impl ReqwestBackend {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            scoped: Vec::new(),
+        }
+    }
+
+    /// Send requests to `base_url`'s origin under its path through `client`. Fails when
+    /// `base_url` is not a valid url.
+    pub fn with_scope(mut self, base_url: &str, client: Client) -> HoResult<Self> {
+        let base = reqwest::Url::parse(base_url)
+            .map_err(|e| HoError::Config(format!("Invalid base url {}: {}", base_url, e)))?;
+        self.scoped.push((base, client));
+        // longest path wins
+        self.scoped
+            .sort_by(|a, b| b.0.path().len().cmp(&a.0.path().len()));
+        Ok(self)
+    }
+
+    fn client_for(&self, url: &reqwest::Url) -> &Client {
+        self.scoped
+            .iter()
+            .find(|(base, _)| in_scope(base, url))
+            .map(|(_, client)| client)
+            .unwrap_or(&self.client)
+    }
+}
+
+#[async_trait]
+impl HttpBackend for ReqwestBackend {
+    async fn send(&self, request: reqwest::Request) -> HoResult<HttpResponse> {
+        let network = |e: reqwest::Error| match e.is_timeout() {
+            true => HoError::Timeout(e.to_string()),
+            false => HoError::Network(e.to_string()),
+        };
+        let response = self
+            .client_for(request.url())
+            .execute(request)
+            .await
+            .map_err(network)?;
+        let status = response.status().as_u16().into();
+        // values that aren't visible ASCII can't be read as text and are left out
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some(HttpHeader {
+                    name: name.as_str().to_string(),
+                    value: value.to_str().ok()?.to_string(),
+                })
+            })
+            .collect();
+        let body = response.bytes().await.map_err(network)?.to_vec();
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls;
+
+    /// Test CA plus a localhost certificate it signed
+    const TESTDATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/llm/testdata");
+
+    /// Answer `ok` over TLS with the CA-signed certificate, returning the server url
+    async fn tls_server() -> String {
+        let pem = |name: &str| std::fs::read(format!("{}/{}", TESTDATA, name)).unwrap();
+        let certs = rustls_pemfile::certs(&mut pem("server.pem").as_slice())
+            .unwrap()
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+        let key = rustls_pemfile::pkcs8_private_keys(&mut pem("server.key").as_slice())
+            .unwrap()
+            .remove(0);
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, rustls::PrivateKey(key))
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // clients that do not trust the CA abort the handshake
+                    if let Ok(mut tls) = acceptor.accept(stream).await {
+                        let mut request = [0u8; 1024];
+                        let _ = tls.read(&mut request).await;
+                        let _ = tls
+                            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok")
+                            .await;
+                        let _ = tls.shutdown().await;
+                    }
+                });
+            }
+        });
+        format!("https://localhost:{}/", port)
+    }
+
+    #[tokio::test]
+    async fn test_custom_ca_is_required_for_self_hosted_provider() {
+        let url = tls_server().await;
+        let timeout = Duration::from_secs(5);
+        let default = build_client(timeout, None).unwrap();
+        assert!(default.get(&url).send().await.is_err());
+
+        let tls = TlsConfig {
+            ca_bundle: Some(format!("{}/ca.pem", TESTDATA)),
+            ..Default::default()
+        };
+        let trusted = build_client(timeout, Some(&tls)).unwrap();
+        let response = trusted.get(&url).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        // the backend only uses the CA-aware client under its base url
+        let backend = ReqwestBackend::new(default)
+            .with_scope(&url, trusted)
+            .unwrap();
+        let request = Client::new().get(&url).build().unwrap();
+        assert_eq!(backend.send(request).await.unwrap().text(), "ok");
+
+        let half_identity = TlsConfig {
+            client_cert: Some(format!("{}/server.pem", TESTDATA)),
+            ..Default::default()
+        };
+        assert!(build_client(timeout, Some(&half_identity)).is_err());
+    }
+
+    #[test]
+    fn test_scope_matches_origin_and_path_segments() {
+        let backend = ReqwestBackend::new(Client::new())
+            .with_scope("https://llm.internal/v1", Client::new())
+            .unwrap();
+        let scoped = |url: &str| {
+            std::ptr::eq(
+                backend.client_for(&reqwest::Url::parse(url).unwrap()),
+                &backend.scoped[0].1,
+            )
+        };
+        assert!(scoped("https://llm.internal/v1"));
+        assert!(scoped("https://llm.internal:443/v1/chat/completions"));
+        // a host merely starting with the base's, another scheme or port, or a path
+        // sharing only a prefix is out of scope
+        assert!(!scoped("https://llm.internal.attacker.net/v1/chat"));
+        assert!(!scoped("http://llm.internal/v1/chat"));
+        assert!(!scoped("https://llm.internal:8443/v1/chat"));
+        assert!(!scoped("https://llm.internal/v10/chat"));
+        assert!(ReqwestBackend::new(Client::new())
+            .with_scope("not a url", Client::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_response_headers_and_status_read_back() {
+        let response = HttpResponse::new(StatusCode::TOO_MANY_REQUESTS, "slow down")
+            .with_header("Retry-After", "2")
+            .with_header("retry-after", "3");
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.header("RETRY-AFTER"), Some("2"));
+        assert_eq!(response.header("date"), None);
+        assert_eq!(response.text(), "slow down");
+
+        let garbled = HttpResponse {
+            status: 70_000,
+            ..Default::default()
+        };
+        assert_eq!(garbled.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -1,10 +1,23 @@
 mod cost;
+mod format;
+mod http;
+mod moderation;
 mod prompt;
+mod provider;
+mod tokenizer;
+mod usage;
+use crate::error::{HoError, HoResult};
 use crate::orchestrate::ModelSelectionStrategy;
-use crate::prelude::LlmEntity;
+use crate::prelude::{LlmCapabilities, LlmEntity, PromptRequest};
 use anyhow::Result;
 pub use cost::*;
+pub use format::*;
+pub use http::*;
+pub use moderation::*;
 pub use prompt::*;
+pub use provider::*;
+pub use tokenizer::*;
+pub use usage::*;
 
 use {
     crate::{
@@ -13,23 +26,63 @@ use {
         traits::LlmModelTrait,
     },
     camino::Utf8Path,
+    tracing::warn,
 };
 
 impl LlmRouterConfig {
+    /// Largest completion requested from `model`: its entity's `max_output_tokens`, or
+    /// the provider's known cap when the entity sets none
+    pub fn max_output_tokens(&self, model: LlmModel) -> u32 {
+        self.entities
+            .iter()
+            .find(|e| e.name == model.as_str_name())
+            .and_then(|e| e.max_output_tokens)
+            .filter(|cap| *cap > 0)
+            .unwrap_or_else(|| model.max_output_tokens())
+    }
+
+    /// Clamp a requested completion size to `1..=self.max_output_tokens(model)`
+    pub fn clamp_max_tokens(&self, model: LlmModel, requested: u32) -> u32 {
+        let clamped = requested.clamp(1, self.max_output_tokens(model));
+        if clamped != requested {
+            warn!(
+                "✂️ Clamped max_tokens for {} from {} to {}",
+                model.as_str_name(),
+                requested,
+                clamped
+            );
+        }
+        clamped
+    }
+
     pub fn new(data_dir: &Utf8Path) -> Self {
         let mut neurons = Self::default();
         neurons.api_keys_file = data_dir.join(LLM_API_KEYS_FILE).to_string();
-        neurons.default_entity = LlmModel::AkashChat as u32;
         neurons.default_strategy = ModelSelectionStrategy::Unspecified.into();
         neurons.entities = vec![LlmModel::AkashChat.default_entity()];
         neurons
     }
     pub fn update_default_entity(&mut self, model: LlmModel) {
-        self.default_entity = model as u32;
+        self.default_entity = Some(model as u32);
+    }
+    /// The default entity's model, `None` unless the config sets a known one
+    pub fn configured_default(&self) -> Option<LlmModel> {
+        self.default_entity
+            .and_then(|model| LlmModel::try_from(model as i32).ok())
     }
     pub fn update_default_strategy(&mut self, strategy: ModelSelectionStrategy) {
         self.default_strategy = strategy.into();
     }
+    /// Check every enabled entity can build a request url
+    pub fn validate_entities(&self) -> HoResult<()> {
+        self.enabled_entities()
+            .into_iter()
+            .try_for_each(LlmEntity::validate_base_url)
+    }
+    /// Entities the operator has left enabled, in config order
+    pub fn enabled_entities(&self) -> Vec<&LlmEntity> {
+        self.entities.iter().filter(|e| e.enabled).collect()
+    }
     pub fn add_entity(&mut self, entity: LlmEntity) {
         if !self.entities.contains(&entity) {
             self.entities.push(entity);
@@ -46,6 +99,53 @@ impl LlmRouterConfig {
     }
 }
 
+impl LlmEntity {
+    /// The entity's declared capabilities, or its provider's known ones when it declares
+    /// none
+    pub fn resolved_capabilities(&self) -> LlmCapabilities {
+        self.capabilities.unwrap_or_else(|| {
+            LlmModel::from_str_name(&self.name)
+                .map(|model| model.capabilities())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Whether the entity's answers are stored, unset meaning they are
+    pub fn stores_responses(&self) -> bool {
+        self.store_responses.unwrap_or(true)
+    }
+
+    /// Whether stored answers keep their prompt text, unset meaning they do
+    pub fn stores_prompts(&self) -> bool {
+        self.store_prompts.unwrap_or(true)
+    }
+
+    /// Reject entities without a base url whose provider has no default to fall back on,
+    /// which would otherwise fail at request time with an invalid url
+    pub fn validate_base_url(&self) -> HoResult<()> {
+        let has_default = LlmModel::from_str_name(&self.name)
+            .and_then(|model| model.default_base_url())
+            .is_some();
+        if self.base_url.trim().is_empty() && !has_default {
+            return Err(HoError::Config(format!(
+                "LLM entity {} has no base_url and its provider has no default; set one explicitly",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl LlmCapabilities {
+    /// First feature `request` uses that these capabilities lack
+    pub fn missing_for(&self, request: &PromptRequest) -> Option<&'static str> {
+        if !request.tools.is_empty() && !self.supports_tools {
+            return Some("tools");
+        }
+        None
+    }
+}
+
 impl LlmModelTrait for LlmModel {
     /// (default_model, all_available_models)
     fn models(&self) -> (String, Vec<String>) {
@@ -64,28 +164,158 @@ impl LlmModelTrait for LlmModel {
 
         (all.first().cloned().unwrap_or_default(), all)
     }
-    fn default_base_url(&self) -> String {
+    fn default_base_url(&self) -> Option<String> {
         match self {
-            LlmModel::AkashChat => AKASH_CHAT_BASE_URL.to_string(),
-            LlmModel::KimiResearch => KIMI_RESEARCH_BASE_URL.to_string(),
-            LlmModel::Grok => GROK_BASE_URL.to_string(),
-            LlmModel::OpenAi => OPENAI_BASE_URL.to_string(),
-            LlmModel::Anthropic => ANTHROPIC_BASE_URL.to_string(),
-            // Any other variant (e.g., `Custom`) gets an empty string.
-            _ => String::new(),
+            LlmModel::AkashChat => Some(AKASH_CHAT_BASE_URL.to_string()),
+            LlmModel::KimiResearch => Some(KIMI_RESEARCH_BASE_URL.to_string()),
+            LlmModel::Grok => Some(GROK_BASE_URL.to_string()),
+            LlmModel::OpenAi => Some(OPENAI_BASE_URL.to_string()),
+            LlmModel::Anthropic => Some(ANTHROPIC_BASE_URL.to_string()),
+            LlmModel::OllamaLocal => Some(format!(
+                "http://{}:{}",
+                OLLAMA_LOCAL_HOST, OLLAMA_LOCAL_PORT
+            )),
+            // a custom endpoint can only come from the operator
+            LlmModel::Custom => None,
         }
     }
     fn default_entity(&self) -> LlmEntity {
         LlmEntity {
             name: self.as_str_name().into(),
-            base_url: self.default_base_url(),
+            base_url: self.default_base_url().unwrap_or_default(),
             models: self.models().1,
             default_model: self.models().0,
             priority: 1,
             enabled: true,
             default_strategy: ModelSelectionStrategy::Priority.into(),
-            timeout_seconds: 696969,
+            timeout_seconds: DEFAULT_LLM_TIMEOUT_SECS,
             max_retries: 2,
+            max_concurrent_requests: None,
+            tls: None,
+            capabilities: Some(self.capabilities()),
+            store_prompts: None,
+            store_responses: None,
+            max_output_tokens: None,
         }
     }
+    fn max_output_tokens(&self) -> u32 {
+        match self {
+            LlmModel::AkashChat => AKASH_CHAT_MAX_OUTPUT_TOKENS,
+            LlmModel::KimiResearch => KIMI_RESEARCH_MAX_OUTPUT_TOKENS,
+            LlmModel::Grok => GROK_MAX_OUTPUT_TOKENS,
+            LlmModel::OllamaLocal => OLLAMA_LOCAL_MAX_OUTPUT_TOKENS,
+            LlmModel::OpenAi => OPENAI_MAX_OUTPUT_TOKENS,
+            LlmModel::Anthropic => ANTHROPIC_MAX_OUTPUT_TOKENS,
+            LlmModel::Custom => DEFAULT_MAX_OUTPUT_TOKENS,
+        }
+    }
+    fn supports_json_mode(&self) -> bool {
+        matches!(
+            self,
+            LlmModel::OpenAi | LlmModel::Grok | LlmModel::AkashChat
+        )
+    }
+    fn capabilities(&self) -> LlmCapabilities {
+        // an operator declares a custom endpoint's capabilities on its entity
+        let known = !matches!(self, LlmModel::Custom);
+        LlmCapabilities {
+            supports_tools: matches!(
+                self,
+                LlmModel::OpenAi | LlmModel::Anthropic | LlmModel::Grok
+            ),
+            supports_vision: matches!(self, LlmModel::OpenAi | LlmModel::Anthropic),
+            supports_json_mode: self.supports_json_mode(),
+            supports_streaming: known,
+        }
+    }
+    fn provider_key(&self) -> &'static str {
+        match self {
+            LlmModel::AkashChat => "akash_chat",
+            LlmModel::OllamaLocal => "ollama_local",
+            LlmModel::KimiResearch => "kimi",
+            LlmModel::Grok => "grok",
+            LlmModel::OpenAi => "openai",
+            LlmModel::Anthropic => "anthropic",
+            LlmModel::Custom => "custom",
+        }
+    }
+    fn from_provider_key(key: &str) -> Option<Self> {
+        match key {
+            "akash_chat" => Some(LlmModel::AkashChat),
+            "ollama_local" => Some(LlmModel::OllamaLocal),
+            "kimi" => Some(LlmModel::KimiResearch),
+            "grok" => Some(LlmModel::Grok),
+            "openai" => Some(LlmModel::OpenAi),
+            "anthropic" => Some(LlmModel::Anthropic),
+            "custom" => Some(LlmModel::Custom),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_key_roundtrip() {
+        let mut variants = 0;
+        while let Ok(model) = LlmModel::try_from(variants) {
+            assert_eq!(
+                LlmModel::from_provider_key(model.provider_key()),
+                Some(model)
+            );
+            variants += 1;
+        }
+        assert_eq!(variants, 7);
+        assert_eq!(LlmModel::from_provider_key("AkashChat"), None);
+    }
+
+    #[test]
+    fn test_custom_entity_requires_base_url() {
+        let mut custom = LlmModel::Custom.default_entity();
+        assert!(custom.base_url.is_empty());
+        assert!(custom.validate_base_url().is_err());
+
+        let mut config = LlmRouterConfig::default();
+        config.add_entity(custom.clone());
+        assert!(config.validate_entities().is_err());
+
+        custom.base_url = "https://llm.internal.example/v1".to_string();
+        assert!(custom.validate_base_url().is_ok());
+        assert!(LlmModel::OllamaLocal
+            .default_entity()
+            .validate_base_url()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_clamp_max_tokens() {
+        let mut config = LlmRouterConfig {
+            entities: vec![
+                LlmModel::Grok.default_entity(),
+                LlmModel::Anthropic.default_entity(),
+            ],
+            ..Default::default()
+        };
+        // a tiny golden-ratio weight would otherwise request zero tokens
+        assert_eq!(
+            config.clamp_max_tokens(LlmModel::Grok, (2048.0 * 0.0001) as u32),
+            1
+        );
+        assert_eq!(
+            config.clamp_max_tokens(LlmModel::Anthropic, 1_000_000),
+            ANTHROPIC_MAX_OUTPUT_TOKENS
+        );
+        assert_eq!(config.clamp_max_tokens(LlmModel::OpenAi, 2048), 2048);
+
+        // An entity's configured cap replaces the provider's
+        config.entities[1].max_output_tokens = Some(1_024);
+        assert_eq!(config.clamp_max_tokens(LlmModel::Anthropic, 2048), 1_024);
+        config.entities[1].max_output_tokens = Some(0);
+        assert_eq!(
+            config.max_output_tokens(LlmModel::Anthropic),
+            ANTHROPIC_MAX_OUTPUT_TOKENS
+        );
+    }
 }
@@ -0,0 +1,103 @@
+use crate::error::{HoError, HoResult};
+use crate::prelude::{ModerationConfig, ModerationVerdict};
+use crate::traits::ModerationHook;
+use async_trait::async_trait;
+
+/// Category reported by the local keyword filter
+pub const KEYWORD_CATEGORY: &str = "blocked_keyword";
+
+impl ModerationVerdict {
+    pub fn allow() -> Self {
+        Self::default()
+    }
+
+    pub fn block(category: impl Into<String>) -> Self {
+        Self {
+            blocked: true,
+            category: Some(category.into()),
+        }
+    }
+
+    /// Turn a blocked verdict into an `HoError::Llm` naming where it was caught
+    pub fn ensure_allowed(&self, stage: &str) -> HoResult<()> {
+        match self.blocked {
+            false => Ok(()),
+            true => Err(HoError::Llm(format!(
+                "{} blocked by moderation: {}",
+                stage,
+                self.category.as_deref().unwrap_or("unspecified")
+            ))),
+        }
+    }
+}
+
+/// Moderation hook that allows everything
+#[derive(Debug, Default, Clone)]
+pub struct NoopModeration;
+
+#[async_trait]
+impl ModerationHook for NoopModeration {
+    async fn check(&self, _text: &str) -> HoResult<ModerationVerdict> {
+        Ok(ModerationVerdict::allow())
+    }
+}
+
+/// Blocks text containing any configured keyword, ignoring case
+#[derive(Debug, Default, Clone)]
+pub struct KeywordFilter {
+    keywords: Vec<String>,
+}
+
+impl KeywordFilter {
+    pub fn new(keywords: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            keywords: keywords
+                .into_iter()
+                .map(|k| k.as_ref().trim().to_lowercase())
+                .filter(|k| !k.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl From<&ModerationConfig> for KeywordFilter {
+    fn from(config: &ModerationConfig) -> Self {
+        Self::new(&config.blocked_keywords)
+    }
+}
+
+#[async_trait]
+impl ModerationHook for KeywordFilter {
+    async fn check(&self, text: &str) -> HoResult<ModerationVerdict> {
+        let text = text.to_lowercase();
+        Ok(match self.keywords.iter().any(|k| text.contains(k)) {
+            true => ModerationVerdict::block(KEYWORD_CATEGORY),
+            false => ModerationVerdict::allow(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keyword_filter_blocks_flagged_content() {
+        let filter = KeywordFilter::from(&ModerationConfig {
+            blocked_keywords: vec!["Forbidden".to_string(), " ".to_string()],
+        });
+
+        let flagged = filter.check("some FORBIDDEN words").await.unwrap();
+        assert!(flagged.blocked);
+        assert_eq!(flagged.category.as_deref(), Some(KEYWORD_CATEGORY));
+        assert!(matches!(
+            flagged.ensure_allowed("prompt"),
+            Err(HoError::Llm(_))
+        ));
+
+        let clean = filter.check("a perfectly ordinary prompt").await.unwrap();
+        assert!(!clean.blocked);
+        assert!(clean.ensure_allowed("prompt").is_ok());
+        assert!(!NoopModeration.check("forbidden").await.unwrap().blocked);
+    }
+}
@@ -101,3 +101,39 @@ impl PromptResponseTrait for PromptResponse {
         self.latency_ms = Some(latency_ms);
     }
 }
+
+impl ToolDefinition {
+    /// Parsed JSON schema of the arguments, an empty object schema when unset
+    pub fn parameters_schema(&self) -> HoResult<serde_json::Value> {
+        if self.parameters.trim().is_empty() {
+            return Ok(serde_json::json!({"type": "object", "properties": {}}));
+        }
+        serde_json::from_str(&self.parameters).map_err(|e| {
+            HoError::Llm(format!(
+                "invalid parameters schema for tool {}: {}",
+                self.name, e
+            ))
+        })
+    }
+
+    /// Entry of the `tools` body parameter for OpenAI-compatible chat completions
+    pub fn openai_param(&self) -> HoResult<serde_json::Value> {
+        Ok(serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters_schema()?,
+            },
+        }))
+    }
+
+    /// Entry of the `tools` body parameter for Anthropic messages
+    pub fn anthropic_param(&self) -> HoResult<serde_json::Value> {
+        Ok(serde_json::json!({
+            "name": self.name,
+            "description": self.description,
+            "input_schema": self.parameters_schema()?,
+        }))
+    }
+}
@@ -0,0 +1,179 @@
+use crate::error::{HoError, HoResult};
+use crate::prelude::{LlmEntity, LlmModel, LlmProvider, ProviderWithAuth, ResolvedProvider};
+use crate::traits::LlmModelTrait;
+
+/// Variable named by a `${ENV_VAR}` credential reference
+pub fn env_placeholder(raw: &str) -> Option<&str> {
+    raw.strip_prefix("${").and_then(|r| r.strip_suffix('}'))
+}
+
+/// Resolve an api-keys.json credential, expanding `${ENV_VAR}` references.
+/// Empty values and unset variables mean no key.
+pub fn resolve_api_key(raw: &str) -> Option<String> {
+    let key = match env_placeholder(raw) {
+        Some(env_var) => std::env::var(env_var).ok()?,
+        None => raw.to_string(),
+    };
+    (!key.is_empty()).then_some(key)
+}
+
+impl TryFrom<&LlmProvider> for LlmModel {
+    type Error = HoError;
+
+    /// The provider's `provider_type`, rejecting unknown values and names that disagree with it
+    fn try_from(provider: &LlmProvider) -> HoResult<Self> {
+        let model = LlmModel::try_from(provider.provider_type).map_err(|_| {
+            HoError::Config(format!(
+                "provider {} has unknown provider_type {}",
+                provider.name, provider.provider_type
+            ))
+        })?;
+        match LlmModel::from_str_name(&provider.name) {
+            Some(named) if named != model => Err(HoError::Config(format!(
+                "provider {} does not match its provider_type {}",
+                provider.name,
+                model.as_str_name()
+            ))),
+            _ => Ok(model),
+        }
+    }
+}
+
+impl TryFrom<&LlmEntity> for LlmProvider {
+    type Error = HoError;
+
+    fn try_from(entity: &LlmEntity) -> HoResult<Self> {
+        let model = LlmModel::from_str_name(&entity.name).ok_or_else(|| {
+            HoError::Config(format!(
+                "LLM entity {} is not a known provider",
+                entity.name
+            ))
+        })?;
+        Ok(LlmProvider {
+            name: entity.name.clone(),
+            base_url: entity.base_url.clone(),
+            supported_models: entity.models.clone(),
+            provider_type: model.into(),
+        })
+    }
+}
+
+impl TryFrom<&LlmProvider> for LlmEntity {
+    type Error = HoError;
+
+    /// The provider type's default entity with the provider's endpoint and models
+    fn try_from(provider: &LlmProvider) -> HoResult<Self> {
+        let mut entity = LlmModel::try_from(provider)?.default_entity();
+        if !provider.base_url.is_empty() {
+            entity.base_url = provider.base_url.clone();
+        }
+        if let Some(first) = provider.supported_models.first() {
+            entity.default_model = first.clone();
+            entity.models = provider.supported_models.clone();
+        }
+        entity.validate_base_url()?;
+        Ok(entity)
+    }
+}
+
+impl TryFrom<&ProviderWithAuth> for ResolvedProvider {
+    type Error = HoError;
+
+    fn try_from(config: &ProviderWithAuth) -> HoResult<Self> {
+        let entity = config
+            .entity
+            .clone()
+            .ok_or_else(|| HoError::Config("provider config has no entity".to_string()))?;
+        let provider = LlmProvider::try_from(&entity)?;
+        entity.validate_base_url()?;
+        Ok(Self {
+            model: LlmModel::try_from(&provider)?.into(),
+            entity: Some(entity),
+            api_key: config.api_key.as_deref().and_then(resolve_api_key),
+        })
+    }
+}
+
+impl From<&ResolvedProvider> for LlmProvider {
+    fn from(resolved: &ResolvedProvider) -> Self {
+        let entity = resolved.entity.clone().unwrap_or_default();
+        LlmProvider {
+            name: entity.name,
+            base_url: entity.base_url,
+            supported_models: entity.models,
+            provider_type: resolved.model,
+        }
+    }
+}
+
+impl From<ResolvedProvider> for ProviderWithAuth {
+    fn from(resolved: ResolvedProvider) -> Self {
+        ProviderWithAuth {
+            api_key: resolved.api_key,
+            entity: resolved.entity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_for_every_provider_type() {
+        let mut variants = 0;
+        while let Ok(model) = LlmModel::try_from(variants) {
+            let mut entity = model.default_entity();
+            if model == LlmModel::Custom {
+                entity.base_url = "https://llm.internal.example/v1".to_string();
+            }
+
+            let provider = LlmProvider::try_from(&entity).unwrap();
+            assert_eq!(provider.provider_type(), model);
+            assert_eq!(LlmModel::try_from(&provider).unwrap(), model);
+            assert_eq!(LlmEntity::try_from(&provider).unwrap(), entity);
+
+            let env_var = format!("CW_HO_PROVIDER_TEST_KEY_{}", variants);
+            std::env::set_var(&env_var, "secret");
+            let config = ProviderWithAuth {
+                api_key: Some(format!("${{{}}}", env_var)),
+                entity: Some(entity.clone()),
+            };
+            let resolved = ResolvedProvider::try_from(&config).unwrap();
+            assert_eq!(resolved.model(), model);
+            assert_eq!(resolved.api_key.as_deref(), Some("secret"));
+            assert_eq!(LlmProvider::from(&resolved), provider);
+            assert_eq!(ProviderWithAuth::from(resolved).entity, Some(entity));
+            variants += 1;
+        }
+        assert_eq!(variants, 7);
+    }
+
+    #[test]
+    fn test_rejects_unknown_or_mismatched_provider_type() {
+        let unknown = LlmProvider {
+            name: "mystery".to_string(),
+            provider_type: 99,
+            ..Default::default()
+        };
+        assert!(LlmModel::try_from(&unknown).is_err());
+
+        let mismatched = LlmProvider {
+            name: "Grok".to_string(),
+            provider_type: LlmModel::OpenAi.into(),
+            ..Default::default()
+        };
+        assert!(LlmModel::try_from(&mismatched).is_err());
+
+        let custom = LlmProvider {
+            name: "Custom".to_string(),
+            provider_type: LlmModel::Custom.into(),
+            ..Default::default()
+        };
+        assert!(LlmEntity::try_from(&custom).is_err());
+
+        assert_eq!(resolve_api_key("${CW_HO_PROVIDER_TEST_UNSET}"), None);
+        assert_eq!(resolve_api_key(""), None);
+        assert_eq!(resolve_api_key("sk-plain").as_deref(), Some("sk-plain"));
+    }
+}
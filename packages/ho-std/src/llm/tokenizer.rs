@@ -0,0 +1,99 @@
+use crate::traits::Tokenizer;
+
+/// Rough characters-per-token ratio used when no vocabulary is available
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Character-length heuristic, the fallback for every model family
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CharEstimator;
+
+impl Tokenizer for CharEstimator {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(CHARS_PER_TOKEN)
+    }
+}
+
+/// Exact counts from OpenAI's published BPE vocabularies
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenTokenizer(tiktoken_rs::CoreBPE);
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenTokenizer {
+    /// Vocabulary of gpt-3.5 and gpt-4
+    pub fn cl100k() -> Self {
+        Self(tiktoken_rs::cl100k_base().expect("cl100k vocabulary is bundled"))
+    }
+
+    /// Vocabulary of gpt-4o, gpt-4.1, gpt-5 and the o-series
+    pub fn o200k() -> Self {
+        Self(tiktoken_rs::o200k_base().expect("o200k vocabulary is bundled"))
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl Tokenizer for TiktokenTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.0.encode_with_special_tokens(text).len()
+    }
+}
+
+/// Tokenizer for `model`. With the `tiktoken` feature, OpenAI model families get their
+/// exact vocabulary; everything else, and every model without the feature, gets
+/// [`CharEstimator`].
+pub fn tokenizer_for(model: &str) -> &'static dyn Tokenizer {
+    #[cfg(feature = "tiktoken")]
+    {
+        use std::sync::OnceLock;
+        static CL100K: OnceLock<TiktokenTokenizer> = OnceLock::new();
+        static O200K: OnceLock<TiktokenTokenizer> = OnceLock::new();
+
+        let model = model.to_lowercase();
+        let o200k = ["gpt-4o", "gpt-4.1", "gpt-5", "o1", "o3", "o4"];
+        if o200k.iter().any(|family| model.starts_with(family)) {
+            return O200K.get_or_init(TiktokenTokenizer::o200k);
+        }
+        if model.starts_with("gpt-") {
+            return CL100K.get_or_init(TiktokenTokenizer::cl100k);
+        }
+    }
+    #[cfg(not(feature = "tiktoken"))]
+    let _ = model;
+    &CharEstimator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_estimator_rounds_up() {
+        assert_eq!(CharEstimator.count_tokens(""), 0);
+        assert_eq!(CharEstimator.count_tokens("abcd"), 1);
+        assert_eq!(CharEstimator.count_tokens("abcde"), 2);
+        assert_eq!(tokenizer_for("claude-sonnet-4").count_tokens("abcde"), 2);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken_matches_known_counts() {
+        // reference counts from OpenAI's tokenizer
+        let samples = [
+            ("hello world", 2),
+            ("Hello, world!", 4),
+            ("The quick brown fox jumps over the lazy dog.", 10),
+        ];
+        for model in ["gpt-4", "gpt-4o", "gpt-5-mini"] {
+            for (text, expected) in samples {
+                let counted = tokenizer_for(model).count_tokens(text);
+                assert!(
+                    counted.abs_diff(expected) <= 1,
+                    "{} counted {} tokens in {:?}, expected {}",
+                    model,
+                    counted,
+                    text,
+                    expected
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use super::tokenizer_for;
+use crate::prelude::{Histogram, LlmModel, PromptMessage, TokenUsage};
+use crate::traits::Tokenizer;
+use serde_json::Value;
+
+impl Histogram {
+    /// Empty histogram over the ascending upper `bounds`
+    pub fn with_bounds(bounds: &[u64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            counts: vec![0; bounds.len() + 1],
+            count: 0,
+            sum: 0,
+        }
+    }
+
+    /// Count `value` into the first bucket whose bound it does not exceed
+    pub fn observe(&mut self, value: u64) {
+        self.counts.resize(self.bounds.len() + 1, 0);
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+    }
+}
+
+/// Drop the oldest non-system messages until their size under `tokenizer` fits `budget`
+/// tokens. System messages are always kept and order is preserved.
+pub fn trim_history(
+    history: &[PromptMessage],
+    budget: u32,
+    tokenizer: &dyn Tokenizer,
+) -> Vec<PromptMessage> {
+    let cost = |m: &PromptMessage| tokenizer.count_tokens(&m.content) as u32;
+    let mut used: u32 = history
+        .iter()
+        .filter(|m| m.role == "system")
+        .map(cost)
+        .sum();
+    let mut keep: Vec<bool> = history.iter().map(|m| m.role == "system").collect();
+    for (i, message) in history.iter().enumerate().rev() {
+        if keep[i] {
+            continue;
+        }
+        if used + cost(message) > budget {
+            // keep a contiguous tail so no turn loses the ones it follows
+            break;
+        }
+        used += cost(message);
+        keep[i] = true;
+    }
+    history
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(m, _)| m.clone())
+        .collect()
+}
+
+/// Map a provider response body to a uniform `TokenUsage`.
+/// When the body carries no usage, the completion is estimated from the returned text
+/// with `model`'s tokenizer.
+pub fn normalize_usage(raw: &Value, provider: LlmModel, model: &str) -> TokenUsage {
+    let count = |v: &Value, key: &str| v.get(key).and_then(Value::as_u64).map(|n| n as u32);
+
+    let reported = match provider {
+        // {"usage": {"input_tokens", "output_tokens", "cache_*_input_tokens"}}
+        LlmModel::Anthropic => raw.get("usage").map(|u| {
+            let cached = count(u, "cache_creation_input_tokens").unwrap_or(0)
+                + count(u, "cache_read_input_tokens").unwrap_or(0);
+            (
+                count(u, "input_tokens").unwrap_or(0) + cached,
+                count(u, "output_tokens").unwrap_or(0),
+                None,
+            )
+        }),
+        // top-level {"prompt_eval_count", "eval_count"}
+        LlmModel::OllamaLocal => count(raw, "eval_count").map(|completion| {
+            (
+                count(raw, "prompt_eval_count").unwrap_or(0),
+                completion,
+                None,
+            )
+        }),
+        // OpenAI-compatible {"usage": {"prompt_tokens", "completion_tokens", "total_tokens"}}
+        _ => raw.get("usage").map(|u| {
+            (
+                count(u, "prompt_tokens").unwrap_or(0),
+                count(u, "completion_tokens").unwrap_or(0),
+                count(u, "total_tokens"),
+            )
+        }),
+    };
+
+    let (prompt, completion, total) = reported.unwrap_or_else(|| {
+        let estimated = tokenizer_for(model).count_tokens(&response_text(raw)) as u32;
+        (0, estimated, None)
+    });
+    TokenUsage {
+        prompt,
+        completion,
+        total: total.unwrap_or(prompt + completion),
+    }
+}
+
+/// Concatenated completion text across the response shapes we talk to
+fn response_text(raw: &Value) -> String {
+    let mut text = String::new();
+    // OpenAI-compatible choices
+    for choice in raw
+        .get("choices")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(content) = choice.pointer("/message/content").and_then(Value::as_str) {
+            text.push_str(content);
+        }
+    }
+    // Anthropic content blocks
+    for block in raw
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(t) = block.get("text").and_then(Value::as_str) {
+            text.push_str(t);
+        }
+    }
+    // Ollama generate
+    if let Some(t) = raw.get("response").and_then(Value::as_str) {
+        text.push_str(t);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::CharEstimator;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_usage_per_provider() {
+        let openai =
+            json!({"usage": {"prompt_tokens": 12, "completion_tokens": 30, "total_tokens": 42}});
+        let anthropic = json!({"usage": {"input_tokens": 10, "output_tokens": 5, "cache_read_input_tokens": 2}});
+        let ollama = json!({"response": "hi", "prompt_eval_count": 7, "eval_count": 3});
+        let bare =
+            json!({"choices": [{"message": {"role": "assistant", "content": "twelve chars"}}]});
+
+        let usage = |p, c, t| TokenUsage {
+            prompt: p,
+            completion: c,
+            total: t,
+        };
+        assert_eq!(
+            normalize_usage(&openai, LlmModel::OpenAi, "gpt-4o"),
+            usage(12, 30, 42)
+        );
+        assert_eq!(
+            normalize_usage(&openai, LlmModel::Grok, "grok-3"),
+            usage(12, 30, 42)
+        );
+        assert_eq!(
+            normalize_usage(&openai, LlmModel::AkashChat, "llama"),
+            usage(12, 30, 42)
+        );
+        assert_eq!(
+            normalize_usage(&anthropic, LlmModel::Anthropic, "claude"),
+            usage(12, 5, 17)
+        );
+        assert_eq!(
+            normalize_usage(&ollama, LlmModel::OllamaLocal, "llama3"),
+            usage(7, 3, 10)
+        );
+        assert_eq!(
+            normalize_usage(&bare, LlmModel::AkashChat, "llama"),
+            usage(0, 3, 3)
+        );
+        // Estimates count with the answering model's tokenizer
+        let estimated = tokenizer_for("gpt-4o").count_tokens("twelve chars") as u32;
+        assert_eq!(
+            normalize_usage(&bare, LlmModel::OpenAi, "gpt-4o"),
+            usage(0, estimated, estimated)
+        );
+    }
+
+    #[test]
+    fn test_trim_history_keeps_system_and_newest_turns() {
+        let message = |role: &str, content: &str| PromptMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        };
+        let history = vec![
+            message("system", "be brief"),
+            message("user", "first question here"),
+            message("assistant", "first answer"),
+            message("user", "second"),
+            message("assistant", "reply"),
+        ];
+        // "be brief" = 2, "second" = 2, "reply" = 2 tokens
+        let trimmed = trim_history(&history, 6, &CharEstimator);
+        let contents: Vec<&str> = trimmed.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["be brief", "second", "reply"]);
+        assert_eq!(trim_history(&history, 1_000, &CharEstimator), history);
+    }
+}
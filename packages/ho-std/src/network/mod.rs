@@ -1,10 +1,17 @@
+use crate::constants::{CAPABILITY_GOSSIP, CAPABILITY_RPC, NETWORK_MESSAGE_NAMESPACE};
 use crate::error::HoResult;
 use crate::llm::HoError;
-use crate::prelude::{MessageType, NetworkMessage, NetworkTopology, Response};
+use crate::prelude::{
+    MessageKind, MessagePriority, MessageType, NetworkMessage, NetworkTopology, Response,
+    SignedNetworkMessage,
+};
 use crate::traits::Message as _;
 use crate::traits::{NetworkConfigTrait, NetworkMessageTrait, NetworkTopologyTrait};
 
 use crate::commonware::error::{CommonwareNetworkError, CommonwareNetworkResult};
+use crate::commonware::identity::{NodePrivKey, NodePubkey};
+use commonware_codec::{DecodeExt, Encode};
+use commonware_cryptography::ed25519;
 
 impl NetworkTopologyTrait for NetworkTopology {
     type NodeInfo = crate::types::cw_ho::network::v1::NodeInfo;
@@ -63,7 +70,106 @@ impl NetworkMessageTrait for NetworkMessage {
     }
 
     fn channel(&self) -> HoResult<u8> {
-        todo!()
+        // discovery 0, tasks 1, state 2, health 3, as registered by the network manager
+        match &self.message_type {
+            Some(MessageType::NodeAnnounce(_)) | Some(MessageType::GossipRelay(_)) => Ok(0),
+            Some(MessageType::TaskCoordination(_))
+            | Some(MessageType::Request(_))
+            | Some(MessageType::Response(_)) => Ok(1),
+            Some(MessageType::SandloopState(_)) | Some(MessageType::FractalSync(_)) => Ok(2),
+            Some(MessageType::TetrahedralPing(_)) => Ok(3),
+            None => Err(HoError::Network(
+                "message without a type has no channel".to_string(),
+            )),
+        }
+    }
+
+    fn priority(&self) -> MessagePriority {
+        match &self.message_type {
+            Some(MessageType::TetrahedralPing(_)) => MessagePriority::Critical,
+            Some(MessageType::NodeAnnounce(_))
+            | Some(MessageType::GossipRelay(_))
+            | Some(MessageType::Request(_))
+            | Some(MessageType::Response(_)) => MessagePriority::High,
+            Some(MessageType::TaskCoordination(_)) | None => MessagePriority::Normal,
+            Some(MessageType::SandloopState(_)) | Some(MessageType::FractalSync(_)) => {
+                MessagePriority::Low
+            }
+        }
+    }
+}
+
+impl MessageKind {
+    pub fn of(message: &MessageType) -> Self {
+        match message {
+            MessageType::NodeAnnounce(_) => Self::NodeAnnounce,
+            MessageType::TaskCoordination(_) => Self::TaskCoordination,
+            MessageType::SandloopState(_) => Self::SandloopState,
+            MessageType::FractalSync(_) => Self::FractalSync,
+            MessageType::TetrahedralPing(_) => Self::TetrahedralPing,
+            MessageType::Request(_) => Self::Request,
+            MessageType::Response(_) => Self::Response,
+            MessageType::GossipRelay(_) => Self::GossipRelay,
+        }
+    }
+
+    /// Capability a peer must announce before it is sent this kind, `None` for kinds
+    /// every protocol version decodes
+    pub fn required_capability(self) -> Option<&'static str> {
+        match self {
+            Self::Request | Self::Response => Some(CAPABILITY_RPC),
+            Self::GossipRelay => Some(CAPABILITY_GOSSIP),
+            _ => None,
+        }
+    }
+
+    /// Whether this kind is relayed past its originator's direct peers
+    pub fn is_gossiped(self) -> bool {
+        matches!(self, Self::NodeAnnounce)
+    }
+}
+
+impl SignedNetworkMessage {
+    /// Sign a network message with this node's identity key
+    pub fn sign(msg: &NetworkMessage, key: &NodePrivKey) -> HoResult<Self> {
+        let payload = msg.to_bytes()?;
+        let signature = key.sign(Some(NETWORK_MESSAGE_NAMESPACE), &payload);
+        Ok(Self {
+            payload,
+            signature: signature.encode().to_vec(),
+            signer: key.id().0.encode().to_vec(),
+        })
+    }
+
+    /// Public key the message claims to be signed by
+    pub fn signer_key(&self) -> CommonwareNetworkResult<NodePubkey> {
+        NodePubkey::from_bytes(&self.signer).ok_or_else(|| {
+            CommonwareNetworkError::InvalidSignature("malformed signer public key".to_string())
+        })
+    }
+
+    /// Verify the signature against the expected signer and return the inner message
+    pub fn verify(&self, expected: &NodePubkey) -> CommonwareNetworkResult<NetworkMessage> {
+        let signer = self.signer_key()?;
+        if &signer != expected {
+            return Err(CommonwareNetworkError::InvalidSignature(format!(
+                "signed by {} but expected {}",
+                hex::encode(&self.signer),
+                hex::encode(expected.0.encode())
+            )));
+        }
+
+        let signature = ed25519::Signature::decode(self.signature.as_slice()).map_err(|_| {
+            CommonwareNetworkError::InvalidSignature("malformed signature".to_string())
+        })?;
+        if !signer.verify(Some(NETWORK_MESSAGE_NAMESPACE), &self.payload, &signature) {
+            return Err(CommonwareNetworkError::InvalidSignature(format!(
+                "verification failed for signer {}",
+                hex::encode(&self.signer)
+            )));
+        }
+
+        Ok(NetworkMessage::from_bytes(&self.payload)?)
     }
 }
 
@@ -114,4 +220,24 @@ mod test {
         let address = NetworkUtils::format_address(&host, port);
         assert_eq!(address, "127.0.0.1:8080");
     }
+
+    #[test]
+    fn test_signed_message_rejects_tampering() {
+        let key = NodePrivKey::from_seed(7);
+        let msg = NetworkMessage {
+            message_type: Some(MessageType::Response(Response::default())),
+        };
+        let signed = SignedNetworkMessage::sign(&msg, &key).unwrap();
+        assert!(signed.verify(&key.id()).is_ok());
+
+        let mut tampered = signed.clone();
+        tampered.payload.push(0);
+        assert!(matches!(
+            tampered.verify(&key.id()),
+            Err(CommonwareNetworkError::InvalidSignature(_))
+        ));
+
+        let other = NodePrivKey::from_seed(8);
+        assert!(signed.verify(&other.id()).is_err());
+    }
 }
@@ -1,6 +1,15 @@
+use std::any::Any;
 use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use futures::FutureExt;
+use tokio::sync::watch;
+use tracing::{error, warn};
 
 use crate::constants::*;
+use crate::error::{HoError, HoResult};
 use crate::traits::{CosmicContextExt, FractalRequirementsExt};
 
 // Re-export proto types for orchestration
@@ -8,20 +17,40 @@ pub use crate::types::cw_ho::orchestration::v1::*;
 
 impl CosmicContextExt for CosmicContext {
     fn new_context(task_id: String, prompt: &str, recursion_depth: u32) -> CosmicContext {
-        CosmicContext {
+        let mut context = CosmicContext {
             task_id,
             user_input: prompt.to_string(),
             current_step: 0,
             total_steps: recursion_depth,
             fractal_level: 0,
-            golden_ratio_state: 1.618.to_string(),
+            golden_ratio_state: String::new(),
             previous_responses: Vec::new(),
             cosmic_metadata: HashMap::new(),
-        }
+        };
+        context.set_golden_ratio_state(GOLDEN_RATIO);
+        context
     }
     type CosmicContext = CosmicContext;
 }
 
+impl CosmicContext {
+    /// `golden_ratio_state` as a number. Empty, non-numeric and non-finite values read as
+    /// [`GOLDEN_RATIO`], the state every context starts in.
+    pub fn golden_ratio_state_f64(&self) -> f64 {
+        self.golden_ratio_state
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|state| state.is_finite())
+            .unwrap_or(GOLDEN_RATIO)
+    }
+
+    /// Store `state` in its shortest form that parses back to the same value
+    pub fn set_golden_ratio_state(&mut self, state: f64) {
+        self.golden_ratio_state = state.to_string();
+    }
+}
+
 impl FractalRequirementsExt for FractalRequirements {
     type FractalRequirements = FractalRequirements;
     fn new_default() -> Self::FractalRequirements {
@@ -38,10 +67,349 @@ impl FractalRequirementsExt for FractalRequirements {
                 TETRAHEDRAL_CONNECTIVITY.into(),
                 FRACTAL_RECURSION.into(),
             ],
+            convergence_threshold: None,
+        }
+    }
+}
+
+impl FractalRequirements {
+    /// Sandloop convergence threshold, falling back to [`DEFAULT_SANDLOOP_CONVERGENCE`]
+    pub fn sandloop_convergence(&self) -> f64 {
+        self.convergence_threshold
+            .unwrap_or(DEFAULT_SANDLOOP_CONVERGENCE)
+    }
+}
+
+/// Jaccard similarity of the lowercase word sets of two outputs, in `0.0..=1.0`.
+/// Two empty outputs are identical.
+pub fn output_similarity(a: &str, b: &str) -> f64 {
+    let words = |text: &str| -> std::collections::HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    match union {
+        0 => 1.0,
+        _ => a.intersection(&b).count() as f64 / union as f64,
+    }
+}
+
+impl SandloopConvergence {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            previous: None,
+            converged_at: None,
+        }
+    }
+
+    /// Record the output of 1-based `iteration`, returning true when it is at least
+    /// `threshold` similar to the previous one and the loop should stop
+    pub fn observe(&mut self, iteration: u32, output: &str) -> bool {
+        let converged = self
+            .previous
+            .as_deref()
+            .is_some_and(|previous| output_similarity(previous, output) >= self.threshold);
+        if converged && self.converged_at.is_none() {
+            self.converged_at = Some(iteration);
+        }
+        self.previous = Some(output.to_string());
+        converged
+    }
+}
+
+impl TetrahedralPosition {
+    /// The four valid vertices, in topology order
+    pub const VERTICES: [TetrahedralPosition; 4] = [
+        TetrahedralPosition::Coordinator,
+        TetrahedralPosition::Executor,
+        TetrahedralPosition::Referee,
+        TetrahedralPosition::Development,
+    ];
+
+    /// Parse a vertex name, ignoring case. Accepts "executor" as well as the proto
+    /// name `TETRAHEDRAL_POSITION_EXECUTOR`; anything else is rejected.
+    pub fn parse(name: &str) -> HoResult<Self> {
+        let upper = name.trim().to_uppercase();
+        Self::from_str_name(&upper)
+            .or_else(|| Self::from_str_name(&format!("TETRAHEDRAL_POSITION_{}", upper)))
+            .filter(|p| *p != TetrahedralPosition::Unspecified)
+            .ok_or_else(|| {
+                HoError::Orchestration(format!(
+                    "invalid tetrahedral position {:?}, expected coordinator, executor, referee or development",
+                    name
+                ))
+            })
+    }
+
+    /// Lowercase vertex name, e.g. "executor"
+    pub fn label(&self) -> &'static str {
+        match self {
+            TetrahedralPosition::Unspecified => "unspecified",
+            TetrahedralPosition::Coordinator => "coordinator",
+            TetrahedralPosition::Executor => "executor",
+            TetrahedralPosition::Referee => "referee",
+            TetrahedralPosition::Development => "development",
+        }
+    }
+}
+
+impl AgentSpec {
+    /// Build a spec for `position`, which must name one of the four vertices
+    pub fn new(agent_id: String, position: &str, execution_prompt: String) -> HoResult<Self> {
+        let position = TetrahedralPosition::parse(position)?;
+        Ok(AgentSpec {
+            agent_id,
+            tetrahedral_position: position as i32,
+            capabilities: vec![
+                COSMIC_ORCHESTRATION.to_string(),
+                FRACTAL_RECURSION.to_string(),
+                format!("tetrahedral-{}", position.label()),
+            ],
+            execution_prompt,
+        })
+    }
+
+    /// Recursive task that runs this agent's prompt
+    pub fn to_task(&self) -> CosmicTask {
+        let now = chrono::Utc::now();
+        CosmicTask {
+            id: self.agent_id.clone(),
+            task_type: OrchestrateTask::Recursive as i32,
+            status: CosmicTaskStatus::Pending as i32,
+            prompt: self.execution_prompt.clone(),
+            fractal_requirements: Some(FractalRequirements::new_default()),
+            created_at: Some(now.into()),
+            updated_at: Some(now.into()),
+            result: None,
+            error: None,
+            depends_on: Vec::new(),
+            timeout_secs: None,
+            selection_strategy: None,
+            custom_handler: None,
+        }
+    }
+}
+
+/// Indices of `tasks` in an order where every task follows its dependencies, ready tasks
+/// keeping submission order. Rejects duplicate ids, unknown dependencies and cycles.
+pub fn topological_order(tasks: &[CosmicTask]) -> HoResult<Vec<usize>> {
+    let mut index = HashMap::new();
+    for (i, task) in tasks.iter().enumerate() {
+        if index.insert(task.id.as_str(), i).is_some() {
+            return Err(HoError::Orchestration(format!(
+                "task graph has two tasks with id {}",
+                task.id
+            )));
+        }
+    }
+    let mut waiting_on = vec![0usize; tasks.len()];
+    let mut dependents = vec![Vec::new(); tasks.len()];
+    for (i, task) in tasks.iter().enumerate() {
+        for dependency in &task.depends_on {
+            let &d = index.get(dependency.as_str()).ok_or_else(|| {
+                HoError::Orchestration(format!(
+                    "task {} depends on unknown task {}",
+                    task.id, dependency
+                ))
+            })?;
+            waiting_on[i] += 1;
+            dependents[d].push(i);
+        }
+    }
+
+    let mut ready: std::collections::BTreeSet<usize> =
+        (0..tasks.len()).filter(|&i| waiting_on[i] == 0).collect();
+    let mut order = Vec::with_capacity(tasks.len());
+    while let Some(i) = ready.pop_first() {
+        order.push(i);
+        for &dependent in &dependents[i] {
+            waiting_on[dependent] -= 1;
+            if waiting_on[dependent] == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+    if order.len() < tasks.len() {
+        let cycle: Vec<&str> = (0..tasks.len())
+            .filter(|&i| waiting_on[i] > 0)
+            .map(|i| tasks[i].id.as_str())
+            .collect();
+        return Err(HoError::Orchestration(format!(
+            "task graph has a dependency cycle through {}",
+            cycle.join(", ")
+        )));
+    }
+    Ok(order)
+}
+
+/// Run `tasks` through `execute` in dependency order. Each dependency's result is passed
+/// into the dependent's `context.cosmic_metadata` under the dependency's id, and
+/// dependents of a task that did not complete fail without running.
+/// Returns the tasks in the order they were handled.
+pub async fn run_task_graph<F, Fut, E>(
+    tasks: Vec<CosmicTask>,
+    mut execute: F,
+) -> HoResult<Vec<CosmicTask>>
+where
+    F: FnMut(CosmicTask) -> Fut,
+    Fut: std::future::Future<Output = Result<CosmicTask, E>>,
+    E: std::fmt::Display,
+{
+    let order = topological_order(&tasks)?;
+    let mut pending: Vec<Option<CosmicTask>> = tasks.into_iter().map(Some).collect();
+    let mut finished: HashMap<String, CosmicTask> = HashMap::new();
+    let mut handled = Vec::with_capacity(order.len());
+
+    for i in order {
+        let mut task = pending[i].take().expect("each task is ordered once");
+        let unmet = task
+            .depends_on
+            .iter()
+            .find(|dependency| finished[*dependency].status() != CosmicTaskStatus::Completed)
+            .cloned();
+        let task = match unmet {
+            Some(dependency) => {
+                fail_task(task, format!("dependency {} did not complete", dependency))
+            }
+            None => {
+                let inputs: Vec<(String, pbjson_types::Struct)> = task
+                    .depends_on
+                    .iter()
+                    .map(|dependency| {
+                        let result = finished[dependency].result.clone().unwrap_or_default();
+                        (dependency.clone(), result)
+                    })
+                    .collect();
+                let context = CosmicContext::new_context(
+                    task.id.clone(),
+                    &task.prompt,
+                    DEFAULT_RECURSION_DEPTH,
+                );
+                task.fractal_requirements
+                    .get_or_insert_with(FractalRequirements::new_default)
+                    .context
+                    .get_or_insert(context)
+                    .cosmic_metadata
+                    .extend(inputs);
+                match execute(task.clone()).await {
+                    Ok(done) => done,
+                    Err(e) => fail_task(task, e.to_string()),
+                }
+            }
+        };
+        finished.insert(task.id.clone(), task.clone());
+        handled.push(task);
+    }
+    Ok(handled)
+}
+
+/// `task` marked failed with `error`, as of now
+pub fn fail_task(mut task: CosmicTask, error: String) -> CosmicTask {
+    task.status = CosmicTaskStatus::Failed.into();
+    task.error = Some(error);
+    task.updated_at = Some(chrono::Utc::now().into());
+    task
+}
+
+impl CosmicTask {
+    /// How long the task may run, `None` when it sets no deadline of its own
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+
+    /// Selection strategy the task asks for, `None` when it leaves the choice to the router
+    pub fn strategy_override(&self) -> Option<ModelSelectionStrategy> {
+        self.selection_strategy
+            .and_then(|strategy| ModelSelectionStrategy::try_from(strategy).ok())
+            .filter(|strategy| *strategy != ModelSelectionStrategy::Unspecified)
+    }
+}
+
+impl ResultDiff {
+    /// Both results have the same fields, whatever their values
+    pub fn same_shape(&self) -> bool {
+        self.missing.is_empty() && self.added.is_empty()
+    }
+}
+
+/// Run `task` through `execute`, failing it with [`TASK_TIMED_OUT_ERROR`] once its
+/// [`timeout`](CosmicTask::timeout), or `default_timeout` when it has none, elapses.
+/// On expiry the execution is dropped and the signal handed to `execute` turns true,
+/// so sub-work it spawned stops as well. Returns the state the caller should persist.
+pub async fn run_with_timeout<F, Fut, E>(
+    task: CosmicTask,
+    default_timeout: Option<Duration>,
+    execute: F,
+) -> Result<CosmicTask, E>
+where
+    F: FnOnce(CosmicTask, watch::Receiver<bool>) -> Fut,
+    Fut: Future<Output = Result<CosmicTask, E>>,
+{
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    let Some(limit) = task.timeout().or(default_timeout) else {
+        return execute(task, cancel_rx).await;
+    };
+    match tokio::time::timeout(limit, execute(task.clone(), cancel_rx)).await {
+        Ok(result) => result,
+        Err(_) => {
+            cancel_tx.send_replace(true);
+            warn!("⏰ Task {} timed out after {:?}", task.id, limit);
+            Ok(fail_task(task, TASK_TIMED_OUT_ERROR.to_string()))
         }
     }
 }
 
+/// Run `task` through `execute`, failing it with [`TASK_PANICKED_ERROR`] and the panic
+/// message should the execution panic, so an executor hitting a `todo!()` or a bad
+/// `unwrap` leaves a failed task rather than one stuck in `Running` and a dead worker.
+/// Returns the state the caller should persist.
+pub async fn run_catching_panics<F, Fut, E>(task: CosmicTask, execute: F) -> Result<CosmicTask, E>
+where
+    F: FnOnce(CosmicTask) -> Fut,
+    Fut: Future<Output = Result<CosmicTask, E>>,
+{
+    match AssertUnwindSafe(execute(task.clone())).catch_unwind().await {
+        Ok(result) => result,
+        Err(panic) => {
+            let message = panic_message(panic.as_ref());
+            error!("💥 Task {} panicked: {}", task.id, message);
+            Ok(fail_task(
+                task,
+                format!("{}: {}", TASK_PANICKED_ERROR, message),
+            ))
+        }
+    }
+}
+
+/// Text of a caught panic payload, as given to `panic!` or `todo!`
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// Whether a fast/slow allocation split is within `tolerance` of φ
+pub fn golden_ratio_allocation_valid(fast: f64, slow: f64, tolerance: f64) -> bool {
+    slow > 0.0 && (fast / slow - GOLDEN_RATIO).abs() < tolerance
+}
+
+/// Fraction of the four vertices occupied by at least one agent
+pub fn tetrahedral_coverage(agents: &[AgentSpec]) -> f64 {
+    let occupied = TetrahedralPosition::VERTICES
+        .iter()
+        .filter(|vertex| agents.iter().any(|a| a.tetrahedral_position() == **vertex))
+        .count();
+    occupied as f64 / TetrahedralPosition::VERTICES.len() as f64
+}
+
 // /// Execute recursive orchestration task
 // pub async fn execute_recursive_orchestration_task(
 //     executor: &PythonExecutor,
@@ -149,3 +517,275 @@ impl FractalRequirementsExt for FractalRequirements {
 //         "recursion_depth": recursion_depth
 //     }))
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_ratio_state_round_trips_and_tolerates_garbage() {
+        let mut context = CosmicContext::new_context("t".into(), "prompt", 3);
+        assert_eq!(context.golden_ratio_state_f64(), GOLDEN_RATIO);
+
+        context.set_golden_ratio_state(0.1 + 0.2);
+        assert_eq!(context.golden_ratio_state_f64(), 0.1 + 0.2);
+        context.set_golden_ratio_state(2.618);
+        assert_eq!(context.golden_ratio_state, "2.618");
+
+        for garbage in ["", "phi", " 1.6x", "NaN", "inf"] {
+            context.golden_ratio_state = garbage.to_string();
+            assert_eq!(
+                context.golden_ratio_state_f64(),
+                GOLDEN_RATIO,
+                "{:?}",
+                garbage
+            );
+        }
+        context.golden_ratio_state = " 1.5 ".to_string();
+        assert_eq!(context.golden_ratio_state_f64(), 1.5);
+    }
+
+    #[test]
+    fn test_lowercase_executor_counts_toward_executor_vertex() {
+        let executor = AgentSpec::new("a".into(), "executor", "run".into()).unwrap();
+        assert_eq!(
+            executor.tetrahedral_position(),
+            TetrahedralPosition::Executor
+        );
+        assert_eq!(tetrahedral_coverage(&[executor.clone()]), 0.25);
+
+        let referee = AgentSpec::new("b".into(), "Referee", "judge".into()).unwrap();
+        assert_eq!(tetrahedral_coverage(&[executor.clone(), referee]), 0.5);
+        assert!(AgentSpec::new("c".into(), "observer", "".into()).is_err());
+        assert!(AgentSpec::new("d".into(), "unspecified", "".into()).is_err());
+
+        let task = executor.to_task();
+        assert_eq!(task.id, "a");
+        assert_eq!(task.prompt, "run");
+        assert_eq!(task.status(), CosmicTaskStatus::Pending);
+    }
+
+    #[test]
+    fn test_sandloop_stops_on_near_identical_outputs() {
+        let outputs = [
+            "Draft a plan for the node rollout",
+            "The rollout plan covers staging, canary and full deployment",
+            "The rollout plan covers staging, canary and then full deployment",
+            "unreachable",
+        ];
+        let max_iterations = 10;
+        let mut convergence =
+            SandloopConvergence::new(FractalRequirements::new_default().sandloop_convergence());
+        let mut ran = 0;
+        for iteration in 1..=max_iterations {
+            ran = iteration;
+            if convergence.observe(iteration, outputs[iteration as usize - 1]) {
+                break;
+            }
+        }
+        assert_eq!(ran, 3);
+        assert_eq!(convergence.converged_at, Some(3));
+
+        assert_eq!(output_similarity("", ""), 1.0);
+        assert_eq!(output_similarity("alpha beta", "gamma delta"), 0.0);
+    }
+
+    #[test]
+    fn test_allocation_validation_uses_precise_golden_ratio() {
+        let fast = 1.0 / GOLDEN_RATIO;
+        assert!(golden_ratio_allocation_valid(fast, 1.0 - fast, 1e-12));
+        // the rounded 61.8/38.2 split is only close to φ
+        assert!(golden_ratio_allocation_valid(
+            0.618,
+            0.382,
+            GOLDEN_RATIO_TOLERANCE
+        ));
+        assert!(!golden_ratio_allocation_valid(0.618, 0.382, 1e-4));
+        assert!(!golden_ratio_allocation_valid(
+            1.0,
+            0.0,
+            GOLDEN_RATIO_TOLERANCE
+        ));
+    }
+
+    fn graph_task(id: &str, depends_on: &[&str]) -> CosmicTask {
+        CosmicTask {
+            id: id.to_string(),
+            prompt: format!("run {}", id),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Completes the task with its prompt as the result, failing ids in `failing`
+    fn complete(mut task: CosmicTask, failing: &[&str]) -> Result<CosmicTask, String> {
+        if failing.contains(&task.id.as_str()) {
+            return Err(format!("{} blew up", task.id));
+        }
+        let output = pbjson_types::Value {
+            kind: Some(pbjson_types::value::Kind::StringValue(task.prompt.clone())),
+        };
+        task.result = Some(pbjson_types::Struct {
+            fields: [("output".to_string(), output)].into(),
+        });
+        task.status = CosmicTaskStatus::Completed.into();
+        Ok(task)
+    }
+
+    #[tokio::test]
+    async fn test_task_past_its_timeout_fails_and_cancels_sub_work() {
+        let (stopped_tx, stopped_rx) = tokio::sync::oneshot::channel();
+        let failed = run_with_timeout(
+            graph_task("slow", &[]),
+            Some(Duration::from_millis(20)),
+            |task, mut cancel| async move {
+                tokio::spawn(async move {
+                    if cancel.wait_for(|cancelled| *cancelled).await.is_ok() {
+                        let _ = stopped_tx.send(());
+                    }
+                });
+                std::future::pending::<()>().await;
+                Ok::<_, String>(task)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(failed.status(), CosmicTaskStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some(TASK_TIMED_OUT_ERROR));
+        tokio::time::timeout(Duration::from_secs(1), stopped_rx)
+            .await
+            .expect("spawned sub-work should see the cancellation")
+            .unwrap();
+
+        // The task's own timeout replaces the default
+        let mut patient = graph_task("patient", &[]);
+        patient.timeout_secs = Some(5);
+        let done = run_with_timeout(patient, Some(Duration::ZERO), |task, _| async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            complete(task, &[])
+        })
+        .await
+        .unwrap();
+        assert_eq!(done.status(), CosmicTaskStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_executor_fails_its_task_and_the_graph_runs_on() {
+        // b's task type has no executor yet; its dependent d fails, c still runs
+        let tasks = vec![
+            graph_task("a", &[]),
+            graph_task("b", &["a"]),
+            graph_task("c", &["a"]),
+            graph_task("d", &["b"]),
+        ];
+        let handled = run_task_graph(tasks, |task| {
+            run_catching_panics(task, |task| async move {
+                if task.id == "b" {
+                    todo!("executor for {}", task.id)
+                }
+                complete(task, &[])
+            })
+        })
+        .await
+        .unwrap();
+
+        let status = |id: &str| {
+            let task = handled.iter().find(|t| t.id == id).unwrap();
+            (task.status(), task.error.clone())
+        };
+        assert_eq!(
+            status("b"),
+            (
+                CosmicTaskStatus::Failed,
+                Some(format!(
+                    "{}: not yet implemented: executor for b",
+                    TASK_PANICKED_ERROR
+                ))
+            )
+        );
+        assert_eq!(
+            status("d"),
+            (
+                CosmicTaskStatus::Failed,
+                Some("dependency b did not complete".to_string())
+            )
+        );
+        assert_eq!(status("a").0, CosmicTaskStatus::Completed);
+        assert_eq!(status("c").0, CosmicTaskStatus::Completed);
+
+        let payload: Box<dyn Any + Send> = Box::new(7u8);
+        assert_eq!(panic_message(payload.as_ref()), "unknown panic payload");
+    }
+
+    #[tokio::test]
+    async fn test_diamond_graph_runs_in_dependency_order() {
+        // a feeds b and c, which both feed d; submitted back to front
+        let tasks = vec![
+            graph_task("d", &["b", "c"]),
+            graph_task("c", &["a"]),
+            graph_task("b", &["a"]),
+            graph_task("a", &[]),
+        ];
+        let mut ran = Vec::new();
+        let handled = run_task_graph(tasks.clone(), |task| {
+            ran.push(task.id.clone());
+            async move { complete(task, &[]) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(ran, vec!["a", "c", "b", "d"]);
+        assert!(handled
+            .iter()
+            .all(|t| t.status() == CosmicTaskStatus::Completed));
+
+        let d = handled.last().unwrap();
+        let metadata = &d
+            .fractal_requirements
+            .as_ref()
+            .unwrap()
+            .context
+            .as_ref()
+            .unwrap()
+            .cosmic_metadata;
+        let mut inputs: Vec<&String> = metadata.keys().collect();
+        inputs.sort();
+        assert_eq!(inputs, vec!["b", "c"]);
+        assert_eq!(
+            metadata["b"].fields["output"].kind,
+            Some(pbjson_types::value::Kind::StringValue("run b".to_string()))
+        );
+
+        // b failing fails d without running it, c is unaffected
+        let mut ran = Vec::new();
+        let handled = run_task_graph(tasks, |task| {
+            ran.push(task.id.clone());
+            async move { complete(task, &["b"]) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(ran, vec!["a", "c", "b"]);
+        let d = handled.iter().find(|t| t.id == "d").unwrap();
+        assert_eq!(d.status(), CosmicTaskStatus::Failed);
+        assert_eq!(d.error.as_deref(), Some("dependency b did not complete"));
+        let c = handled.iter().find(|t| t.id == "c").unwrap();
+        assert_eq!(c.status(), CosmicTaskStatus::Completed);
+    }
+
+    #[test]
+    fn test_graph_rejects_cycles_and_unknown_dependencies() {
+        let cycle = [
+            graph_task("x", &["z"]),
+            graph_task("y", &["x"]),
+            graph_task("z", &["y"]),
+            graph_task("free", &[]),
+        ];
+        let err = topological_order(&cycle).unwrap_err().to_string();
+        assert!(err.contains("dependency cycle through x, y, z"), "{}", err);
+
+        let unknown = [graph_task("x", &["missing"])];
+        assert!(topological_order(&unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown task missing"));
+    }
+}
@@ -9,56 +9,103 @@ pub use crate::types::cw_ho::types::v1::{
 };
 
 pub use crate::types::cw_ho::network::v1::{
-    network_event::EventType, network_message::MessageType, HostOs, MessageReceived, NetworkConfig,
-    NetworkError, NetworkEvent, NetworkMessage, NetworkTopology, NodeAnnounce, NodeIdentity,
-    NodeInfo, NodeType, PeerConnected, PeerDisconnected, Request, Response, TetrahedralPing,
-    TopologyChanged,
+    network_event::EventType, network_message::MessageType, GossipRelay, HostOs, MessageKind,
+    MessagePriority, MessageReceived, NetworkConfig, NetworkError, NetworkEvent, NetworkMessage,
+    NetworkTopology, NodeAnnounce, NodeIdentity, NodeInfo, NodeType, PeerConnected,
+    PeerDisconnected, PeerStatus, Request, Response, SignedNetworkMessage, TetrahedralPing,
+    TopologyChanged, TopologyDiff,
 };
 
 pub use crate::types::cw_ho::orchestration::v1::{
+    ActiveTaskCounts,
+    AgentSpec,
+    ApiError,
+    ApiKeysIssue,
+    ApiKeysIssueKind,
     ApiKeysJson,
     ApiKeysMetadata,
+    ArtifactInfo,
     // Route request/response types
     BootstrapNodeRequest,
     BootstrapNodeResponse,
     // Orchestration types
+    ConfigNode,
+    ConfigNodeFailure,
     CosmicContext,
     CosmicTask,
     CosmicTaskStatus,
     CreateFractalRequest,
     CreateFractalResponse,
+    EgressConfig,
+    EgressPolicy,
     FractalRequirements,
     GetTopologyRequest,
     GetTopologyResponse,
     GlobalSettings,
     HealthRequest,
     HealthResponse,
+    Histogram,
     HoConfig,
+    HttpHeader,
     // Route metadata types
     HttpMethod,
+    HttpResponse,
+    ImportLineError,
+    ImportPromptsResponse,
     Instructions,
+    LlmCapabilities,
     LlmEntity,
+    LlmEntityStatus,
     LlmModel,
+    LlmModelsQuery,
+    LlmProvider,
+    LlmProviderModels,
     LlmRouterConfig,
+    LlmRouterMetrics,
     LocalLlmConfig,
+    LoggingConfig,
+    ModerationConfig,
+    ModerationVerdict,
+    NodeMetrics,
     OrchestrateTask,
+    OrchestrationReport,
+    OrchestratorShutdownSummary,
     PromptContext,
     PromptMessage,
     PromptRequest,
     PromptResponse,
+    ProviderBench,
+    ProviderHealth,
+    ProviderLatencyMetrics,
+    ProviderQueueMetrics,
+    ProviderReadiness,
+    ProviderReliabilityMetrics,
     ProviderWithAuth,
     PruneNodeRequest,
     PruneNodeResponse,
     QueryPromptsRequest,
     QueryPromptsResponse,
+    ResolvedProvider,
+    ResponseFormat,
+    ResponseFormatType,
+    ResultDiff,
     RouteMetadata,
     RouteRegistry as ProtoRouteRegistry,
+    SetDefaultEntityRequest,
+    SshRegistrationReport,
+    SshStepResult,
+    StorageBackend,
     StorageConfig,
+    TaskSummary,
+    TetrahedralPosition,
+    TlsConfig,
     TokenUsage,
+    ToolDefinition,
 };
 pub use crate::types::cw_ho::storage::v1::{
-    BootstrapRequest, BootstrapResponse, ErrorResponse, HealthResponse as StorageHealthResponse,
-    QueryRequest, StorageIndex, StorageMetrics, StorageQuery, StorageSnapshot,
+    BootstrapRequest, BootstrapResponse, ErrorResponse, GcReport,
+    HealthResponse as StorageHealthResponse, QueryRequest, StorageIndex, StorageMetrics,
+    StorageQuery, StorageSnapshot, WalRecovery,
 };
 
 // Re-export other prost types that don't need shimming
@@ -0,0 +1,132 @@
+//! Startup probe for the python3 interpreter and tool scripts some features shell out to
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use crate::constants::*;
+use crate::error::{HoError, HoResult};
+
+/// Python-backed features and the tool scripts each runs, relative to the workspace root
+pub const PYTHON_FEATURES: &[(&str, &[&str])] = &[
+    ("bootstrap", &[TOOLS_SSH_TRANSPORT]),
+    ("MetaPromptGeneration", &[TOOLS_METAPROMPT_GENERATOR]),
+];
+
+/// Whether python3 and the tool scripts are present, so Python-dependent work fails with a
+/// clear reason instead of a raw spawn error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonCapabilities {
+    /// Resolved python3 executable, `None` when it is not on the search path
+    pub interpreter: Option<PathBuf>,
+    tools_root: PathBuf,
+}
+
+impl PythonCapabilities {
+    /// Look for python3 in the `PATH`-style `search_path`, with scripts under `tools_root`
+    pub fn probe(search_path: Option<&OsStr>, tools_root: impl Into<PathBuf>) -> Self {
+        let interpreter = search_path.and_then(|paths| {
+            std::env::split_paths(paths)
+                .map(|dir| dir.join(CMD_PYTHON3))
+                .find(|candidate| is_executable(candidate))
+        });
+        Self {
+            interpreter,
+            tools_root: tools_root.into(),
+        }
+    }
+
+    /// Probe the process `PATH` and working directory, where tool scripts are run from
+    pub fn detect() -> Self {
+        Self::probe(
+            std::env::var_os("PATH").as_deref(),
+            std::env::current_dir().unwrap_or_default(),
+        )
+    }
+
+    /// Error naming what is missing unless python3 and every script `feature` runs exist
+    pub fn require(&self, feature: &str, scripts: &[&str]) -> HoResult<()> {
+        if self.interpreter.is_none() {
+            return Err(HoError::Config(format!(
+                "{} not found; {} unavailable",
+                CMD_PYTHON3, feature
+            )));
+        }
+        match scripts.iter().find(|script| {
+            !self
+                .tools_root
+                .join(script.trim_start_matches('/'))
+                .is_file()
+        }) {
+            Some(script) => Err(HoError::Config(format!(
+                "{} not found; {} unavailable",
+                script, feature
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Reasons each of [`PYTHON_FEATURES`] is unavailable, empty when all can run
+    pub fn unavailable(&self) -> Vec<HoError> {
+        PYTHON_FEATURES
+            .iter()
+            .filter_map(|(feature, scripts)| self.require(feature, scripts).err())
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_python_disables_python_features() {
+        let root = std::env::temp_dir().join(format!("cw-ho-python-{}", uuid::Uuid::new_v4()));
+        let bin = root.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+
+        let without = PythonCapabilities::probe(Some(bin.as_os_str()), &root);
+        assert_eq!(without.interpreter, None);
+        assert_eq!(
+            without
+                .require("MetaPromptGeneration", &[TOOLS_METAPROMPT_GENERATOR])
+                .unwrap_err()
+                .to_string(),
+            "Configuration error: python3 not found; MetaPromptGeneration unavailable"
+        );
+        assert_eq!(without.unavailable().len(), PYTHON_FEATURES.len());
+
+        // An executable python3 is found, then each feature needs its scripts
+        let python = bin.join(CMD_PYTHON3);
+        std::fs::write(&python, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&python, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let with = PythonCapabilities::probe(Some(bin.as_os_str()), &root);
+        assert_eq!(with.interpreter.as_deref(), Some(python.as_path()));
+        let err = with
+            .require("bootstrap", &[TOOLS_SSH_TRANSPORT])
+            .unwrap_err();
+        assert!(err.to_string().contains("tools/ssh/transport.py not found"));
+
+        let script = root.join(TOOLS_SSH_TRANSPORT);
+        std::fs::create_dir_all(script.parent().unwrap()).unwrap();
+        std::fs::write(&script, "").unwrap();
+        assert!(with.require("bootstrap", &[TOOLS_SSH_TRANSPORT]).is_ok());
+        assert_eq!(with.unavailable().len(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1 +1,2 @@
+pub mod capabilities;
 pub mod executor;
@@ -1,12 +1,14 @@
 //! Authentication middleware for route protection
 
+use super::ApiError;
+use crate::commonware::identity::NodePrivKey;
 use axum::{
     body::Body,
     extract::Request,
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
-use commonware_codec::DecodeExt;
+use commonware_codec::{DecodeExt, Encode};
 use commonware_cryptography::{blake3, Hasher, Verifier};
 use futures_util::future::BoxFuture;
 use http_body_util::BodyExt;
@@ -29,40 +31,49 @@ pub enum AuthError {
     RequestExpired,
 }
 
-impl IntoResponse for AuthError {
-    fn into_response(self) -> Response {
-        use axum::http::StatusCode;
-        use serde::Serialize;
-
-        #[derive(Serialize)]
-        struct ErrorResponse {
-            error: String,
+impl AuthError {
+    /// Error code in the [`ApiError`] envelope
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingSignature => "MISSING_SIGNATURE",
+            AuthError::MissingTimestamp => "MISSING_TIMESTAMP",
+            AuthError::InvalidSignature => "INVALID_SIGNATURE",
+            AuthError::VerificationFailed => "SIGNATURE_MISMATCH",
+            AuthError::RequestExpired => "REQUEST_EXPIRED",
         }
+    }
+}
 
-        let (status, message) = match self {
-            AuthError::MissingSignature | AuthError::MissingTimestamp => {
-                (StatusCode::UNAUTHORIZED, self.to_string())
-            }
-            AuthError::InvalidSignature | AuthError::VerificationFailed => {
-                (StatusCode::FORBIDDEN, self.to_string())
-            }
-            AuthError::RequestExpired => (StatusCode::REQUEST_TIMEOUT, self.to_string()),
-        };
-
-        (status, axum::Json(ErrorResponse { error: message })).into_response()
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
     }
 }
 
-impl From<AuthError> for StatusCode {
+impl From<AuthError> for ApiError {
     fn from(err: AuthError) -> Self {
+        ApiError::new(StatusCode::from(&err), err.code(), err.to_string())
+    }
+}
+
+impl From<&AuthError> for StatusCode {
+    fn from(err: &AuthError) -> Self {
         match err {
-            AuthError::MissingSignature | AuthError::MissingTimestamp => StatusCode::UNAUTHORIZED,
+            // An expired signature has to be signed again, like a missing one
+            AuthError::MissingSignature
+            | AuthError::MissingTimestamp
+            | AuthError::RequestExpired => StatusCode::UNAUTHORIZED,
             AuthError::InvalidSignature | AuthError::VerificationFailed => StatusCode::FORBIDDEN,
-            AuthError::RequestExpired => StatusCode::REQUEST_TIMEOUT,
         }
     }
 }
 
+impl From<AuthError> for StatusCode {
+    fn from(err: AuthError) -> Self {
+        StatusCode::from(&err)
+    }
+}
+
 /// Extract header value as string
 fn extract_header(headers: &HeaderMap, name: &str) -> Result<String, AuthError> {
     headers
@@ -72,6 +83,24 @@ fn extract_header(headers: &HeaderMap, name: &str) -> Result<String, AuthError>
         .ok_or(AuthError::MissingSignature)
 }
 
+/// Headers authenticating a request to a protected route: H(body||timestamp) signed
+/// with `key`, as the middleware verifies it
+pub fn signed_headers(
+    key: &NodePrivKey,
+    body: &[u8],
+    timestamp: u64,
+) -> [(&'static str, String); 3] {
+    let timestamp = timestamp.to_string();
+    let mut contents = body.to_vec();
+    contents.extend_from_slice(timestamp.as_bytes());
+    let signature = key.sign(None, &blake3::Blake3::hash(&contents));
+    [
+        ("x-signature", hex::encode(signature.encode())),
+        ("x-timestamp", timestamp),
+        ("x-public-key", hex::encode(key.id().0.encode())),
+    ]
+}
+
 /// Validate timestamp to prevent replay attacks
 fn validate_timestamp(timestamp_str: &str) -> Result<(), AuthError> {
     let timestamp: u64 = timestamp_str
@@ -229,3 +258,22 @@ fn validate_crypto_signature_with_body(
         Err(AuthError::VerificationFailed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_headers_pass_validation() {
+        let key = NodePrivKey::from_seed(3);
+        let [(_, signature), (_, timestamp), (_, public_key)] =
+            signed_headers(&key, b"{}", 1_700_000_000);
+        assert!(
+            validate_crypto_signature_with_body(&signature, &timestamp, &public_key, b"{}").is_ok()
+        );
+        assert!(matches!(
+            validate_crypto_signature_with_body(&signature, &timestamp, &public_key, b"[]"),
+            Err(AuthError::VerificationFailed)
+        ));
+    }
+}
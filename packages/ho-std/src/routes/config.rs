@@ -50,6 +50,12 @@ impl RouteRegistry {
                 request_type: PromptRequest::type_url(),
                 response_type: PromptResponse::type_url(),
             },
+            RouteDefinition {
+                requires_auth: true,
+                // newline-delimited PromptResponse records
+                request_type: PromptResponse::type_url(),
+                response_type: ImportPromptsResponse::type_url(),
+            },
             RouteDefinition {
                 requires_auth: true,
                 request_type: BootstrapNodeRequest::type_url(),
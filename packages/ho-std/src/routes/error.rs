@@ -0,0 +1,149 @@
+//! JSON error envelope every route answers failures with
+
+use axum::{
+    body::Body,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// A failed request as the client sees it:
+/// `{"error": {"code", "message", "request_id"}}` under `status`.
+///
+/// The response carries the error as an extension, so middleware that knows the request's
+/// correlation id can fill it in with [`ApiError::with_request_id`].
+pub use crate::types::cw_ho::orchestration::v1::ApiError;
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            status: status.as_u16().into(),
+            code: code.into(),
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn bad_request(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn internal(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, code, message)
+    }
+
+    /// The status the error is answered with, 500 for one that is not a valid status
+    pub fn status_code(&self) -> StatusCode {
+        u16::try_from(self.status)
+            .ok()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    pub fn with_hint(mut self, hint: Option<String>) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    /// The envelope, with `request_id` null when the request has none
+    pub fn body(&self, request_id: Option<&str>) -> serde_json::Value {
+        let mut error = serde_json::json!({
+            "code": self.code,
+            "message": self.message,
+            "request_id": request_id,
+        });
+        if let Some(hint) = &self.hint {
+            error["hint"] = hint.clone().into();
+        }
+        serde_json::json!({ "error": error })
+    }
+
+    /// Rewrite an error `response` built from an [`ApiError`] so its body names
+    /// `request_id`. Other responses pass through untouched.
+    pub fn with_request_id(mut response: Response, request_id: &str) -> Response {
+        let Some(error) = response.extensions().get::<ApiError>() else {
+            return response;
+        };
+        let body = error.body(Some(request_id)).to_string();
+        response.headers_mut().remove(header::CONTENT_LENGTH);
+        *response.body_mut() = Body::from(body);
+        response
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {}",
+            self.code,
+            self.status_code(),
+            self.message
+        )
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status_code(), Json(self.body(None))).into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::new(rejection.status(), "INVALID_JSON", rejection.body_text())
+    }
+}
+
+/// [`Json`] whose rejections, such as malformed bodies or missing fields, are answered
+/// in the [`ApiError`] envelope rather than as plain text
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Ok(ApiJson(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn test_request_id_is_filled_into_error_body() {
+        let error = ApiError::bad_request("INVALID_PROMPT", "Prompt messages cannot be empty")
+            .with_hint(Some("send at least one message".to_string()));
+        let response = ApiError::with_request_id(error.into_response(), "trace-me");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": {
+                    "code": "INVALID_PROMPT",
+                    "message": "Prompt messages cannot be empty",
+                    "request_id": "trace-me",
+                    "hint": "send at least one message",
+                }
+            })
+        );
+
+        // Successful responses are left alone
+        let ok = ApiError::with_request_id(Json("fine").into_response(), "trace-me");
+        let body = ok.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"\"fine\"");
+    }
+}
@@ -8,9 +8,11 @@
 
 pub mod auth;
 pub mod config;
+pub mod error;
 
 pub use auth::{AuthError, AuthLayer};
 pub use config::{RouteDefinition, RouteRegistry};
+pub use error::{ApiError, ApiJson};
 
 // Re-export the macro
 pub use crate::define_routes;
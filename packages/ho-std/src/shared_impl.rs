@@ -95,6 +95,10 @@ impl NetworkShareImpl {
             ],
 
             load_factor: 0.5.to_string(), // Default load factor
+            p2p_address: None,
+            protocol_version: None,
+            min_protocol_version: None,
+            seq: None,
         };
 
         NetworkMessage {
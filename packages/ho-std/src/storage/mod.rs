@@ -1,7 +1,12 @@
 use camino::Utf8Path;
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use prost::Message;
+use std::io::{Read, Write};
 
 use crate::constants::DATA_FOLDER_NAME;
-use crate::prelude::StorageConfig;
+use crate::error::{HoError, HoResult};
+use crate::prelude::{QueryRequest, StorageConfig, StorageSnapshot};
 
 impl StorageConfig {
     pub fn new(data_dir: &Utf8Path) -> Self {
@@ -10,3 +15,85 @@ impl StorageConfig {
         memories
     }
 }
+
+/// A `--since`/`--until` bound: an age before `now` such as `90s`, `30m`, `24h`, `7d` or
+/// `2w`, or an RFC 3339 time
+pub fn parse_time_bound(value: &str, now: DateTime<Utc>) -> HoResult<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let invalid = || {
+        HoError::Storage(format!(
+            "invalid time {:?}, expected an age like 24h or 7d, or an RFC 3339 time",
+            value
+        ))
+    };
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let amount: i64 = value[..split].parse().map_err(|_| invalid())?;
+    let age = match &value[split..] {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        "w" => chrono::Duration::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    now.checked_sub_signed(age).ok_or_else(invalid)
+}
+
+impl QueryRequest {
+    /// Resolve `since`/`until` against `now` into `start_time`/`end_time`, replacing any
+    /// range already set
+    pub fn resolve_time_range(&mut self, now: DateTime<Utc>) -> HoResult<()> {
+        if let Some(since) = self.since.take() {
+            self.start_time = Some(parse_time_bound(&since, now)?.into());
+        }
+        if let Some(until) = self.until.take() {
+            self.end_time = Some(parse_time_bound(&until, now)?.into());
+        }
+        Ok(())
+    }
+}
+
+impl StorageSnapshot {
+    /// Gzip the data map into `compressed_data`, leaving `data` empty
+    pub fn compress(&mut self) -> HoResult<()> {
+        let raw = StorageSnapshot {
+            data: std::mem::take(&mut self.data),
+            ..Default::default()
+        }
+        .encode_to_vec();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&raw)?;
+        self.compressed_data = Some(encoder.finish()?);
+        self.raw_size_bytes = Some(raw.len() as u64);
+        Ok(())
+    }
+
+    /// Restore `data` from `compressed_data`, a no-op for uncompressed snapshots
+    pub fn decompress(&mut self) -> HoResult<()> {
+        let Some(compressed) = self.compressed_data.take() else {
+            return Ok(());
+        };
+        let mut raw = Vec::new();
+        GzDecoder::new(compressed.as_slice()).read_to_end(&mut raw)?;
+        let decoded = StorageSnapshot::decode(raw.as_slice())
+            .map_err(|e| HoError::DeSerialization(format!("snapshot data: {}", e)))?;
+        self.data = decoded.data;
+        self.raw_size_bytes = None;
+        Ok(())
+    }
+
+    /// Compressed size over raw size, `None` until the snapshot is compressed
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match (&self.compressed_data, self.raw_size_bytes) {
+            (Some(compressed), Some(raw)) if raw > 0 => Some(compressed.len() as f64 / raw as f64),
+            _ => None,
+        }
+    }
+}
@@ -213,4 +213,7 @@ pub trait NetworkConfigTrait {
 
     /// Check if discovery is enabled
     fn is_discovery_enabled(&self) -> bool;
+
+    /// Seconds of silence before a node is marked offline
+    fn node_stale_after_secs(&self) -> u64;
 }
@@ -1,7 +1,7 @@
 //! LLM-related traits for CW-HO system
 
 use crate::error::HoResult;
-use crate::prelude::LlmEntity;
+use crate::prelude::{HttpResponse, LlmCapabilities, LlmEntity, ModerationVerdict};
 use crate::traits::LLMRouterConfigTrait;
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -194,8 +194,40 @@ pub trait LLMProviderTrait {
 #[async_trait]
 pub trait LlmModelTrait {
     fn models(&self) -> (String, Vec<String>);
-    fn default_base_url(&self) -> String;
+    /// Provider endpoint used when an entity sets none, `None` when it must be configured
+    fn default_base_url(&self) -> Option<String>;
     fn default_entity(&self) -> LlmEntity;
+    /// Largest completion the provider accepts per request
+    fn max_output_tokens(&self) -> u32;
+    /// Whether the provider accepts an OpenAI-style `response_format` parameter
+    fn supports_json_mode(&self) -> bool;
+    /// Request features the provider's known models accept
+    fn capabilities(&self) -> LlmCapabilities;
+    /// Get the lowercase key used for this provider in api-keys.json
+    fn provider_key(&self) -> &'static str;
+    /// Parse a provider from its api-keys.json key
+    fn from_provider_key(key: &str) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+/// Content moderation pass over llm prompts and responses
+#[async_trait]
+pub trait ModerationHook: Send + Sync {
+    /// Inspect text, returning a blocked verdict for disallowed content
+    async fn check(&self, text: &str) -> HoResult<ModerationVerdict>;
+}
+
+/// Counts the tokens a model sees in a piece of text
+pub trait Tokenizer: Send + Sync {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Transport used by the LLM router to reach providers, swappable for tests
+#[async_trait]
+pub trait HttpBackend: Send + Sync {
+    /// Send a built request and read the full response
+    async fn send(&self, request: reqwest::Request) -> HoResult<HttpResponse>;
 }
 
 #[async_trait]
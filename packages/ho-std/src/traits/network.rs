@@ -1,7 +1,7 @@
 //! Network-related traits for CW-HO system
 
 use crate::commonware::error::CommonwareNetworkResult;
-use crate::commonware::identity::NodePrivKey;
+use crate::commonware::identity::{NodePrivKey, NodePubkey};
 use crate::error::HoResult;
 use crate::traits::NetworkConfigTrait;
 use async_trait::async_trait;
@@ -37,9 +37,15 @@ pub trait NodeIdentityTrait {
     /// Get API address
     fn api_address(&self) -> String;
 
+    /// API port as a `u16`, failing when the configured value is out of range
+    fn checked_api_port(&self) -> CommonwareNetworkResult<u16>;
+
     /// Get display-friendly identifier
     fn display_id(&self) -> String;
 
+    /// Canonical node id derived from the public key
+    fn node_id(&self) -> String;
+
     fn get_private_key_from_env() -> NodePrivKey;
     fn private_key_from_hex(hex_string: &str) -> Option<NodePrivKey>;
 }
@@ -89,6 +95,20 @@ pub trait NetworkMessageTrait {
 
     /// Return channel message type identifier
     fn channel(&self) -> HoResult<u8>;
+
+    /// Scheduling priority for outbound queues
+    fn priority(&self) -> crate::prelude::MessagePriority;
+}
+
+/// Handler for one kind of inbound network message
+#[async_trait]
+pub trait MessageHandler: Send + Sync {
+    /// Handle a verified message from `from`, optionally returning a reply to send back
+    async fn handle(
+        &self,
+        from: &NodePubkey,
+        message: &crate::prelude::NetworkMessage,
+    ) -> CommonwareNetworkResult<Option<crate::prelude::NetworkMessage>>;
 }
 
 /// Core trait for minimal network management
@@ -168,6 +168,6 @@ pub trait OrchestratorTrait {
 
     /// Apply golden ratio scaling
     fn apply_golden_ratio_scaling(&self, value: f64) -> f64 {
-        value * 1.618033988749894
+        value * crate::constants::GOLDEN_RATIO
     }
 }
@@ -1,31 +1,635 @@
 use anyhow::Context;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::HashSet;
 use std::path::Path;
-use tracing::{error, info};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
 
+use crate::commonware::identity::NodePrivKey;
 use crate::constants::*;
+use crate::prelude::{EgressPolicy, OrchestrationReport, SshStepResult};
+use crate::python::capabilities::PythonCapabilities;
+use crate::utils::backoff::{retry_async, ExponentialBackoff};
+use crate::utils::shared_config_loader;
 
-/// SSH Connection Manager for orchestration
+/// Fields every node entry in the SSH config must provide
+pub const SSH_REQUIRED_FIELDS: [&str; 2] = ["host", "username"];
+
+/// The SSH node map under `home`, where `init ssh-nodes` writes it and every reader
+/// looks for it
+pub fn ssh_config_path(home: &Utf8Path) -> Utf8PathBuf {
+    home.join(SSH_JSON_PATH)
+}
+
+/// Template SSH node map with a single placeholder node, in the shape read from `SSH_JSON_PATH`.
+/// Keys prefixed with `_` are comments and are ignored by readers.
+pub fn ssh_config_template() -> serde_json::Value {
+    serde_json::json!({
+        "node-1": {
+            "_comment": "Node name is the key used with `--node` and in bootstrap requests",
+            "host": "192.168.1.100",
+            "_host": "IP address or hostname reachable from this machine",
+            "port": 22,
+            "_port": "SSH port, defaults to 22 when omitted",
+            "username": "ubuntu",
+            "_username": "Remote login; workspace is unpacked under /home/<username>/CW-AGENT",
+            "wsl": false,
+            "_wsl": "Set true for Windows hosts, commands are wrapped with `wsl bash -c`",
+            "identity_file": "~/.ssh/id_rsa",
+            "_identity_file": "Private key used when no password is set",
+            "password": "",
+            "_password": "Leave empty to use identity_file; non-empty values require sshpass"
+        }
+    })
+}
+
+/// Validate an SSH node map, returning the number of nodes it defines
+pub fn validate_ssh_config(config: &serde_json::Value) -> Result<usize, anyhow::Error> {
+    let nodes = config
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("SSH config must be a JSON object keyed by node name"))?;
+    if nodes.is_empty() {
+        return Err(anyhow::anyhow!("SSH config defines no nodes"));
+    }
+
+    for (name, node) in nodes {
+        let node = node
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Node {} must be a JSON object", name))?;
+        for field in SSH_REQUIRED_FIELDS {
+            match node.get(field).and_then(|v| v.as_str()) {
+                Some(value) if !value.trim().is_empty() => {}
+                _ => return Err(anyhow::anyhow!("No {} found for node {}", field, name)),
+            }
+        }
+        if let Some(port) = node.get("port") {
+            match port.as_u64() {
+                Some(p) if p > 0 && p <= u16::MAX as u64 => {}
+                _ => return Err(anyhow::anyhow!("Invalid port for node {}: {}", name, port)),
+            }
+        }
+        if let Some(wsl) = node.get("wsl") {
+            if !wsl.is_boolean() {
+                return Err(anyhow::anyhow!("wsl must be a boolean for node {}", name));
+            }
+        }
+        let has_password = node
+            .get("password")
+            .and_then(|v| v.as_str())
+            .is_some_and(|p| !p.is_empty());
+        let has_identity = node
+            .get("identity_file")
+            .and_then(|v| v.as_str())
+            .is_some_and(|p| !p.is_empty());
+        if !has_password && !has_identity {
+            return Err(anyhow::anyhow!(
+                "Node {} needs either an identity_file or a password",
+                name
+            ));
+        }
+    }
+
+    Ok(nodes.len())
+}
+
+/// Confirm `node` can be deployed to before anything is archived or transferred: it must be
+/// in the SSH config, its entry must be complete, and its credentials must be usable
+pub fn preflight_node_config(config: &serde_json::Value, node: &str) -> Result<(), anyhow::Error> {
+    let entry = config.get(node).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Pre-flight failed: node {} is not in the SSH config ({})",
+            node,
+            SSH_JSON_PATH
+        )
+    })?;
+    validate_ssh_config(&serde_json::json!({ node: entry }))
+        .map_err(|e| anyhow::anyhow!("Pre-flight failed: {}", e))?;
+
+    let has_password = entry
+        .get("password")
+        .and_then(|v| v.as_str())
+        .is_some_and(|p| !p.is_empty());
+    if !has_password {
+        let identity = identity_file(entry);
+        let path = match identity.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+            None => identity.into(),
+        };
+        if !path.exists() {
+            return Err(anyhow::anyhow!(
+                "Pre-flight failed: identity file {} for node {} does not exist",
+                identity,
+                node
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reject `archive` if it is over `max_bytes`, before it is sent anywhere. The error names
+/// the archive's largest entries, which are usually build output the excludes missed.
+pub async fn check_archive_size(archive: &Path, max_bytes: u64) -> Result<u64, anyhow::Error> {
+    let size = tokio::fs::metadata(archive)
+        .await
+        .context("Failed to get archive size")?
+        .len();
+    if size <= max_bytes {
+        return Ok(size);
+    }
+    let path = archive.to_path_buf();
+    let largest = tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(path)?;
+        largest_archive_entries(flate2::read::GzDecoder::new(file), ARCHIVE_LARGEST_ENTRIES)
+    })
+    .await?
+    .context("Failed to list archive entries")?
+    .into_iter()
+    .map(|(bytes, path)| format!("\n  {:>12} {}", bytes, path))
+    .collect::<String>();
+    Err(anyhow::anyhow!(
+        "Workspace archive is {} bytes, over the {} byte limit (max_workspace_archive_bytes). \
+         Largest entries:{}",
+        size,
+        max_bytes,
+        largest
+    ))
+}
+
+/// The `limit` largest files in the uncompressed tar stream `archive` as (bytes, path),
+/// biggest first. Read with the `tar` crate rather than a `tar -tv` listing, whose
+/// columns differ between GNU tar, bsdtar and busybox.
+pub fn largest_archive_entries(
+    archive: impl std::io::Read,
+    limit: usize,
+) -> std::io::Result<Vec<(u64, String)>> {
+    let mut entries = Vec::new();
+    for entry in tar::Archive::new(archive).entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            let path = entry.path()?.to_string_lossy().into_owned();
+            entries.push((entry.size(), path));
+        }
+    }
+    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+/// Remote shell command that reads a hex node key from stdin into `<workspace>/priv/`,
+/// readable by the owner only
+pub fn node_key_install_command(workspace: &str) -> String {
+    format!(
+        "umask 077 && mkdir -p {dir}/priv && cat > {dir}/{key} && chmod 600 {dir}/{key}",
+        dir = workspace,
+        key = NODE_KEY_PATH
+    )
+}
+
+/// Shells a manager has started and not yet seen exit, by pid. Each leads its own
+/// process group, so terminating it also ends the ssh or scp client it runs.
+#[derive(Debug, Clone, Default)]
+struct ShellSessions(Arc<Mutex<HashSet<u32>>>);
+
+impl ShellSessions {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<u32>> {
+        self.0.lock().expect("shell sessions poisoned")
+    }
+
+    /// Terminate every running shell, returning how many there were
+    fn terminate_all(&self) -> usize {
+        let pids: Vec<u32> = self.lock().drain().collect();
+        pids.iter().copied().for_each(terminate_group);
+        pids.len()
+    }
+}
+
+/// Send SIGTERM to the process group led by `pid`
+fn terminate_group(pid: u32) {
+    let _ = std::process::Command::new("kill")
+        .args(["-TERM", "--", &format!("-{}", pid)])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+/// Stops tracking a shell once it exits, and terminates it if its command is abandoned first
+struct SessionGuard {
+    sessions: ShellSessions,
+    pid: u32,
+    exited: bool,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        if self.sessions.lock().remove(&self.pid) && !self.exited {
+            terminate_group(self.pid);
+        }
+    }
+}
+
+/// Run `command` through the local shell, feeding `input` on stdin when given.
+/// Returns trimmed stdout, or an error carrying stdout and stderr on failure.
+async fn run_shell(command: &str, input: Option<&[u8]>) -> Result<String, anyhow::Error> {
+    run_tracked_shell(&ShellSessions::default(), command, input).await
+}
+
+/// [`run_shell`], tracked in `sessions` while it runs
+async fn run_tracked_shell(
+    sessions: &ShellSessions,
+    command: &str,
+    input: Option<&[u8]>,
+) -> Result<String, anyhow::Error> {
+    run_tracked_output(sessions, command, input)
+        .await?
+        .into_result()
+}
+
+/// Run `command` through the local shell, tracked in `sessions` while it runs, and return
+/// its output whether or not it succeeded. Fails only when the shell could not be run.
+async fn run_tracked_output(
+    sessions: &ShellSessions,
+    command: &str,
+    input: Option<&[u8]>,
+) -> Result<ShellOutput, anyhow::Error> {
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+
+    let mut shell = tokio::process::Command::new(CMD_BASH);
+    shell
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    shell.process_group(0);
+    let mut child = shell.spawn().context("Failed to spawn shell")?;
+    let mut guard = child.id().map(|pid| {
+        sessions.lock().insert(pid);
+        SessionGuard {
+            sessions: sessions.clone(),
+            pid,
+            exited: false,
+        }
+    });
+    // dropping stdin closes it, so commands reading it see EOF
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    if let Some(input) = input {
+        stdin.write_all(input).await?;
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().await?;
+    if let Some(guard) = guard.as_mut() {
+        guard.exited = true;
+    }
+    Ok(output.into())
+}
+
+/// Output of a finished ssh or scp client
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellOutput {
+    pub success: bool,
+    /// Exit code, `None` when the client was killed by a signal or never started
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl ShellOutput {
+    /// Trimmed stdout on success, an error carrying stdout and stderr otherwise
+    pub fn into_result(self) -> Result<String, anyhow::Error> {
+        match self.success {
+            true => Ok(self.stdout),
+            false => Err(anyhow::anyhow!("{} (stderr: {})", self.stdout, self.stderr)),
+        }
+    }
+}
+
+impl From<std::process::Output> for ShellOutput {
+    fn from(output: std::process::Output) -> Self {
+        Self {
+            success: output.status.success(),
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+    }
+}
+
+/// Why an ssh or scp run failed, deciding whether it is worth running again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SshFailureKind {
+    /// The connection dropped or a name failed to resolve for now
+    Transient,
+    /// Credentials or permissions were refused, which a retry can't change
+    Auth,
+    /// Anything else, such as the remote command itself failing
+    Other,
+}
+
+const AUTH_FAILURE_PATTERNS: &[&str] = &[
+    "permission denied",
+    "authentication failed",
+    "too many authentication failures",
+    "no supported authentication methods",
+    "host key verification failed",
+];
+
+const TRANSIENT_FAILURE_PATTERNS: &[&str] = &[
+    "connection reset",
+    "connection timed out",
+    "operation timed out",
+    "connection closed by",
+    "lost connection",
+    "broken pipe",
+    "temporary failure in name resolution",
+    "network is unreachable",
+    "no route to host",
+    "kex_exchange_identification",
+];
+
+/// Classify a failed ssh/scp run from its exit code and stderr. Auth failures win over
+/// transient ones, so a refused login is never retried.
+pub fn classify_ssh_failure(code: Option<i32>, stderr: &str) -> SshFailureKind {
+    let stderr = stderr.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| stderr.contains(p));
+    // sshpass exits 5 on a wrong password and 6 on an unknown host key
+    if matches!(code, Some(5) | Some(6)) || matches(AUTH_FAILURE_PATTERNS) {
+        return SshFailureKind::Auth;
+    }
+    if matches(TRANSIENT_FAILURE_PATTERNS) {
+        return SshFailureKind::Transient;
+    }
+    SshFailureKind::Other
+}
+
+/// Jittered backoff allowing `retries` more runs after the first
+pub fn ssh_retry_policy(retries: u32) -> impl Iterator<Item = std::time::Duration> {
+    ExponentialBackoff::new(SSH_RETRY_BASE_DELAY, SSH_RETRY_MAX_DELAY)
+        .with_jitter(0.25)
+        .take(retries as usize)
+}
+
+/// Run `op` until it succeeds, waiting out `policy` between transient failures and giving
+/// up at once on any other. Returns the last output with the number of runs made.
+pub async fn retry_transient<Op, Fut>(
+    policy: impl IntoIterator<Item = std::time::Duration>,
+    mut op: Op,
+) -> (ShellOutput, u32)
+where
+    Op: FnMut() -> Fut,
+    Fut: std::future::Future<Output = ShellOutput>,
+{
+    let mut attempts = 0;
+    let should_retry =
+        |failed: &ShellOutput, delay| match classify_ssh_failure(failed.code, &failed.stderr) {
+            SshFailureKind::Transient => {
+                warn!(
+                    "🔁 Transient ssh failure, retrying in {:?}: {}",
+                    delay, failed.stderr
+                );
+                Some(delay)
+            }
+            _ => None,
+        };
+    let result = retry_async(policy, should_retry, || {
+        attempts += 1;
+        let run = op();
+        async move {
+            let output = run.await;
+            match output.success {
+                true => Ok(output),
+                false => Err(output),
+            }
+        }
+    })
+    .await;
+    (result.unwrap_or_else(|failed| failed), attempts)
+}
+
+impl SshStepResult {
+    pub fn ok(step: &str, stdout: impl Into<String>) -> Self {
+        Self {
+            step: step.to_string(),
+            success: true,
+            stdout: stdout.into(),
+            stderr: String::new(),
+            error: None,
+            attempts: None,
+        }
+    }
+
+    pub fn failed(step: &str, error: impl std::fmt::Display) -> Self {
+        Self {
+            step: step.to_string(),
+            success: false,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some(error.to_string()),
+            attempts: None,
+        }
+    }
+
+    /// A step's output on success, its error otherwise
+    pub fn from_result<E: std::fmt::Display>(step: &str, result: Result<String, E>) -> Self {
+        match result {
+            Ok(stdout) => Self::ok(step, stdout),
+            Err(e) => Self::failed(step, e),
+        }
+    }
+}
+
+impl OrchestrationReport {
+    pub fn new(target_node: &str) -> Self {
+        Self {
+            target_node: target_node.to_string(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Append `step`, returning whether it succeeded
+    pub fn push(&mut self, step: SshStepResult) -> bool {
+        let success = step.success;
+        self.steps.push(step);
+        success
+    }
+
+    /// Append the outcome of `step`, returning whether it succeeded
+    pub fn record<E: std::fmt::Display>(&mut self, step: &str, result: Result<String, E>) -> bool {
+        self.push(SshStepResult::from_result(step, result))
+    }
+
+    pub fn successful_steps(&self) -> usize {
+        self.steps.iter().filter(|step| step.success).count()
+    }
+
+    /// Fraction of recorded steps that succeeded, 0 when none ran
+    pub fn success_rate(&self) -> f64 {
+        match self.steps.len() {
+            0 => 0.0,
+            total => self.successful_steps() as f64 / total as f64,
+        }
+    }
+
+    /// First step that failed, if any
+    pub fn failed_step(&self) -> Option<&SshStepResult> {
+        self.steps.iter().find(|step| !step.success)
+    }
+
+    /// The `success_rate`/`orchestration_results` JSON that callers already read, with
+    /// each step's result keyed by its name
+    pub fn summary(&self) -> serde_json::Value {
+        let results: std::collections::BTreeMap<&str, &SshStepResult> = self
+            .steps
+            .iter()
+            .map(|step| (step.step.as_str(), step))
+            .collect();
+        serde_json::json!({
+            "target_node": self.target_node,
+            "success_rate": self.success_rate(),
+            "steps_completed": self.successful_steps(),
+            "total_steps": self.steps.len(),
+            "orchestration_results": results,
+        })
+    }
+}
+
+/// Identity file for a node entry, falling back to the default ssh key
+fn identity_file(node_config: &serde_json::Value) -> &str {
+    node_config
+        .get("identity_file")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .unwrap_or("~/.ssh/id_rsa")
+}
+
+/// SSH Connection Manager for orchestration. Call [`close`](Self::close) when done with
+/// it; dropping it unclosed terminates whatever remote commands it still has running.
 #[derive(Debug)]
 pub struct SSHConnectionManager {
     /// Target node name
     pub target_node: String,
     /// Connection status
     pub is_connected: bool,
+    /// SSH node map the target node's entry is read from
+    pub ssh_config: Utf8PathBuf,
+    /// Hosts connections may be opened to. Unset allows any host in the SSH config
+    pub egress: Option<EgressPolicy>,
+    /// Largest workspace archive that will be transferred
+    pub max_archive_bytes: u64,
+    /// Retries of a transfer or remote command that failed transiently
+    pub transient_retries: u32,
+    /// ssh and scp clients started for this node that are still running
+    sessions: ShellSessions,
+    /// Runs of retried commands since the last bootstrap step was recorded
+    runs: u32,
 }
 
 impl SSHConnectionManager {
-    /// Create new SSH connection manager
-    pub fn new(target_node: String) -> Self {
+    /// Create new SSH connection manager, reading nodes from the SSH config under `home`
+    pub fn new(target_node: String, home: &Utf8Path) -> Self {
         Self {
             target_node,
             is_connected: false,
+            ssh_config: ssh_config_path(home),
+            egress: None,
+            max_archive_bytes: DEFAULT_MAX_WORKSPACE_ARCHIVE_BYTES,
+            transient_retries: DEFAULT_SSH_TRANSIENT_RETRIES,
+            sessions: ShellSessions::default(),
+            runs: 0,
+        }
+    }
+
+    /// Check every node host against `policy` before connecting to it
+    pub fn with_egress(mut self, policy: EgressPolicy) -> Self {
+        self.egress = Some(policy);
+        self
+    }
+
+    /// Refuse to transfer a workspace archive over `max_bytes`
+    pub fn with_max_archive_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_archive_bytes = max_bytes;
+        self
+    }
+
+    /// Retry transfers and remote commands up to `retries` times on transient failures
+    pub fn with_transient_retries(mut self, retries: u32) -> Self {
+        self.transient_retries = retries;
+        self
+    }
+
+    /// Run the ssh or scp `command` through the local shell, rerunning it up to
+    /// `transient_retries` times while it fails transiently. Refused logins and failing
+    /// remote commands are not rerun.
+    async fn run_retrying(
+        &mut self,
+        command: &str,
+        input: Option<&[u8]>,
+    ) -> Result<String, anyhow::Error> {
+        let sessions = self.sessions.clone();
+        let (output, runs) = retry_transient(ssh_retry_policy(self.transient_retries), || {
+            let sessions = sessions.clone();
+            async move {
+                run_tracked_output(&sessions, command, input)
+                    .await
+                    .unwrap_or_else(|e| ShellOutput {
+                        stderr: e.to_string(),
+                        ..Default::default()
+                    })
+            }
+        })
+        .await;
+        self.runs += runs;
+        output.into_result()
+    }
+
+    /// Append `result` to `report` as `step`, with the runs it took when it ran retried
+    /// commands, returning whether it succeeded
+    fn record_step(
+        &mut self,
+        report: &mut OrchestrationReport,
+        step: &str,
+        result: Result<String, anyhow::Error>,
+    ) -> bool {
+        let runs = std::mem::take(&mut self.runs);
+        report.push(SshStepResult {
+            attempts: (runs > 0).then_some(runs),
+            ..SshStepResult::from_result(step, result)
+        })
+    }
+
+    /// Host of the node's SSH config entry, rejected unless the egress policy allows it
+    fn node_host<'a>(
+        &self,
+        node_config: &'a serde_json::Value,
+        purpose: &str,
+    ) -> Result<&'a str, anyhow::Error> {
+        let host = node_config
+            .get("host")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("No host found for node {}", self.target_node))?;
+        if let Some(policy) = &self.egress {
+            policy.check(purpose, host)?;
         }
+        Ok(host)
     }
 
     /// Test SSH connection (simplified approach)
     pub async fn connect(&mut self) -> Result<(), anyhow::Error> {
         info!("🔌 Testing SSH connection to node: {}", self.target_node);
+        if self.egress.is_some() {
+            let ssh_config = shared_config_loader()
+                .json::<serde_json::Value, _>(&self.ssh_config)
+                .context("Failed to load SSH config")?;
+            let node_config = ssh_config.get(&self.target_node).ok_or_else(|| {
+                anyhow::anyhow!("Node {} not found in SSH config", self.target_node)
+            })?;
+            self.node_host(node_config, "ssh")?;
+        }
 
+        PythonCapabilities::detect().require("SSH connection test", &[TOOLS_SSH_TRANSPORT])?;
         let ssh_config = "";
         // Use the existing SSH transport script to test connection
         let ssh_test_command = format!(
@@ -65,6 +669,39 @@ impl SSHConnectionManager {
         }
     }
 
+    /// Check the node is configured, has credentials and answers a trivial command.
+    /// Run before any deploy step so an unreachable node fails early and cleanly.
+    pub async fn preflight(&mut self) -> Result<(), anyhow::Error> {
+        info!(
+            "🛫 Running pre-flight checks for node: {}",
+            self.target_node
+        );
+        let ssh_config = shared_config_loader()
+            .json::<serde_json::Value, _>(&self.ssh_config)
+            .context("Pre-flight failed: could not load SSH config")?;
+        preflight_node_config(&ssh_config, &self.target_node)?;
+
+        let echoed = self
+            .execute_command("echo cw-ho-preflight")
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Pre-flight failed: node {} did not run a test command: {}",
+                    self.target_node,
+                    e
+                )
+            })?;
+        if echoed != "cw-ho-preflight" {
+            return Err(anyhow::anyhow!(
+                "Pre-flight failed: node {} answered the test command with {:?}",
+                self.target_node,
+                echoed
+            ));
+        }
+        info!("✅ Pre-flight passed for node: {}", self.target_node);
+        Ok(())
+    }
+
     /// Execute command via SSH (uses individual SSH calls)
     pub async fn execute_command(&mut self, command: &str) -> Result<String, anyhow::Error> {
         if !self.is_connected {
@@ -72,22 +709,23 @@ impl SSHConnectionManager {
         }
 
         info!("🔧 Executing SSH command: {}", command);
-
-        // Execute command via SSH (reading config dynamically)
-        let ssh_config_content = tokio::fs::read_to_string(SSH_JSON_PATH)
+        let ssh_command = self.ssh_command_line(command)?;
+        self.run_retrying(&ssh_command, None)
             .await
-            .context("Failed to read SSH config")?;
-        let ssh_config: serde_json::Value =
-            serde_json::from_str(&ssh_config_content).context("Failed to parse SSH config")?;
+            .map_err(|e| anyhow::anyhow!("SSH command failed: {}", e))
+    }
+
+    /// Local shell command running `command` on the target node, read from the SSH config
+    fn ssh_command_line(&self, command: &str) -> Result<String, anyhow::Error> {
+        let ssh_config = shared_config_loader()
+            .json::<serde_json::Value, _>(&self.ssh_config)
+            .context("Failed to load SSH config")?;
 
         let node_config = ssh_config
             .get(&self.target_node)
             .ok_or_else(|| anyhow::anyhow!("Node {} not found in SSH config", self.target_node))?;
 
-        let host = node_config
-            .get("host")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("No host found for node {}", self.target_node))?;
+        let host = self.node_host(node_config, "ssh")?;
         let username = node_config
             .get("username")
             .and_then(|v| v.as_str())
@@ -112,37 +750,49 @@ impl SSHConnectionManager {
             command.to_string()
         };
 
-        let ssh_command = if let Some(pwd) = password {
+        Ok(if let Some(pwd) = password.filter(|p| !p.is_empty()) {
             format!(
                 "sshpass -p '{}' ssh -p {} -o StrictHostKeyChecking=no {}@{} '{}'",
                 pwd, port, username, host, final_command
             )
         } else {
             format!(
-                "ssh -i ~/.ssh/id_rsa -p {} -o StrictHostKeyChecking=no {}@{} '{}'",
-                port, username, host, final_command
+                "ssh -i {} -p {} -o StrictHostKeyChecking=no {}@{} '{}'",
+                identity_file(node_config),
+                port,
+                username,
+                host,
+                final_command
             )
-        };
+        })
+    }
 
-        let output = tokio::process::Command::new(CMD_BASH)
-            .arg("-c")
-            .arg(&ssh_command)
-            .output()
-            .await
-            .context("Failed to execute SSH command")?;
+    /// Give the node a fresh identity key. The workspace archive never carries `priv/`, so
+    /// the key is generated here and streamed over SSH stdin into the remote `priv/` with
+    /// mode 0600, keeping it out of the archive, the process list and shell history.
+    /// Returns the node id of the new key.
+    pub async fn provision_node_key(&mut self) -> Result<String, anyhow::Error> {
+        info!(
+            "🔑 Provisioning identity key for node: {}",
+            self.target_node
+        );
+        if !self.is_connected {
+            self.connect().await?;
+        }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let key = NodePrivKey::new(&mut rand::rngs::OsRng);
+        let node_id = key.id().node_id();
+        let ssh_command = self.ssh_command_line(&node_key_install_command(WORKSPACE_HOME))?;
+        let key_hex = hex::encode(key.into_bytes());
+        run_tracked_shell(&self.sessions, &ssh_command, Some(key_hex.as_bytes()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Key provisioning failed: {}", e))?;
 
-        if output.status.success() {
-            Ok(stdout.trim().to_string())
-        } else {
-            Err(anyhow::anyhow!(
-                "SSH command failed: {} (stderr: {})",
-                stdout.trim(),
-                stderr.trim()
-            ))
-        }
+        info!(
+            "✅ Node {} provisioned with node id {}",
+            self.target_node, node_id
+        );
+        Ok(node_id)
     }
 
     /// Execute multiple commands in sequence
@@ -158,12 +808,20 @@ impl SSHConnectionManager {
         Ok(results)
     }
 
-    /// Mark SSH connection as closed
+    /// Close the connection, terminating any remote command still running. The preferred
+    /// way to finish with a manager; dropping it does the same on a best-effort basis.
     pub async fn close(&mut self) -> Result<(), anyhow::Error> {
         info!(
             "🔌 Marking SSH connection closed for node: {}",
             self.target_node
         );
+        let terminated = self.sessions.terminate_all();
+        if terminated > 0 {
+            info!(
+                "🔌 Terminated {} running SSH sessions for node: {}",
+                terminated, self.target_node
+            );
+        }
         self.is_connected = false;
         Ok(())
     }
@@ -180,31 +838,65 @@ impl SSHConnectionManager {
             self.target_node
         );
 
-        // Step 1: Ensure SSH connection
-        if !self.is_connected {
-            self.connect().await?;
+        let report = self.run_bootstrap().await;
+        if let Some(failed) = report.failed_step() {
+            return Err(anyhow::anyhow!(failed.error.clone().unwrap_or_default()));
+        }
+
+        let summary = report
+            .steps
+            .iter()
+            .map(|step| format!("\n- {}: {}", step.step, step.stdout))
+            .collect::<String>();
+        info!(
+            "✅ Bootstrap process completed for node: {}",
+            self.target_node
+        );
+        Ok(format!("Bootstrap completed:{}", summary))
+    }
+
+    /// Run the bootstrap steps in order, recording each and stopping at the first failure
+    /// since every step builds on the one before. Steps that ran ssh or scp record how
+    /// many runs their transient failures took.
+    pub async fn run_bootstrap(&mut self) -> OrchestrationReport {
+        let mut report = OrchestrationReport::new(&self.target_node);
+        self.runs = 0;
+
+        // Step 1: Verify the node is configured and reachable before touching the workspace
+        let preflight = self
+            .preflight()
+            .await
+            .map(|()| "pre-flight passed".to_string());
+        if !self.record_step(&mut report, "preflight", preflight) {
+            return report;
         }
 
         // Step 2: Create and transfer workspace archive
-        let archive_result = self.create_workspace_archive().await?;
-        let transfer_result = self.transfer_workspace().await?;
+        let archive = self.create_workspace_archive().await;
+        if !self.record_step(&mut report, "archive", archive) {
+            return report;
+        }
+        let transfer = self.transfer_workspace().await;
+        if !self.record_step(&mut report, "transfer", transfer) {
+            return report;
+        }
 
         // Step 3: Install development environment
-        let install_result = self.install_dev_environment().await?;
+        let install = self.install_dev_environment().await;
+        if !self.record_step(&mut report, "install", install) {
+            return report;
+        }
 
         // Step 4: Extract workspace and setup
-        let setup_result = self.setup_workspace().await?;
-
-        let summary = format!(
-            "Bootstrap completed:\n- Archive: {}\n- Transfer: {}\n- Install: {}\n- Setup: {}",
-            archive_result, transfer_result, install_result, setup_result
-        );
+        let setup = self.setup_workspace().await;
+        if !self.record_step(&mut report, "setup", setup) {
+            return report;
+        }
 
-        info!(
-            "✅ Bootstrap process completed for node: {}",
-            self.target_node
-        );
-        Ok(summary)
+        // Step 5: The archive carries no priv/, so give the node its own identity
+        let node_id = self.provision_node_key().await;
+        self.record_step(&mut report, "node_id", node_id);
+        report
     }
 
     /// Create compressed workspace archive
@@ -212,7 +904,7 @@ impl SSHConnectionManager {
         info!("📦 Creating workspace archive");
 
         let create_archive_cmd = format!(
-            "cd {} && tar -czf {} --exclude=target --exclude=node_modules --exclude=.git --exclude='*.log' .",
+            "cd {} && tar -czf {} --exclude=target --exclude=node_modules --exclude=.git --exclude=./priv --exclude='*.log' .",
             WORKSPACE_HOME,
             WORKSPACE_ARCHIVE_PATH
         );
@@ -225,20 +917,10 @@ impl SSHConnectionManager {
             .context("Failed to create workspace archive")?;
 
         if output.status.success() {
-            let size_output = tokio::process::Command::new(CMD_BASH)
-                .arg("-c")
-                .arg(&format!(
-                    "ls -lh {} | awk '{{print $5}}'",
-                    WORKSPACE_ARCHIVE_PATH
-                ))
-                .output()
-                .await
-                .context("Failed to get archive size")?;
-
-            let size = String::from_utf8_lossy(&size_output.stdout)
-                .trim()
-                .to_string();
-            Ok(format!("Archive created ({} size)", size))
+            let size =
+                check_archive_size(Path::new(WORKSPACE_ARCHIVE_PATH), self.max_archive_bytes)
+                    .await?;
+            Ok(format!("Archive created ({} bytes)", size))
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
             Err(anyhow::anyhow!("Archive creation failed: {}", stderr))
@@ -250,20 +932,15 @@ impl SSHConnectionManager {
         info!("📤 Transferring workspace to node: {}", self.target_node);
 
         // Read SSH config to get connection details
-        let ssh_config_content = tokio::fs::read_to_string(SSH_JSON_PATH)
-            .await
-            .context("Failed to read SSH config")?;
-        let ssh_config: serde_json::Value =
-            serde_json::from_str(&ssh_config_content).context("Failed to parse SSH config")?;
+        let ssh_config = shared_config_loader()
+            .json::<serde_json::Value, _>(&self.ssh_config)
+            .context("Failed to load SSH config")?;
 
         let node_config = ssh_config
             .get(&self.target_node)
             .ok_or_else(|| anyhow::anyhow!("Node {} not found in SSH config", self.target_node))?;
 
-        let host = node_config
-            .get("host")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("No host found for node {}", self.target_node))?;
+        let host = self.node_host(node_config, "scp")?;
         let username = node_config
             .get("username")
             .and_then(|v| v.as_str())
@@ -275,31 +952,26 @@ impl SSHConnectionManager {
             .unwrap_or(22);
 
         // Use SCP to transfer the archive
-        let scp_command = if let Some(pwd) = password {
+        let scp_command = if let Some(pwd) = password.filter(|p| !p.is_empty()) {
             format!(
                 "sshpass -p '{}' scp -P {} -o StrictHostKeyChecking=no {} {}@{}:~/workspace.tar.gz",
                 pwd, port, WORKSPACE_ARCHIVE_PATH, username, host
             )
         } else {
             format!(
-                "scp -i ~/.ssh/id_rsa -P {} -o StrictHostKeyChecking=no {} {}@{}:~/workspace.tar.gz",
-                port, WORKSPACE_ARCHIVE_PATH, username, host
+                "scp -i {} -P {} -o StrictHostKeyChecking=no {} {}@{}:~/workspace.tar.gz",
+                identity_file(node_config),
+                port,
+                WORKSPACE_ARCHIVE_PATH,
+                username,
+                host
             )
         };
 
-        let output = tokio::process::Command::new(CMD_BASH)
-            .arg("-c")
-            .arg(&scp_command)
-            .output()
+        self.run_retrying(&scp_command, None)
             .await
-            .context("Failed to transfer workspace archive")?;
-
-        if output.status.success() {
-            Ok("Workspace transferred successfully".to_string())
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow::anyhow!("Transfer failed: {}", stderr))
-        }
+            .map_err(|e| anyhow::anyhow!("Transfer failed: {}", e))?;
+        Ok("Workspace transferred successfully".to_string())
     }
 
     /// Install development environment on target node
@@ -307,20 +979,15 @@ impl SSHConnectionManager {
         info!("🛠️ Installing development environment on target node");
 
         // Transfer the installation script first
-        let ssh_config_content = tokio::fs::read_to_string(SSH_JSON_PATH)
-            .await
-            .context("Failed to read SSH config")?;
-        let ssh_config: serde_json::Value =
-            serde_json::from_str(&ssh_config_content).context("Failed to parse SSH config")?;
+        let ssh_config = shared_config_loader()
+            .json::<serde_json::Value, _>(&self.ssh_config)
+            .context("Failed to load SSH config")?;
 
         let node_config = ssh_config
             .get(&self.target_node)
             .ok_or_else(|| anyhow::anyhow!("Node {} not found in SSH config", self.target_node))?;
 
-        let host = node_config
-            .get("host")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("No host found for node {}", self.target_node))?;
+        let host = self.node_host(node_config, "scp")?;
         let username = node_config
             .get("username")
             .and_then(|v| v.as_str())
@@ -332,29 +999,24 @@ impl SSHConnectionManager {
             .unwrap_or(22);
 
         // Transfer installation script
-        let script_transfer_cmd = if let Some(pwd) = password {
+        let script_transfer_cmd = if let Some(pwd) = password.filter(|p| !p.is_empty()) {
             format!(
                 "sshpass -p '{}' scp -P {} -o StrictHostKeyChecking=no tools/deploy/install-dev-environment.sh {}@{}:~/install-dev-environment.sh",
                 pwd, port, username, host
             )
         } else {
             format!(
-                "scp -i ~/.ssh/id_rsa -P {} -o StrictHostKeyChecking=no tools/deploy/install-dev-environment.sh {}@{}:~/install-dev-environment.sh",
-                port, username, host
+                "scp -i {} -P {} -o StrictHostKeyChecking=no tools/deploy/install-dev-environment.sh {}@{}:~/install-dev-environment.sh",
+                identity_file(node_config),
+                port,
+                username,
+                host
             )
         };
 
-        let transfer_output = tokio::process::Command::new(CMD_BASH)
-            .arg("-c")
-            .arg(&script_transfer_cmd)
-            .output()
+        self.run_retrying(&script_transfer_cmd, None)
             .await
-            .context("Failed to transfer installation script")?;
-
-        if !transfer_output.status.success() {
-            let stderr = String::from_utf8_lossy(&transfer_output.stderr);
-            return Err(anyhow::anyhow!("Script transfer failed: {}", stderr));
-        }
+            .map_err(|e| anyhow::anyhow!("Script transfer failed: {}", e))?;
 
         // Execute installation script remotely
         let install_result = self
@@ -396,3 +1058,310 @@ impl SSHConnectionManager {
         ))
     }
 }
+
+impl Drop for SSHConnectionManager {
+    /// Terminating is a plain signal, so no runtime or cleanup task is needed here
+    fn drop(&mut self) {
+        let terminated = self.sessions.terminate_all();
+        if terminated > 0 {
+            warn!(
+                "⚠️ SSH manager for {} dropped without close(), terminated {} running sessions",
+                self.target_node, terminated
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_config_template_is_valid() {
+        assert_eq!(validate_ssh_config(&ssh_config_template()).unwrap(), 1);
+
+        let missing_auth = serde_json::json!({"node-1": {"host": "h", "username": "u"}});
+        assert!(validate_ssh_config(&missing_auth).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_archive_is_rejected_with_its_size() {
+        use rand::RngCore;
+
+        let root = std::env::temp_dir().join(format!("cw-ho-archive-{}", uuid::Uuid::new_v4()));
+        let workspace = root.join("workspace");
+        std::fs::create_dir_all(workspace.join("target")).unwrap();
+        // Random bytes do not compress, so the archive stays about this large
+        let mut artifact = vec![0u8; 64 * 1024];
+        rand::thread_rng().fill_bytes(&mut artifact);
+        std::fs::write(workspace.join("target/app.bin"), &artifact).unwrap();
+        std::fs::write(workspace.join("README.md"), "hello").unwrap();
+        let archive = root.join("workspace.tar.gz");
+        let gz = flate2::write::GzEncoder::new(
+            std::fs::File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(gz);
+        builder.append_dir_all(".", &workspace).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        let size = std::fs::metadata(&archive).unwrap().len();
+
+        assert_eq!(check_archive_size(&archive, size).await.unwrap(), size);
+        let err = check_archive_size(&archive, 1024).await.unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains(&format!("{} bytes, over the 1024 byte limit", size)),
+            "{}",
+            message
+        );
+        // The biggest entry leads the list
+        let listed = message.split("Largest entries:").nth(1).unwrap();
+        assert!(listed.lines().nth(1).unwrap().ends_with("target/app.bin"));
+        assert!(listed.contains(&artifact.len().to_string()));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_preflight_rejects_missing_node() {
+        let err = preflight_node_config(&ssh_config_template(), "node-9").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Pre-flight failed: node node-9 is not in the SSH config ({})",
+                SSH_JSON_PATH
+            )
+        );
+
+        let missing_key = serde_json::json!({"node-1": {
+            "host": "h",
+            "username": "u",
+            "identity_file": "/nonexistent/cw-ho/id_ed25519",
+        }});
+        let err = preflight_node_config(&missing_key, "node-1").unwrap_err();
+        assert!(err.to_string().contains("identity file"));
+
+        let password =
+            serde_json::json!({"node-1": {"host": "h", "username": "u", "password": "pw"}});
+        assert!(preflight_node_config(&password, "node-1").is_ok());
+    }
+
+    #[test]
+    fn test_egress_blocks_unlisted_ssh_host() {
+        let node = serde_json::json!({"host": "203.0.113.9", "username": "u"});
+        let open = SSHConnectionManager::new("node-1".to_string(), Utf8Path::new("."));
+        assert_eq!(open.node_host(&node, "ssh").unwrap(), "203.0.113.9");
+
+        let restricted = open.with_egress(EgressPolicy::new(["192.168.1.100"]));
+        let err = restricted.node_host(&node, "scp").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("scp egress to 203.0.113.9 blocked"));
+        let allowed = serde_json::json!({"host": "192.168.1.100", "username": "u"});
+        assert!(restricted.node_host(&allowed, "ssh").is_ok());
+    }
+
+    #[test]
+    fn test_failed_step_marks_report_unsuccessful() {
+        let mut report = OrchestrationReport::new("node-1");
+        assert!(report.record::<anyhow::Error>("archive", Ok("Archive created".to_string())));
+        assert!(!report.record("transfer", Err(anyhow::anyhow!("scp: connection refused"))));
+
+        let failed = report.failed_step().unwrap();
+        assert_eq!(failed.step, "transfer");
+        assert!(!failed.success);
+        assert_eq!(report.success_rate(), 0.5);
+
+        let json = report.summary();
+        assert_eq!(json["success_rate"], 0.5);
+        assert_eq!(json["steps_completed"], 1);
+        assert_eq!(json["total_steps"], 2);
+        assert_eq!(json["orchestration_results"]["transfer"]["success"], false);
+        assert_eq!(
+            json["orchestration_results"]["transfer"]["error"],
+            "scp: connection refused"
+        );
+        assert_eq!(json["orchestration_results"]["archive"]["success"], true);
+    }
+
+    #[tokio::test]
+    async fn test_transient_scp_failure_retries_and_permission_error_does_not() {
+        let failed = |code, stderr: &str| ShellOutput {
+            success: false,
+            code: Some(code),
+            stderr: stderr.to_string(),
+            ..Default::default()
+        };
+        let ok = ShellOutput {
+            success: true,
+            code: Some(0),
+            ..Default::default()
+        };
+        let no_wait = || std::iter::repeat(std::time::Duration::ZERO).take(3);
+        let scripted = |outputs: Vec<ShellOutput>| {
+            let outputs = Mutex::new(outputs.into_iter());
+            move || {
+                let next = outputs.lock().unwrap().next().expect("ran too often");
+                async move { next }
+            }
+        };
+
+        let reset = "Connection reset by 203.0.113.9 port 22\r\nlost connection";
+        let (output, attempts) = retry_transient(
+            no_wait(),
+            scripted(vec![failed(1, reset), failed(1, reset), ok.clone()]),
+        )
+        .await;
+        assert_eq!(output, ok);
+        assert_eq!(attempts, 3);
+
+        let denied = "u@203.0.113.9: Permission denied (publickey,password).\r\nlost connection";
+        assert_eq!(classify_ssh_failure(Some(1), denied), SshFailureKind::Auth);
+        let (output, attempts) =
+            retry_transient(no_wait(), scripted(vec![failed(1, denied), ok.clone()])).await;
+        assert!(!output.success);
+        assert_eq!(attempts, 1);
+
+        // Transient failures stop once the retries run out
+        let dns = "ssh: Could not resolve hostname node: Temporary failure in name resolution";
+        let (output, attempts) =
+            retry_transient(no_wait(), scripted(vec![failed(255, dns); 4])).await;
+        assert_eq!(output.stderr, dns);
+        assert_eq!(attempts, 4);
+    }
+
+    #[tokio::test]
+    async fn test_manager_reruns_transient_failures_up_to_configured_retries() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-ssh-retry-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Drops the connection on its first run only, like a flaky link
+        let marker = dir.join("dropped");
+        let flaky = format!(
+            "test -f {0} || {{ touch {0}; echo 'lost connection' >&2; exit 255; }}; echo copied",
+            marker.display()
+        );
+
+        let mut manager =
+            SSHConnectionManager::new("cw-ho-test-node".to_string(), Utf8Path::new("."))
+                .with_transient_retries(0);
+        assert!(manager.run_retrying(&flaky, None).await.is_err());
+        let mut report = OrchestrationReport::new("cw-ho-test-node");
+        assert!(!manager.record_step(&mut report, "transfer", Err(anyhow::anyhow!("lost"))));
+        assert_eq!(report.steps[0].attempts, Some(1));
+
+        std::fs::remove_file(&marker).unwrap();
+        let mut manager = manager.with_transient_retries(1);
+        assert_eq!(manager.run_retrying(&flaky, None).await.unwrap(), "copied");
+        assert!(manager.record_step(&mut report, "transfer", Ok("copied".to_string())));
+        assert_eq!(report.steps[1].attempts, Some(2));
+
+        // A refused login is not rerun however many retries are allowed
+        let denied = "echo 'Permission denied (publickey).' >&2; exit 255";
+        assert!(manager.run_retrying(denied, None).await.is_err());
+        assert!(!manager.record_step(&mut report, "setup", Err(anyhow::anyhow!("denied"))));
+        assert_eq!(report.steps[2].attempts, Some(1));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_manager_reads_ssh_config_under_home() {
+        let home = Utf8PathBuf::from_path_buf(
+            std::env::temp_dir().join(format!("cw-ho-ssh-home-{}", uuid::Uuid::new_v4())),
+        )
+        .unwrap();
+        let path = ssh_config_path(&home);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let config = serde_json::json!({"node-1": {"host": "203.0.113.9", "username": "u"}});
+        std::fs::write(&path, config.to_string()).unwrap();
+
+        let manager = SSHConnectionManager::new("node-1".to_string(), &home);
+        assert!(manager
+            .ssh_command_line("true")
+            .unwrap()
+            .contains("u@203.0.113.9"));
+        // Another home has no config, whatever the working directory holds
+        let elsewhere = SSHConnectionManager::new("node-1".to_string(), &home.join("other"));
+        assert!(elsewhere.ssh_command_line("true").is_err());
+        std::fs::remove_dir_all(&home).ok();
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_stops_at_failed_preflight() {
+        let mut manager = SSHConnectionManager::new(
+            "cw-ho-test-unconfigured-node".to_string(),
+            Utf8Path::new("."),
+        );
+        let report = manager.run_bootstrap().await;
+        assert_eq!(report.steps.len(), 1);
+        assert_eq!(report.steps[0].step, "preflight");
+        assert!(!report.steps[0].success);
+        assert_eq!(report.success_rate(), 0.0);
+    }
+
+    // No sshd is assumed on test machines, so the remote half of provisioning runs
+    // through a local shell standing in for the node
+    #[tokio::test]
+    async fn test_key_install_writes_owner_only_key() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let workspace =
+            std::env::temp_dir().join(format!("cw-ho-provision-{}", uuid::Uuid::new_v4()));
+        let key = NodePrivKey::from_seed(11);
+        let key_hex = hex::encode(key.clone().into_bytes());
+
+        let command = node_key_install_command(&workspace.display().to_string());
+        run_shell(&command, Some(key_hex.as_bytes())).await.unwrap();
+
+        let written = workspace.join(NODE_KEY_PATH);
+        let mode = std::fs::metadata(&written).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let stored = NodePrivKey::from_hex(&std::fs::read_to_string(&written).unwrap()).unwrap();
+        assert_eq!(stored.id().node_id(), key.id().node_id());
+
+        assert!(run_shell("echo out; echo oops >&2; exit 3", None)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("oops"));
+        let _ = std::fs::remove_dir_all(&workspace);
+    }
+
+    #[tokio::test]
+    async fn test_drop_without_close_terminates_running_session() {
+        let dir = std::env::temp_dir().join(format!("cw-ho-ssh-drop-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("pid");
+        let alive = |pid: &str| {
+            std::process::Command::new("kill")
+                .args(["-0", pid])
+                .status()
+                .is_ok_and(|s| s.success())
+        };
+
+        // A long remote command, left running by a step that returned early
+        let manager = SSHConnectionManager::new("node-1".to_string(), Utf8Path::new("."));
+        let sessions = manager.sessions.clone();
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let running =
+            tokio::spawn(async move { run_tracked_shell(&sessions, &command, None).await });
+        let sleeper = loop {
+            match std::fs::read_to_string(&pid_file) {
+                Ok(pid) if pid.ends_with('\n') => break pid.trim().to_string(),
+                _ => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        assert!(alive(&sleeper));
+
+        drop(manager);
+        assert!(running.await.unwrap().is_err());
+        // The terminated child is reaped by init, give it a moment
+        for _ in 0..100 {
+            if !alive(&sleeper) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(!alive(&sleeper));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -43,7 +43,7 @@ impl Default for WebSocketConfig {
             bind_address: "0.0.0.0:8080".to_string(),
             max_connections: 1000,
             connection_timeout: Duration::from_secs(30),
-            ping_interval: Duration::from_millis((1000.0 * GOLDEN_RATIO as f64) as u64), // ~1618ms
+            ping_interval: Duration::from_millis((1000.0 * GOLDEN_RATIO) as u64), // ~1618ms
             enable_cors: true,
             custom_headers: HashMap::new(),
         }
@@ -80,6 +80,9 @@ pub struct NetworkConfig {
     pub limits: ::core::option::Option<NetworkLimits>,
     #[prost(message, optional, tag = "10")]
     pub channels: ::core::option::Option<ChannelConfig>,
+    /// Seconds without messages before a node is marked offline
+    #[prost(uint64, optional, tag = "11")]
+    pub node_stale_after_secs: ::core::option::Option<u64>,
 }
 impl ::prost::Name for NetworkConfig {
     const NAME: &'static str = "NetworkConfig";
@@ -94,7 +97,7 @@ impl ::prost::Name for NetworkConfig {
 /// Network Communication Types
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct NetworkMessage {
-    #[prost(oneof = "network_message::MessageType", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[prost(oneof = "network_message::MessageType", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
     pub message_type: ::core::option::Option<network_message::MessageType>,
 }
 /// Nested message and enum types in `NetworkMessage`.
@@ -115,6 +118,8 @@ pub mod network_message {
         Request(super::Request),
         #[prost(message, tag = "7")]
         Response(super::Response),
+        #[prost(message, tag = "8")]
+        GossipRelay(super::GossipRelay),
     }
 }
 impl ::prost::Name for NetworkMessage {
@@ -127,6 +132,53 @@ impl ::prost::Name for NetworkMessage {
         "/hoe.network.v1.NetworkMessage".into()
     }
 }
+/// NetworkMessage signed by the originating node's identity key
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct SignedNetworkMessage {
+    /// protobuf-encoded NetworkMessage
+    #[prost(bytes = "vec", tag = "1")]
+    pub payload: ::prost::alloc::vec::Vec<u8>,
+    /// ed25519 signature over payload
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    /// ed25519 public key of the signer
+    #[prost(bytes = "vec", tag = "3")]
+    pub signer: ::prost::alloc::vec::Vec<u8>,
+}
+impl ::prost::Name for SignedNetworkMessage {
+    const NAME: &'static str = "SignedNetworkMessage";
+    const PACKAGE: &'static str = "hoe.network.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.network.v1.SignedNetworkMessage".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.network.v1.SignedNetworkMessage".into()
+    }
+}
+/// A message passed on beyond its originator's direct peers. Each node relays it to the
+/// peers not yet on its path until the ttl runs out.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct GossipRelay {
+    /// the message as its originating node signed it
+    #[prost(message, optional, tag = "1")]
+    pub original: ::core::option::Option<SignedNetworkMessage>,
+    /// hops left, the relay is not passed on once it reaches 1
+    #[prost(uint32, tag = "2")]
+    pub ttl: u32,
+    /// node ids of the originator and every node that relayed it
+    #[prost(string, repeated, tag = "3")]
+    pub path: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+impl ::prost::Name for GossipRelay {
+    const NAME: &'static str = "GossipRelay";
+    const PACKAGE: &'static str = "hoe.network.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.network.v1.GossipRelay".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.network.v1.GossipRelay".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct TetrahedralPing {
     #[prost(string, tag = "1")]
@@ -254,6 +306,9 @@ pub struct NodeInfo {
     pub online: bool,
     #[prost(uint64, tag = "4")]
     pub last_seen: u64,
+    /// What the node announced it can do, e.g. "minimal"
+    #[prost(string, repeated, tag = "5")]
+    pub capabilities: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 impl ::prost::Name for NodeInfo {
     const NAME: &'static str = "NodeInfo";
@@ -275,6 +330,20 @@ pub struct NodeAnnounce {
     pub capabilities: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     #[prost(string, tag = "4")]
     pub load_factor: ::prost::alloc::string::String,
+    /// p2p socket address the node listens on
+    #[prost(string, optional, tag = "5")]
+    pub p2p_address: ::core::option::Option<::prost::alloc::string::String>,
+    /// Network protocol version the node speaks
+    #[prost(uint32, optional, tag = "6")]
+    pub protocol_version: ::core::option::Option<u32>,
+    /// Oldest protocol version the node still speaks, 1 when absent
+    #[prost(uint32, optional, tag = "7")]
+    pub min_protocol_version: ::core::option::Option<u32>,
+    /// Unix milliseconds the node announced at, rising with each announcement. Signed
+    /// with the rest, so gossip keeps only an origin's newest, fresh announcement;
+    /// announcements without it are not gossiped
+    #[prost(uint64, optional, tag = "8")]
+    pub seq: ::core::option::Option<u64>,
 }
 impl ::prost::Name for NodeAnnounce {
     const NAME: &'static str = "NodeAnnounce";
@@ -411,6 +480,9 @@ impl ::prost::Name for MessageReceived {
 pub struct TopologyChanged {
     #[prost(message, optional, tag = "1")]
     pub topology: ::core::option::Option<NetworkTopology>,
+    /// what changed since the previous snapshot
+    #[prost(message, optional, tag = "2")]
+    pub diff: ::core::option::Option<TopologyDiff>,
 }
 impl ::prost::Name for TopologyChanged {
     const NAME: &'static str = "TopologyChanged";
@@ -422,6 +494,31 @@ impl ::prost::Name for TopologyChanged {
         "/hoe.network.v1.TopologyChanged".into()
     }
 }
+/// Difference between two topology snapshots
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct TopologyDiff {
+    #[prost(message, repeated, tag = "1")]
+    pub added_nodes: ::prost::alloc::vec::Vec<NodeInfo>,
+    #[prost(string, repeated, tag = "2")]
+    pub removed_nodes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// nodes present in both snapshots whose info changed, e.g. going offline
+    #[prost(message, repeated, tag = "3")]
+    pub changed_nodes: ::prost::alloc::vec::Vec<NodeInfo>,
+    #[prost(message, repeated, tag = "4")]
+    pub added_connections: ::prost::alloc::vec::Vec<Connection>,
+    #[prost(message, repeated, tag = "5")]
+    pub removed_connections: ::prost::alloc::vec::Vec<Connection>,
+}
+impl ::prost::Name for TopologyDiff {
+    const NAME: &'static str = "TopologyDiff";
+    const PACKAGE: &'static str = "hoe.network.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.network.v1.TopologyDiff".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.network.v1.TopologyDiff".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct NetworkError {
     #[prost(string, tag = "1")]
@@ -455,6 +552,37 @@ impl ::prost::Name for NetworkTopology {
         "/hoe.network.v1.NetworkTopology".into()
     }
 }
+/// A connected peer as this node sees it
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct PeerStatus {
+    #[prost(message, optional, tag = "1")]
+    pub node_info: ::core::option::Option<NodeInfo>,
+    /// p2p socket address from the peer's announcement
+    #[prost(string, optional, tag = "2")]
+    pub address: ::core::option::Option<::prost::alloc::string::String>,
+    /// Lower of the two nodes' protocol versions
+    #[prost(uint32, tag = "3")]
+    pub protocol_version: u32,
+    /// Unix seconds of the peer's first announcement
+    #[prost(uint64, tag = "4")]
+    pub connected_at: u64,
+    /// Unix seconds of the last message from the peer
+    #[prost(uint64, tag = "5")]
+    pub last_seen: u64,
+    /// Round trip of the latest answered ping, in milliseconds
+    #[prost(double, optional, tag = "6")]
+    pub rtt_ms: ::core::option::Option<f64>,
+}
+impl ::prost::Name for PeerStatus {
+    const NAME: &'static str = "PeerStatus";
+    const PACKAGE: &'static str = "hoe.network.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.network.v1.PeerStatus".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.network.v1.PeerStatus".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct Connection {
     #[prost(string, tag = "1")]
@@ -567,3 +695,114 @@ impl NodeType {
         }
     }
 }
+/// Scheduling priority for outbound network messages
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum MessagePriority {
+    Unspecified = 0,
+    /// Bulk state transfer such as fractal and sandloop syncs
+    Low = 1,
+    Normal = 2,
+    /// Discovery and request/response traffic
+    High = 3,
+    /// Health and tetrahedral pings
+    Critical = 4,
+}
+impl MessagePriority {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "MESSAGE_PRIORITY_UNSPECIFIED",
+            Self::Low => "MESSAGE_PRIORITY_LOW",
+            Self::Normal => "MESSAGE_PRIORITY_NORMAL",
+            Self::High => "MESSAGE_PRIORITY_HIGH",
+            Self::Critical => "MESSAGE_PRIORITY_CRITICAL",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "MESSAGE_PRIORITY_UNSPECIFIED" => Some(Self::Unspecified),
+            "MESSAGE_PRIORITY_LOW" => Some(Self::Low),
+            "MESSAGE_PRIORITY_NORMAL" => Some(Self::Normal),
+            "MESSAGE_PRIORITY_HIGH" => Some(Self::High),
+            "MESSAGE_PRIORITY_CRITICAL" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+}
+/// Variant of a NetworkMessage, which handlers are registered by
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum MessageKind {
+    Unspecified = 0,
+    NodeAnnounce = 1,
+    TaskCoordination = 2,
+    SandloopState = 3,
+    FractalSync = 4,
+    TetrahedralPing = 5,
+    Request = 6,
+    Response = 7,
+    GossipRelay = 8,
+}
+impl MessageKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "MESSAGE_KIND_UNSPECIFIED",
+            Self::NodeAnnounce => "MESSAGE_KIND_NODE_ANNOUNCE",
+            Self::TaskCoordination => "MESSAGE_KIND_TASK_COORDINATION",
+            Self::SandloopState => "MESSAGE_KIND_SANDLOOP_STATE",
+            Self::FractalSync => "MESSAGE_KIND_FRACTAL_SYNC",
+            Self::TetrahedralPing => "MESSAGE_KIND_TETRAHEDRAL_PING",
+            Self::Request => "MESSAGE_KIND_REQUEST",
+            Self::Response => "MESSAGE_KIND_RESPONSE",
+            Self::GossipRelay => "MESSAGE_KIND_GOSSIP_RELAY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "MESSAGE_KIND_UNSPECIFIED" => Some(Self::Unspecified),
+            "MESSAGE_KIND_NODE_ANNOUNCE" => Some(Self::NodeAnnounce),
+            "MESSAGE_KIND_TASK_COORDINATION" => Some(Self::TaskCoordination),
+            "MESSAGE_KIND_SANDLOOP_STATE" => Some(Self::SandloopState),
+            "MESSAGE_KIND_FRACTAL_SYNC" => Some(Self::FractalSync),
+            "MESSAGE_KIND_TETRAHEDRAL_PING" => Some(Self::TetrahedralPing),
+            "MESSAGE_KIND_REQUEST" => Some(Self::Request),
+            "MESSAGE_KIND_RESPONSE" => Some(Self::Response),
+            "MESSAGE_KIND_GOSSIP_RELAY" => Some(Self::GossipRelay),
+            _ => None,
+        }
+    }
+}
@@ -20,6 +20,21 @@ pub struct CosmicTask {
     pub result: ::core::option::Option<::pbjson_types::Struct>,
     #[prost(string, optional, tag = "9")]
     pub error: ::core::option::Option<::prost::alloc::string::String>,
+    /// Ids of tasks whose results this task needs, run first when submitted as a graph
+    #[serde(default)]
+    #[prost(string, repeated, tag = "10")]
+    pub depends_on: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// seconds the task may run before it fails as timed out. unset falls back to
+    /// the node's task_timeout_secs
+    #[prost(uint64, optional, tag = "11")]
+    pub timeout_secs: ::core::option::Option<u64>,
+    /// how a provider is picked for this task's prompt. unset falls back to the
+    /// router's default_strategy
+    #[prost(enumeration = "ModelSelectionStrategy", optional, tag = "12")]
+    pub selection_strategy: ::core::option::Option<i32>,
+    /// handler a custom task runs with, as registered on the node
+    #[prost(string, optional, tag = "13")]
+    pub custom_handler: ::core::option::Option<::prost::alloc::string::String>,
 }
 impl ::prost::Name for CosmicTask {
     const NAME: &'static str = "CosmicTask";
@@ -31,6 +46,94 @@ impl ::prost::Name for CosmicTask {
         "/hoe.orchestration.v1.CosmicTask".into()
     }
 }
+/// What remains of a swept task: enough to audit the outcome without the payload
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct TaskSummary {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(enumeration = "CosmicTaskStatus", tag = "2")]
+    pub status: i32,
+    #[prost(message, optional, tag = "3")]
+    pub created_at: ::core::option::Option<::pbjson_types::Timestamp>,
+    #[prost(message, optional, tag = "4")]
+    pub updated_at: ::core::option::Option<::pbjson_types::Timestamp>,
+    /// blake3 of the task's result and error
+    #[prost(string, tag = "5")]
+    pub result_digest: ::prost::alloc::string::String,
+}
+impl ::prost::Name for TaskSummary {
+    const NAME: &'static str = "TaskSummary";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.TaskSummary".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.TaskSummary".into()
+    }
+}
+/// A file holding a large task output, as listed for its task
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ArtifactInfo {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub bytes: u64,
+}
+impl ::prost::Name for ArtifactInfo {
+    const NAME: &'static str = "ArtifactInfo";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ArtifactInfo".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ArtifactInfo".into()
+    }
+}
+/// How a replayed task result differs from the original, by top-level field
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ResultDiff {
+    /// fields only the original result has
+    #[prost(string, repeated, tag = "1")]
+    pub missing: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// fields only the replayed result has
+    #[prost(string, repeated, tag = "2")]
+    pub added: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// fields both have, with different values
+    #[prost(string, repeated, tag = "3")]
+    pub changed: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+impl ::prost::Name for ResultDiff {
+    const NAME: &'static str = "ResultDiff";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ResultDiff".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ResultDiff".into()
+    }
+}
+/// Outcome of a graceful orchestrator shutdown
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct OrchestratorShutdownSummary {
+    /// Tasks that finished within the grace period
+    #[prost(string, repeated, tag = "1")]
+    pub drained: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Tasks still running at the deadline and forcibly terminated
+    #[prost(string, repeated, tag = "2")]
+    pub terminated: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint64, tag = "3")]
+    pub elapsed_ms: u64,
+}
+impl ::prost::Name for OrchestratorShutdownSummary {
+    const NAME: &'static str = "OrchestratorShutdownSummary";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.OrchestratorShutdownSummary".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.OrchestratorShutdownSummary".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct CosmicContext {
     #[prost(string, tag = "1")]
@@ -79,6 +182,10 @@ pub struct FractalRequirements {
     pub fractal_coherence: f64,
     #[prost(string, repeated, tag = "8")]
     pub expansion_criteria: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// similarity between consecutive sandloop outputs at which the loop stops early.
+    /// unset uses DEFAULT_SANDLOOP_CONVERGENCE
+    #[prost(double, optional, tag = "9")]
+    pub convergence_threshold: ::core::option::Option<f64>,
 }
 impl ::prost::Name for FractalRequirements {
     const NAME: &'static str = "FractalRequirements";
@@ -90,6 +197,51 @@ impl ::prost::Name for FractalRequirements {
         "/hoe.orchestration.v1.FractalRequirements".into()
     }
 }
+/// Tracks successive sandloop outputs and signals when they stop changing, so the loop
+/// can end before recursion_depth once further LLM calls would add little
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct SandloopConvergence {
+    #[prost(double, tag = "1")]
+    pub threshold: f64,
+    /// output of the latest iteration observed
+    #[prost(string, optional, tag = "2")]
+    pub previous: ::core::option::Option<::prost::alloc::string::String>,
+    /// 1-based iteration at which the outputs converged
+    #[prost(uint32, optional, tag = "3")]
+    pub converged_at: ::core::option::Option<u32>,
+}
+impl ::prost::Name for SandloopConvergence {
+    const NAME: &'static str = "SandloopConvergence";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.SandloopConvergence".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.SandloopConvergence".into()
+    }
+}
+/// Agent spawned by fractal agent creation, pinned to one tetrahedral vertex
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct AgentSpec {
+    #[prost(string, tag = "1")]
+    pub agent_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "TetrahedralPosition", tag = "2")]
+    pub tetrahedral_position: i32,
+    #[prost(string, repeated, tag = "3")]
+    pub capabilities: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "4")]
+    pub execution_prompt: ::prost::alloc::string::String,
+}
+impl ::prost::Name for AgentSpec {
+    const NAME: &'static str = "AgentSpec";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.AgentSpec".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.AgentSpec".into()
+    }
+}
 /// LLM and Prompt Types
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct PromptRequest {
@@ -101,6 +253,15 @@ pub struct PromptRequest {
     pub context: ::core::option::Option<PromptContext>,
     #[prost(message, optional, tag = "4")]
     pub llm_config: ::core::option::Option<LlmPromptConfig>,
+    #[prost(message, optional, tag = "5")]
+    pub response_format: ::core::option::Option<ResponseFormat>,
+    /// Provider to route to, by entity name or api-keys.json key, instead of inferring it from the model
+    #[prost(string, optional, tag = "6")]
+    pub provider: ::core::option::Option<::prost::alloc::string::String>,
+    /// tools the model may call. rejected for entities without tool support
+    #[serde(default)]
+    #[prost(message, repeated, tag = "7")]
+    pub tools: ::prost::alloc::vec::Vec<ToolDefinition>,
 }
 impl ::prost::Name for PromptRequest {
     const NAME: &'static str = "PromptRequest";
@@ -112,6 +273,45 @@ impl ::prost::Name for PromptRequest {
         "/hoe.orchestration.v1.PromptRequest".into()
     }
 }
+/// A function the model may call, in OpenAI function-calling shape
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ToolDefinition {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub description: ::prost::alloc::string::String,
+    /// JSON schema of the function's arguments
+    #[prost(string, tag = "3")]
+    pub parameters: ::prost::alloc::string::String,
+}
+impl ::prost::Name for ToolDefinition {
+    const NAME: &'static str = "ToolDefinition";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ToolDefinition".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ToolDefinition".into()
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ResponseFormat {
+    #[prost(enumeration = "ResponseFormatType", tag = "1")]
+    pub format_type: i32,
+    /// JSON schema the completion must satisfy, for RESPONSE_FORMAT_TYPE_JSON_SCHEMA
+    #[prost(string, optional, tag = "2")]
+    pub json_schema: ::core::option::Option<::prost::alloc::string::String>,
+}
+impl ::prost::Name for ResponseFormat {
+    const NAME: &'static str = "ResponseFormat";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ResponseFormat".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ResponseFormat".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct PromptResponse {
     #[prost(bytes = "vec", tag = "1")]
@@ -132,6 +332,12 @@ pub struct PromptResponse {
     pub cost: ::core::option::Option<f64>,
     #[prost(uint64, optional, tag = "9")]
     pub latency_ms: ::core::option::Option<u64>,
+    /// Correlation id of the HTTP request that produced this response
+    #[prost(string, optional, tag = "10")]
+    pub request_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// session, user and thread of the request that produced this response
+    #[prost(message, optional, tag = "11")]
+    pub context: ::core::option::Option<PromptContext>,
 }
 impl ::prost::Name for PromptResponse {
     const NAME: &'static str = "PromptResponse";
@@ -272,6 +478,38 @@ pub struct HoConfig {
     pub storage: ::core::option::Option<StorageConfig>,
     #[prost(message, optional, tag = "4")]
     pub llm: ::core::option::Option<LlmRouterConfig>,
+    /// on-disk logging. unset logs to stdout only
+    #[prost(message, optional, tag = "5")]
+    pub logging: ::core::option::Option<LoggingConfig>,
+    /// hosts LLM requests and SSH/SCP may reach. unset allows the configured
+    /// provider endpoints and SSH nodes
+    #[prost(message, optional, tag = "6")]
+    pub egress: ::core::option::Option<EgressConfig>,
+    /// seconds a task without its own timeout may run. unset lets tasks run until done
+    #[prost(uint64, optional, tag = "7")]
+    pub task_timeout_secs: ::core::option::Option<u64>,
+    /// tokio worker threads of the node runtime. unset keeps the runtime default;
+    /// IO-bound nodes rarely gain from more than the core count
+    #[prost(uint32, optional, tag = "8")]
+    pub worker_threads: ::core::option::Option<u32>,
+    /// upper bound on the runtime's blocking pool. unset keeps the runtime default
+    #[prost(uint32, optional, tag = "9")]
+    pub max_blocking_threads: ::core::option::Option<u32>,
+    /// largest workspace archive bootstrap will transfer over SCP.
+    /// unset uses the 512 MiB default
+    #[prost(uint64, optional, tag = "10")]
+    pub max_workspace_archive_bytes: ::core::option::Option<u64>,
+    /// directory large task outputs such as generated code are written to, leaving
+    /// only a reference in the task result. unset keeps outputs inline
+    #[prost(string, optional, tag = "11")]
+    pub artifact_dir: ::core::option::Option<::prost::alloc::string::String>,
+    /// nodes of the ssh node map registered at once on startup. unset registers 8
+    #[prost(uint32, optional, tag = "12")]
+    pub ssh_registration_concurrency: ::core::option::Option<u32>,
+    /// times bootstrap reruns an ssh or scp step that failed on a dropped connection
+    /// or unresolved host. unset retries 3 times, 0 never retries
+    #[prost(uint32, optional, tag = "13")]
+    pub ssh_transient_retries: ::core::option::Option<u32>,
 }
 impl ::prost::Name for HoConfig {
     const NAME: &'static str = "HoConfig";
@@ -291,6 +529,17 @@ pub struct StorageConfig {
     pub max_size_mb: u32,
     #[prost(bool, tag = "3")]
     pub enable_compression: bool,
+    /// seconds a completed or failed task keeps its full state before the sweep
+    /// reduces it to a TaskSummary. unset keeps task states forever
+    #[prost(uint64, optional, tag = "4")]
+    pub task_retention_secs: ::core::option::Option<u64>,
+    /// where records are kept. unset keeps them in cnidarium under data_dir
+    #[prost(enumeration = "StorageBackend", optional, tag = "5")]
+    pub backend: ::core::option::Option<i32>,
+    /// store each distinct prompt text once under its digest, referenced from
+    /// every response that used it. unset stores prompts inline
+    #[prost(bool, optional, tag = "6")]
+    pub dedupe_prompts: ::core::option::Option<bool>,
 }
 impl ::prost::Name for StorageConfig {
     const NAME: &'static str = "StorageConfig";
@@ -393,6 +642,45 @@ impl ::prost::Name for OpenAiChoice {
         "/hoe.orchestration.v1.OpenAiChoice".into()
     }
 }
+/// A header of an llm provider response
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct HttpHeader {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+impl ::prost::Name for HttpHeader {
+    const NAME: &'static str = "HttpHeader";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.HttpHeader".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.HttpHeader".into()
+    }
+}
+/// Fully read llm provider response returned by an HttpBackend
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct HttpResponse {
+    #[prost(uint32, tag = "1")]
+    pub status: u32,
+    /// in the order received, a name repeated once per value
+    #[prost(message, repeated, tag = "2")]
+    pub headers: ::prost::alloc::vec::Vec<HttpHeader>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub body: ::prost::alloc::vec::Vec<u8>,
+}
+impl ::prost::Name for HttpResponse {
+    const NAME: &'static str = "HttpResponse";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.HttpResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.HttpResponse".into()
+    }
+}
 /// Llm config is the global configuration of all llm models available, and their subconfigurations.
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct LlmRouterConfig {
@@ -406,8 +694,16 @@ pub struct LlmRouterConfig {
     pub timeout_seconds: u64,
     #[prost(uint32, tag = "5")]
     pub max_retries: u32,
-    #[prost(uint32, tag = "6")]
-    pub default_entity: u32,
+    /// llm model of the entity requests naming no provider go to. unset leaves them to the
+    /// highest-priority enabled entity
+    #[prost(uint32, optional, tag = "6")]
+    pub default_entity: ::core::option::Option<u32>,
+    /// content moderation applied to prompts and responses. unset disables it
+    #[prost(message, optional, tag = "7")]
+    pub moderation: ::core::option::Option<ModerationConfig>,
+    /// answer prompts locally without calling any provider, for development
+    #[prost(bool, optional, tag = "8")]
+    pub mock: ::core::option::Option<bool>,
 }
 impl ::prost::Name for LlmRouterConfig {
     const NAME: &'static str = "LlmRouterConfig";
@@ -441,6 +737,26 @@ pub struct LlmEntity {
     pub timeout_seconds: u64,
     #[prost(uint32, tag = "9")]
     pub max_retries: u32,
+    /// max in-flight requests to this entity, excess requests queue. unset means unlimited
+    #[prost(uint32, optional, tag = "10")]
+    pub max_concurrent_requests: ::core::option::Option<u32>,
+    /// TLS settings for self-hosted endpoints behind an internal CA
+    #[prost(message, optional, tag = "11")]
+    pub tls: ::core::option::Option<TlsConfig>,
+    /// features the entity's models accept. unset means the provider's known capabilities
+    #[prost(message, optional, tag = "12")]
+    pub capabilities: ::core::option::Option<LlmCapabilities>,
+    /// keep the prompt text of its answers in storage. unset means true
+    #[prost(bool, optional, tag = "13")]
+    pub store_prompts: ::core::option::Option<bool>,
+    /// keep its answers in storage at all; false still returns them to the caller.
+    /// unset means true
+    #[prost(bool, optional, tag = "14")]
+    pub store_responses: ::core::option::Option<bool>,
+    /// largest completion requested from the entity; larger max_tokens are clamped to it.
+    /// unset or 0 means the provider's known cap
+    #[prost(uint32, optional, tag = "15")]
+    pub max_output_tokens: ::core::option::Option<u32>,
 }
 impl ::prost::Name for LlmEntity {
     const NAME: &'static str = "LlmEntity";
@@ -452,12 +768,453 @@ impl ::prost::Name for LlmEntity {
         "/hoe.orchestration.v1.LlmEntity".into()
     }
 }
+/// Request features an llm entity's models accept
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LlmCapabilities {
+    #[prost(bool, tag = "1")]
+    pub supports_tools: bool,
+    #[prost(bool, tag = "2")]
+    pub supports_vision: bool,
+    /// native response_format parameter; other entities get JSON by instruction
+    #[prost(bool, tag = "3")]
+    pub supports_json_mode: bool,
+    #[prost(bool, tag = "4")]
+    pub supports_streaming: bool,
+}
+impl ::prost::Name for LlmCapabilities {
+    const NAME: &'static str = "LlmCapabilities";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.LlmCapabilities".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.LlmCapabilities".into()
+    }
+}
+/// TLS settings for an llm entity's endpoint
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct TlsConfig {
+    /// PEM file of extra CA certificates to trust
+    #[prost(string, optional, tag = "1")]
+    pub ca_bundle: ::core::option::Option<::prost::alloc::string::String>,
+    /// PEM client certificate chain presented for mutual TLS
+    #[prost(string, optional, tag = "2")]
+    pub client_cert: ::core::option::Option<::prost::alloc::string::String>,
+    /// PEM private key for client_cert
+    #[prost(string, optional, tag = "3")]
+    pub client_key: ::core::option::Option<::prost::alloc::string::String>,
+    /// Skip certificate verification. Development only, warned about whenever a client is built
+    #[prost(bool, optional, tag = "4")]
+    pub danger_accept_invalid_certs: ::core::option::Option<bool>,
+}
+impl ::prost::Name for TlsConfig {
+    const NAME: &'static str = "TlsConfig";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.TlsConfig".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.TlsConfig".into()
+    }
+}
+/// Queue statistics for a single llm entity's concurrency limit
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ProviderQueueMetrics {
+    #[prost(string, tag = "1")]
+    pub provider: ::prost::alloc::string::String,
+    /// requests that acquired a slot
+    #[prost(uint64, tag = "2")]
+    pub acquired: u64,
+    #[prost(uint64, tag = "3")]
+    pub total_wait_ms: u64,
+    #[prost(uint64, tag = "4")]
+    pub max_wait_ms: u64,
+}
+impl ::prost::Name for ProviderQueueMetrics {
+    const NAME: &'static str = "ProviderQueueMetrics";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ProviderQueueMetrics".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ProviderQueueMetrics".into()
+    }
+}
+/// Result of the startup pre-flight request to a single llm entity
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ProviderReadiness {
+    #[prost(string, tag = "1")]
+    pub provider: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub ready: bool,
+    #[prost(string, optional, tag = "3")]
+    pub error: ::core::option::Option<::prost::alloc::string::String>,
+    /// unix seconds of the last check
+    #[prost(uint64, tag = "4")]
+    pub checked_at: u64,
+    #[prost(enumeration = "ProviderHealth", tag = "5")]
+    pub health: i32,
+}
+impl ::prost::Name for ProviderReadiness {
+    const NAME: &'static str = "ProviderReadiness";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ProviderReadiness".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ProviderReadiness".into()
+    }
+}
+/// An llm entity as listed by GET /llm/providers
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LlmEntityStatus {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub enabled: bool,
+    #[prost(uint32, tag = "3")]
+    pub priority: u32,
+    /// requests naming no provider or model go to this entity
+    #[prost(bool, tag = "4")]
+    pub is_default: bool,
+    #[prost(string, tag = "5")]
+    pub default_model: ::prost::alloc::string::String,
+    /// the config's default entity. Differs from is_default while it is disabled and
+    /// requests fall back to the highest-priority enabled entity
+    #[prost(bool, tag = "6")]
+    pub configured_default: bool,
+}
+impl ::prost::Name for LlmEntityStatus {
+    const NAME: &'static str = "LlmEntityStatus";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.LlmEntityStatus".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.LlmEntityStatus".into()
+    }
+}
+/// Body of POST /llm/default
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetDefaultEntityRequest {
+    /// enabled entity to make the default, by name or api-keys.json key
+    #[prost(string, tag = "1")]
+    pub model: ::prost::alloc::string::String,
+}
+impl ::prost::Name for SetDefaultEntityRequest {
+    const NAME: &'static str = "SetDefaultEntityRequest";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.SetDefaultEntityRequest".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.SetDefaultEntityRequest".into()
+    }
+}
+/// Models an enabled entity serves, as listed by GET /llm/models
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LlmProviderModels {
+    #[prost(string, tag = "1")]
+    pub provider: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "2")]
+    pub models: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// model used when a request to this provider names none
+    #[prost(string, tag = "3")]
+    pub default_model: ::prost::alloc::string::String,
+    /// requests naming no provider or model go to this entity
+    #[prost(bool, tag = "4")]
+    pub is_default: bool,
+}
+impl ::prost::Name for LlmProviderModels {
+    const NAME: &'static str = "LlmProviderModels";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.LlmProviderModels".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.LlmProviderModels".into()
+    }
+}
+/// Query of GET /llm/models
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LlmModelsQuery {
+    /// leave out providers the last warm-up marked down
+    #[prost(bool, optional, tag = "1")]
+    pub live: ::core::option::Option<bool>,
+}
+impl ::prost::Name for LlmModelsQuery {
+    const NAME: &'static str = "LlmModelsQuery";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.LlmModelsQuery".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.LlmModelsQuery".into()
+    }
+}
+/// Local content moderation settings for the llm router
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ModerationConfig {
+    /// case-insensitive terms that block a prompt or response
+    #[prost(string, repeated, tag = "1")]
+    pub blocked_keywords: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+impl ::prost::Name for ModerationConfig {
+    const NAME: &'static str = "ModerationConfig";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ModerationConfig".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ModerationConfig".into()
+    }
+}
+/// Outcome of a moderation check on a prompt or response
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ModerationVerdict {
+    #[prost(bool, tag = "1")]
+    pub blocked: bool,
+    /// category that triggered the block
+    #[prost(string, optional, tag = "2")]
+    pub category: ::core::option::Option<::prost::alloc::string::String>,
+}
+impl ::prost::Name for ModerationVerdict {
+    const NAME: &'static str = "ModerationVerdict";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ModerationVerdict".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ModerationVerdict".into()
+    }
+}
+/// Observations counted into buckets by inclusive upper bound
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct Histogram {
+    /// ascending upper bounds; larger observations land in a final, unbounded bucket
+    #[prost(uint64, repeated, tag = "1")]
+    pub bounds: ::prost::alloc::vec::Vec<u64>,
+    /// one per bound, then the unbounded bucket
+    #[prost(uint64, repeated, tag = "2")]
+    pub counts: ::prost::alloc::vec::Vec<u64>,
+    #[prost(uint64, tag = "3")]
+    pub count: u64,
+    #[prost(uint64, tag = "4")]
+    pub sum: u64,
+}
+impl ::prost::Name for Histogram {
+    const NAME: &'static str = "Histogram";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.Histogram".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.Histogram".into()
+    }
+}
+/// Response latency of the requests served by one provider
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ProviderLatencyMetrics {
+    #[prost(string, tag = "1")]
+    pub provider: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub latency_ms: ::core::option::Option<Histogram>,
+}
+impl ::prost::Name for ProviderLatencyMetrics {
+    const NAME: &'static str = "ProviderLatencyMetrics";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ProviderLatencyMetrics".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ProviderLatencyMetrics".into()
+    }
+}
+/// How often one provider failed over or was taken out of routing
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ProviderReliabilityMetrics {
+    #[prost(string, tag = "1")]
+    pub provider: ::prost::alloc::string::String,
+    /// requests passed on to the next provider in the chain after this one failed,
+    /// or to another provider while it was disabled
+    #[prost(uint64, tag = "2")]
+    pub fallbacks: u64,
+    /// times a warm-up found it down, opening its circuit so routing skips it
+    #[prost(uint64, tag = "3")]
+    pub circuit_opened: u64,
+    /// times a warm-up found it back up, closing its circuit again
+    #[prost(uint64, tag = "4")]
+    pub circuit_closed: u64,
+}
+impl ::prost::Name for ProviderReliabilityMetrics {
+    const NAME: &'static str = "ProviderReliabilityMetrics";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ProviderReliabilityMetrics".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ProviderReliabilityMetrics".into()
+    }
+}
+/// Runtime metrics for the llm router
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LlmRouterMetrics {
+    #[prost(message, repeated, tag = "1")]
+    pub queues: ::prost::alloc::vec::Vec<ProviderQueueMetrics>,
+    /// prompt tokens per answered request, as reported by the provider
+    #[prost(message, optional, tag = "2")]
+    pub prompt_tokens: ::core::option::Option<Histogram>,
+    /// completion tokens per answered request
+    #[prost(message, optional, tag = "3")]
+    pub completion_tokens: ::core::option::Option<Histogram>,
+    #[prost(message, repeated, tag = "4")]
+    pub latency: ::prost::alloc::vec::Vec<ProviderLatencyMetrics>,
+    #[prost(message, repeated, tag = "5")]
+    pub reliability: ::prost::alloc::vec::Vec<ProviderReliabilityMetrics>,
+}
+impl ::prost::Name for LlmRouterMetrics {
+    const NAME: &'static str = "LlmRouterMetrics";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.LlmRouterMetrics".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.LlmRouterMetrics".into()
+    }
+}
+/// Latency, throughput and cost of one provider, as measured by cw-ho bench
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct ProviderBench {
+    #[prost(string, tag = "1")]
+    pub provider: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub model: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub requests: u64,
+    /// requests that failed or were answered by another provider
+    #[prost(uint64, tag = "4")]
+    pub errors: u64,
+    #[prost(double, tag = "5")]
+    pub error_rate: f64,
+    #[prost(double, tag = "6")]
+    pub p50_ms: f64,
+    #[prost(double, tag = "7")]
+    pub p95_ms: f64,
+    #[prost(double, tag = "8")]
+    pub p99_ms: f64,
+    /// completion tokens per second of wall time
+    #[prost(double, tag = "9")]
+    pub tokens_per_sec: f64,
+    /// USD, priced from the reported usage
+    #[prost(double, tag = "10")]
+    pub estimated_cost: f64,
+}
+impl ::prost::Name for ProviderBench {
+    const NAME: &'static str = "ProviderBench";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ProviderBench".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ProviderBench".into()
+    }
+}
+/// Tasks running on the node, all counted from one snapshot so they add up
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, ::prost::Message,
+)]
+pub struct ActiveTaskCounts {
+    #[prost(uint64, tag = "1")]
+    pub total: u64,
+    /// tasks carrying fractal requirements
+    #[prost(uint64, tag = "2")]
+    pub fractal: u64,
+    /// tasks with a deadline of their own or the node's
+    #[prost(uint64, tag = "3")]
+    pub with_deadline: u64,
+}
+impl ::prost::Name for ActiveTaskCounts {
+    const NAME: &'static str = "ActiveTaskCounts";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ActiveTaskCounts".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ActiveTaskCounts".into()
+    }
+}
+/// Runtime metrics served on /metrics
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct NodeMetrics {
+    #[prost(message, repeated, tag = "1")]
+    pub queues: ::prost::alloc::vec::Vec<ProviderQueueMetrics>,
+    #[prost(message, repeated, tag = "2")]
+    pub peers: ::prost::alloc::vec::Vec<super::super::network::v1::PeerStatus>,
+    #[prost(message, optional, tag = "3")]
+    pub prompt_tokens: ::core::option::Option<Histogram>,
+    #[prost(message, optional, tag = "4")]
+    pub completion_tokens: ::core::option::Option<Histogram>,
+    #[prost(message, repeated, tag = "5")]
+    pub latency: ::prost::alloc::vec::Vec<ProviderLatencyMetrics>,
+    #[prost(message, repeated, tag = "6")]
+    pub reliability: ::prost::alloc::vec::Vec<ProviderReliabilityMetrics>,
+    #[prost(message, optional, tag = "7")]
+    pub tasks: ::core::option::Option<ActiveTaskCounts>,
+}
+impl ::prost::Name for NodeMetrics {
+    const NAME: &'static str = "NodeMetrics";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.NodeMetrics".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.NodeMetrics".into()
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct EgressConfig {
+    /// host patterns: exact hosts ("api.openai.com"), subdomain wildcards
+    /// ("*.openai.com") or "*" for any host
+    #[prost(string, repeated, tag = "1")]
+    pub allowed_hosts: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+impl ::prost::Name for EgressConfig {
+    const NAME: &'static str = "EgressConfig";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.EgressConfig".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.EgressConfig".into()
+    }
+}
+/// Host patterns every outbound provider request and SSH/SCP connection is checked against
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct EgressPolicy {
+    /// normalized patterns, sorted and deduplicated
+    #[prost(string, repeated, tag = "1")]
+    pub patterns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+impl ::prost::Name for EgressPolicy {
+    const NAME: &'static str = "EgressPolicy";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.EgressPolicy".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.EgressPolicy".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct LoggingConfig {
     #[prost(string, tag = "1")]
     pub level: ::prost::alloc::string::String,
+    /// log file, relative paths resolve under the home directory. rotated daily
     #[prost(string, optional, tag = "2")]
     pub file: ::core::option::Option<::prost::alloc::string::String>,
+    /// "full" (default), "compact" or "json"
+    #[prost(string, optional, tag = "3")]
+    pub format: ::core::option::Option<::prost::alloc::string::String>,
 }
 impl ::prost::Name for LoggingConfig {
     const NAME: &'static str = "LoggingConfig";
@@ -496,6 +1253,8 @@ pub struct HealthResponse {
     pub storage_status: ::prost::alloc::string::String,
     #[prost(string, optional, tag = "5")]
     pub network_status: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "6")]
+    pub llm_providers: ::prost::alloc::vec::Vec<ProviderReadiness>,
 }
 impl ::prost::Name for HealthResponse {
     const NAME: &'static str = "HealthResponse";
@@ -538,14 +1297,57 @@ pub struct QueryPromptsResponse {
     #[prost(uint32, tag = "2")]
     pub total_count: u32,
 }
-impl ::prost::Name for QueryPromptsResponse {
-    const NAME: &'static str = "QueryPromptsResponse";
+impl ::prost::Name for QueryPromptsResponse {
+    const NAME: &'static str = "QueryPromptsResponse";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.QueryPromptsResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.QueryPromptsResponse".into()
+    }
+}
+/// Outcome of a bulk NDJSON prompt import
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
+pub struct ImportPromptsResponse {
+    /// records newly stored
+    #[prost(uint64, tag = "1")]
+    pub imported: u64,
+    /// records whose id was already stored
+    #[prost(uint64, tag = "2")]
+    pub skipped: u64,
+    /// lines that could not be parsed or stored
+    #[prost(uint64, tag = "3")]
+    pub failed: u64,
+    #[prost(message, repeated, tag = "4")]
+    pub errors: ::prost::alloc::vec::Vec<ImportLineError>,
+}
+impl ::prost::Name for ImportPromptsResponse {
+    const NAME: &'static str = "ImportPromptsResponse";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ImportPromptsResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ImportPromptsResponse".into()
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ImportLineError {
+    /// 1-based line number in the request body
+    #[prost(uint64, tag = "1")]
+    pub line: u64,
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+}
+impl ::prost::Name for ImportLineError {
+    const NAME: &'static str = "ImportLineError";
     const PACKAGE: &'static str = "hoe.orchestration.v1";
     fn full_name() -> ::prost::alloc::string::String {
-        "hoe.orchestration.v1.QueryPromptsResponse".into()
+        "hoe.orchestration.v1.ImportLineError".into()
     }
     fn type_url() -> ::prost::alloc::string::String {
-        "/hoe.orchestration.v1.QueryPromptsResponse".into()
+        "/hoe.orchestration.v1.ImportLineError".into()
     }
 }
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -799,6 +1601,110 @@ impl ::prost::Name for BootstrapNodeResponse {
         "/hoe.orchestration.v1.BootstrapNodeResponse".into()
     }
 }
+/// Outcome of one deploy step on a node
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SshStepResult {
+    #[prost(string, tag = "1")]
+    pub step: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    #[prost(string, tag = "3")]
+    pub stdout: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub stderr: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "5")]
+    pub error: ::core::option::Option<::prost::alloc::string::String>,
+    /// runs it took, for steps retried on transient failures
+    #[prost(uint32, optional, tag = "6")]
+    pub attempts: ::core::option::Option<u32>,
+}
+impl ::prost::Name for SshStepResult {
+    const NAME: &'static str = "SshStepResult";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.SshStepResult".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.SshStepResult".into()
+    }
+}
+/// Step results of one orchestration run against a node, in execution order
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct OrchestrationReport {
+    #[prost(string, tag = "1")]
+    pub target_node: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub steps: ::prost::alloc::vec::Vec<SshStepResult>,
+}
+impl ::prost::Name for OrchestrationReport {
+    const NAME: &'static str = "OrchestrationReport";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.OrchestrationReport".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.OrchestrationReport".into()
+    }
+}
+/// A node of the ssh node map, registered under a generated id
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ConfigNode {
+    /// key of the node in the ssh node map
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub node_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "TetrahedralPosition", tag = "3")]
+    pub position: i32,
+    #[prost(string, tag = "4")]
+    pub host: ::prost::alloc::string::String,
+}
+impl ::prost::Name for ConfigNode {
+    const NAME: &'static str = "ConfigNode";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ConfigNode".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ConfigNode".into()
+    }
+}
+/// A node of the ssh node map that could not be registered
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ConfigNodeFailure {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+}
+impl ::prost::Name for ConfigNodeFailure {
+    const NAME: &'static str = "ConfigNodeFailure";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ConfigNodeFailure".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ConfigNodeFailure".into()
+    }
+}
+/// Outcome of registering the nodes of an ssh node map, by node name
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SshRegistrationReport {
+    #[prost(message, repeated, tag = "1")]
+    pub registered: ::prost::alloc::vec::Vec<ConfigNode>,
+    #[prost(message, repeated, tag = "2")]
+    pub failed: ::prost::alloc::vec::Vec<ConfigNodeFailure>,
+}
+impl ::prost::Name for SshRegistrationReport {
+    const NAME: &'static str = "SshRegistrationReport";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.SshRegistrationReport".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.SshRegistrationReport".into()
+    }
+}
 /// Fractal creation endpoint
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct CreateFractalRequest {
@@ -913,6 +1819,31 @@ impl ::prost::Name for GetTopologyResponse {
         "/hoe.orchestration.v1.GetTopologyResponse".into()
     }
 }
+/// A failed request, answered as {"error": {"code", "message", "request_id", "hint"}}
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ApiError {
+    /// HTTP status the error is answered with
+    #[prost(uint32, tag = "1")]
+    pub status: u32,
+    /// stable, machine-readable cause such as INVALID_PROMPT
+    #[prost(string, tag = "2")]
+    pub code: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub message: ::prost::alloc::string::String,
+    /// what the operator can do about it, when known
+    #[prost(string, optional, tag = "4")]
+    pub hint: ::core::option::Option<::prost::alloc::string::String>,
+}
+impl ::prost::Name for ApiError {
+    const NAME: &'static str = "ApiError";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ApiError".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ApiError".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct RouteMetadata {
     #[prost(string, tag = "1")]
@@ -968,6 +1899,9 @@ pub struct ApiKeysJson {
     pub global_settings: ::core::option::Option<GlobalSettings>,
     #[prost(message, optional, tag = "4")]
     pub instructions: ::core::option::Option<Instructions>,
+    /// provider key picked as the default in the interactive setup
+    #[prost(string, optional, tag = "5")]
+    pub default_provider: ::core::option::Option<::prost::alloc::string::String>,
 }
 impl ::prost::Name for ApiKeysJson {
     const NAME: &'static str = "ApiKeysJson";
@@ -979,6 +1913,28 @@ impl ::prost::Name for ApiKeysJson {
         "/hoe.orchestration.v1.ApiKeysJson".into()
     }
 }
+/// A provider setup the router would reject, caught before the config is saved
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ApiKeysIssue {
+    #[prost(enumeration = "ApiKeysIssueKind", tag = "1")]
+    pub kind: i32,
+    /// provider key the issue is about
+    #[prost(string, tag = "2")]
+    pub provider: ::prost::alloc::string::String,
+    /// default model missing from the provider's models
+    #[prost(string, optional, tag = "3")]
+    pub model: ::core::option::Option<::prost::alloc::string::String>,
+}
+impl ::prost::Name for ApiKeysIssue {
+    const NAME: &'static str = "ApiKeysIssue";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ApiKeysIssue".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ApiKeysIssue".into()
+    }
+}
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct ApiKeysMetadata {
     #[prost(string, tag = "1")]
@@ -1017,6 +1973,27 @@ impl ::prost::Name for ProviderWithAuth {
         "/hoe.orchestration.v1.ProviderWithAuth".into()
     }
 }
+/// A configured provider bound to its resolved credential, as the router sees it
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ResolvedProvider {
+    #[prost(enumeration = "LlmModel", tag = "1")]
+    pub model: i32,
+    #[prost(message, optional, tag = "2")]
+    pub entity: ::core::option::Option<LlmEntity>,
+    /// credential with any ${ENV_VAR} reference expanded, unset when there is none
+    #[prost(string, optional, tag = "3")]
+    pub api_key: ::core::option::Option<::prost::alloc::string::String>,
+}
+impl ::prost::Name for ResolvedProvider {
+    const NAME: &'static str = "ResolvedProvider";
+    const PACKAGE: &'static str = "hoe.orchestration.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.orchestration.v1.ResolvedProvider".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.orchestration.v1.ResolvedProvider".into()
+    }
+}
 #[derive(
     serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, ::prost::Message,
 )]
@@ -1077,6 +2054,11 @@ pub enum OrchestrateTask {
     Unspecified = 0,
     Bootstrap = 1,
     Recursive = 2,
+    /// run by the handler the task names in custom_handler
+    Custom = 3,
+    /// answered again with each answer fed back, until successive answers converge or
+    /// fractal_requirements.recursion_depth iterations ran
+    Sandloop = 4,
 }
 impl OrchestrateTask {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -1088,6 +2070,8 @@ impl OrchestrateTask {
             Self::Unspecified => "ORCHESTRATE_TASK_UNSPECIFIED",
             Self::Bootstrap => "ORCHESTRATE_TASK_BOOTSTRAP",
             Self::Recursive => "ORCHESTRATE_TASK_RECURSIVE",
+            Self::Custom => "ORCHESTRATE_TASK_CUSTOM",
+            Self::Sandloop => "ORCHESTRATE_TASK_SANDLOOP",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -1096,6 +2080,8 @@ impl OrchestrateTask {
             "ORCHESTRATE_TASK_UNSPECIFIED" => Some(Self::Unspecified),
             "ORCHESTRATE_TASK_BOOTSTRAP" => Some(Self::Bootstrap),
             "ORCHESTRATE_TASK_RECURSIVE" => Some(Self::Recursive),
+            "ORCHESTRATE_TASK_CUSTOM" => Some(Self::Custom),
+            "ORCHESTRATE_TASK_SANDLOOP" => Some(Self::Sandloop),
             _ => None,
         }
     }
@@ -1153,6 +2139,96 @@ impl CosmicTaskStatus {
         }
     }
 }
+/// The four vertices of the orchestration tetrahedron
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum TetrahedralPosition {
+    Unspecified = 0,
+    Coordinator = 1,
+    Executor = 2,
+    Referee = 3,
+    Development = 4,
+}
+impl TetrahedralPosition {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "TETRAHEDRAL_POSITION_UNSPECIFIED",
+            Self::Coordinator => "TETRAHEDRAL_POSITION_COORDINATOR",
+            Self::Executor => "TETRAHEDRAL_POSITION_EXECUTOR",
+            Self::Referee => "TETRAHEDRAL_POSITION_REFEREE",
+            Self::Development => "TETRAHEDRAL_POSITION_DEVELOPMENT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "TETRAHEDRAL_POSITION_UNSPECIFIED" => Some(Self::Unspecified),
+            "TETRAHEDRAL_POSITION_COORDINATOR" => Some(Self::Coordinator),
+            "TETRAHEDRAL_POSITION_EXECUTOR" => Some(Self::Executor),
+            "TETRAHEDRAL_POSITION_REFEREE" => Some(Self::Referee),
+            "TETRAHEDRAL_POSITION_DEVELOPMENT" => Some(Self::Development),
+            _ => None,
+        }
+    }
+}
+/// Shape the completion must take
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum ResponseFormatType {
+    Text = 0,
+    JsonObject = 1,
+    JsonSchema = 2,
+}
+impl ResponseFormatType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Text => "RESPONSE_FORMAT_TYPE_TEXT",
+            Self::JsonObject => "RESPONSE_FORMAT_TYPE_JSON_OBJECT",
+            Self::JsonSchema => "RESPONSE_FORMAT_TYPE_JSON_SCHEMA",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "RESPONSE_FORMAT_TYPE_TEXT" => Some(Self::Text),
+            "RESPONSE_FORMAT_TYPE_JSON_OBJECT" => Some(Self::JsonObject),
+            "RESPONSE_FORMAT_TYPE_JSON_SCHEMA" => Some(Self::JsonSchema),
+            _ => None,
+        }
+    }
+}
 #[derive(
     serde::Serialize,
     serde::Deserialize,
@@ -1220,6 +2296,46 @@ impl LlmModel {
     ::prost::Enumeration,
 )]
 #[repr(i32)]
+pub enum StorageBackend {
+    /// persistent cnidarium database
+    Cnidarium = 0,
+    /// process memory, lost when the node stops
+    Memory = 1,
+}
+impl StorageBackend {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Cnidarium => "STORAGE_BACKEND_CNIDARIUM",
+            Self::Memory => "STORAGE_BACKEND_MEMORY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "STORAGE_BACKEND_CNIDARIUM" => Some(Self::Cnidarium),
+            "STORAGE_BACKEND_MEMORY" => Some(Self::Memory),
+            _ => None,
+        }
+    }
+}
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
 pub enum ModelSelectionStrategy {
     Unspecified = 0,
     /// Always use highest priority available
@@ -1257,6 +2373,57 @@ impl ModelSelectionStrategy {
         }
     }
 }
+/// Why an llm entity is or is not ready to serve requests
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum ProviderHealth {
+    Unspecified = 0,
+    Ready = 1,
+    /// configured, but the endpoint could not be reached or failed
+    Unreachable = 2,
+    /// reached, but the api key was missing or rejected
+    AuthError = 3,
+    /// no base_url or default model to probe
+    Misconfigured = 4,
+}
+impl ProviderHealth {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "PROVIDER_HEALTH_UNSPECIFIED",
+            Self::Ready => "PROVIDER_HEALTH_READY",
+            Self::Unreachable => "PROVIDER_HEALTH_UNREACHABLE",
+            Self::AuthError => "PROVIDER_HEALTH_AUTH_ERROR",
+            Self::Misconfigured => "PROVIDER_HEALTH_MISCONFIGURED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PROVIDER_HEALTH_UNSPECIFIED" => Some(Self::Unspecified),
+            "PROVIDER_HEALTH_READY" => Some(Self::Ready),
+            "PROVIDER_HEALTH_UNREACHABLE" => Some(Self::Unreachable),
+            "PROVIDER_HEALTH_AUTH_ERROR" => Some(Self::AuthError),
+            "PROVIDER_HEALTH_MISCONFIGURED" => Some(Self::Misconfigured),
+            _ => None,
+        }
+    }
+}
 /// Route Metadata and Configuration
 #[derive(
     serde::Serialize,
@@ -1308,3 +2475,52 @@ impl HttpMethod {
         }
     }
 }
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum ApiKeysIssueKind {
+    Unspecified = 0,
+    /// enabled but lists no models
+    NoModels = 1,
+    /// default model is not among its models
+    DefaultModelNotListed = 2,
+    /// chosen as the default provider but not enabled
+    DefaultProviderDisabled = 3,
+}
+impl ApiKeysIssueKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "API_KEYS_ISSUE_KIND_UNSPECIFIED",
+            Self::NoModels => "API_KEYS_ISSUE_KIND_NO_MODELS",
+            Self::DefaultModelNotListed => "API_KEYS_ISSUE_KIND_DEFAULT_MODEL_NOT_LISTED",
+            Self::DefaultProviderDisabled => "API_KEYS_ISSUE_KIND_DEFAULT_PROVIDER_DISABLED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "API_KEYS_ISSUE_KIND_UNSPECIFIED" => Some(Self::Unspecified),
+            "API_KEYS_ISSUE_KIND_NO_MODELS" => Some(Self::NoModels),
+            "API_KEYS_ISSUE_KIND_DEFAULT_MODEL_NOT_LISTED" => Some(Self::DefaultModelNotListed),
+            "API_KEYS_ISSUE_KIND_DEFAULT_PROVIDER_DISABLED" => {
+                Some(Self::DefaultProviderDisabled)
+            }
+            _ => None,
+        }
+    }
+}
@@ -60,6 +60,12 @@ pub struct StorageSnapshot {
     #[prost(map = "string, bytes", tag = "5")]
     pub data:
         ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::vec::Vec<u8>>,
+    /// gzip of the encoded data map; when set, data is left empty
+    #[prost(bytes = "vec", optional, tag = "6")]
+    pub compressed_data: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// encoded size of the data map before compression
+    #[prost(uint64, optional, tag = "7")]
+    pub raw_size_bytes: ::core::option::Option<u64>,
 }
 impl ::prost::Name for StorageSnapshot {
     const NAME: &'static str = "StorageSnapshot";
@@ -83,6 +89,21 @@ pub struct StorageMetrics {
     pub last_compaction: ::core::option::Option<::pbjson_types::Timestamp>,
     #[prost(double, tag = "5")]
     pub fragmentation_ratio: f64,
+    /// compressed over raw size of the latest snapshot
+    #[prost(double, optional, tag = "6")]
+    pub compression_ratio: ::core::option::Option<f64>,
+    /// task states still holding their full payload
+    #[prost(uint64, optional, tag = "7")]
+    pub tasks_retained: ::core::option::Option<u64>,
+    /// task states reduced to a summary by the retention sweep
+    #[prost(uint64, optional, tag = "8")]
+    pub tasks_swept: ::core::option::Option<u64>,
+    /// distinct prompt texts stored under their digest
+    #[prost(uint64, optional, tag = "9")]
+    pub prompt_bodies: ::core::option::Option<u64>,
+    /// prompt text bytes not written because an identical body was already stored
+    #[prost(uint64, optional, tag = "10")]
+    pub prompt_dedup_saved_bytes: ::core::option::Option<u64>,
 }
 impl ::prost::Name for StorageMetrics {
     const NAME: &'static str = "StorageMetrics";
@@ -94,6 +115,52 @@ impl ::prost::Name for StorageMetrics {
         "/hoe.storage.v1.StorageMetrics".into()
     }
 }
+/// What opening the write-ahead log found
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, ::prost::Message,
+)]
+pub struct WalRecovery {
+    /// intents never marked done, committed again
+    #[prost(uint64, tag = "1")]
+    pub replayed: u64,
+    /// lines cut short by a crash mid-append, dropped
+    #[prost(uint64, tag = "2")]
+    pub torn: u64,
+}
+impl ::prost::Name for WalRecovery {
+    const NAME: &'static str = "WalRecovery";
+    const PACKAGE: &'static str = "hoe.storage.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.storage.v1.WalRecovery".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.storage.v1.WalRecovery".into()
+    }
+}
+/// What a gc run reclaimed
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, ::prost::Message,
+)]
+pub struct GcReport {
+    /// prompts deleted, with their indexes
+    #[prost(uint64, tag = "1")]
+    pub prompts_pruned: u64,
+    /// records left unreferenced, such as deduplicated prompt bodies, deleted after
+    #[prost(uint64, tag = "2")]
+    pub records_compacted: u64,
+    #[prost(uint64, tag = "3")]
+    pub bytes_reclaimed: u64,
+}
+impl ::prost::Name for GcReport {
+    const NAME: &'static str = "GcReport";
+    const PACKAGE: &'static str = "hoe.storage.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "hoe.storage.v1.GcReport".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "/hoe.storage.v1.GcReport".into()
+    }
+}
 /// Keep existing API types for backward compatibility
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct QueryRequest {
@@ -107,6 +174,16 @@ pub struct QueryRequest {
     pub end_time: ::core::option::Option<::pbjson_types::Timestamp>,
     #[prost(uint32, optional, tag = "5")]
     pub limit: ::core::option::Option<u32>,
+    /// lower bound as an age before now such as 24h or 7d, or an RFC 3339 time.
+    /// resolved into start_time
+    #[prost(string, optional, tag = "6")]
+    pub since: ::core::option::Option<::prost::alloc::string::String>,
+    /// upper bound in the same forms, resolved into end_time
+    #[prost(string, optional, tag = "7")]
+    pub until: ::core::option::Option<::prost::alloc::string::String>,
+    /// only responses to requests in this conversation thread
+    #[prost(string, optional, tag = "8")]
+    pub thread_id: ::core::option::Option<::prost::alloc::string::String>,
 }
 impl ::prost::Name for QueryRequest {
     const NAME: &'static str = "QueryRequest";
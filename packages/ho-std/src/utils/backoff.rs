@@ -0,0 +1,180 @@
+//! Exponential backoff with jitter, and a retry loop driven by it
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::future::Future;
+use std::time::Duration;
+
+/// Endless sequence of delays growing by `multiplier` from `base` up to `max`. With
+/// jitter each delay is scaled down by a random fraction of up to `jitter`, so clients
+/// failing together do not retry together. Bound the attempts with `take`.
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    max: Duration,
+    multiplier: f64,
+    jitter: f64,
+    rng: StdRng,
+    current: Duration,
+}
+
+impl ExponentialBackoff {
+    /// Doubling delays from `base`, capped at `max`, without jitter
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            max,
+            multiplier: 2.0,
+            jitter: 0.0,
+            rng: StdRng::from_entropy(),
+            current: base.min(max),
+        }
+    }
+
+    /// Growth factor between delays; values below 1 are treated as 1
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Largest fraction, between 0 and 1, randomly taken off each delay
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Draw the jitter from a fixed seed, making the sequence reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Iterator for ExponentialBackoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay = self.current;
+        // whole nanoseconds keep integer multipliers exact
+        let grown = self.current.as_nanos() as f64 * self.multiplier;
+        self.current = Duration::from_nanos(grown.min(self.max.as_nanos() as f64) as u64);
+        if self.jitter == 0.0 {
+            return Some(delay);
+        }
+        let shaved = self.rng.gen_range(0.0..=self.jitter);
+        Some(delay.mul_f64(1.0 - shaved))
+    }
+}
+
+/// Run `op` until it succeeds, waiting between attempts. After each failure
+/// `should_retry` gets the error and the policy's next delay, and returns how long to
+/// actually wait, or `None` to give up with the error. Once `policy` runs out the last
+/// error is returned.
+pub async fn retry_async<T, E, Op, Fut, R>(
+    policy: impl IntoIterator<Item = Duration>,
+    mut should_retry: R,
+    mut op: Op,
+) -> Result<T, E>
+where
+    Op: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    R: FnMut(&E, Duration) -> Option<Duration>,
+{
+    let mut delays = policy.into_iter();
+    loop {
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        match delays.next().and_then(|delay| should_retry(&err, delay)) {
+            Some(delay) => tokio::time::sleep(delay).await,
+            None => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn millis(delays: impl Iterator<Item = Duration>) -> Vec<u128> {
+        delays.map(|delay| delay.as_millis()).collect()
+    }
+
+    #[test]
+    fn test_delays_grow_to_the_cap() {
+        let backoff = ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(millis(backoff.take(6)), [100, 200, 400, 800, 1000, 1000]);
+
+        let backoff = ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1))
+            .with_multiplier(3.0);
+        assert_eq!(millis(backoff.take(4)), [100, 300, 900, 1000]);
+
+        // A base above the cap starts at the cap
+        let backoff = ExponentialBackoff::new(Duration::from_secs(5), Duration::from_secs(1));
+        assert_eq!(millis(backoff.take(2)), [1000, 1000]);
+    }
+
+    #[test]
+    fn test_seeded_jitter_is_reproducible_and_bounded() {
+        let jittered = |seed| {
+            ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1))
+                .with_jitter(0.5)
+                .with_seed(seed)
+                .take(8)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(jittered(7), jittered(7));
+        assert_ne!(jittered(7), jittered(8));
+
+        let plain = ExponentialBackoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        for (delay, full) in jittered(7).into_iter().zip(plain) {
+            assert!(
+                delay <= full && delay >= full / 2,
+                "{:?} vs {:?}",
+                delay,
+                full
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_success_refusal_or_exhaustion() {
+        let policy =
+            || ExponentialBackoff::new(Duration::from_millis(10), Duration::from_millis(40));
+        let attempts = AtomicU32::new(0);
+        let flaky = || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err("busy"),
+                n => Ok(n),
+            }
+        };
+
+        let started = std::time::Instant::now();
+        let mut waited = Vec::new();
+        let result = retry_async(
+            policy(),
+            |_: &&str, delay| {
+                waited.push(delay);
+                Some(delay)
+            },
+            flaky,
+        )
+        .await;
+        assert_eq!(result, Ok(2));
+        assert_eq!(
+            waited,
+            [Duration::from_millis(10), Duration::from_millis(20)]
+        );
+        assert!(started.elapsed() >= Duration::from_millis(30));
+
+        // A refused error is returned at once
+        attempts.store(0, Ordering::SeqCst);
+        let result = retry_async(policy(), |_: &&str, _| None, flaky).await;
+        assert_eq!(result, Err("busy"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // So is the last error once the policy runs out
+        attempts.store(0, Ordering::SeqCst);
+        let result = retry_async(policy().take(1), |_: &&str, delay| Some(delay), flaky).await;
+        assert_eq!(result, Err("busy"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
@@ -4,6 +4,8 @@
 //! used throughout the CW-HO system, respecting the sacred geometric, fractal requirements
 //! of the workspace for interoperability and effectiveness in organization.
 
+pub mod backoff;
+
 use crate::{
     constants::ENV_KEYS,
     error::{HoError, HoResult},
@@ -12,9 +14,14 @@ use crate::{
 use chrono::{DateTime, Utc};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
+    any::{Any, TypeId},
     collections::HashMap,
     fs,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
     time::{SystemTime, UNIX_EPOCH},
 };
 use uuid::Uuid;
@@ -81,6 +88,98 @@ impl ConfigLoaderTrait for DefaultFileOps {
     }
 }
 
+/// A parsed file and the metadata it was parsed from
+struct CachedFile {
+    modified: SystemTime,
+    len: u64,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+/// Config loader that memoizes parsed files by path and type, re-parsing only
+/// when the file's modification time or size changes.
+#[derive(Default)]
+pub struct CachedConfigLoader {
+    files: Mutex<HashMap<(PathBuf, TypeId), CachedFile>>,
+    parses: AtomicU64,
+}
+
+impl CachedConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load and cache a JSON file
+    pub fn json<T, P>(&self, path: P) -> HoResult<Arc<T>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+        P: AsRef<Path>,
+    {
+        self.load(path, |p| DefaultFileOps::from_json_file(p))
+    }
+
+    /// Load and cache a TOML file
+    pub fn toml<T, P>(&self, path: P) -> HoResult<Arc<T>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+        P: AsRef<Path>,
+    {
+        self.load(path, |p| DefaultFileOps::from_toml_file(p))
+    }
+
+    /// Drop cached entries for a path so the next load re-reads it
+    pub fn reload<P: AsRef<Path>>(&self, path: P) {
+        self.files
+            .lock()
+            .unwrap()
+            .retain(|(cached, _), _| cached != path.as_ref());
+    }
+
+    /// Number of times a file was actually read and parsed
+    pub fn parse_count(&self) -> u64 {
+        self.parses.load(Ordering::Relaxed)
+    }
+
+    fn load<T, P, F>(&self, path: P, parse: F) -> HoResult<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+        P: AsRef<Path>,
+        F: FnOnce(&Path) -> HoResult<T>,
+    {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path).map_err(|e| {
+            HoError::from(format!("Failed to read file '{}': {}", path.display(), e))
+        })?;
+        let modified = metadata.modified()?;
+        let key = (path.to_path_buf(), TypeId::of::<T>());
+
+        if let Some(cached) = self.files.lock().unwrap().get(&key) {
+            if cached.modified == modified && cached.len == metadata.len() {
+                if let Ok(value) = cached.value.clone().downcast::<T>() {
+                    return Ok(value);
+                }
+            }
+        }
+
+        let value = Arc::new(parse(path)?);
+        self.parses.fetch_add(1, Ordering::Relaxed);
+        self.files.lock().unwrap().insert(
+            key,
+            CachedFile {
+                modified,
+                len: metadata.len(),
+                value: value.clone(),
+            },
+        );
+        Ok(value)
+    }
+}
+
+/// Process-wide cached loader shared by the orchestration and transport paths
+pub fn shared_config_loader() -> &'static CachedConfigLoader {
+    static LOADER: OnceLock<CachedConfigLoader> = OnceLock::new();
+    LOADER.get_or_init(CachedConfigLoader::new)
+}
+
 impl FileOptsTrait for DefaultFileOps {
     /// Read file contents as string with error handling
     fn read_string<P: AsRef<Path>>(path: P) -> HoResult<String> {
@@ -261,6 +360,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cached_loader_reparses_only_on_change() {
+        let path = env::temp_dir().join(format!("cached-loader-{}.json", Uuid::new_v4()));
+        DefaultFileOps::write_string(&path, r#"{"node": 1}"#).unwrap();
+        let loader = CachedConfigLoader::new();
+
+        let first = loader.json::<serde_json::Value, _>(&path).unwrap();
+        let second = loader.json::<serde_json::Value, _>(&path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(loader.parse_count(), 1);
+
+        // Touch the file with new content and a later mtime
+        DefaultFileOps::write_string(&path, r#"{"node": 22}"#).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        let touched = loader.json::<serde_json::Value, _>(&path).unwrap();
+        assert_eq!(touched["node"], 22);
+        assert_eq!(loader.parse_count(), 2);
+
+        loader.reload(&path);
+        loader.json::<serde_json::Value, _>(&path).unwrap();
+        assert_eq!(loader.parse_count(), 3);
+        fs::remove_file(&path).ok();
+    }
+
     #[test]
     fn test_id_generation() {
         let uuid_bytes = IdGenerator::new_uuid_bytes();
@@ -5,6 +5,7 @@
 use std::path::PathBuf;
 
 const SERDE_JSON: &str = "#[derive(serde::Serialize, serde::Deserialize)]";
+const SERDE_DEFAULT: &str = "#[serde(default)]";
 fn main() -> anyhow::Result<()> {
     let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     println!("root: {}", root.display());
@@ -28,6 +29,9 @@ fn main() -> anyhow::Result<()> {
     config.extern_path(".google.protobuf", "::pbjson_types");
 
     config.type_attribute(".", SERDE_JSON);
+    // Fields added after clients started sending json, which may still omit them
+    config.field_attribute("hoe.orchestration.v1.CosmicTask.depends_on", SERDE_DEFAULT);
+    config.field_attribute("hoe.orchestration.v1.PromptRequest.tools", SERDE_DEFAULT);
     // config.type_attribute("hoe.orchestration.v1.HoConfig", SERDE_JSON);
     // config.type_attribute("hoe.network.v1.NetworkConfig", SERDE_JSON);
     // config.type_attribute("hoe.network.v1.NodeIdentity", SERDE_JSON);